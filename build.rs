@@ -1,57 +1,129 @@
 fn main() -> std::io::Result<()> {
+    #[cfg(feature = "video")]
     {
+        println!("cargo:rerun-if-env-changed=FAERY_X264");
         let cargo_manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
         println!("cargo:rerun-if-changed={cargo_manifest_dir}/src/x264.h",);
-        println!("cargo:rustc-link-search=native={cargo_manifest_dir}/x264/");
-        println!("cargo:rustc-link-lib=static=x264");
-        if !std::process::Command::new("/bin/bash")
-            .args([
-                "configure",
-                "--disable-cli",
-                "--enable-static",
-                "--disable-interlaced",
-                "--bit-depth=8",
-                "--enable-lto",
-                "--enable-strip",
-                "--disable-avs",
-                "--disable-swscale",
-                "--disable-lavf",
-                "--disable-ffms",
-                "--disable-gpac",
-                "--disable-lsmash",
-            ])
-            .current_dir("x264")
-            .status()
-            .expect("Failed to spawn '/bin/bash configure' for x264")
-            .success()
-        {
-            panic!("Failed to configure x264");
-        }
-        if !std::process::Command::new("make")
-            .args(["clean"])
-            .current_dir("x264")
-            .status()
-            .expect("Failed to spawn 'make clean' for x264")
-            .success()
-        {
-            panic!("Failed to make clean x264");
-        }
-        if !std::process::Command::new("make")
-            .current_dir("x264")
-            .status()
-            .expect("Failed to spawn 'make' for x264")
-            .success()
-        {
-            panic!("Failed to make x264");
-        }
-        let bindings = bindgen::Builder::default()
-            .header("src/x264.h")
-            .generate()
-            .expect("Unable to generate bindings");
+        let bindings = match std::env::var("FAERY_X264").as_deref() {
+            Ok("vendored") => build_vendored_x264(&cargo_manifest_dir),
+            Ok("system") => link_system_x264(),
+            Ok(value) => panic!("FAERY_X264 must be \"vendored\" or \"system\" (got \"{value}\")"),
+            Err(_) => {
+                if vendored_x264_supported() {
+                    build_vendored_x264(&cargo_manifest_dir)
+                } else {
+                    link_system_x264()
+                }
+            }
+        };
         let out_path = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
-        bindings
-            .write_to_file(out_path.join("x264_bindings.rs"))
-            .expect("Couldn't write bindings");
+        std::fs::write(
+            out_path.join("x264_bindings.rs"),
+            alias_encoder_open(&bindings.to_string()),
+        )
+        .expect("Couldn't write bindings");
     }
     Ok(())
 }
+
+/// x264.h renames x264_encoder_open to x264_encoder_open_<X264_BUILD> with a macro, which bindgen does not expand.
+/// The alias lets the encoder call x264_encoder_open regardless of the build of the linked library.
+#[cfg(feature = "video")]
+fn alias_encoder_open(bindings: &str) -> String {
+    let build = bindings
+        .lines()
+        .find_map(|line| {
+            line.trim()
+                .strip_prefix("pub const X264_BUILD: u32 = ")?
+                .strip_suffix(';')
+        })
+        .expect("the x264 bindings do not define X264_BUILD");
+    format!("{bindings}\npub use x264_encoder_open_{build} as x264_encoder_open;\n")
+}
+
+/// The vendored x264 is built with its configure script, which requires bash and make.
+#[cfg(feature = "video")]
+fn vendored_x264_supported() -> bool {
+    std::env::var("CARGO_CFG_TARGET_OS").is_ok_and(|target_os| target_os != "windows")
+        && std::path::Path::new("/bin/bash").exists()
+        && std::process::Command::new("make")
+            .arg("--version")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+}
+
+#[cfg(feature = "video")]
+fn build_vendored_x264(cargo_manifest_dir: &str) -> bindgen::Bindings {
+    println!("cargo:rustc-link-search=native={cargo_manifest_dir}/x264/");
+    println!("cargo:rustc-link-lib=static=x264");
+    if !std::process::Command::new("/bin/bash")
+        .args([
+            "configure",
+            "--disable-cli",
+            "--enable-static",
+            "--disable-interlaced",
+            "--bit-depth=8",
+            "--enable-lto",
+            "--enable-strip",
+            "--disable-avs",
+            "--disable-swscale",
+            "--disable-lavf",
+            "--disable-ffms",
+            "--disable-gpac",
+            "--disable-lsmash",
+        ])
+        .current_dir("x264")
+        .status()
+        .expect("Failed to spawn '/bin/bash configure' for x264")
+        .success()
+    {
+        panic!("Failed to configure x264");
+    }
+    if !std::process::Command::new("make")
+        .args(["clean"])
+        .current_dir("x264")
+        .status()
+        .expect("Failed to spawn 'make clean' for x264")
+        .success()
+    {
+        panic!("Failed to make clean x264");
+    }
+    if !std::process::Command::new("make")
+        .current_dir("x264")
+        .status()
+        .expect("Failed to spawn 'make' for x264")
+        .success()
+    {
+        panic!("Failed to make x264");
+    }
+    bindgen::Builder::default()
+        .header("src/x264.h")
+        .generate()
+        .expect("Unable to generate bindings")
+}
+
+/// pkg-config prints the link flags, the bindings are generated from the system header.
+#[cfg(feature = "video")]
+fn link_system_x264() -> bindgen::Bindings {
+    let library = match pkg_config::Config::new().probe("x264") {
+        Ok(library) => library,
+        Err(error) => panic!(
+            "pkg-config did not find a system libx264 ({error}), install libx264, build the vendored x264 with FAERY_X264=vendored (requires bash and make), or disable the \"video\" feature"
+        ),
+    };
+    bindgen::Builder::default()
+        .header_contents(
+            "x264_system.h",
+            "#include <inttypes.h>\n#include <x264.h>\n",
+        )
+        .clang_args(
+            library
+                .include_paths
+                .iter()
+                .map(|path| format!("-I{}", path.display())),
+        )
+        .generate()
+        .expect("Unable to generate bindings")
+}
@@ -0,0 +1,45 @@
+import argparse
+import concurrent.futures
+import pathlib
+import time
+
+import faery
+
+dirname = pathlib.Path(__file__).resolve().parent
+
+# decoders release the GIL while they read and parse packets,
+# hence decoding files on several Python threads is faster than decoding them one after the other
+parser = argparse.ArgumentParser()
+parser.add_argument(
+    "path",
+    nargs="?",
+    default=str(dirname.parent / "tests" / "data" / "dvs.es"),
+    help="DVS file (.es or .aedat4)",
+)
+parser.add_argument("--threads", type=int, default=4, help="number of Python threads")
+args = parser.parse_args()
+path = pathlib.Path(args.path)
+
+
+def decode(path: pathlib.Path) -> int:
+    events = 0
+    if path.suffix == ".es":
+        with faery.event_stream.Decoder(path, t0=0) as decoder:
+            for packet in decoder:
+                events += len(packet)
+    else:
+        with faery.aedat.Decoder(path) as decoder:
+            for track, packet in decoder:
+                if track.data_type == "events":
+                    events += len(packet)
+    return events
+
+
+for threads in (1, args.threads):
+    begin = time.perf_counter()
+    with concurrent.futures.ThreadPoolExecutor(max_workers=threads) as executor:
+        events = sum(executor.map(decode, [path] * args.threads))
+    duration = time.perf_counter() - begin
+    print(
+        f"{threads} thread{'' if threads == 1 else 's'}: {events / duration / 1e6:.2f} Mev/s ({duration:.3f} s)"
+    )
@@ -0,0 +1,52 @@
+import argparse
+import pathlib
+import time
+
+import faery
+
+dirname = pathlib.Path(__file__).resolve().parent
+
+# DVS packets are NumPy arrays that own the decoded events' memory (no copy)
+# "scan" decodes the file without building arrays (lower bound)
+# "views" iterates over the packets (zero-copy arrays)
+# "copies" iterates over the packets and copies each array (the cost that zero-copy arrays avoid)
+parser = argparse.ArgumentParser()
+parser.add_argument(
+    "path",
+    nargs="?",
+    default=str(dirname.parent / "tests" / "data" / "dvs.es"),
+    help="DVS Event Stream file (.es)",
+)
+parser.add_argument("--repeats", type=int, default=10, help="number of decodes per mode")
+args = parser.parse_args()
+path = pathlib.Path(args.path)
+
+
+def scan() -> int:
+    with faery.event_stream.Decoder(path, t0=0) as decoder:
+        decoder.duration()
+        return decoder.event_count()
+
+
+def views() -> int:
+    events = 0
+    with faery.event_stream.Decoder(path, t0=0) as decoder:
+        for packet in decoder:
+            assert packet.base is not None
+            events += len(packet)
+    return events
+
+
+def copies() -> int:
+    events = 0
+    with faery.event_stream.Decoder(path, t0=0) as decoder:
+        for packet in decoder:
+            events += len(packet.copy())
+    return events
+
+
+for name, decode in (("scan", scan), ("views", views), ("copies", copies)):
+    begin = time.perf_counter()
+    events = sum(decode() for _ in range(args.repeats))
+    duration = time.perf_counter() - begin
+    print(f"{name:>6}: {events / duration / 1e6:.2f} Mev/s ({duration:.3f} s)")
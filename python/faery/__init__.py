@@ -1,17 +1,22 @@
 from __future__ import annotations
 
 import pathlib
+import time
 import typing
 
 import numpy
 
+from . import encoder
 from .common import FileType
 from .common import Time as Time
 from .common import parse_timestamp as parse_timestamp
 from .common import timestamp_to_timecode as timestamp_to_timecode
 from .common import timestamp_to_seconds as timestamp_to_seconds
 from .decoder import Decoder
+from .decoder import MergeDecoder as MergeDecoder
+from .frame import ColorRender as ColorRender
 from .stream import DVS_DTYPE as DVS_DTYPE
+from .stream import NORMALIZED_DVS_DTYPE as NORMALIZED_DVS_DTYPE
 from .stream import Array as Array
 from .stream import Stream as Stream
 
@@ -20,11 +25,25 @@ if typing.TYPE_CHECKING:
     from . import dat  # type: ignore
     from . import event_stream  # type: ignore
     from . import evt  # type: ignore
+    from . import render  # type: ignore
+    from . import video  # type: ignore
+
+    VIDEO_AVAILABLE: bool
+
+    class TruncatedFileError(EOFError): ...
+
 else:
+    from .faery import VIDEO_AVAILABLE as VIDEO_AVAILABLE
+    from .faery import TruncatedFileError as TruncatedFileError
     from .faery import aedat
     from .faery import dat
     from .faery import event_stream
     from .faery import evt
+    from .faery import render
+
+    # the video module is only compiled with the "video" Cargo feature
+    if VIDEO_AVAILABLE:
+        from .faery import video
 
 
 def stream_from_file(
@@ -36,8 +55,13 @@ def stream_from_file(
     ] = None,
     t0: Time = 0,
     file_type: typing.Optional[FileType] = None,
+    normalize_coordinates: bool = False,
+    record_stride: typing.Optional[int] = None,
+    coalesce_packets: bool = False,
+    coalesce_max_events: typing.Optional[int] = None,
+    reverse: bool = False,
 ) -> Stream:
-    """An event file decoder (supports .aedat4, .es, .raw, and .dat).
+    """An event file decoder (supports .aedat4, .aedat (2.0 and 3.1), .es, .raw, and .dat).
 
     track_id is only used if the type is aedat. It selects a specific stream in the container.
     If left unspecified (None), the first event stream is selected.
@@ -50,6 +74,14 @@ def stream_from_file(
 
     t0 is only used if the file type is ES.
 
+    normalize_coordinates replaces the integer pixel coordinates with x / width and y / height (float32 in [0, 1)).
+
+    record_stride pads each event to the given number of bytes (for instance 16 for aligned SIMD loads).
+
+    coalesce_packets concatenates consecutive native packets into a single array per iteration.
+
+    reverse yields events in descending timestamp order. The whole stream is loaded in memory first.
+
     Args:
         path: Path of the input event file.
         track_id: Stream ID, only used with aedat files. Defaults to None.
@@ -57,6 +89,11 @@ def stream_from_file(
         version_fallback: Version fallback for EVT (.raw) and DAT files. Defaults to "dat2" for DAT and "evt3" for EVT.
         t0: Initial time for ES files, in seconds. Defaults to None.
         file_type: Override the type determination algorithm. Defaults to None.
+        normalize_coordinates: Whether to divide coordinates by the sensor dimensions. Defaults to False.
+        record_stride: Size of each event in bytes, None for tight packing. Defaults to None.
+        coalesce_packets: Whether to combine consecutive native packets. Defaults to False.
+        coalesce_max_events: Maximum number of events per combined packet, None for no limit. Defaults to None.
+        reverse: Whether to yield events in descending timestamp order. Defaults to False.
     """
     return Decoder(
         path=pathlib.Path(path),
@@ -65,8 +102,111 @@ def stream_from_file(
         version_fallback=version_fallback,
         t0=t0,
         file_type=file_type,
+        normalize_coordinates=normalize_coordinates,
+        record_stride=record_stride,
+        coalesce_packets=coalesce_packets,
+        coalesce_max_events=coalesce_max_events,
+        reverse=reverse,
+    )
+
+
+# faery.open mirrors the builtin's name, the rest of this module must not call open (use builtins.open)
+def open(
+    path: typing.Union[str, pathlib.Path],
+    format: typing.Optional[
+        typing.Literal["aedat", "aedat4", "dat", "es", "evt", "raw"]
+    ] = None,
+    **options: typing.Any,
+) -> Stream:
+    """Opens an event file with the decoder that matches format.
+
+    If format is None, the type is guessed from the file's first bytes (and from its extension if they are ambiguous).
+    Use format to decode misnamed files that the guess gets wrong.
+
+    Args:
+        path: Path of the input event file.
+        format: Name of the file format, None to guess it. Defaults to None.
+        options: Keyword arguments forwarded to stream_from_file (except file_type).
+    """
+    return stream_from_file(
+        path,
+        file_type=None if format is None else FileType.from_name(format),
+        **options,
     )
 
 
 def stream_from_array(events: numpy.ndarray, dimensions: tuple[int, int]) -> Stream:
     return Array(events=events, dimensions=dimensions)
+
+
+def benchmark(
+    path: typing.Union[str, pathlib.Path],
+    file_type: typing.Optional[FileType] = None,
+    options: typing.Optional[dict[str, typing.Any]] = None,
+) -> dict[str, typing.Union[int, float]]:
+    """Decodes a whole event file and reports throughput.
+
+    Args:
+        path: Path of the input event file.
+        file_type: Override the type determination algorithm. Defaults to None.
+        options: Keyword arguments forwarded to stream_from_file. Defaults to None.
+
+    Returns:
+        dict[str, typing.Union[int, float]]: Duration (seconds), event count, packet count, events per second,
+        megabytes (10^6 bytes) per second, and size of the largest decoded packet in bytes
+        (largest_packet_bytes is not the peak memory usage, which also includes the decoder's buffers).
+    """
+    path = pathlib.Path(path)
+    stream = stream_from_file(
+        path, file_type=file_type, **({} if options is None else options)
+    )
+    events = 0
+    packets = 0
+    largest_packet_bytes = 0
+    begin = time.perf_counter()
+    for packet in stream:
+        events += len(packet)
+        packets += 1
+        largest_packet_bytes = max(largest_packet_bytes, packet.nbytes)
+    duration = time.perf_counter() - begin
+    return {
+        "duration": duration,
+        "events": events,
+        "packets": packets,
+        "events_per_second": events / duration if duration > 0.0 else 0.0,
+        "megabytes_per_second": (
+            path.stat().st_size / 1e6 / duration if duration > 0.0 else 0.0
+        ),
+        "largest_packet_bytes": largest_packet_bytes,
+    }
+
+
+def split_by_polarity(
+    path: typing.Union[str, pathlib.Path],
+    on_path: typing.Union[str, pathlib.Path],
+    off_path: typing.Union[str, pathlib.Path],
+    file_type: typing.Optional[FileType] = None,
+):
+    """Writes the ON events and the OFF events of a file to two separate files.
+
+    The input is decoded twice (once per output) so that memory usage does not depend on the file size.
+    Timestamps are written as-is (zero_t0 is false) so that merging the outputs reconstructs the input.
+
+    Args:
+        path: Path of the input event file.
+        on_path: Path of the output file for ON events.
+        off_path: Path of the output file for OFF events.
+        file_type: Override the type determination algorithm for the output files. Defaults to None.
+    """
+    stream = stream_from_file(path)
+    for output_path, polarity_stream in (
+        (on_path, stream.remove_off_events()),
+        (off_path, stream.remove_on_events()),
+    ):
+        encoder.save(
+            polarity_stream,
+            path=output_path,
+            dimensions=stream.dimensions(),
+            zero_t0=False,
+            file_type=file_type,
+        )
@@ -5,6 +5,7 @@ import typing
 import types
 
 import numpy
+import numpy.typing
 
 LZ4_FASTEST: tuple[typing.Literal["lz4"], typing.Literal[1]]
 LZ4_DEFAULT: tuple[typing.Literal["lz4"], typing.Literal[1]]
@@ -52,8 +53,46 @@ class Frame:
     offset_y: int
     pixels: numpy.ndarray
 
+    def to_pil(self) -> typing.Any: ...
+    def staleness(self, t: int) -> numpy.ndarray: ...
+
 class Decoder:
-    def __init__(self, path: typing.Union[pathlib.Path, str]): ...
+    unwrap_timestamps: bool
+    polarity: typing.Optional[bool]
+    downsample: int
+    imus_as_dict: bool
+    lenient: bool
+    on_overflow: typing.Literal["error", "clamp", "drop"]
+    mmap: bool
+    zero_origin: bool
+    as_pil: bool
+    composite_frames: bool
+    frame_background: int
+    closed: bool
+    width: int
+    height: int
+
+    def __init__(
+        self,
+        path: typing.Union[pathlib.Path, str, typing.BinaryIO],
+        skip_empty: bool = True,
+        unwrap_timestamps: bool = False,
+        polarity: typing.Optional[bool] = None,
+        downsample: int = 1,
+        track_ids: typing.Optional[list[int]] = None,
+        imus_as_dict: bool = False,
+        lenient: bool = False,
+        on_overflow: typing.Literal["error", "clamp", "drop"] = "error",
+        mmap: bool = False,
+        zero_origin: bool = False,
+        as_pil: bool = False,
+        mask_pixels: typing.Optional[list[tuple[int, int]]] = None,
+        remap: typing.Optional[numpy.ndarray] = None,
+        median_denoise: typing.Optional[int] = None,
+        composite_frames: bool = False,
+        frame_background: int = 0,
+        buffer_size: int = 65536,
+    ): ...
     def __enter__(self) -> Decoder: ...
     def __exit__(
         self,
@@ -61,15 +100,91 @@ class Decoder:
         value: typing.Optional[BaseException],
         traceback: typing.Optional[types.TracebackType],
     ) -> bool: ...
+    def __len__(self) -> int: ...
+    def skipped_packets(self) -> int: ...
+    def origin_t(self) -> typing.Optional[int]: ...
     def __iter__(self) -> Decoder: ...
     def __next__(
         self,
     ) -> tuple[
         Track,
-        typing.Union[numpy.ndarray, Frame],
+        typing.Union[
+            numpy.ndarray,
+            Frame,
+            dict[
+                typing.Literal[
+                    "t", "temperature", "accelerometer", "gyroscope", "magnetometer"
+                ],
+                numpy.ndarray,
+            ],
+        ],
     ]: ...
+    def enumerate(self) -> EnumeratedDecoder: ...
     def tracks(self) -> list[Track]: ...
     def description(self) -> str: ...
+    def parsed_description(
+        self,
+    ) -> dict[
+        typing.Literal["device_name", "serial_number", "sources"],
+        typing.Union[str, dict[int, str]],
+    ]: ...
+    def state_token(self) -> int: ...
+    def resume_from(self, token: int): ...
+    def seek(self, t_us: int): ...
+    def seek_to_start(self): ...
+    def reset(self) -> None: ...
+    def first_activation(
+        self, track_id: int, after_t: int
+    ) -> numpy.typing.NDArray[numpy.uint64]: ...
+    def collect_fused(self, event_track_id: int, imu_track_id: int) -> numpy.ndarray: ...
+    def triggers_only(self) -> numpy.ndarray: ...
+    def frame_metadata(self) -> numpy.ndarray: ...
+    def trigger_intervals(self) -> numpy.ndarray: ...
+
+class EnumeratedDecoder:
+    def __iter__(self) -> EnumeratedDecoder: ...
+    def __next__(
+        self,
+    ) -> tuple[
+        int,
+        tuple[
+            Track,
+            typing.Union[
+                numpy.ndarray,
+                Frame,
+                dict[
+                    typing.Literal[
+                        "t", "temperature", "accelerometer", "gyroscope", "magnetometer"
+                    ],
+                    numpy.ndarray,
+                ],
+            ],
+        ],
+    ]: ...
+
+class LegacyDecoder:
+    version: typing.Literal["2.0", "3.1"]
+    closed: bool
+    width: int
+    height: int
+
+    def __init__(
+        self,
+        path: typing.Union[pathlib.Path, str, typing.BinaryIO],
+        skip_empty: bool = True,
+        buffer_size: int = 65536,
+    ): ...
+    def __enter__(self) -> LegacyDecoder: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> LegacyDecoder: ...
+    def __next__(self) -> tuple[Track, numpy.ndarray]: ...
+    def tracks(self) -> list[Track]: ...
+    def reset(self) -> None: ...
 
 class Encoder:
     def __init__(
@@ -1,4 +1,5 @@
 import enum
+import gzip
 import pathlib
 import re
 import typing
@@ -6,6 +7,9 @@ import typing
 FULL_TIMECODE_PATTERN: re.Pattern = re.compile(r"^(\d+):(\d{2}):(\d{2})(\.\d{0,6})?$")
 MINUTES_TIMECODE_PATTERN: re.Pattern = re.compile(r"^(\d+):(\d{2})(\.\d{0,6})?$")
 SECONDS_TIMECODE_PATTERN: re.Pattern = re.compile(r"^(\d+)(\.\d{0,6})?$")
+GZIP_MAGIC: bytes = b"\x1f\x8b"
+# Prophesee headers are a few hundred bytes long
+HEADER_SNIFF_LENGTH: int = 1 << 16
 
 
 Time = typing.Union[int, float, str]
@@ -60,6 +64,21 @@ def parse_timestamp(value: Time) -> int:
     raise RuntimeError(f'parsing the timecode "{value}" failed')
 
 
+def open_decompressed(path: pathlib.Path) -> typing.BinaryIO:
+    """
+    Opens a file for reading, gzip-compressed files are decompressed transparently (like the decoders).
+
+    Args:
+        path: Path of the file.
+    """
+    file = open(path, "rb")
+    if file.read(len(GZIP_MAGIC)) == GZIP_MAGIC:
+        file.close()
+        return typing.cast(typing.BinaryIO, gzip.open(path, "rb"))
+    file.seek(0)
+    return file
+
+
 def timestamp_to_timecode(value: int) -> str:
     value = int(value)
     hours = value // (1000000 * 60 * 60)
@@ -83,7 +102,8 @@ class FileType(enum.Enum):
 
     def magic(self) -> typing.Optional[bytes]:
         if self == FileType.AEDAT:
-            return b"#!AER-DAT4.0\r\n"
+            # matches AEDAT 2.0, 3.1, and 4.0 files
+            return b"#!AER-DAT"
         if self == FileType.DAT:
             return None
         elif self == FileType.ES:
@@ -105,24 +125,76 @@ class FileType(enum.Enum):
         else:
             raise Exception(f"extensions is not implemented for {self}")
 
+    @staticmethod
+    def from_name(name: str) -> "FileType":
+        """
+        Converts a format name or an extension (with or without a leading dot) to a file type.
+
+        Args:
+            name: "aedat", "aedat4", "dat", "es", "evt", or "raw".
+        """
+        extension = name if name.startswith(".") else f".{name}"
+        for file_type in FileType:
+            if extension in file_type.extensions():
+                return file_type
+        raise ValueError(
+            f'unknown format "{name}" (expected "aedat", "aedat4", "dat", "es", "evt", or "raw")'
+        )
+
     @staticmethod
     def guess(path: pathlib.Path) -> "FileType":
+        """
+        Guesses the file type from the file's first bytes, and from its extension otherwise.
+
+        DAT and EVT files both start with a Prophesee header (lines that begin with "%").
+        Headers with an "evt" or "format EVT..." line are EVT, other headers are DAT unless the extension is an EVT extension.
+        gzip-compressed files are sniffed after decompression and their ".gz" extension is ignored.
+        The error raised for unsupported files lists the sniffed bytes and the extension.
+        """
         longest_magic = max(
             0 if magic is None else len(magic)
             for magic in (file_type.magic() for file_type in FileType)
         )
+        extension = path.suffix
+        if extension == ".gz":
+            extension = pathlib.Path(path.stem).suffix
+        magic = None
         try:
-            with open(path, "rb") as file:
+            with open_decompressed(path) as file:
                 magic = file.read(longest_magic)
-            for file_type in FileType:
-                if file_type.magic() == magic:
-                    return file_type
+                for file_type in FileType:
+                    type_magic = file_type.magic()
+                    if type_magic is not None and magic.startswith(type_magic):
+                        return file_type
+                if magic.startswith(b"%"):
+                    file.seek(0)
+                    # the file may not contain any line break, only a bounded prefix is read
+                    header = file.read(HEADER_SNIFF_LENGTH)
+                    for line in header.split(b"\n"):
+                        if not line.startswith(b"%"):
+                            break
+                        words = line[1:].strip().lower().split()
+                        if len(words) > 1 and (
+                            words[0] == b"evt"
+                            or (words[0] == b"format" and words[1].startswith(b"evt"))
+                        ):
+                            return FileType.EVT
+                    if extension in FileType.EVT.extensions():
+                        return FileType.EVT
+                    return FileType.DAT
         except FileNotFoundError as exception:
             pass
-        extension = path.suffix
         for file_type in FileType:
             if any(
                 extension == type_extension for type_extension in file_type.extensions()
             ):
                 return file_type
-        raise Exception(f"unsupported file {path}")
+        extensions = ", ".join(
+            f'"{type_extension}"'
+            for file_type in FileType
+            for type_extension in file_type.extensions()
+        )
+        sniffed = "the file does not exist" if magic is None else f"first bytes {magic!r}"
+        raise ValueError(
+            f'unsupported file {path} ({sniffed}, extension "{extension}", expected a magic number, a Prophesee header, or one of the extensions {extensions})'
+        )
@@ -6,16 +6,27 @@ import types
 
 import numpy
 
+BitLayoutPreset = typing.Literal["dat1", "dat2", "dvs128", "davis240"]
+
 class Decoder:
     version: typing.Literal["dat1", "dat2"]
+    bit_layout: dict[str, tuple[int, int]]
     event_type: typing.Literal["2d", "cd", "trigger"]
     dimensions: typing.Optional[tuple[int, int]]
+    width: typing.Optional[int]
+    height: typing.Optional[int]
+    indexed: bool
 
     def __init__(
         self,
-        path: typing.Union[pathlib.Path, str],
+        path: typing.Union[pathlib.Path, str, typing.BinaryIO],
         dimensions_fallback: typing.Optional[tuple[int, int]] = None,
         version_fallback: typing.Optional[typing.Literal["dat1", "dat2"]] = None,
+        skip_empty: bool = True,
+        bit_layout: typing.Union[
+            BitLayoutPreset, dict[str, tuple[int, int]], None
+        ] = None,
+        buffer_size: int = 65536,
     ): ...
     def __enter__(self) -> Decoder: ...
     def __exit__(
@@ -26,6 +37,14 @@ class Decoder:
     ) -> bool: ...
     def __iter__(self) -> Decoder: ...
     def __next__(self) -> numpy.ndarray: ...
+    def comment_lines(self) -> list[str]: ...
+    def seek(self, t_us: int): ...
+    def enumerate(self) -> EnumeratedDecoder: ...
+    def reset(self) -> None: ...
+
+class EnumeratedDecoder:
+    def __iter__(self) -> EnumeratedDecoder: ...
+    def __next__(self) -> tuple[int, numpy.ndarray]: ...
 
 class Encoder:
     @typing.overload
@@ -36,6 +55,7 @@ class Encoder:
         event_type: typing.Literal["2d"],
         zero_t0: bool,
         dimensions: tuple[int, int],
+        index_interval: typing.Optional[int] = None,
     ): ...
     @typing.overload
     def __init__(
@@ -45,6 +65,7 @@ class Encoder:
         event_type: typing.Literal["cd"],
         zero_t0: bool,
         dimensions: tuple[int, int],
+        index_interval: typing.Optional[int] = None,
     ): ...
     @typing.overload
     def __init__(
@@ -54,6 +75,7 @@ class Encoder:
         event_type: typing.Literal["trigger"],
         zero_t0: bool,
         dimensions: None,
+        index_interval: typing.Optional[int] = None,
     ): ...
     def __enter__(self) -> Encoder: ...
     def __exit__(
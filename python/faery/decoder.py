@@ -2,6 +2,8 @@ from __future__ import annotations
 
 import collections.abc
 import pathlib
+import queue
+import threading
 import types
 import typing
 
@@ -23,6 +25,35 @@ else:
     from .faery import evt
 
 
+def padded_dtype(dtype: numpy.dtype, record_stride: int) -> numpy.dtype:
+    """
+    Returns a copy of dtype with the same fields and offsets, and an itemsize of record_stride bytes.
+    """
+    if record_stride < dtype.itemsize:
+        raise ValueError(
+            f"record_stride must be larger than or equal to {dtype.itemsize} (got {record_stride})"
+        )
+    assert dtype.names is not None
+    fields = [dtype.fields[name] for name in dtype.names]  # type: ignore
+    return numpy.dtype(
+        {
+            "names": dtype.names,
+            "formats": [field[0] for field in fields],
+            "offsets": [field[1] for field in fields],
+            "titles": [field[2] if len(field) > 2 else None for field in fields],
+            "itemsize": record_stride,
+        }
+    )
+
+
+def is_legacy_aedat(path: pathlib.Path) -> bool:
+    """
+    Returns True for AEDAT 2.0 and 3.1 files, which are read with aedat.LegacyDecoder.
+    """
+    with common.open_decompressed(path) as file:
+        return not file.read(12).startswith(b"#!AER-DAT4.0")
+
+
 class DecoderIterator(stream.StreamIterator):
     def __init__(
         self,
@@ -31,6 +62,11 @@ class DecoderIterator(stream.StreamIterator):
         is_atis: bool,
         dimensions: tuple[int, int],
         inner: collections.abc.Iterable,
+        normalize_coordinates: bool = False,
+        record_stride: typing.Optional[int] = None,
+        coalesce_packets: bool = False,
+        coalesce_max_events: typing.Optional[int] = None,
+        reverse: bool = False,
     ):
         super().__init__()
         self.file_type = file_type
@@ -38,8 +74,108 @@ class DecoderIterator(stream.StreamIterator):
         self.is_atis = is_atis
         self.dimensions = dimensions
         self.inner = iter(inner)
+        self.normalize_coordinates = normalize_coordinates
+        self.record_stride = record_stride
+        # normalization and padding write the decoded fields into a single array per packet
+        self.output_dtype: typing.Optional[numpy.dtype] = (
+            stream.NORMALIZED_DVS_DTYPE if normalize_coordinates else None
+        )
+        if record_stride is not None:
+            self.output_dtype = padded_dtype(
+                (
+                    stream.NORMALIZED_DVS_DTYPE
+                    if normalize_coordinates
+                    else stream.DVS_DTYPE
+                ),
+                record_stride,
+            )
+        self.coalesce_packets = coalesce_packets
+        self.coalesce_max_events = coalesce_max_events
+        self.pending_events: typing.Optional[numpy.ndarray] = None
+        self.reverse = reverse
+        self.reversed_packets: typing.Optional[list[numpy.ndarray]] = None
 
     def __next__(self) -> numpy.ndarray:
+        if self.reverse:
+            return self.next_reversed_events()
+        return self.next_forward_events()
+
+    def next_reversed_events(self) -> numpy.ndarray:
+        """
+        Reads all the packets on the first call, then returns them last-to-first.
+
+        The events in each packet are reversed as well, hence timestamps are in descending order.
+        """
+        if self.reversed_packets is None:
+            self.reversed_packets = []
+            while True:
+                try:
+                    self.reversed_packets.append(self.next_forward_events())
+                except StopIteration:
+                    break
+        if len(self.reversed_packets) == 0:
+            raise StopIteration()
+        return self.reversed_packets.pop()[::-1].copy()
+
+    def next_forward_events(self) -> numpy.ndarray:
+        events = (
+            self.next_coalesced_events()
+            if self.coalesce_packets
+            else self.next_events()
+        )
+        if self.output_dtype is None:
+            return events
+        if self.record_stride is None:
+            output = numpy.empty(len(events), dtype=self.output_dtype)
+        else:
+            # padding bytes must be deterministic, numpy.zeros gets zeroed pages without an extra pass
+            output = numpy.zeros(len(events), dtype=self.output_dtype)
+        assert self.output_dtype.names is not None
+        for name in self.output_dtype.names:
+            if self.normalize_coordinates and (name == "x" or name == "y"):
+                numpy.divide(
+                    events[name],
+                    numpy.float32(self.dimensions[0 if name == "x" else 1]),
+                    out=output[name],
+                    dtype=numpy.float32,
+                )
+            else:
+                output[name] = events[name]
+        return output
+
+    def next_coalesced_events(self) -> numpy.ndarray:
+        """
+        Concatenates consecutive packets until the next packet would exceed coalesce_max_events.
+
+        A single packet larger than coalesce_max_events is returned as is (it is never split).
+        """
+        packets: list[numpy.ndarray] = []
+        length = 0
+        if self.pending_events is not None:
+            packets.append(self.pending_events)
+            length += len(self.pending_events)
+            self.pending_events = None
+        while self.coalesce_max_events is None or length < self.coalesce_max_events:
+            try:
+                events = self.next_events()
+            except StopIteration:
+                break
+            if (
+                self.coalesce_max_events is not None
+                and len(packets) > 0
+                and length + len(events) > self.coalesce_max_events
+            ):
+                self.pending_events = events
+                break
+            packets.append(events)
+            length += len(events)
+        if len(packets) == 0:
+            raise StopIteration()
+        if len(packets) == 1:
+            return packets[0]
+        return numpy.concatenate(packets)
+
+    def next_events(self) -> numpy.ndarray:
         assert self.inner is not None
         try:
             if self.file_type == common.FileType.AEDAT:
@@ -107,7 +243,7 @@ class DecoderIterator(stream.StreamIterator):
 
 
 class Decoder(stream.Stream):
-    """An event file decoder (supports .aedat4, .es, .raw, and .dat).
+    """An event file decoder (supports .aedat4, .aedat (2.0 and 3.1), .es, .raw, and .dat).
 
     track_id is only used if the type is aedat. It selects a specific stream in the container.
     If left unspecified (None), the first event stream is selected.
@@ -120,6 +256,20 @@ class Decoder(stream.Stream):
 
     t0 is only used if the file type is ES.
 
+    normalize_coordinates replaces the integer pixel coordinates with x / width and y / height (float32 in [0, 1)).
+    The resulting arrays have the dtype faery.NORMALIZED_DVS_DTYPE, which is not supported by filters and encoders.
+
+    record_stride pads each event to the given number of bytes (for instance 16 for aligned SIMD loads).
+    Fields keep their offsets and padding bytes are zero. Padded arrays are not supported by filters and encoders.
+
+    coalesce_packets concatenates consecutive native packets into a single array per iteration,
+    up to coalesce_max_events events (unbounded if None). Native packets are never split.
+
+    reverse yields packets last-to-first, with events in descending timestamp order within each packet.
+    None of the supported formats can be read backwards, hence the whole stream is loaded in memory
+    before the first packet is returned. Filters that assume increasing timestamps (for instance time_slice)
+    and encoders do not support reversed streams.
+
     Args:
         path: Path of the input event file.
         track_id: Stream ID, only used with aedat files. Defaults to None.
@@ -127,6 +277,11 @@ class Decoder(stream.Stream):
         version_fallback: Version fallback for EVT (.raw) and DAT files. Defaults to "dat2" for DAT and "evt3" for EVT.
         t0: Initial time for ES files, in seconds. Defaults to None.
         file_type: Override the type determination algorithm. Defaults to None.
+        normalize_coordinates: Whether to divide coordinates by the sensor dimensions. Defaults to False.
+        record_stride: Size of each event in bytes, None for tight packing. Defaults to None.
+        coalesce_packets: Whether to combine consecutive native packets. Defaults to False.
+        coalesce_max_events: Maximum number of events per combined packet, None for no limit. Defaults to None.
+        reverse: Whether to yield events in descending timestamp order. Defaults to False.
     """
 
     def __init__(
@@ -139,9 +294,19 @@ class Decoder(stream.Stream):
         ] = None,
         t0: common.Time = 0,
         file_type: typing.Optional[common.FileType] = None,
+        normalize_coordinates: bool = False,
+        record_stride: typing.Optional[int] = None,
+        coalesce_packets: bool = False,
+        coalesce_max_events: typing.Optional[int] = None,
+        reverse: bool = False,
     ):
         super().__init__()
         self.path = pathlib.Path(path)
+        self.normalize_coordinates = normalize_coordinates
+        self.record_stride = record_stride
+        self.coalesce_packets = coalesce_packets
+        self.coalesce_max_events = coalesce_max_events
+        self.reverse = reverse
         self.track_id = track_id
         self.dimensions_fallback = dimensions_fallback
         self.version_fallback = version_fallback
@@ -152,8 +317,14 @@ class Decoder(stream.Stream):
         self.inner_dimensions: tuple[int, int]
         self.event_type: typing.Optional[str] = None
         self._time_range_us: typing.Optional[tuple[int, int]] = None
+        self.legacy_aedat = False
         if self.file_type == common.FileType.AEDAT:
-            with aedat.Decoder(self.path) as decoder:
+            self.legacy_aedat = is_legacy_aedat(self.path)
+            with (
+                aedat.LegacyDecoder(self.path)
+                if self.legacy_aedat
+                else aedat.Decoder(self.path)
+            ) as decoder:
                 found = False
                 for track in decoder.tracks():
                     if self.track_id is None:
@@ -218,6 +389,12 @@ class Decoder(stream.Stream):
                 self.inner_dimensions = decoder.dimensions
         else:
             raise Exception(f"file type {self.file_type} not implemented")
+        if self.normalize_coordinates and (
+            self.inner_dimensions[0] == 0 or self.inner_dimensions[1] == 0
+        ):
+            raise Exception(
+                f"normalize_coordinates requires known dimensions (got {self.inner_dimensions})"
+            )
 
     def dimensions(self) -> tuple[int, int]:
         return self.inner_dimensions
@@ -228,9 +405,10 @@ class Decoder(stream.Stream):
             end: typing.Optional[int] = None
             for events in self:
                 if len(events) > 0:
-                    if begin is None:
-                        begin = events["t"][0]
-                    end = events["t"][-1]
+                    first_t = events["t"][-1] if self.reverse else events["t"][0]
+                    last_t = events["t"][0] if self.reverse else events["t"][-1]
+                    begin = first_t if begin is None else min(begin, first_t)
+                    end = last_t if end is None else max(end, last_t)
             if begin is None or end is None:
                 self._time_range_us = (0, 1)
             else:
@@ -239,7 +417,11 @@ class Decoder(stream.Stream):
 
     def __iter__(self) -> stream.StreamIterator:
         if self.file_type == common.FileType.AEDAT:
-            inner = aedat.Decoder(self.path)
+            assert self.track_id is not None
+            if self.legacy_aedat:
+                inner = aedat.LegacyDecoder(self.path)
+            else:
+                inner = aedat.Decoder(self.path, track_ids=[self.track_id])
         elif self.file_type == common.FileType.DAT:
             inner = dat.Decoder(self.path, self.dimensions_fallback, self.version_fallback)  # type: ignore
         elif self.file_type == common.FileType.ES:
@@ -254,4 +436,151 @@ class Decoder(stream.Stream):
             is_atis=self.event_type == "atis",
             dimensions=self.inner_dimensions,
             inner=inner,
+            normalize_coordinates=self.normalize_coordinates,
+            record_stride=self.record_stride,
+            coalesce_packets=self.coalesce_packets,
+            coalesce_max_events=self.coalesce_max_events,
+            reverse=self.reverse,
+        )
+
+
+def read_packets(decoder: Decoder, output: queue.Queue, stop: threading.Event):
+    """Reads the decoder's packets into output until the end of the file or until stop is set.
+
+    The last item is None at the end of the file, or the exception raised by the decoder.
+    stop is checked before each put, hence a reader adds at most one item after its queue
+    is drained by MergeDecoderIterator.close.
+    """
+    iterator = decoder.__iter__()
+    try:
+        for events in iterator:
+            if stop.is_set():
+                return
+            if len(events) > 0:
+                output.put(events)
+        if not stop.is_set():
+            output.put(None)
+    except BaseException as exception:
+        if not stop.is_set():
+            output.put(exception)
+    finally:
+        iterator.close()
+
+
+class MergeDecoderIterator(stream.StreamIterator):
+    def __init__(self, decoders: list[Decoder], buffer_size: int):
+        self.stop = threading.Event()
+        self.queues: list[queue.Queue] = [
+            queue.Queue(maxsize=buffer_size) for _ in decoders
+        ]
+        self.buffers: list[typing.Optional[numpy.ndarray]] = [None for _ in decoders]
+        self.done = [False for _ in decoders]
+        # the threads do not reference the iterator, which can be garbage-collected (and closed) while they run
+        self.threads = [
+            threading.Thread(
+                target=read_packets,
+                args=(decoder, self.queues[index], self.stop),
+                daemon=True,
+            )
+            for index, decoder in enumerate(decoders)
+        ]
+        for thread in self.threads:
+            thread.start()
+
+    def __next__(self) -> tuple[int, numpy.ndarray]:  # type: ignore[override]
+        while True:
+            # block on every file that has no buffered events, including lagging ones,
+            # since their next packet may precede the events buffered for other files
+            for index, buffer in enumerate(self.buffers):
+                if buffer is None and not self.done[index]:
+                    packet = self.queues[index].get()
+                    if packet is None:
+                        self.done[index] = True
+                    elif isinstance(packet, BaseException):
+                        self.close()
+                        raise packet
+                    else:
+                        self.buffers[index] = packet
+            candidates = [
+                index for index, buffer in enumerate(self.buffers) if buffer is not None
+            ]
+            if len(candidates) == 0:
+                self.close()
+                raise StopIteration()
+            first = min(candidates, key=lambda index: self.buffers[index]["t"][0])  # type: ignore
+            buffer = self.buffers[first]
+            assert buffer is not None
+            others = [
+                self.buffers[index]["t"][0]  # type: ignore
+                for index in candidates
+                if index != first
+            ]
+            if len(others) == 0:
+                self.buffers[first] = None
+                return (first, buffer)
+            end = numpy.searchsorted(buffer["t"], min(others), side="right")
+            if end == len(buffer):
+                self.buffers[first] = None
+                return (first, buffer)
+            self.buffers[first] = buffer[end:]
+            return (first, buffer[:end])
+
+    def __enter__(self) -> "MergeDecoderIterator":
+        return self
+
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool:
+        self.close()
+        return False
+
+    def __del__(self):
+        self.close()
+
+    def close(self):
+        """
+        Stops and joins the reader threads, the iterator is exhausted afterwards.
+        """
+        self.stop.set()
+        for output in self.queues:
+            while True:
+                try:
+                    output.get_nowait()
+                except queue.Empty:
+                    break
+        for thread in self.threads:
+            thread.join()
+        self.buffers = [None for _ in self.buffers]
+        self.done = [True for _ in self.done]
+
+
+class MergeDecoder:
+    """Reads several event files concurrently and yields their events in global time order.
+
+    Each file is read by its own thread, which buffers at most buffer_size packets ahead of the consumer.
+    The iterator yields (file_index, events) tuples, where file_index is the position of the events' file in paths.
+    Events from a given file keep their order and the timestamps of successive tuples never decrease.
+    Use the iterator as a context manager (or call its close method) to stop the threads
+    when the files are not read to the end.
+
+    Args:
+        paths: Paths of the input event files.
+        buffer_size: Maximum number of packets buffered per file. Defaults to 4.
+    """
+
+    def __init__(
+        self,
+        paths: list[typing.Union[pathlib.Path, str]],
+        buffer_size: int = 4,
+    ):
+        assert buffer_size > 0
+        self.decoders = [Decoder(path) for path in paths]
+        self.buffer_size = buffer_size
+
+    def __iter__(self) -> MergeDecoderIterator:
+        return MergeDecoderIterator(
+            decoders=self.decoders, buffer_size=self.buffer_size
         )
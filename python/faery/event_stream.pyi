@@ -5,13 +5,73 @@ import typing
 import types
 
 import numpy
+import numpy.typing
+
+class AsyncDecoder:
+    def __aiter__(self) -> AsyncDecoder: ...
+    async def __anext__(
+        self,
+    ) -> typing.Union[
+        numpy.ndarray, dict[typing.Literal["t", "offsets", "bytes"], numpy.ndarray]
+    ]: ...
+    def next_packet(
+        self,
+    ) -> typing.Union[
+        numpy.ndarray, dict[typing.Literal["t", "offsets", "bytes"], numpy.ndarray]
+    ]: ...
 
 class Decoder:
     version: str
     event_type: typing.Literal["generic", "dvs", "atis", "color"]
+    declared_type: typing.Literal["generic", "dvs", "atis", "color"]
     dimensions: typing.Optional[tuple[int, int]]
+    width: typing.Optional[int]
+    height: typing.Optional[int]
 
-    def __init__(self, path: typing.Union[pathlib.Path, str], t0: int): ...
+    def __init__(
+        self,
+        path: typing.Union[pathlib.Path, str, typing.BinaryIO],
+        t0: int,
+        skip_empty: bool = True,
+        ragged: bool = False,
+        roi: typing.Optional[tuple[int, int, int, int]] = None,
+        downsample: int = 1,
+        flip_x: bool = False,
+        flip_y: bool = False,
+        rotate: typing.Literal[0, 90, 180, 270] = 0,
+        field_names: typing.Optional[dict[str, str]] = None,
+        t_dtype: typing.Literal["uint64", "int64"] = "uint64",
+        xy_dtype: typing.Literal["uint16", "uint8"] = "uint16",
+        split_atis: bool = False,
+        soa: bool = False,
+        mask_pixels: typing.Optional[list[tuple[int, int]]] = None,
+        remap: typing.Optional[numpy.ndarray] = None,
+        median_denoise: typing.Optional[int] = None,
+        override_type: typing.Optional[typing.Literal["dvs", "atis", "color"]] = None,
+        buffer_size: int = 65536,
+    ): ...
+    @staticmethod
+    def from_bytes(
+        data: bytes,
+        t0: int,
+        skip_empty: bool = True,
+        ragged: bool = False,
+        roi: typing.Optional[tuple[int, int, int, int]] = None,
+        downsample: int = 1,
+        flip_x: bool = False,
+        flip_y: bool = False,
+        rotate: typing.Literal[0, 90, 180, 270] = 0,
+        field_names: typing.Optional[dict[str, str]] = None,
+        t_dtype: typing.Literal["uint64", "int64"] = "uint64",
+        xy_dtype: typing.Literal["uint16", "uint8"] = "uint16",
+        split_atis: bool = False,
+        soa: bool = False,
+        mask_pixels: typing.Optional[list[tuple[int, int]]] = None,
+        remap: typing.Optional[numpy.ndarray] = None,
+        median_denoise: typing.Optional[int] = None,
+        override_type: typing.Optional[typing.Literal["dvs", "atis", "color"]] = None,
+        buffer_size: int = 65536,
+    ) -> Decoder: ...
     def __enter__(self) -> Decoder: ...
     def __exit__(
         self,
@@ -20,9 +80,165 @@ class Decoder:
         traceback: typing.Optional[types.TracebackType],
     ) -> bool: ...
     def __iter__(self) -> Decoder: ...
+    def __next__(
+        self,
+    ) -> typing.Union[
+        numpy.ndarray,
+        dict[typing.Literal["t", "offsets", "bytes"], numpy.ndarray],
+        dict[typing.Literal["cd", "exposure"], numpy.ndarray],
+        dict[str, numpy.ndarray],
+    ]: ...
+    def __aiter__(self) -> AsyncDecoder: ...
+    def enumerate(self) -> EnumeratedDecoder: ...
+    def duration(self) -> int: ...
+    def event_count(self) -> int: ...
+    def statistics(
+        self,
+    ) -> dict[
+        typing.Literal[
+            "event_count",
+            "on_count",
+            "off_count",
+            "duration",
+            "mean_rate",
+            "peak_events_per_ms",
+            "active_pixels",
+            "bounding_box",
+        ],
+        typing.Any,
+    ]: ...
+    def tell(self) -> int: ...
+    def seek_bytes(self, offset: int) -> None: ...
+    def reset(self) -> None: ...
+    def time_histogram(
+        self, bin_us: int, polarity: bool = False
+    ) -> numpy.typing.NDArray[numpy.int64]: ...
+    def clock_glitches(
+        self, threshold_us: int
+    ) -> numpy.typing.NDArray[numpy.uint64]: ...
+    def set_progress_callback(
+        self, callback: typing.Optional[typing.Callable[[int, int], None]]
+    ) -> None: ...
+    def to_array(self) -> numpy.ndarray: ...
+    def to_csv(
+        self, path: typing.Union[pathlib.Path, str], signed_polarity: bool = False
+    ): ...
+    def save_npy(self, path: typing.Union[pathlib.Path, str]): ...
+    def to_dataframe(self) -> typing.Any: ...
+    def to_torch(
+        self,
+    ) -> dict[typing.Literal["t", "x", "y", "polarity"], typing.Any]: ...
+    def hot_pixel_filter(self, threshold_hz: float) -> HotPixelFilter: ...
+    def refractory_filter(self, period_us: int) -> RefractoryFilter: ...
+    def background_activity_filter(self, dt_us: int) -> BackgroundActivityFilter: ...
+    def chunks(self, duration_us: int) -> TimeChunks: ...
+    def chunks_by_count(self, count: int) -> CountChunks: ...
+    def atis_frames(self, frame_duration_us: int, k: float = 25500.0) -> AtisFrames: ...
+
+class HotPixelFilter:
+    hot_pixels: list[tuple[int, int]]
+
+    def __enter__(self) -> HotPixelFilter: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> HotPixelFilter: ...
+    def __next__(self) -> numpy.ndarray: ...
+    def enumerate(self) -> EnumeratedDecoder: ...
+    def reset(self) -> None: ...
+
+class RefractoryFilter:
+    def __enter__(self) -> RefractoryFilter: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> RefractoryFilter: ...
+    def __next__(self) -> numpy.ndarray: ...
+    def enumerate(self) -> EnumeratedDecoder: ...
+    def reset(self) -> None: ...
+
+class BackgroundActivityFilter:
+    def __enter__(self) -> BackgroundActivityFilter: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> BackgroundActivityFilter: ...
+    def __next__(self) -> numpy.ndarray: ...
+    def enumerate(self) -> EnumeratedDecoder: ...
+    def reset(self) -> None: ...
+
+class EnumeratedDecoder:
+    def __iter__(self) -> EnumeratedDecoder: ...
+    def __next__(
+        self,
+    ) -> tuple[
+        int,
+        typing.Union[
+            numpy.ndarray,
+            dict[typing.Literal["t", "offsets", "bytes"], numpy.ndarray],
+            dict[typing.Literal["cd", "exposure"], numpy.ndarray],
+            dict[str, numpy.ndarray],
+        ],
+    ]: ...
+
+class TimeChunks:
+    def __enter__(self) -> TimeChunks: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> TimeChunks: ...
+    def __next__(self) -> numpy.ndarray: ...
+
+class CountChunks:
+    def __enter__(self) -> CountChunks: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> CountChunks: ...
+    def __next__(self) -> numpy.ndarray: ...
+
+class AtisFrames:
+    def __enter__(self) -> AtisFrames: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> AtisFrames: ...
+    def __next__(self) -> tuple[int, numpy.typing.NDArray[numpy.uint8]]: ...
+
+class Merge:
+    dimensions: tuple[int, int]
+
+    def __enter__(self) -> Merge: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def __iter__(self) -> Merge: ...
     def __next__(self) -> numpy.ndarray: ...
 
 class Encoder:
+    closed: bool
+
     @typing.overload
     def __init__(
         self,
@@ -64,3 +280,11 @@ class Encoder:
     ) -> bool: ...
     def t0(self) -> typing.Optional[int]: ...
     def write(self, packet: numpy.ndarray): ...
+    def close(self): ...
+
+def decode_many(
+    paths: list[typing.Union[pathlib.Path, str]],
+    workers: int = 0,
+    t0: int = 0,
+) -> list[numpy.ndarray]: ...
+def merge(decoders: list[Decoder], packet_size: int = 65536) -> Merge: ...
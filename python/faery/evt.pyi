@@ -9,14 +9,19 @@ import numpy
 class Decoder:
     version: typing.Literal["evt2", "evt2.1", "evt3"]
     dimensions: tuple[int, int]
+    width: int
+    height: int
+    dimensions_source: typing.Literal["header", "database", "fallback"]
 
     def __init__(
         self,
-        path: typing.Union[pathlib.Path, str],
+        path: typing.Union[pathlib.Path, str, typing.BinaryIO],
         dimensions_fallback: typing.Optional[tuple[int, int]] = None,
         version_fallback: typing.Optional[
             typing.Literal["evt2", "evt2.1", "evt3"]
         ] = None,
+        skip_empty: bool = True,
+        buffer_size: int = 65536,
     ): ...
     def __enter__(self) -> Decoder: ...
     def __exit__(
@@ -27,6 +32,14 @@ class Decoder:
     ) -> bool: ...
     def __iter__(self) -> Decoder: ...
     def __next__(self) -> dict[typing.Literal["events", "triggers"], numpy.ndarray]: ...
+    def enumerate(self) -> EnumeratedDecoder: ...
+    def reset(self) -> None: ...
+
+class EnumeratedDecoder:
+    def __iter__(self) -> EnumeratedDecoder: ...
+    def __next__(
+        self,
+    ) -> tuple[int, dict[typing.Literal["events", "triggers"], numpy.ndarray]]: ...
 
 class Encoder:
     def __init__(
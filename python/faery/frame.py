@@ -1,4 +1,5 @@
 import collections.abc
+import pathlib
 import types
 import typing
 
@@ -10,19 +11,26 @@ from . import stream
 
 
 if typing.TYPE_CHECKING:
+    from . import event_stream  # type: ignore
     from . import render  # type: ignore
 else:
-    from . import render
+    from .faery import event_stream
+    from .faery import render
 
 
 class FrameFloat64:
     """
     A frame with one channel per pixel, with values are in the range [-1, 1]
+
+    staleness is only set if the frame was rendered with staleness=True,
+    it contains the time elapsed since each pixel's last update, in µs
+    (the maximum uint64 value for pixels that have not been updated yet)
     """
 
     index: int
     timecode: str
     pixels: numpy.typing.NDArray[numpy.float64]
+    staleness: typing.Optional[numpy.typing.NDArray[numpy.uint64]]
 
 
 class FrameRgba8888:
@@ -195,6 +203,44 @@ class FrameStreamRgb888:
         return False
 
 
+class RenderIterator(FrameStreamIteratorFloat64):
+    def __init__(
+        self,
+        parent: stream.StreamIterator,
+        inner: render.RenderIterator,
+        frames_times_us: range,
+        staleness: bool,
+        colormap: typing.Optional[str],
+    ):
+        super().__init__()
+        self.parent = parent
+        self.inner = inner
+        self.frames_times_us = frames_times_us
+        self.staleness = staleness
+        self.colormap = colormap
+        self.index = 0
+
+    def __next__(self) -> FrameFloat64:
+        result = self.inner.__next__()
+        # colormapped pixels are RGB, the frame class only matters for documentation
+        frame = FrameFloat64() if self.colormap is None else FrameRgb888()
+        frame.index = self.index
+        frame.timecode = common.timestamp_to_timecode(
+            self.frames_times_us[self.index]
+        )
+        if self.staleness:
+            frame.pixels, frame.staleness = result  # type: ignore
+        else:
+            frame.pixels = result
+            frame.staleness = None  # type: ignore
+        self.index += 1
+        return frame  # type: ignore
+
+    def close(self):
+        self.inner.close()
+        self.parent.close()
+
+
 class Render(FrameStreamFloat64):
     def __init__(
         self,
@@ -204,9 +250,14 @@ class Render(FrameStreamFloat64):
             "exponential",
             "linear",
             "step",
+            "time_encoded",
         ],
         tau: common.Time,
         ignore_polarity: bool,
+        staleness: bool = False,
+        colormap: typing.Optional[
+            typing.Literal["polarity", "gray", "viridis", "jet"]
+        ] = None,
     ):
         super().__init__()
         self.parent = parent
@@ -214,6 +265,8 @@ class Render(FrameStreamFloat64):
         self.decay = decay
         self.tau = common.parse_timestamp(tau)
         self.ignore_polarity = ignore_polarity
+        self.staleness = staleness
+        self.colormap = colormap
 
     def dimensions(self) -> tuple[int, int]:
         return self.parent.dimensions()
@@ -227,11 +280,197 @@ class Render(FrameStreamFloat64):
         )
 
     def __iter__(self) -> FrameStreamIteratorFloat64:
-        iterator = render.RenderIterator(
-            parent=self.parent.__iter__(),
+        frames_times_us = self.frames_times_us()
+        assert isinstance(frames_times_us, range)
+        parent = self.parent.__iter__()
+        return RenderIterator(
+            parent=parent,
+            inner=render.RenderIterator(
+                parent=parent,
+                dimensions=self.dimensions(),
+                next_frame_t=frames_times_us.start,
+                frame_duration=self.frame_duration,
+                frame_count=len(frames_times_us),
+                decay=self.decay,
+                tau=self.tau,
+                ignore_polarity=self.ignore_polarity,
+                staleness=self.staleness,
+                colormap=self.colormap,
+            ),
+            frames_times_us=frames_times_us,
+            staleness=self.staleness,
+            colormap=self.colormap,
+        )
+
+
+BAYER_PATTERNS: dict[str, tuple[tuple[int, int], tuple[int, int]]] = {
+    "RGGB": ((0, 1), (1, 2)),
+    "BGGR": ((2, 1), (1, 0)),
+    "GRBG": ((1, 0), (2, 1)),
+    "GBRG": ((1, 2), (0, 1)),
+}
+
+
+def demosaic_bilinear(
+    mosaic: numpy.ndarray, channels: numpy.ndarray
+) -> numpy.typing.NDArray[numpy.uint8]:
+    """
+    Interpolates missing channels with a normalized 3x3 bilinear kernel.
+
+    Args:
+        mosaic: Per-pixel values with shape (height, width).
+        channels: Per-pixel channel index (0 for red, 1 for green, 2 for blue) with shape (height, width).
+
+    Returns:
+        numpy.typing.NDArray[numpy.uint8]: RGB pixels with shape (height, width, 3).
+    """
+    height, width = mosaic.shape
+    kernel = numpy.array([[1.0, 2.0, 1.0], [2.0, 4.0, 2.0], [1.0, 2.0, 1.0]])
+    pixels = numpy.zeros((height, width, 3), dtype=numpy.uint8)
+    for channel in range(3):
+        mask = (channels == channel).astype(numpy.float64)
+        known = numpy.pad(mosaic * mask, 1)
+        padded_mask = numpy.pad(mask, 1)
+        values = numpy.zeros((height, width), dtype=numpy.float64)
+        weights = numpy.zeros((height, width), dtype=numpy.float64)
+        for row in range(3):
+            for column in range(3):
+                values += (
+                    kernel[row, column]
+                    * known[row : row + height, column : column + width]
+                )
+                weights += (
+                    kernel[row, column]
+                    * padded_mask[row : row + height, column : column + width]
+                )
+        pixels[:, :, channel] = numpy.round(
+            numpy.divide(
+                values, weights, out=numpy.zeros_like(values), where=weights > 0
+            )
+        ).astype(numpy.uint8)
+    return pixels
+
+
+class ColorRenderIterator(FrameStreamIteratorRgb888):
+    def __init__(
+        self,
+        decoder: event_stream.Decoder,
+        frame_duration: int,
+        bayer_pattern: typing.Literal["RGGB", "BGGR", "GRBG", "GBRG"],
+    ):
+        super().__init__()
+        self.decoder = decoder
+        self.frame_duration = frame_duration
+        assert decoder.dimensions is not None
+        width, height = decoder.dimensions
+        self.channels = numpy.tile(
+            numpy.array(BAYER_PATTERNS[bayer_pattern], dtype=numpy.uint8),
+            ((height + 1) // 2, (width + 1) // 2),
+        )[:height, :width]
+        self.mosaic = numpy.zeros((height, width), dtype=numpy.float64)
+        self.next_frame_t: typing.Optional[int] = None
+        self.index = 0
+        self.buffer: typing.Optional[numpy.ndarray] = None
+        self.done = False
+
+    def frame(self) -> FrameRgb888:
+        assert self.next_frame_t is not None
+        frame = FrameRgb888()
+        frame.index = self.index
+        frame.timecode = common.timestamp_to_timecode(self.next_frame_t)
+        frame.pixels = demosaic_bilinear(self.mosaic, self.channels)
+        self.index += 1
+        self.next_frame_t += self.frame_duration
+        return frame
+
+    def accumulate(self, events: numpy.ndarray):
+        rows = self.mosaic.shape[0] - 1 - events["y"].astype(numpy.int64)
+        columns = events["x"].astype(numpy.int64)
+        channels = self.channels[rows, columns]
+        values = numpy.stack(
+            (
+                events["r"].view(numpy.uint8),
+                events["g"].view(numpy.uint8),
+                events["b"].view(numpy.uint8),
+            )
+        )
+        # later events overwrite earlier ones at the same pixel
+        self.mosaic[rows, columns] = values[channels, numpy.arange(len(events))]
+
+    def __next__(self) -> FrameRgb888:
+        if self.done:
+            raise StopIteration()
+        while True:
+            if self.buffer is None:
+                try:
+                    self.buffer = self.decoder.__next__()
+                except StopIteration:
+                    self.done = True
+                    self.close()
+                    if self.next_frame_t is None:
+                        raise StopIteration()
+                    return self.frame()
+            events = self.buffer
+            if len(events) == 0:
+                self.buffer = None
+                continue
+            if self.next_frame_t is None:
+                self.next_frame_t = int(events["t"][0]) + self.frame_duration
+            end = numpy.searchsorted(events["t"], self.next_frame_t)
+            self.accumulate(events[:end])
+            if end < len(events):
+                self.buffer = events[end:]
+                return self.frame()
+            self.buffer = None
+
+    def close(self):
+        if self.decoder is not None:
+            self.decoder.__exit__(None, None, None)
+            self.decoder = None  # type: ignore
+
+
+class ColorRender(FrameStreamRgb888):
+    """Renders a color event stream (.es) into demosaiced RGB frames.
+
+    Each event is assigned to the channel of its pixel in the Bayer pattern, the pattern's
+    first row is the top row of the frame.
+
+    Args:
+        path: Path of the input .es file, which must contain color events.
+        frame_duration: Time between two frames.
+        bayer_pattern: Color filter layout of the top-left 2x2 block. Defaults to "RGGB".
+        t0: Initial time, in seconds. Defaults to 0.
+    """
+
+    def __init__(
+        self,
+        path: typing.Union[pathlib.Path, str],
+        frame_duration: common.Time,
+        bayer_pattern: typing.Literal["RGGB", "BGGR", "GRBG", "GBRG"] = "RGGB",
+        t0: common.Time = 0,
+    ):
+        super().__init__()
+        assert (
+            bayer_pattern in BAYER_PATTERNS
+        ), f"unknown bayer pattern {bayer_pattern}"
+        self.path = pathlib.Path(path)
+        self.frame_duration = common.parse_timestamp(frame_duration)
+        self.bayer_pattern = bayer_pattern
+        self.t0 = common.parse_timestamp(t0)
+        with event_stream.Decoder(path=self.path, t0=self.t0) as decoder:
+            if decoder.event_type != "color":
+                raise Exception(
+                    f'the stream "{self.path}" has the type "{decoder.event_type}" (expected "color")'
+                )
+            assert decoder.dimensions is not None
+            self.inner_dimensions = decoder.dimensions
+
+    def dimensions(self) -> tuple[int, int]:
+        return self.inner_dimensions
+
+    def __iter__(self) -> ColorRenderIterator:
+        return ColorRenderIterator(
+            decoder=event_stream.Decoder(path=self.path, t0=self.t0),
             frame_duration=self.frame_duration,
-            decay=self.decay,  # type: ignore
-            tau=self.tau,
-            ignore_polarity=self.ignore_polarity,
+            bayer_pattern=self.bayer_pattern,  # type: ignore
         )
-        return iterator  # type: ignore
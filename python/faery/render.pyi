@@ -16,10 +16,51 @@ class RenderIterator:
             "exponential",
             "linear",
             "step",
+            "time_encoded",
         ],
         tau: int,
-
+        ignore_polarity: bool = False,
+        staleness: bool = False,
+        colormap: typing.Optional[
+            typing.Literal["polarity", "gray", "viridis", "jet"]
+        ] = None,
     ): ...
     def __iter__(self) -> RenderIterator: ...
-    def __next__(self) -> numpy.ndarray: ...
+    def __next__(
+        self,
+    ) -> typing.Union[
+        numpy.typing.NDArray[numpy.float64],
+        numpy.typing.NDArray[numpy.uint8],
+        tuple[numpy.typing.NDArray[numpy.float64], numpy.typing.NDArray[numpy.uint64]],
+        tuple[numpy.typing.NDArray[numpy.uint8], numpy.typing.NDArray[numpy.uint64]],
+    ]: ...
+    def close(self): ...
+
+class TimeSurfaceIterator:
+    def __init__(
+        self,
+        parent: collections.abc.Iterable[numpy.ndarray],
+        dimensions: tuple[int, int],
+        next_frame_t: int,
+        frame_duration: int,
+        frame_count: int,
+        tau: int,
+        split_polarities: bool = True,
+    ): ...
+    def __iter__(self) -> TimeSurfaceIterator: ...
+    def __next__(self) -> numpy.typing.NDArray[numpy.float64]: ...
+    def close(self): ...
+
+class CountImageIterator:
+    def __init__(
+        self,
+        parent: collections.abc.Iterable[numpy.ndarray],
+        dimensions: tuple[int, int],
+        next_frame_t: int,
+        frame_duration: int,
+        frame_count: int,
+        split_polarities: bool = False,
+    ): ...
+    def __iter__(self) -> CountImageIterator: ...
+    def __next__(self) -> numpy.typing.NDArray[numpy.int32]: ...
     def close(self): ...
@@ -7,6 +7,7 @@ import types
 import typing
 
 import numpy
+import numpy.typing
 
 from . import common
 from . import encoder
@@ -19,16 +20,22 @@ if typing.TYPE_CHECKING:
     from . import evt  # type: ignore
     from . import filter
     from . import frame
+    from . import render  # type: ignore
 else:
     from .faery import aedat
     from .faery import dat
     from .faery import event_stream
     from .faery import evt
+    from .faery import render
 
 DVS_DTYPE: numpy.dtype = numpy.dtype(
     [("t", "<u8"), ("x", "<u2"), ("y", "<u2"), (("p", "on"), "?")]
 )
 
+NORMALIZED_DVS_DTYPE: numpy.dtype = numpy.dtype(
+    [("t", "<u8"), ("x", "<f4"), ("y", "<f4"), (("p", "on"), "?")]
+)
+
 
 @dataclasses.dataclass
 class Box:
@@ -96,6 +103,74 @@ class Stream:
         start, end = self.time_range_us()
         return (common.timestamp_to_timecode(start), common.timestamp_to_timecode(end))
 
+    def active_range_us(
+        self, min_rate: float, window: common.Time = 0.01
+    ) -> tuple[int, int]:
+        """
+        Time range spanning the first to the last window whose event rate is at least min_rate.
+
+        Windows are aligned on multiples of window (t = 0 is the start of the first window).
+
+        Args:
+            min_rate: Minimum event rate, in events per second.
+            window: Duration of the windows used to estimate the event rate. Defaults to 0.01 (10 ms).
+
+        Returns:
+            tuple[int, int]: Start of the first active window and end of the last active window, in µs.
+        """
+        window_us = common.parse_timestamp(window)
+        assert window_us > 0
+        min_count = min_rate * window_us / 1e6
+        first: typing.Optional[int] = None
+        last: typing.Optional[int] = None
+        # the last window of a packet may continue in the next packet
+        pending_index: typing.Optional[int] = None
+        pending_count = 0
+        for events in self:
+            if len(events) == 0:
+                continue
+            indices, counts = numpy.unique(
+                events["t"] // window_us, return_counts=True
+            )
+            if pending_index is not None:
+                if pending_index == indices[0]:
+                    counts[0] += pending_count
+                else:
+                    indices = numpy.concatenate(
+                        (numpy.array([pending_index], dtype=indices.dtype), indices)
+                    )
+                    counts = numpy.concatenate(
+                        (numpy.array([pending_count], dtype=counts.dtype), counts)
+                    )
+            active = numpy.flatnonzero(counts[:-1] >= min_count)
+            if len(active) > 0:
+                if first is None:
+                    first = int(indices[active[0]])
+                last = int(indices[active[-1]])
+            pending_index = int(indices[-1])
+            pending_count = int(counts[-1])
+        if pending_index is not None and pending_count >= min_count:
+            if first is None:
+                first = pending_index
+            last = pending_index
+        if first is None or last is None:
+            raise Exception(f"no window reaches {min_rate} events per second")
+        return (first * window_us, (last + 1) * window_us)
+
+    def trim(self, min_rate: float, window: common.Time = 0.01) -> "filter.TimeSlice":
+        """
+        Removes the inactive periods at the beginning and at the end of the stream.
+
+        Args:
+            min_rate: Minimum event rate, in events per second.
+            window: Duration of the windows used to estimate the event rate. Defaults to 0.01 (10 ms).
+        """
+        start, end = self.active_range_us(min_rate=min_rate, window=window)
+        return self.time_slice(
+            start=common.timestamp_to_timecode(start),
+            end=common.timestamp_to_timecode(end),
+        )
+
     def __iter__(self) -> StreamIterator:
         raise NotImplementedError()
 
@@ -219,6 +294,58 @@ class Stream:
     def render(self) -> "frame.Render":
         pass
 
+    def image_at(
+        self,
+        t: common.Time,
+        window: common.Time,
+        decay: typing.Literal[
+            "exponential",
+            "linear",
+            "step",
+            "time_encoded",
+        ],
+        tau: common.Time,
+        ignore_polarity: bool = False,
+    ) -> numpy.typing.NDArray[numpy.float64]:
+        """
+        Renders a single decayed image at t from the events in [t - window, t).
+
+        Events at t are excluded, like in the frames of frame.Render, hence the image is equal to
+        the rendered frame at t if window is long enough for older events to have fully decayed.
+        The image is empty (all zeros) if t is before the first event.
+
+        Args:
+            t: Timestamp of the image.
+            window: Duration of the time slice that precedes t.
+            decay: Decay function applied to each pixel's last event.
+            tau: Decay time constant.
+            ignore_polarity: Whether OFF events are rendered as positive values. Defaults to False.
+
+        Returns:
+            numpy.typing.NDArray[numpy.float64]: Pixels with shape (height, width) and values in [-1, 1].
+        """
+        t_us = common.parse_timestamp(t)
+        start_us = max(t_us - common.parse_timestamp(window), 0)
+        if start_us < t_us:
+            parent = self.time_slice(
+                start=common.timestamp_to_timecode(start_us),
+                end=common.timestamp_to_timecode(t_us),
+            ).__iter__()
+        else:
+            parent = iter(())
+        return next(
+            render.RenderIterator(
+                parent=parent,
+                dimensions=self.dimensions(),
+                next_frame_t=t_us,
+                frame_duration=1,
+                frame_count=1,
+                decay=decay,
+                tau=common.parse_timestamp(tau),
+                ignore_polarity=ignore_polarity,
+            )
+        )
+
 
 class ArrayIterator(StreamIterator):
     def __init__(self, events: numpy.ndarray):
@@ -239,7 +366,7 @@ class ArrayIterator(StreamIterator):
 class Array(Stream):
     def __init__(self, events: numpy.ndarray, dimensions: tuple[int, int]):
         super().__init__()
-        assert self.events.dtype == DVS_DTYPE
+        assert events.dtype == DVS_DTYPE
         self.events = events
         self.inner_dimensions = dimensions
 
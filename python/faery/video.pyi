@@ -0,0 +1,43 @@
+from __future__ import annotations
+
+import pathlib
+import typing
+import types
+
+import numpy
+import numpy.typing
+
+class VideoEncoder:
+    closed: bool
+
+    def __init__(
+        self,
+        path: typing.Union[pathlib.Path, str],
+        dimensions: tuple[int, int],
+        frame_rate: float,
+        crf: typing.Optional[float] = None,
+        bitrate_kbps: typing.Optional[int] = None,
+        preset: typing.Literal[
+            "ultrafast",
+            "superfast",
+            "veryfast",
+            "faster",
+            "fast",
+            "medium",
+            "slow",
+            "slower",
+            "veryslow",
+            "placebo",
+        ] = "medium",
+        chunk_frames: typing.Optional[int] = None,
+        workers: int = 0,
+    ): ...
+    def __enter__(self) -> VideoEncoder: ...
+    def __exit__(
+        self,
+        exception_type: typing.Optional[typing.Type[BaseException]],
+        value: typing.Optional[BaseException],
+        traceback: typing.Optional[types.TracebackType],
+    ) -> bool: ...
+    def write_frame(self, frame: numpy.typing.NDArray[numpy.uint8]): ...
+    def close(self): ...
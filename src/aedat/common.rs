@@ -65,21 +65,30 @@ pub mod triggers_generated;
 
 pub const MAGIC_NUMBER: &str = "#!AER-DAT4.0\r\n";
 
+/// Period of the timestamps written by producers that only keep the lower 32 bits of the clock.
+pub const WRAP_PERIOD: u64 = 1 << 32;
+
+// t_offset is a multiple of WRAP_PERIOD, it is only non-zero when timestamps are unwrapped
+// each track has its own state since tracks may be driven by independent clocks
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Track {
     Events {
         dimensions: (u16, u16),
         previous_t: u64,
+        t_offset: u64,
     },
     Frame {
         dimensions: (u16, u16),
         previous_t: u64,
+        t_offset: u64,
     },
     Imus {
         previous_t: u64,
+        t_offset: u64,
     },
     Triggers {
         previous_t: u64,
+        t_offset: u64,
     },
 }
 
@@ -105,6 +114,13 @@ pub enum Error {
         height: u16,
     },
 
+    #[error("the track \"{name}\" must have a non-zero width and height (got {width}x{height})")]
+    ZeroDimensions {
+        name: String,
+        width: u16,
+        height: u16,
+    },
+
     #[error("the width must be strictly smaller than {maximum} (got {value})")]
     Width { value: u16, maximum: u16 },
 
@@ -120,6 +136,13 @@ impl Track {
         match identifier {
             "EVTS" | "FRME" => match dimensions {
                 Some(dimensions) => {
+                    if dimensions.0 == 0 || dimensions.1 == 0 {
+                        return Err(Error::ZeroDimensions {
+                            name: identifier.to_owned(),
+                            width: dimensions.0,
+                            height: dimensions.1,
+                        });
+                    }
                     if dimensions.0 >= 32768 {
                         return Err(Error::Width {
                             value: dimensions.0,
@@ -136,11 +159,13 @@ impl Track {
                         Track::Events {
                             dimensions,
                             previous_t: 0,
+                            t_offset: 0,
                         }
                     } else {
                         Track::Frame {
                             dimensions,
                             previous_t: 0,
+                            t_offset: 0,
                         }
                     })
                 }
@@ -153,9 +178,15 @@ impl Track {
                     height: dimensions.1,
                 }),
                 None => Ok(if identifier == "IMUS" {
-                    Track::Imus { previous_t: 0 }
+                    Track::Imus {
+                        previous_t: 0,
+                        t_offset: 0,
+                    }
                 } else {
-                    Track::Triggers { previous_t: 0 }
+                    Track::Triggers {
+                        previous_t: 0,
+                        t_offset: 0,
+                    }
                 }),
             },
             identifier => Err(Error::UnknownIdentifier(identifier.to_owned())),
@@ -201,6 +232,106 @@ impl Track {
             Self::Triggers { .. } => None,
         }
     }
+
+    /// Returns the timestamp state (previous_t, t_offset) updated by next_t.
+    pub fn timestamp_state(&self) -> (u64, u64) {
+        match *self {
+            Self::Events {
+                previous_t,
+                t_offset,
+                ..
+            }
+            | Self::Frame {
+                previous_t,
+                t_offset,
+                ..
+            }
+            | Self::Imus {
+                previous_t,
+                t_offset,
+            }
+            | Self::Triggers {
+                previous_t,
+                t_offset,
+            } => (previous_t, t_offset),
+        }
+    }
+
+    pub fn set_timestamp_state(&mut self, state: (u64, u64)) {
+        match self {
+            Self::Events {
+                previous_t,
+                t_offset,
+                ..
+            }
+            | Self::Frame {
+                previous_t,
+                t_offset,
+                ..
+            }
+            | Self::Imus {
+                previous_t,
+                t_offset,
+            }
+            | Self::Triggers {
+                previous_t,
+                t_offset,
+            } => (*previous_t, *t_offset) = state,
+        }
+    }
+
+    pub fn reset_previous_t(&mut self) {
+        self.set_timestamp_state((0, 0));
+    }
+
+    /// Reads the timestamps of the first and last elements of a decompressed packet without decoding the other elements.
+    ///
+    /// Returns None if the packet is empty.
+    pub fn first_and_last_t(
+        &self,
+        buffer: &[u8],
+    ) -> Result<Option<(i64, i64)>, flatbuffers::InvalidFlatbuffer> {
+        Ok(match self {
+            Self::Events { .. } => events_generated::size_prefixed_root_as_event_packet(buffer)?
+                .elements()
+                .and_then(|events| {
+                    (!events.is_empty())
+                        .then(|| (events.get(0).t(), events.get(events.len() - 1).t()))
+                }),
+            Self::Frame { .. } => {
+                let t = frame_generated::size_prefixed_root_as_frame(buffer)?.t();
+                Some((t, t))
+            }
+            Self::Imus { .. } => imus_generated::size_prefixed_root_as_imu_packet(buffer)?
+                .elements()
+                .and_then(|imus| {
+                    (!imus.is_empty()).then(|| (imus.get(0).t(), imus.get(imus.len() - 1).t()))
+                }),
+            Self::Triggers { .. } => {
+                triggers_generated::size_prefixed_root_as_trigger_packet(buffer)?
+                    .elements()
+                    .and_then(|triggers| {
+                        (!triggers.is_empty())
+                            .then(|| (triggers.get(0).t(), triggers.get(triggers.len() - 1).t()))
+                    })
+            }
+        })
+    }
+}
+
+/// Converts a raw timestamp into a monotonic timestamp and updates the track state.
+///
+/// If unwrap_timestamps is true, a backward jump larger than half WRAP_PERIOD is interpreted as a wrap
+/// and shifts this timestamp and the following ones by WRAP_PERIOD. Other backward jumps are clamped.
+pub fn next_t(t: i64, previous_t: &mut u64, t_offset: &mut u64, unwrap_timestamps: bool) -> u64 {
+    let mut t = t.max(0) as u64 + *t_offset;
+    if unwrap_timestamps && t + WRAP_PERIOD / 2 < *previous_t {
+        *t_offset += WRAP_PERIOD;
+        t += WRAP_PERIOD;
+    }
+    let t = t.max(*previous_t);
+    *previous_t = t;
+    t
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -353,3 +484,78 @@ pub fn description_to_id_to_tracks(
         Ok(id_to_track)
     }
 }
+
+/// Camera information found in the description, missing fields are None (or empty).
+pub struct DeviceInfo {
+    pub device_name: Option<String>,
+    pub serial_number: Option<String>,
+    pub id_to_source: std::collections::BTreeMap<u32, String>,
+}
+
+/// Reads the "source" attribute of each track (for instance "DAVIS346_00000002").
+///
+/// The device name and serial number are read from the first source, split at its last underscore.
+/// Unlike description_to_id_to_tracks, this function ignores malformed nodes instead of returning an error.
+pub fn description_to_device_info(description: &str) -> DeviceInfo {
+    let mut device_info = DeviceInfo {
+        device_name: None,
+        serial_number: None,
+        id_to_source: std::collections::BTreeMap::new(),
+    };
+    let document = match roxmltree::Document::parse(description) {
+        Ok(document) => document,
+        Err(_) => return device_info,
+    };
+    let output_node = match document.root().first_child().and_then(|dv_node| {
+        dv_node.children().find(|node| {
+            node.is_element()
+                && node.has_tag_name("node")
+                && node.attribute("name") == Some("outInfo")
+        })
+    }) {
+        Some(content) => content,
+        None => return device_info,
+    };
+    for track_node in output_node.children() {
+        if !track_node.is_element() || !track_node.has_tag_name("node") {
+            continue;
+        }
+        let track_id = match track_node
+            .attribute("name")
+            .and_then(|name| name.parse::<u32>().ok())
+        {
+            Some(track_id) => track_id,
+            None => continue,
+        };
+        let source = track_node
+            .children()
+            .find(|node| {
+                node.is_element()
+                    && node.has_tag_name("node")
+                    && node.attribute("name") == Some("info")
+            })
+            .and_then(|info_node| {
+                info_node.children().find(|node| {
+                    node.is_element()
+                        && node.has_tag_name("attr")
+                        && node.attribute("key") == Some("source")
+                })
+            })
+            .and_then(|source_node| source_node.text());
+        if let Some(source) = source {
+            device_info.id_to_source.insert(track_id, source.to_owned());
+        }
+    }
+    if let Some(source) = device_info.id_to_source.values().next() {
+        match source.rsplit_once('_') {
+            Some((device_name, serial_number)) => {
+                device_info.device_name = Some(device_name.to_owned());
+                device_info.serial_number = Some(serial_number.to_owned());
+            }
+            None => {
+                device_info.device_name = Some(source.clone());
+            }
+        }
+    }
+    device_info
+}
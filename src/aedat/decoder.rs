@@ -1,6 +1,9 @@
 use std::io::Read;
+use std::io::Seek;
 
 use crate::aedat::common;
+use crate::aedat::endianness;
+use crate::utilities;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -16,27 +19,120 @@ pub enum Error {
     #[error(transparent)]
     Description(#[from] common::DescriptionError),
 
+    #[error(transparent)]
+    Endianness(#[from] endianness::Error),
+
     #[error("bad magic number (expected \"#!AER-DAT4.0\\r\\n\", got \"{0}\")")]
     MagicNumber(String),
 
     #[error("empty description")]
     EmptyDescription,
+
+    #[error("unknown track ID {0}")]
+    UnknownTrackId(u32),
+}
+
+/// Buffered reads, or a memory-mapped file whose uncompressed packets are parsed in place.
+enum Reader {
+    Buffered(std::io::BufReader<utilities::Source>),
+    Mapped(std::io::Cursor<memmap2::Mmap>),
+}
+
+impl Reader {
+    /// Maps files if mmap is true, other sources (and files that cannot be mapped) are read with a buffer of buffer_size bytes.
+    fn new(source: utilities::Source, mmap: bool, buffer_size: usize) -> Self {
+        if mmap {
+            if let utilities::Source::File { ref file, .. } = source {
+                // the mapping stays valid after the file is closed,
+                // but modifying the file while it is mapped is undefined behaviour
+                if let Ok(mapping) = unsafe { memmap2::Mmap::map(file) } {
+                    return Reader::Mapped(std::io::Cursor::new(mapping));
+                }
+            }
+        }
+        Reader::Buffered(std::io::BufReader::with_capacity(buffer_size, source))
+    }
+
+    /// Moves length bytes forward, forward-only sources (gzip, stdin, file-like objects) read and discard the bytes.
+    fn skip(&mut self, length: u64) -> Result<(), std::io::Error> {
+        match self {
+            Reader::Buffered(file) if !file.get_ref().is_seekable() => {
+                std::io::copy(&mut std::io::Read::take(file, length), &mut std::io::sink())
+                    .map(|_| ())
+            }
+            Reader::Buffered(file) => file.seek_relative(length as i64),
+            Reader::Mapped(cursor) => cursor
+                .seek(std::io::SeekFrom::Current(length as i64))
+                .map(|_| ()),
+        }
+    }
+}
+
+impl std::io::Read for Reader {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Reader::Buffered(file) => file.read(buffer),
+            Reader::Mapped(cursor) => cursor.read(buffer),
+        }
+    }
+}
+
+impl std::io::Seek for Reader {
+    fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Reader::Buffered(file) => file.seek(position),
+            Reader::Mapped(cursor) => cursor.seek(position),
+        }
+    }
+}
+
+/// Returns the bytes of the last packet read by next_raw, either in the mapping or in buffer.
+fn packet_bytes<'a>(
+    file: &'a Reader,
+    buffer: &'a [u8],
+    mapped_range: &Option<std::ops::Range<usize>>,
+) -> &'a [u8] {
+    match (file, mapped_range) {
+        (Reader::Mapped(cursor), Some(range)) => &cursor.get_ref()[range.clone()],
+        _ => buffer,
+    }
 }
 
 pub struct Decoder {
     pub id_to_track: std::collections::HashMap<u32, common::Track>,
-    file: std::io::BufReader<std::fs::File>,
+    file: Reader,
     description: String,
     position: i64,
+    data_position: i64,
     compression: common::ioheader_generated::Compression,
     file_data_position: i64,
     raw_buffer: Vec<u8>,
     buffer: Vec<u8>,
+    /// Position of the last packet in the mapping, None if the packet was copied to buffer.
+    mapped_range: Option<std::ops::Range<usize>>,
+    /// Target of seek in unwrapped time (see common::next_t).
+    seek_t: u64,
+    seek_unwrap_timestamps: bool,
+    seeking_track_ids: std::collections::HashSet<u32>,
+    big_endian: bool,
+    packet_count: Option<usize>,
+    track_ids: Option<std::collections::HashSet<u32>>,
+    /// Number of packets (of all tracks) before position.
+    packet_index: usize,
 }
 
 impl Decoder {
-    pub fn new<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Error> {
-        let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+    /// track_ids restricts next to the packets of the given tracks, None reads all the tracks.
+    ///
+    /// mmap memory-maps files, uncompressed little-endian packets are then parsed from the mapping without copies
+    /// and seeking does not discard a read buffer. Sources that cannot be mapped fall back to buffered reads.
+    pub fn new(
+        source: utilities::Source,
+        track_ids: Option<std::collections::HashSet<u32>>,
+        mmap: bool,
+        buffer_size: usize,
+    ) -> Result<Self, Error> {
+        let mut file = Reader::new(source, mmap, buffer_size);
         {
             let mut magic_number_buffer = [0; common::MAGIC_NUMBER.len()];
             file.read_exact(&mut magic_number_buffer)?;
@@ -45,13 +141,25 @@ impl Decoder {
                 return Err(Error::MagicNumber(magic_number));
             }
         }
-        let length = {
-            let mut bytes = [0; 4];
+        // the header's size prefix and root offset determine the byte order of all the flatbuffers in the file,
+        // packet headers (track ID and size) are always little-endian
+        let (length, big_endian, root) = {
+            let mut bytes = [0; 8];
             file.read_exact(&mut bytes)?;
-            u32::from_le_bytes(bytes)
+            let big_endian = endianness::is_big_endian(bytes)?;
+            let length = if big_endian {
+                u32::from_be_bytes(bytes[0..4].try_into().expect("four bytes"))
+            } else {
+                u32::from_le_bytes(bytes[0..4].try_into().expect("four bytes"))
+            };
+            (length, big_endian, [bytes[4], bytes[5], bytes[6], bytes[7]])
         };
         let mut buffer = std::vec![0; length as usize];
-        file.read_exact(&mut buffer)?;
+        buffer[0..4].copy_from_slice(&root);
+        file.read_exact(&mut buffer[4..])?;
+        if big_endian {
+            endianness::swap(&mut buffer, false, endianness::IOHEADER)?;
+        }
         let ioheader = unsafe { common::ioheader_generated::root_as_ioheader_unchecked(&buffer) };
         let compression = ioheader.compression();
         let file_data_position = ioheader.file_data_position();
@@ -60,27 +168,75 @@ impl Decoder {
             None => return Err(Error::EmptyDescription),
         };
         let id_to_track = common::description_to_id_to_tracks(&description)?;
+        if let Some(track_ids) = track_ids.as_ref() {
+            if let Some(track_id) = track_ids
+                .iter()
+                .find(|track_id| !id_to_track.contains_key(track_id))
+            {
+                return Err(Error::UnknownTrackId(*track_id));
+            }
+        }
+        let data_position = (common::MAGIC_NUMBER.len() + 4 + length as usize) as i64;
         Ok(Decoder {
             id_to_track,
             file,
             description,
-            position: (common::MAGIC_NUMBER.len() + 4 + length as usize) as i64,
+            position: data_position,
+            data_position,
             compression,
             file_data_position,
+            packet_index: 0,
             raw_buffer: Vec::new(),
             buffer,
+            mapped_range: None,
+            seek_t: 0,
+            seek_unwrap_timestamps: false,
+            seeking_track_ids: std::collections::HashSet::new(),
+            big_endian,
+            packet_count: None,
+            track_ids,
         })
     }
 
     pub fn description(&self) -> &str {
         self.description.as_str()
     }
+
+    /// Whether the file is memory-mapped (mmap was requested and mapping the source succeeded).
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.file, Reader::Mapped(_))
+    }
+
+    /// Whether next returns the packets of this track (see track_ids in new).
+    pub fn is_selected(&self, track_id: u32) -> bool {
+        self.track_ids
+            .as_ref()
+            .is_none_or(|track_ids| track_ids.contains(&track_id))
+    }
+
+    /// Changes the tracks returned by next (None reads all the tracks) and returns the previous selection.
+    pub fn replace_track_ids(
+        &mut self,
+        track_ids: Option<std::collections::HashSet<u32>>,
+    ) -> Option<std::collections::HashSet<u32>> {
+        std::mem::replace(&mut self.track_ids, track_ids)
+    }
 }
 
 pub struct Packet<'a> {
-    pub buffer: &'a std::vec::Vec<u8>,
+    /// Points to the mapping when the file is memory-mapped and the packet is neither compressed nor big-endian.
+    pub buffer: &'a [u8],
     pub track_id: u32,
     pub track: &'a mut common::Track,
+    /// Position of the packet in the file, counting the packets of all tracks (selected or not).
+    pub index: usize,
+}
+
+/// Everything needed to resume decoding at a packet boundary.
+pub struct StateToken {
+    pub position: u64,
+    /// (previous_t, t_offset) of each track, sorted by track id.
+    pub timestamp_states: Vec<(u64, u64)>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -91,6 +247,9 @@ pub enum ReadError {
     #[error(transparent)]
     Flatbuffers(#[from] flatbuffers::InvalidFlatbuffer),
 
+    #[error(transparent)]
+    Endianness(#[from] endianness::Error),
+
     #[error("unknown compression algorithm")]
     CompressionAlgorithm,
 
@@ -104,18 +263,33 @@ pub enum ReadError {
         got: String,
     },
 
-    #[error("empty events packet")]
-    EmptyEventsPacket,
-
     #[error("missing packet size prefix")]
     MissingPacketSizePrefix,
 
     #[error("unknown frame format")]
     UnknownFrameFormat,
 
+    #[error(
+        "the frame ({width}x{height} at x={offset_x}, y={offset_y}) does not fit in the sensor ({sensor_width}x{sensor_height})"
+    )]
+    FrameOutsideSensor {
+        offset_x: i16,
+        offset_y: i16,
+        width: usize,
+        height: usize,
+        sensor_width: u16,
+        sensor_height: u16,
+    },
+
     #[error("unknown trigger source")]
     UnknownTriggerSource,
 
+    #[error("the token {0} does not point to a packet boundary")]
+    PacketBoundary(u64),
+
+    #[error("the token has the state of {got} tracks but the file has {expected} tracks")]
+    TokenTracks { expected: usize, got: usize },
+
     #[error("x overflow (x={x} should be larger than 0 and strictly smaller than width={width})")]
     XOverflow { x: i16, width: u16 },
 
@@ -123,42 +297,309 @@ pub enum ReadError {
         "y overflow (y={y} should be larger than 0 and strictly smaller than height={height})"
     )]
     YOverflow { y: i16, height: u16 },
+
+    #[error("truncated file (the packet at byte {offset} has {available} of {expected} bytes)")]
+    TruncatedFile {
+        offset: u64,
+        expected: u64,
+        available: u64,
+    },
 }
 
 impl Decoder {
-    pub fn next(&mut self) -> Result<Option<Packet>, ReadError> {
-        if self.file_data_position > -1 && self.position == self.file_data_position {
+    /// Returns the position of the next packet and the timestamp state of each track, sorted by track id.
+    pub fn state_token(&self) -> StateToken {
+        let mut track_ids: Vec<u32> = self.id_to_track.keys().copied().collect();
+        track_ids.sort();
+        StateToken {
+            position: self.position as u64,
+            timestamp_states: track_ids
+                .into_iter()
+                .map(|track_id| self.id_to_track[&track_id].timestamp_state())
+                .collect(),
+        }
+    }
+
+    pub fn resume_from(&mut self, token: &StateToken) -> Result<(), ReadError> {
+        if token.timestamp_states.len() != self.id_to_track.len() {
+            return Err(ReadError::TokenTracks {
+                expected: self.id_to_track.len(),
+                got: token.timestamp_states.len(),
+            });
+        }
+        let packet_index = self.packet_boundary_index(token.position);
+        let position = match packet_index {
+            Ok(Some(_)) => token.position,
+            _ => self.position as u64,
+        };
+        self.file.seek(std::io::SeekFrom::Start(position))?;
+        if let Some(packet_index) = packet_index? {
+            self.position = token.position as i64;
+            self.packet_index = packet_index;
+            self.seeking_track_ids.clear();
+            let mut track_ids: Vec<u32> = self.id_to_track.keys().copied().collect();
+            track_ids.sort();
+            for (track_id, state) in track_ids.into_iter().zip(&token.timestamp_states) {
+                self.id_to_track
+                    .get_mut(&track_id)
+                    .expect("track_id comes from id_to_track")
+                    .set_timestamp_state(*state);
+            }
+            Ok(())
+        } else {
+            Err(ReadError::PacketBoundary(token.position))
+        }
+    }
+
+    pub fn seek_to_start(&mut self) -> Result<(), ReadError> {
+        self.file
+            .seek(std::io::SeekFrom::Start(self.data_position as u64))?;
+        self.position = self.data_position;
+        self.packet_index = 0;
+        self.seeking_track_ids.clear();
+        for track in self.id_to_track.values_mut() {
+            track.reset_previous_t();
+        }
+        Ok(())
+    }
+
+    /// Rewinds the decoder and skips, for each track, the packets that begin before t.
+    ///
+    /// t is compared with the timestamps returned by common::next_t (unwrapped if unwrap_timestamps is true).
+    /// Skipped packets are decompressed but only their first and last timestamps are read,
+    /// they update the track state as if they had been decoded.
+    /// Tracks are handled independently since packets from different tracks may be interleaved out of order.
+    pub fn seek(&mut self, t: u64, unwrap_timestamps: bool) -> Result<(), ReadError> {
+        self.seek_to_start()?;
+        self.seek_t = t;
+        self.seek_unwrap_timestamps = unwrap_timestamps;
+        self.seeking_track_ids = self.id_to_track.keys().copied().collect();
+        Ok(())
+    }
+
+    /// Returns the timestamp of the first element of the selected tracks and rewinds the decoder.
+    pub fn first_t(&mut self) -> Result<Option<u64>, ReadError> {
+        self.seek_to_start()?;
+        let first_t = loop {
+            match self.next_raw()? {
+                Some(track_id) => {
+                    let range = self.id_to_track[&track_id]
+                        .first_and_last_t(packet_bytes(
+                            &self.file,
+                            &self.buffer,
+                            &self.mapped_range,
+                        ))
+                        .map_err(|_| ReadError::MissingPacketSizePrefix)?;
+                    if let Some((first_t, _)) = range {
+                        break Some(first_t.max(0) as u64);
+                    }
+                }
+                None => break None,
+            }
+        };
+        self.seek_to_start()?;
+        Ok(first_t)
+    }
+
+    /// Returns the number of packets before token, or None if token is not a packet boundary.
+    fn packet_boundary_index(&mut self, token: u64) -> Result<Option<usize>, std::io::Error> {
+        if self.file_data_position > -1 && token > self.file_data_position as u64 {
             return Ok(None);
         }
-        let (track_id, length) = {
+        let mut position = self.data_position as u64;
+        let mut packet_index = 0;
+        while position < token {
+            self.file.seek(std::io::SeekFrom::Start(position + 4))?;
+            let mut bytes = [0; 4];
+            if self.file.read_exact(&mut bytes).is_err() {
+                return Ok(None);
+            }
+            position += 8 + u32::from_le_bytes(bytes) as u64;
+            packet_index += 1;
+        }
+        Ok((position == token).then_some(packet_index))
+    }
+
+    /// Returns the number of complete packets in the file.
+    ///
+    /// Only the packet headers (track ID and size) are read, and the count is cached.
+    /// A truncated last packet is not counted (next returns an error when it reaches it).
+    pub fn packet_count(&mut self) -> Result<usize, ReadError> {
+        if let Some(packet_count) = self.packet_count {
+            return Ok(packet_count);
+        }
+        let result = self.scan_packet_count();
+        self.file
+            .seek(std::io::SeekFrom::Start(self.position as u64))?;
+        let packet_count = result?;
+        self.packet_count = Some(packet_count);
+        Ok(packet_count)
+    }
+
+    /// Counts packets from the beginning of the data, file's position is undefined after this function returns.
+    fn scan_packet_count(&mut self) -> Result<usize, std::io::Error> {
+        let mut end = self.file.seek(std::io::SeekFrom::End(0))?;
+        if self.file_data_position > -1 {
+            end = end.min(self.file_data_position as u64);
+        }
+        let mut position = self.data_position as u64;
+        let mut packet_count = 0;
+        while position + 8 <= end {
+            self.file.seek(std::io::SeekFrom::Start(position + 4))?;
+            let mut bytes = [0; 4];
+            self.file.read_exact(&mut bytes)?;
+            let next_position = position + 8 + u32::from_le_bytes(bytes) as u64;
+            if next_position > end {
+                break;
+            }
+            packet_count += 1;
+            position = next_position;
+        }
+        Ok(packet_count)
+    }
+
+    pub fn next(&mut self) -> Result<Option<Packet>, ReadError> {
+        let track_id = loop {
+            let track_id = self.next_raw()?;
+            match track_id {
+                Some(track_id) => {
+                    if self.seeking_track_ids.contains(&track_id) {
+                        let track = self
+                            .id_to_track
+                            .get_mut(&track_id)
+                            .expect("next_raw checks the track ID");
+                        let range = track
+                            .first_and_last_t(packet_bytes(
+                                &self.file,
+                                &self.buffer,
+                                &self.mapped_range,
+                            ))
+                            .map_err(|_| ReadError::MissingPacketSizePrefix)?;
+                        let (first_t, last_t) = match range {
+                            Some(range) => range,
+                            None => continue,
+                        };
+                        // the state is only committed if the packet is skipped,
+                        // the caller calls next_t on the elements of returned packets
+                        let (mut previous_t, mut t_offset) = track.timestamp_state();
+                        if common::next_t(
+                            first_t,
+                            &mut previous_t,
+                            &mut t_offset,
+                            self.seek_unwrap_timestamps,
+                        ) >= self.seek_t
+                        {
+                            self.seeking_track_ids.remove(&track_id);
+                        } else {
+                            common::next_t(
+                                last_t,
+                                &mut previous_t,
+                                &mut t_offset,
+                                self.seek_unwrap_timestamps,
+                            );
+                            track.set_timestamp_state((previous_t, t_offset));
+                            continue;
+                        }
+                    }
+                    break track_id;
+                }
+                None => return Ok(None),
+            }
+        };
+        Ok(Some(Packet {
+            buffer: packet_bytes(&self.file, &self.buffer, &self.mapped_range),
+            track_id,
+            track: self
+                .id_to_track
+                .get_mut(&track_id)
+                .expect("next_raw checks the track ID"),
+            index: self.packet_index - 1,
+        }))
+    }
+
+    /// Reads and decompresses the next packet into self.buffer (or locates it in the mapping) and returns its track ID.
+    ///
+    /// The payloads of packets from tracks that are not selected are skipped without being read.
+    fn next_raw(&mut self) -> Result<Option<u32>, ReadError> {
+        let (track_id, length) = loop {
+            if self.file_data_position > -1 && self.position == self.file_data_position {
+                return Ok(None);
+            }
             let mut bytes = [0; 8];
-            if let Err(error) = self.file.read_exact(&mut bytes) {
-                return if self.file_data_position == -1 {
-                    Ok(None)
-                } else {
-                    Err(error.into())
-                };
+            let available = utilities::read_up_to(&mut self.file, &mut bytes)?;
+            // files without a file data table end after the last complete packet
+            if available == 0 && self.file_data_position == -1 {
+                return Ok(None);
+            }
+            if available < bytes.len() {
+                return Err(ReadError::TruncatedFile {
+                    offset: self.position as u64,
+                    expected: bytes.len() as u64,
+                    available: available as u64,
+                });
             }
             let track_id = u32::from_le_bytes(bytes[0..4].try_into().expect("four bytes"));
             let length = u32::from_le_bytes(bytes[4..8].try_into().expect("four bytes"));
-            (track_id, length)
+            self.position += 8i64 + length as i64;
+            self.packet_index += 1;
+            if !self.is_selected(track_id) {
+                self.file.skip(length as u64)?;
+                continue;
+            }
+            break (track_id, length);
+        };
+        let is_uncompressed = self.compression == common::ioheader_generated::Compression::None;
+        let payload = match self.file {
+            Reader::Mapped(ref mut cursor) => {
+                let start = (cursor.position() as usize).min(cursor.get_ref().len());
+                let end = (start + length as usize).min(cursor.get_ref().len());
+                cursor.set_position(end as u64);
+                start..end
+            }
+            Reader::Buffered(ref mut file) => {
+                // uncompressed payloads are read directly into the packet buffer
+                let buffer = if is_uncompressed {
+                    &mut self.buffer
+                } else {
+                    &mut self.raw_buffer
+                };
+                buffer.resize(length as usize, 0u8);
+                0..utilities::read_up_to(file, buffer)?
+            }
+        };
+        if payload.len() < length as usize {
+            return Err(ReadError::TruncatedFile {
+                offset: (self.position - 8 - length as i64) as u64,
+                expected: 8 + length as u64,
+                available: 8 + payload.len() as u64,
+            });
+        }
+        self.mapped_range = None;
+        let raw: &[u8] = match self.file {
+            Reader::Mapped(ref cursor) => &cursor.get_ref()[payload.clone()],
+            Reader::Buffered(_) => &self.raw_buffer,
         };
-        self.position += 8i64 + length as i64;
-        self.raw_buffer.resize(length as usize, 0u8);
-        self.file.read_exact(&mut self.raw_buffer)?;
         match self.compression {
             common::ioheader_generated::Compression::None => {
-                std::mem::swap(&mut self.raw_buffer, &mut self.buffer);
+                if let Reader::Mapped(_) = self.file {
+                    // big-endian packets are swapped in place, hence they must be copied
+                    if self.big_endian {
+                        self.buffer.clear();
+                        self.buffer.extend_from_slice(raw);
+                    } else {
+                        self.mapped_range = Some(payload);
+                    }
+                }
             }
             common::ioheader_generated::Compression::Lz4
             | common::ioheader_generated::Compression::Lz4High => {
-                let mut decoder = lz4::Decoder::new(&self.raw_buffer[..])?;
+                let mut decoder = lz4::Decoder::new(raw)?;
                 self.buffer.clear();
                 decoder.read_to_end(&mut self.buffer)?;
             }
             common::ioheader_generated::Compression::Zstd
             | common::ioheader_generated::Compression::ZstdHigh => {
-                let mut decoder = zstd::Decoder::new(&self.raw_buffer[..])?;
+                let mut decoder = zstd::Decoder::new(raw)?;
                 self.buffer.clear();
                 decoder.read_to_end(&mut self.buffer)?;
             }
@@ -166,19 +607,22 @@ impl Decoder {
         }
         let track = self
             .id_to_track
-            .get_mut(&track_id)
+            .get(&track_id)
             .ok_or(ReadError::UnknownPacketTrackId(track_id))?;
+        if self.big_endian {
+            endianness::swap(&mut self.buffer, true, endianness::track_layout(track))?;
+        }
+        let buffer = packet_bytes(&self.file, &self.buffer, &self.mapped_range);
         let expected = track.to_identifier().to_owned();
-        if !flatbuffers::buffer_has_identifier(&self.buffer, &expected, true) {
+        if !flatbuffers::buffer_has_identifier(buffer, &expected, true) {
             let expected_length = expected.len();
             let offset = flatbuffers::SIZE_SIZEPREFIX + flatbuffers::SIZE_UOFFSET;
             return Err(ReadError::BadPacketPrefix {
                 id: track_id,
                 expected,
-                got: if self.buffer.len() >= offset {
+                got: if buffer.len() >= offset {
                     String::from_utf8_lossy(
-                        &self.buffer
-                            [offset..offset + expected_length.min(self.buffer.len() - offset)],
+                        &buffer[offset..offset + expected_length.min(buffer.len() - offset)],
                     )
                     .into_owned()
                 } else {
@@ -187,10 +631,6 @@ impl Decoder {
                 .to_string(),
             });
         }
-        Ok(Some(Packet {
-            buffer: &self.buffer,
-            track_id,
-            track,
-        }))
+        Ok(Some(track_id))
     }
 }
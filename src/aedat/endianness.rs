@@ -0,0 +1,210 @@
+use crate::aedat::common;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("the flatbuffer is malformed (offset {0} is out of bounds)")]
+    OutOfBounds(usize),
+
+    #[error("the byte order of the file header is ambiguous (the size prefix and root offset are identical in little-endian and big-endian)")]
+    Ambiguous,
+
+    #[error("the byte order of the file header could not be determined (the size prefix and root offset are invalid in both little-endian and big-endian)")]
+    Unknown,
+}
+
+/// Flatbuffers layouts, listed in vtable order.
+///
+/// Only multi-byte scalars are listed in structs since single bytes do not need to be swapped.
+pub enum Field {
+    Scalar(usize),
+    String,
+    Bytes,
+    StructVector {
+        size: usize,
+        scalars: &'static [(usize, usize)],
+    },
+    TableVector(&'static [Field]),
+}
+
+pub const IOHEADER: &[Field] = &[Field::Scalar(4), Field::Scalar(8), Field::String];
+
+pub const EVENT_PACKET: &[Field] = &[Field::StructVector {
+    size: 16,
+    scalars: &[(0, 8), (8, 2), (10, 2)],
+}];
+
+pub const FRAME: &[Field] = &[
+    Field::Scalar(8),
+    Field::Scalar(8),
+    Field::Scalar(8),
+    Field::Scalar(8),
+    Field::Scalar(8),
+    Field::Scalar(1),
+    Field::Scalar(2),
+    Field::Scalar(2),
+    Field::Scalar(2),
+    Field::Scalar(2),
+    Field::Bytes,
+];
+
+pub const IMU_PACKET: &[Field] = &[Field::TableVector(&[
+    Field::Scalar(8),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+    Field::Scalar(4),
+])];
+
+pub const TRIGGER_PACKET: &[Field] = &[Field::TableVector(&[Field::Scalar(8), Field::Scalar(1)])];
+
+pub fn track_layout(track: &common::Track) -> &'static [Field] {
+    match track {
+        common::Track::Events { .. } => EVENT_PACKET,
+        common::Track::Frame { .. } => FRAME,
+        common::Track::Imus { .. } => IMU_PACKET,
+        common::Track::Triggers { .. } => TRIGGER_PACKET,
+    }
+}
+
+/// Guesses the byte order of the file header from its size prefix and root offset (the first 8 bytes).
+///
+/// Returns true if the header is big-endian. A valid root offset points inside the buffer,
+/// after the root offset itself. Flatbuffers builders write the root table last, at the beginning
+/// of the buffer, hence the smallest valid root offset is used if both byte orders are valid.
+pub fn is_big_endian(prefix: [u8; 8]) -> Result<bool, Error> {
+    let root = |length: u32, root: u32| (root >= 4 && root < length).then_some(root);
+    let little_endian_root = root(
+        u32::from_le_bytes(prefix[0..4].try_into().expect("four bytes")),
+        u32::from_le_bytes(prefix[4..8].try_into().expect("four bytes")),
+    );
+    let big_endian_root = root(
+        u32::from_be_bytes(prefix[0..4].try_into().expect("four bytes")),
+        u32::from_be_bytes(prefix[4..8].try_into().expect("four bytes")),
+    );
+    match (little_endian_root, big_endian_root) {
+        (Some(little_endian_root), Some(big_endian_root)) => {
+            match little_endian_root.cmp(&big_endian_root) {
+                std::cmp::Ordering::Less => Ok(false),
+                std::cmp::Ordering::Equal => Err(Error::Ambiguous),
+                std::cmp::Ordering::Greater => Ok(true),
+            }
+        }
+        (Some(_), None) => Ok(false),
+        (None, Some(_)) => Ok(true),
+        (None, None) => Err(Error::Unknown),
+    }
+}
+
+/// Converts a big-endian flatbuffer to little-endian in place.
+///
+/// The root table must match layout. Vtables shared by several tables are only swapped once.
+pub fn swap(buffer: &mut [u8], size_prefixed: bool, layout: &[Field]) -> Result<(), Error> {
+    let mut swapper = Swapper {
+        buffer,
+        swapped_vtables: std::collections::HashSet::new(),
+    };
+    let root_position = if size_prefixed {
+        swapper.swap(0, 4)?;
+        4
+    } else {
+        0
+    };
+    let table = swapper.swap_offset(root_position)?;
+    swapper.swap_table(table, layout)
+}
+
+struct Swapper<'a> {
+    buffer: &'a mut [u8],
+    swapped_vtables: std::collections::HashSet<usize>,
+}
+
+impl Swapper<'_> {
+    fn swap(&mut self, position: usize, size: usize) -> Result<(), Error> {
+        match self.buffer.get_mut(position..position + size) {
+            Some(bytes) => {
+                bytes.reverse();
+                Ok(())
+            }
+            None => Err(Error::OutOfBounds(position + size)),
+        }
+    }
+
+    fn read_u32(&self, position: usize) -> Result<u32, Error> {
+        match self.buffer.get(position..position + 4) {
+            Some(bytes) => Ok(u32::from_le_bytes(bytes.try_into().expect("four bytes"))),
+            None => Err(Error::OutOfBounds(position + 4)),
+        }
+    }
+
+    fn read_u16(&self, position: usize) -> Result<u16, Error> {
+        match self.buffer.get(position..position + 2) {
+            Some(bytes) => Ok(u16::from_le_bytes(bytes.try_into().expect("two bytes"))),
+            None => Err(Error::OutOfBounds(position + 2)),
+        }
+    }
+
+    /// Swaps the uoffset at position and returns the absolute position it points to.
+    fn swap_offset(&mut self, position: usize) -> Result<usize, Error> {
+        self.swap(position, 4)?;
+        Ok(position + self.read_u32(position)? as usize)
+    }
+
+    /// Swaps a vector's uoffset and length and returns the position of the first element and the length.
+    fn swap_vector(&mut self, position: usize) -> Result<(usize, usize), Error> {
+        let vector = self.swap_offset(position)?;
+        self.swap(vector, 4)?;
+        Ok((vector + 4, self.read_u32(vector)? as usize))
+    }
+
+    fn swap_table(&mut self, table: usize, layout: &[Field]) -> Result<(), Error> {
+        self.swap(table, 4)?;
+        let vtable = table as i64 - self.read_u32(table)? as i32 as i64;
+        if vtable < 0 {
+            return Err(Error::OutOfBounds(table));
+        }
+        let vtable = vtable as usize;
+        if self.swapped_vtables.insert(vtable) {
+            self.swap(vtable, 2)?;
+            let vtable_size = self.read_u16(vtable)? as usize;
+            for position in (vtable + 2..vtable + vtable_size).step_by(2) {
+                self.swap(position, 2)?;
+            }
+        }
+        let fields_count = (self.read_u16(vtable)? as usize).saturating_sub(4) / 2;
+        for (index, field) in layout.iter().enumerate().take(fields_count) {
+            let offset = self.read_u16(vtable + 4 + index * 2)? as usize;
+            if offset == 0 {
+                continue;
+            }
+            let position = table + offset;
+            match field {
+                Field::Scalar(size) => self.swap(position, *size)?,
+                Field::String | Field::Bytes => {
+                    self.swap_vector(position)?;
+                }
+                Field::StructVector { size, scalars } => {
+                    let (elements, length) = self.swap_vector(position)?;
+                    for index in 0..length {
+                        for (scalar_offset, scalar_size) in scalars.iter() {
+                            self.swap(elements + index * size + scalar_offset, *scalar_size)?;
+                        }
+                    }
+                }
+                Field::TableVector(layout) => {
+                    let (elements, length) = self.swap_vector(position)?;
+                    for index in 0..length {
+                        let element = self.swap_offset(elements + index * 4)?;
+                        self.swap_table(element, layout)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
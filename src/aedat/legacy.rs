@@ -0,0 +1,384 @@
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Seek;
+
+use crate::utilities;
+
+/// Decoder for the AEDAT 2.0 (jAER) and AEDAT 3.1 (cAER) formats.
+///
+/// Only DVS (polarity) events are decoded, other event types (frames, IMU samples, special events) are skipped.
+pub struct Decoder {
+    version: Version,
+    /// Sorted by track ID, AEDAT 2.0 files have a single track (ID 0).
+    pub tracks: Vec<(u32, (u16, u16))>,
+    file: std::io::BufReader<utilities::Source>,
+    raw_buffer: Vec<u8>,
+    event_buffer: Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>,
+    previous_t: u64,
+    t_offset: u64,
+    /// Absolute position of the first event or packet, None if the source is not seekable.
+    data_start: Option<u64>,
+}
+
+pub struct Packet<'a> {
+    pub events: &'a Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>,
+    pub track_id: u32,
+    pub dimensions: (u16, u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    Aedat2,
+    Aedat31,
+}
+
+impl Version {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Version::Aedat2 => "2.0",
+            Version::Aedat31 => "3.1",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    ParseInt(#[from] std::num::ParseIntError),
+
+    #[error("bad magic number (expected \"#!AER-DAT2.0\" or \"#!AER-DAT3.1\", got \"{0}\")")]
+    MagicNumber(String),
+
+    #[error("the header has no \"#!END-HEADER\" line")]
+    MissingEndHeader,
+
+    #[error("unknown source \"{0}\" (supports DVS128, DAVIS240, DAVIS346, and DVXplorer)")]
+    UnknownSource(String),
+
+    #[error("the header has no sources")]
+    NoSources,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReadError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("unknown packet source {0}")]
+    UnknownSource(i16),
+
+    #[error("x overflow (x={x} should be strictly smaller than width={width})")]
+    XOverflow { x: u16, width: u16 },
+
+    #[error("y overflow (y={y} should be strictly smaller than height={height})")]
+    YOverflow { y: u16, height: u16 },
+}
+
+const MAGIC_NUMBER_PREFIX: &[u8] = b"#!AER-DAT";
+
+/// AEDAT 2.0 events are 8 bytes long (big-endian address and timestamp).
+const AEDAT2_EVENT_SIZE: usize = 8;
+
+/// AEDAT 3.1 packet header, see https://docs.inivation.com/software/software-advanced-usage/file-formats/aedat-3.1.html.
+const AEDAT31_PACKET_HEADER_SIZE: usize = 28;
+const AEDAT31_POLARITY_EVENT: i16 = 1;
+
+/// Dimensions of the cameras that wrote AEDAT 2.0 and 3.1 files, matched against a source name.
+fn camera_dimensions(name: &str) -> Option<(u16, u16)> {
+    let name = name.to_lowercase();
+    if name.contains("dvs128") {
+        Some((128, 128))
+    } else if name.contains("davis240") {
+        Some((240, 180))
+    } else if name.contains("davis346") {
+        Some((346, 260))
+    } else if name.contains("dvxplorer") || name.contains("davis640") {
+        Some((640, 480))
+    } else {
+        None
+    }
+}
+
+/// Returns the file version if the first bytes are an AEDAT 2.0 or 3.1 magic number.
+pub fn version_from_magic(bytes: &[u8]) -> Option<Version> {
+    let version = bytes.strip_prefix(MAGIC_NUMBER_PREFIX)?;
+    if version.starts_with(b"2.0") {
+        Some(Version::Aedat2)
+    } else if version.starts_with(b"3.1") {
+        Some(Version::Aedat31)
+    } else {
+        None
+    }
+}
+
+impl Decoder {
+    pub fn new(source: utilities::Source, buffer_size: usize) -> Result<Self, Error> {
+        let mut file = std::io::BufReader::with_capacity(buffer_size, source);
+        let mut lines = Vec::new();
+        let mut end_header = false;
+        loop {
+            let buffer = file.fill_buf()?;
+            if buffer.first() != Some(&b'#') {
+                break;
+            }
+            let mut line = Vec::new();
+            file.read_until(b'\n', &mut line)?;
+            let line = String::from_utf8_lossy(&line).trim_end().to_owned();
+            let is_end = line == "#!END-HEADER" || line == "#End Of ASCII Header";
+            lines.push(line);
+            if is_end {
+                end_header = true;
+                break;
+            }
+        }
+        let version = match lines
+            .first()
+            .and_then(|line| version_from_magic(line.as_bytes()))
+        {
+            Some(version) => version,
+            None => {
+                return Err(Error::MagicNumber(
+                    lines.first().cloned().unwrap_or_default(),
+                ))
+            }
+        };
+        let tracks = match version {
+            // AEDAT 2.0 headers do not list sources, DVS128 is assumed unless a DAVIS is mentioned
+            Version::Aedat2 => {
+                let dimensions = lines
+                    .iter()
+                    .find_map(|line| camera_dimensions(line))
+                    .unwrap_or((128, 128));
+                vec![(0, dimensions)]
+            }
+            Version::Aedat31 => {
+                if !end_header {
+                    return Err(Error::MissingEndHeader);
+                }
+                let mut tracks = Vec::new();
+                for line in lines.iter() {
+                    if let Some(source) = line.strip_prefix("#Source ") {
+                        if let Some((id, name)) = source.split_once(':') {
+                            let name = name.trim();
+                            match camera_dimensions(name) {
+                                Some(dimensions) => {
+                                    tracks.push((id.trim().parse::<u32>()?, dimensions))
+                                }
+                                None => return Err(Error::UnknownSource(name.to_owned())),
+                            }
+                        }
+                    }
+                }
+                if tracks.is_empty() {
+                    return Err(Error::NoSources);
+                }
+                tracks.sort_by_key(|(id, _)| *id);
+                tracks
+            }
+        };
+        let data_start = file.stream_position().ok();
+        Ok(Decoder {
+            version,
+            tracks,
+            file,
+            raw_buffer: vec![0u8; buffer_size],
+            event_buffer: Vec::new(),
+            previous_t: 0,
+            t_offset: 0,
+            data_start,
+        })
+    }
+
+    /// Moves back to the first event and clears the timestamp unwrapping state.
+    pub fn reset(&mut self) -> Result<(), ReadError> {
+        let data_start = match self.data_start {
+            Some(data_start) => data_start,
+            None => return Err(self.file.get_ref().seek_error().into()),
+        };
+        self.file.seek(std::io::SeekFrom::Start(data_start))?;
+        self.previous_t = 0;
+        self.t_offset = 0;
+        Ok(())
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the next packet of DVS events, or None at the end of the file.
+    ///
+    /// AEDAT 2.0 files are read in chunks, AEDAT 3.1 files yield one packet per polarity packet in the file.
+    pub fn next(&mut self) -> Result<Option<Packet<'_>>, ReadError> {
+        match self.version {
+            Version::Aedat2 => self.next_aedat2(),
+            Version::Aedat31 => self.next_aedat31(),
+        }
+    }
+
+    /// Reads until buffer is full or the end of the file is reached, and returns the number of bytes read.
+    fn read_full(&mut self, length: usize) -> Result<usize, std::io::Error> {
+        let mut read = 0;
+        while read < length {
+            let size = self.file.read(&mut self.raw_buffer[read..length])?;
+            if size == 0 {
+                break;
+            }
+            read += size;
+        }
+        Ok(read)
+    }
+
+    /// jAER timestamps are signed 32-bit integers that wrap around.
+    fn unwrap_t(&mut self, t: u32) -> u64 {
+        let mut t = t as u64 + self.t_offset;
+        if t + (1u64 << 31) < self.previous_t {
+            self.t_offset += 1u64 << 32;
+            t += 1u64 << 32;
+        }
+        let t = t.max(self.previous_t);
+        self.previous_t = t;
+        t
+    }
+
+    fn next_aedat2(&mut self) -> Result<Option<Packet<'_>>, ReadError> {
+        let (width, height) = self.tracks[0].1;
+        // DVS128 and DAVIS addresses have different layouts
+        let davis = width != 128;
+        let read = self.read_full(self.raw_buffer.len())?;
+        if read < AEDAT2_EVENT_SIZE {
+            return Ok(None);
+        }
+        self.event_buffer.clear();
+        for index in 0..read / AEDAT2_EVENT_SIZE {
+            let bytes =
+                &self.raw_buffer[index * AEDAT2_EVENT_SIZE..(index + 1) * AEDAT2_EVENT_SIZE];
+            let address = u32::from_be_bytes(bytes[0..4].try_into().expect("4 bytes"));
+            let t = self.unwrap_t(u32::from_be_bytes(bytes[4..8].try_into().expect("4 bytes")));
+            let (x, y, on) = if davis {
+                // the most significant bit is set for APS and IMU samples
+                if address & 0x80000000 != 0 {
+                    continue;
+                }
+                (
+                    ((address >> 12) & 0x3FF) as u16,
+                    ((address >> 22) & 0x1FF) as u16,
+                    (address >> 11) & 1 == 1,
+                )
+            } else {
+                (
+                    ((address >> 1) & 0x7F) as u16,
+                    ((address >> 8) & 0x7F) as u16,
+                    address & 1 == 1,
+                )
+            };
+            if x >= width {
+                return Err(ReadError::XOverflow { x, width });
+            }
+            if y >= height {
+                return Err(ReadError::YOverflow { y, height });
+            }
+            self.event_buffer.push(neuromorphic_types::DvsEvent {
+                t,
+                x,
+                y,
+                polarity: if on {
+                    neuromorphic_types::DvsPolarity::On
+                } else {
+                    neuromorphic_types::DvsPolarity::Off
+                },
+            });
+        }
+        Ok(Some(Packet {
+            events: &self.event_buffer,
+            track_id: 0,
+            dimensions: (width, height),
+        }))
+    }
+
+    fn next_aedat31(&mut self) -> Result<Option<Packet<'_>>, ReadError> {
+        loop {
+            let mut header = [0u8; AEDAT31_PACKET_HEADER_SIZE];
+            let read = {
+                let mut read = 0;
+                while read < header.len() {
+                    let size = self.file.read(&mut header[read..])?;
+                    if size == 0 {
+                        break;
+                    }
+                    read += size;
+                }
+                read
+            };
+            if read == 0 {
+                return Ok(None);
+            }
+            if read < header.len() {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            let event_type = i16::from_le_bytes(header[0..2].try_into().expect("2 bytes"));
+            let event_source = i16::from_le_bytes(header[2..4].try_into().expect("2 bytes"));
+            let event_size = u32::from_le_bytes(header[4..8].try_into().expect("4 bytes")) as usize;
+            let overflow = u32::from_le_bytes(header[12..16].try_into().expect("4 bytes")) as u64;
+            let capacity = u32::from_le_bytes(header[16..20].try_into().expect("4 bytes")) as usize;
+            let size = event_size * capacity;
+            if event_type != AEDAT31_POLARITY_EVENT || event_size != 8 {
+                std::io::copy(
+                    &mut (&mut self.file).take(size as u64),
+                    &mut std::io::sink(),
+                )?;
+                continue;
+            }
+            let (track_id, (width, height)) = match self
+                .tracks
+                .iter()
+                .find(|(id, _)| *id as i64 == event_source as i64)
+            {
+                Some(track) => *track,
+                None => return Err(ReadError::UnknownSource(event_source)),
+            };
+            if self.raw_buffer.len() < size {
+                self.raw_buffer.resize(size, 0u8);
+            }
+            if self.read_full(size)? < size {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            self.event_buffer.clear();
+            for index in 0..capacity {
+                let bytes = &self.raw_buffer[index * 8..(index + 1) * 8];
+                let data = u32::from_le_bytes(bytes[0..4].try_into().expect("4 bytes"));
+                // the first bit marks valid events
+                if data & 1 == 0 {
+                    continue;
+                }
+                let x = ((data >> 17) & 0x7FFF) as u16;
+                let y = ((data >> 2) & 0x7FFF) as u16;
+                if x >= width {
+                    return Err(ReadError::XOverflow { x, width });
+                }
+                if y >= height {
+                    return Err(ReadError::YOverflow { y, height });
+                }
+                let t = (overflow << 31)
+                    | (i32::from_le_bytes(bytes[4..8].try_into().expect("4 bytes")).max(0) as u64);
+                self.event_buffer.push(neuromorphic_types::DvsEvent {
+                    t,
+                    x,
+                    y,
+                    polarity: if (data >> 1) & 1 == 1 {
+                        neuromorphic_types::DvsPolarity::On
+                    } else {
+                        neuromorphic_types::DvsPolarity::Off
+                    },
+                });
+            }
+            return Ok(Some(Packet {
+                events: &self.event_buffer,
+                track_id,
+                dimensions: (width, height),
+            }));
+        }
+    }
+}
@@ -1,6 +1,8 @@
 mod common;
 mod decoder;
 mod encoder;
+mod endianness;
+mod legacy;
 
 use crate::types;
 use crate::utilities;
@@ -18,7 +20,12 @@ impl From<decoder::Error> for PyErr {
 
 impl From<decoder::ReadError> for PyErr {
     fn from(error: decoder::ReadError) -> Self {
-        PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string())
+        match error {
+            decoder::ReadError::TruncatedFile { .. } => {
+                utilities::TruncatedFileError::new_err(error.to_string())
+            }
+            _ => PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string()),
+        }
     }
 }
 
@@ -40,6 +47,18 @@ impl From<encoder::PacketError> for PyErr {
     }
 }
 
+impl From<legacy::Error> for PyErr {
+    fn from(error: legacy::Error) -> Self {
+        PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string())
+    }
+}
+
+impl From<legacy::ReadError> for PyErr {
+    fn from(error: legacy::ReadError) -> Self {
+        PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string())
+    }
+}
+
 impl From<common::Error> for PyErr {
     fn from(error: common::Error) -> Self {
         PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string())
@@ -111,6 +130,41 @@ pub struct Frame {
 
 #[pymethods]
 impl Frame {
+    /// Converts the pixels to a PIL image whose mode is format ("L", "RGB", or "RGBA").
+    ///
+    /// Colour pixels are already in RGB order. The timestamps and the offset are copied to the image's info dict.
+    fn to_pil(&self, python: Python) -> PyResult<PyObject> {
+        let image = types::import_optional(python, "PIL.Image", "pillow", "to_pil")?
+            .call_method1("fromarray", (self.pixels.bind(python),))?;
+        let mode: String = image.getattr("mode")?.extract()?;
+        if mode != self.format {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(format!(
+                "PIL created an image with the mode \"{mode}\" (expected \"{}\")",
+                self.format
+            )));
+        }
+        let info = image.getattr("info")?;
+        info.set_item("t", self.t)?;
+        info.set_item("begin_t", self.begin_t)?;
+        info.set_item("end_t", self.end_t)?;
+        info.set_item("exposure_begin_t", self.exposure_begin_t)?;
+        info.set_item("exposure_end_t", self.exposure_end_t)?;
+        info.set_item("offset_x", self.offset_x)?;
+        info.set_item("offset_y", self.offset_y)?;
+        Ok(image.unbind())
+    }
+
+    fn staleness(&self, t: u64) -> PyResult<PyObject> {
+        Python::with_gil(|python| -> PyResult<PyObject> {
+            let shape: Vec<usize> = self.pixels.bind(python).getattr("shape")?.extract()?;
+            Ok(
+                ndarray::Array2::<u64>::from_elem((shape[0], shape[1]), t.saturating_sub(self.t))
+                    .to_pyarray_bound(python)
+                    .to_object(python),
+            )
+        })
+    }
+
     fn __repr__(&self) -> String {
         Python::with_gil(|python| -> String {
             format!(
@@ -132,21 +186,639 @@ impl Frame {
     }
 }
 
+/// Events tracks are rescaled by downsample (rounded up), other tracks are not modified.
+fn track_dimensions(track: &common::Track, downsample: u16) -> Option<(u16, u16)> {
+    match track {
+        common::Track::Events { dimensions, .. } => Some((
+            dimensions.0.div_ceil(downsample),
+            dimensions.1.div_ceil(downsample),
+        )),
+        track => track.dimensions(),
+    }
+}
+
+/// Returns the dimensions of the only events track, multi-stream files must use tracks instead.
+fn single_events_dimensions(
+    mut tracks: Vec<(u32, (u16, u16))>,
+    name: &str,
+) -> PyResult<(u16, u16)> {
+    match tracks.len() {
+        0 => Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "{name} requires a file with an events track"
+        ))),
+        1 => Ok(tracks[0].1),
+        _ => {
+            tracks.sort_by_key(|(id, _)| *id);
+            Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "{name} requires a file with a single events track (got track ids {}), use tracks to get the dimensions of each track",
+                tracks
+                    .iter()
+                    .map(|(id, _)| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )))
+        }
+    }
+}
+
 #[pyclass]
 pub struct Decoder {
     inner: Option<decoder::Decoder>,
+    skip_empty: bool,
+    #[pyo3(get)]
+    unwrap_timestamps: bool,
+    /// Only events with this polarity are decoded, None keeps both polarities.
+    #[pyo3(get)]
+    polarity: Option<bool>,
+    /// Event coordinates are divided by this factor, frames are not modified.
+    #[pyo3(get)]
+    downsample: u16,
+    /// IMU packets are returned as dicts of arrays (3-axis fields have the shape (n, 3)) instead of structured arrays.
+    #[pyo3(get)]
+    imus_as_dict: bool,
+    /// Corrupt packets are skipped with a warning instead of raising an error.
+    #[pyo3(get)]
+    lenient: bool,
+    skipped_packets: usize,
+    on_overflow: Overflow,
+    /// Timestamps are shifted so that the first decoded timestamp (of any track) is 0.
+    #[pyo3(get)]
+    zero_origin: bool,
+    origin_t: Option<u64>,
+    /// Frame packets are converted to PIL images (see Frame.to_pil).
+    #[pyo3(get)]
+    as_pil: bool,
+    /// Frames are placed at their offset in a sensor-sized image filled with frame_background.
+    #[pyo3(get)]
+    composite_frames: bool,
+    #[pyo3(get)]
+    frame_background: u8,
+    /// Events of masked pixels (in sensor coordinates) are dropped, shared with the per-packet parsing threads.
+    mask: Option<std::sync::Arc<utilities::PixelMask>>,
+    /// Applied after the mask, events mapped outside their track are dropped.
+    remap: Option<std::sync::Arc<utilities::PixelRemap>>,
+    /// Tolerance of the median denoiser (applied after the remap table), in µs.
+    #[pyo3(get)]
+    median_denoise: Option<u64>,
+    /// Per-track denoiser state, cleared when the decoder moves (seek, seek_to_start, resume_from, and reset).
+    median_surfaces: std::collections::HashMap<u32, utilities::MedianDenoise>,
+    /// Position in the file (counting the packets of all tracks) of the last packet returned by __next__.
+    packet_index: usize,
+}
+
+/// Subtracts the origin from t if zero_origin is true, the first timestamp passed to this function becomes the origin.
+///
+/// Tracks share the origin, hence timestamps that precede it (interleaved tracks) are clamped to 0.
+fn shift_t(t: u64, zero_origin: bool, origin_t: &mut Option<u64>) -> u64 {
+    if zero_origin {
+        t.saturating_sub(*origin_t.get_or_insert(t))
+    } else {
+        t
+    }
+}
+
+/// What to do with events whose coordinates are outside the track's dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overflow {
+    /// Raise XOverflow or YOverflow.
+    Error,
+    /// Move the event to the nearest pixel.
+    Clamp,
+    /// Omit the event.
+    Drop,
+}
+
+impl Overflow {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(Overflow::Error),
+            "clamp" => Some(Overflow::Clamp),
+            "drop" => Some(Overflow::Drop),
+            _ => None,
+        }
+    }
+
+    fn to_str(self) -> &'static str {
+        match self {
+            Overflow::Error => "error",
+            Overflow::Clamp => "clamp",
+            Overflow::Drop => "drop",
+        }
+    }
+
+    /// Returns the event's coordinates after applying this policy, None if the event is dropped.
+    fn apply(
+        self,
+        x: i16,
+        y: i16,
+        dimensions: (u16, u16),
+    ) -> Result<Option<(u16, u16)>, decoder::ReadError> {
+        if is_inside(x, y, dimensions) {
+            return Ok(Some((x as u16, y as u16)));
+        }
+        match self {
+            Overflow::Error => {
+                if x < 0 || x >= dimensions.0 as i16 {
+                    return Err(decoder::ReadError::XOverflow {
+                        x,
+                        width: dimensions.0,
+                    });
+                }
+                Err(decoder::ReadError::YOverflow {
+                    y,
+                    height: dimensions.1,
+                })
+            }
+            Overflow::Clamp => Ok(Some((
+                x.clamp(0, (dimensions.0 as i16 - 1).max(0)) as u16,
+                y.clamp(0, (dimensions.1 as i16 - 1).max(0)) as u16,
+            ))),
+            Overflow::Drop => Ok(None),
+        }
+    }
+}
+
+fn is_inside(x: i16, y: i16, dimensions: (u16, u16)) -> bool {
+    x >= 0 && x < dimensions.0 as i16 && y >= 0 && y < dimensions.1 as i16
+}
+
+/// Converts a trigger source to the value of the "source" field in trigger arrays.
+fn trigger_source_to_u8(
+    source: common::triggers_generated::TriggerSource,
+) -> Result<u8, decoder::ReadError> {
+    use common::triggers_generated::TriggerSource;
+    match source {
+        TriggerSource::TimestampReset => Ok(0_u8),
+        TriggerSource::ExternalSignalRisingEdge => Ok(1_u8),
+        TriggerSource::ExternalSignalFallingEdge => Ok(2_u8),
+        TriggerSource::ExternalSignalPulse => Ok(3_u8),
+        TriggerSource::ExternalGeneratorRisingEdge => Ok(4_u8),
+        TriggerSource::ExternalGeneratorFallingEdge => Ok(5_u8),
+        TriggerSource::FrameBegin => Ok(6_u8),
+        TriggerSource::FrameEnd => Ok(7_u8),
+        TriggerSource::ExposureBegin => Ok(8_u8),
+        TriggerSource::ExposureEnd => Ok(9_u8),
+        _ => Err(decoder::ReadError::UnknownTriggerSource),
+    }
+}
+
+/// Returns a sensor-sized copy of pixels (height, width, and optional channels), with the frame at (offset_x, offset_y).
+///
+/// The other pixels (all channels) are set to background. Frames that do not fit in the sensor are corrupt.
+fn composite_frame(
+    python: Python,
+    pixels: &PyObject,
+    offset_x: i16,
+    offset_y: i16,
+    sensor_dimensions: (u16, u16),
+    background: u8,
+) -> Result<PyObject, NextError> {
+    let pixels = pixels
+        .bind(python)
+        .downcast::<numpy::PyArrayDyn<u8>>()
+        .map_err(PyErr::from)?
+        .readonly();
+    let pixels = pixels.as_array();
+    let (height, width) = (pixels.shape()[0], pixels.shape()[1]);
+    if offset_x < 0
+        || offset_y < 0
+        || offset_x as usize + width > sensor_dimensions.0 as usize
+        || offset_y as usize + height > sensor_dimensions.1 as usize
+    {
+        return Err(decoder::ReadError::FrameOutsideSensor {
+            offset_x,
+            offset_y,
+            width,
+            height,
+            sensor_width: sensor_dimensions.0,
+            sensor_height: sensor_dimensions.1,
+        }
+        .into());
+    }
+    let (offset_x, offset_y) = (offset_x as usize, offset_y as usize);
+    let mut shape = pixels.shape().to_vec();
+    shape[0] = sensor_dimensions.1 as usize;
+    shape[1] = sensor_dimensions.0 as usize;
+    let mut composite = ndarray::ArrayD::<u8>::from_elem(shape, background);
+    composite
+        .slice_each_axis_mut(|axis| match axis.axis.index() {
+            0 => ndarray::Slice::from(offset_y..offset_y + height),
+            1 => ndarray::Slice::from(offset_x..offset_x + width),
+            _ => ndarray::Slice::from(..),
+        })
+        .assign(&pixels);
+    Ok(composite.to_pyarray_bound(python).to_object(python))
+}
+
+/// Error while converting a packet to a Python object, read errors mean that the packet is corrupt.
+enum NextError {
+    Packet(decoder::ReadError),
+    Python(PyErr),
+}
+
+impl From<decoder::ReadError> for NextError {
+    fn from(error: decoder::ReadError) -> Self {
+        NextError::Packet(error)
+    }
+}
+
+impl From<PyErr> for NextError {
+    fn from(error: PyErr) -> Self {
+        NextError::Python(error)
+    }
+}
+
+/// Whether the decoder can skip the packet that caused the error and read the next one.
+fn is_corrupt_packet(error: &decoder::ReadError) -> bool {
+    matches!(
+        error,
+        decoder::ReadError::Flatbuffers(_)
+            | decoder::ReadError::BadPacketPrefix { .. }
+            | decoder::ReadError::MissingPacketSizePrefix
+            | decoder::ReadError::UnknownFrameFormat
+            | decoder::ReadError::UnknownTriggerSource
+            | decoder::ReadError::XOverflow { .. }
+            | decoder::ReadError::YOverflow { .. }
+    )
+}
+
+impl Decoder {
+    /// Returns the dimensions of the only events track selected by track_ids (after downsampling).
+    fn events_dimensions(&self, name: &str) -> PyResult<(u16, u16)> {
+        match self.inner {
+            Some(ref decoder) => single_events_dimensions(
+                decoder
+                    .id_to_track
+                    .iter()
+                    .filter(|(id, track)| {
+                        matches!(track, common::Track::Events { .. }) && decoder.is_selected(**id)
+                    })
+                    .filter_map(|(id, track)| {
+                        track_dimensions(track, self.downsample).map(|dimensions| (*id, dimensions))
+                    })
+                    .collect(),
+                name,
+            ),
+            None => Err(pyo3::exceptions::PyException::new_err(format!(
+                "{name} called after __exit__"
+            ))),
+        }
+    }
+
+    /// Returns the track with the given ID, which must have the given data type and be selected by track_ids.
+    fn selected_track(
+        &self,
+        name: &str,
+        track_id: u32,
+        data_type: &str,
+    ) -> PyResult<common::Track> {
+        let decoder = match self.inner {
+            Some(ref decoder) => decoder,
+            None => {
+                return Err(pyo3::exceptions::PyException::new_err(format!(
+                    "{} called after __exit__",
+                    name
+                )))
+            }
+        };
+        let track = match decoder.id_to_track.get(&track_id) {
+            Some(track) => *track,
+            None => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown track ID {}",
+                    track_id
+                )))
+            }
+        };
+        if track.to_data_type() != data_type {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "the track {} has the data type \"{}\" (expected \"{}\")",
+                track_id,
+                track.to_data_type(),
+                data_type
+            )));
+        }
+        if !decoder.is_selected(track_id) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "the track {} is not selected by track_ids",
+                track_id
+            )));
+        }
+        Ok(track)
+    }
+
+    /// Calls handle on each packet from the beginning of the file until it returns false.
+    ///
+    /// The decoder's position and timestamp state are restored afterwards, even if handle fails
+    /// (a pending seek is discarded).
+    fn scan<F: FnMut(decoder::Packet) -> PyResult<bool>>(
+        &mut self,
+        name: &str,
+        mut handle: F,
+    ) -> PyResult<()> {
+        let decoder = match self.inner {
+            Some(ref mut decoder) => decoder,
+            None => {
+                return Err(pyo3::exceptions::PyException::new_err(format!(
+                    "{} called after __exit__",
+                    name
+                )))
+            }
+        };
+        let token = decoder.state_token();
+        let result = (|| -> PyResult<()> {
+            decoder.seek_to_start()?;
+            while let Some(packet) = decoder.next()? {
+                if !handle(packet)? {
+                    break;
+                }
+            }
+            Ok(())
+        })();
+        decoder.resume_from(&token)?;
+        result
+    }
+
+    /// Like scan, but handle is only called on the packets of the selected tracks with the given data type.
+    ///
+    /// The payloads of the other packets are skipped without being read or decompressed.
+    fn scan_data_type<F: FnMut(decoder::Packet) -> PyResult<bool>>(
+        &mut self,
+        name: &str,
+        data_type: &str,
+        handle: F,
+    ) -> PyResult<()> {
+        let data_type_track_ids: std::collections::HashSet<u32> = match self.inner {
+            Some(ref decoder) => decoder
+                .id_to_track
+                .iter()
+                .filter(|(id, track)| {
+                    track.to_data_type() == data_type && decoder.is_selected(**id)
+                })
+                .map(|(id, _)| *id)
+                .collect(),
+            None => {
+                return Err(pyo3::exceptions::PyException::new_err(format!(
+                    "{name} called after __exit__"
+                )))
+            }
+        };
+        if data_type_track_ids.is_empty() {
+            return Ok(());
+        }
+        let track_ids = self
+            .inner
+            .as_mut()
+            .expect("inner is checked above")
+            .replace_track_ids(Some(data_type_track_ids));
+        let result = self.scan(name, handle);
+        self.inner
+            .as_mut()
+            .expect("scan does not close the decoder")
+            .replace_track_ids(track_ids);
+        result
+    }
+
+    /// Reads the triggers of the selected tracks as (t, source) pairs sorted by timestamp.
+    ///
+    /// Packets from other tracks are skipped without being read, and the decoder's position is restored afterwards.
+    fn collect_triggers(&mut self, name: &str) -> PyResult<Vec<(u64, u8)>> {
+        let unwrap_timestamps = self.unwrap_timestamps;
+        let mut triggers: Vec<(u64, u8)> = Vec::new();
+        self.scan_data_type(name, "triggers", |packet| {
+            if let common::Track::Triggers {
+                ref mut previous_t,
+                ref mut t_offset,
+            } = packet.track
+            {
+                let packet_triggers =
+                    match common::triggers_generated::size_prefixed_root_as_trigger_packet(
+                        packet.buffer,
+                    ) {
+                        Ok(result) => result.elements().unwrap_or_default(),
+                        Err(_) => return Err(decoder::ReadError::MissingPacketSizePrefix.into()),
+                    };
+                for trigger in packet_triggers {
+                    triggers.push((
+                        common::next_t(trigger.t(), previous_t, t_offset, unwrap_timestamps),
+                        trigger_source_to_u8(trigger.source())?,
+                    ));
+                }
+            }
+            Ok(true)
+        })?;
+        // the sort is stable, triggers with identical timestamps keep their order in the file
+        triggers.sort_by_key(|(t, _)| *t);
+        Ok(triggers)
+    }
+
+    /// Counts and reports a corrupt packet in lenient mode, and returns the error otherwise.
+    fn skip_packet(&mut self, error: decoder::ReadError) -> PyResult<()> {
+        if !self.lenient || !is_corrupt_packet(&error) {
+            return Err(error.into());
+        }
+        self.skipped_packets += 1;
+        Python::with_gil(|python| {
+            PyErr::warn_bound(
+                python,
+                &python.get_type_bound::<pyo3::exceptions::PyRuntimeWarning>(),
+                &format!("skipped a corrupt packet ({error})"),
+                1,
+            )
+        })
+    }
+}
+
+/// Returns dimensions that cover the largest events track (tracks of a file usually share the same sensor).
+///
+/// name is the option that requires an events track (used in the error message).
+fn events_dimensions(decoder: &decoder::Decoder, name: &str) -> PyResult<(u16, u16)> {
+    decoder
+        .id_to_track
+        .values()
+        .filter_map(|track| match track {
+            common::Track::Events { dimensions, .. } => Some(*dimensions),
+            _ => None,
+        })
+        .reduce(|a, b| (a.0.max(b.0), a.1.max(b.1)))
+        .ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "{name} requires a file with an events track"
+            ))
+        })
+}
+
+/// Applies the mask, the remap table, and the median denoiser, events mapped outside the track's dimensions are dropped.
+fn map_pixel(
+    mask: Option<&utilities::PixelMask>,
+    remap: Option<&utilities::PixelRemap>,
+    median_denoise: Option<&mut utilities::MedianDenoise>,
+    t: u64,
+    x: u16,
+    y: u16,
+    dimensions: (u16, u16),
+) -> Option<(u16, u16)> {
+    utilities::map_pixel(mask, remap, None, t, x, y)
+        .filter(|(x, y)| *x < dimensions.0 && *y < dimensions.1)
+        .filter(|(x, y)| match median_denoise {
+            Some(median_denoise) => median_denoise.keep(t, *x, *y),
+            None => true,
+        })
+}
+
+/// Decodes an events packet, applies on_overflow, polarity, the pixel mask, the remap table, the median denoiser, and downsample,
+/// and updates the track's timestamp state.
+#[allow(clippy::too_many_arguments)]
+fn parse_events(
+    buffer: &[u8],
+    dimensions: (u16, u16),
+    previous_t: &mut u64,
+    t_offset: &mut u64,
+    unwrap_timestamps: bool,
+    polarity: Option<bool>,
+    mask: Option<&utilities::PixelMask>,
+    remap: Option<&utilities::PixelRemap>,
+    mut median_denoise: Option<&mut utilities::MedianDenoise>,
+    downsample: u16,
+    on_overflow: Overflow,
+) -> Result<Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>, decoder::ReadError> {
+    let events = match common::events_generated::size_prefixed_root_as_event_packet(buffer) {
+        Ok(result) => result.elements().unwrap_or_default(),
+        Err(_) => return Err(decoder::ReadError::MissingPacketSizePrefix),
+    };
+    let mut result = Vec::with_capacity(events.len());
+    for event in events.iter() {
+        // filtered events still update previous_t and t_offset
+        let t = common::next_t(event.t(), previous_t, t_offset, unwrap_timestamps);
+        let (x, y) = match on_overflow.apply(event.x(), event.y(), dimensions)? {
+            Some(coordinates) => coordinates,
+            None => continue,
+        };
+        if polarity.is_some_and(|polarity| event.on() != polarity) {
+            continue;
+        }
+        let (x, y) = match map_pixel(
+            mask,
+            remap,
+            median_denoise.as_deref_mut(),
+            t,
+            x,
+            y,
+            dimensions,
+        ) {
+            Some(coordinates) => coordinates,
+            None => continue,
+        };
+        result.push(neuromorphic_types::DvsEvent {
+            t,
+            x: x / downsample,
+            y: y / downsample,
+            polarity: if event.on() {
+                neuromorphic_types::DvsPolarity::On
+            } else {
+                neuromorphic_types::DvsPolarity::Off
+            },
+        });
+    }
+    Ok(result)
+}
+
+fn packet_events(
+    buffer: &[u8],
+) -> PyResult<flatbuffers::Vector<'_, common::events_generated::Event>> {
+    match common::events_generated::size_prefixed_root_as_event_packet(buffer) {
+        Ok(result) => Ok(result.elements().unwrap_or_default()),
+        Err(_) => Err(decoder::ReadError::MissingPacketSizePrefix.into()),
+    }
 }
 
 #[pymethods]
 impl Decoder {
     #[new]
-    fn new(path: &pyo3::Bound<'_, pyo3::types::PyAny>) -> Result<Self, PyErr> {
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (path, skip_empty = true, unwrap_timestamps = false, polarity = None, downsample = 1, track_ids = None, imus_as_dict = false, lenient = false, on_overflow = "error", mmap = false, zero_origin = false, as_pil = false, mask_pixels = None, remap = None, median_denoise = None, composite_frames = false, frame_background = 0, buffer_size = utilities::BUFFER_SIZE))]
+    fn new(
+        path: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        skip_empty: bool,
+        unwrap_timestamps: bool,
+        polarity: Option<bool>,
+        downsample: u16,
+        track_ids: Option<Vec<u32>>,
+        imus_as_dict: bool,
+        lenient: bool,
+        on_overflow: &str,
+        mmap: bool,
+        zero_origin: bool,
+        as_pil: bool,
+        mask_pixels: Option<Vec<(u16, u16)>>,
+        remap: Option<&pyo3::Bound<'_, pyo3::types::PyAny>>,
+        median_denoise: Option<u64>,
+        composite_frames: bool,
+        frame_background: u8,
+        buffer_size: usize,
+    ) -> Result<Self, PyErr> {
+        if downsample == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "downsample must be larger than 0",
+            ));
+        }
+        let on_overflow = Overflow::from_name(on_overflow).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown on_overflow \"{on_overflow}\" (expected \"error\", \"clamp\", or \"drop\")"
+            ))
+        })?;
+        utilities::check_buffer_size(buffer_size)?;
         Python::with_gil(|python| -> Result<Self, PyErr> {
-            match types::python_path_to_string(python, path) {
-                Ok(result) => match decoder::Decoder::new(result) {
-                    Ok(result) => Ok(Decoder {
-                        inner: Some(result),
-                    }),
+            // fails early if PIL is not installed, rather than on the first frame
+            if as_pil {
+                types::import_optional(python, "PIL.Image", "pillow", "as_pil")?;
+            }
+            match types::python_source(python, path) {
+                Ok(result) => match decoder::Decoder::new(
+                    result,
+                    track_ids.map(|track_ids| track_ids.into_iter().collect()),
+                    mmap,
+                    buffer_size,
+                ) {
+                    Ok(result) => {
+                        let mask = mask_pixels
+                            .map(|mask_pixels| -> PyResult<_> {
+                                Ok(utilities::PixelMask::new(
+                                    events_dimensions(&result, "mask_pixels")?,
+                                    &mask_pixels,
+                                )?)
+                            })
+                            .transpose()?;
+                        let remap = remap
+                            .map(|remap| {
+                                utilities::PixelRemap::from_array(
+                                    remap,
+                                    events_dimensions(&result, "remap")?,
+                                )
+                            })
+                            .transpose()?;
+                        Ok(Decoder {
+                            inner: Some(result),
+                            skip_empty,
+                            unwrap_timestamps,
+                            polarity,
+                            downsample,
+                            imus_as_dict,
+                            lenient,
+                            skipped_packets: 0,
+                            on_overflow,
+                            zero_origin,
+                            origin_t: None,
+                            as_pil,
+                            composite_frames,
+                            frame_background,
+                            mask: mask.map(std::sync::Arc::new),
+                            remap: remap.map(std::sync::Arc::new),
+                            median_denoise,
+                            median_surfaces: std::collections::HashMap::new(),
+                            packet_index: 0,
+                        })
+                    }
                     Err(error) => Err(PyErr::from(error)),
                 },
                 Err(error) => Err(error),
@@ -163,7 +835,7 @@ impl Decoder {
                     .map(|(id, track)| Track {
                         id: *id,
                         data_type: track.to_data_type().to_owned(),
-                        dimensions: track.dimensions(),
+                        dimensions: track_dimensions(track, self.downsample),
                     })
                     .collect();
                 tracks.sort_by_key(|track| track.id);
@@ -184,6 +856,544 @@ impl Decoder {
         }
     }
 
+    /// Returns the camera information found in the description as a dict.
+    ///
+    /// The dict may have the keys "device_name", "serial_number", and "sources" (track ID to source string),
+    /// keys are omitted if the description does not have the corresponding fields.
+    fn parsed_description(&self) -> PyResult<PyObject> {
+        match self.inner {
+            Some(ref decoder) => {
+                let device_info = common::description_to_device_info(decoder.description());
+                Python::with_gil(|python| -> PyResult<PyObject> {
+                    let dict = pyo3::types::PyDict::new_bound(python);
+                    if let Some(device_name) = device_info.device_name {
+                        dict.set_item("device_name", device_name)?;
+                    }
+                    if let Some(serial_number) = device_info.serial_number {
+                        dict.set_item("serial_number", serial_number)?;
+                    }
+                    if !device_info.id_to_source.is_empty() {
+                        dict.set_item("sources", device_info.id_to_source)?;
+                    }
+                    Ok(dict.into())
+                })
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "parsed_description called after __exit__",
+            )),
+        }
+    }
+
+    /// Returns an integer that resume_from accepts to continue decoding from the current position.
+    ///
+    /// The lower 64 bits are the byte offset of the next packet, the upper bits encode the origin (zero_origin)
+    /// and the timestamp unwrapping state of each track.
+    fn state_token(&self, python: Python) -> PyResult<PyObject> {
+        match self.inner {
+            Some(ref decoder) => {
+                let token = decoder.state_token();
+                let mut bytes = Vec::with_capacity(16 + token.timestamp_states.len() * 16);
+                bytes.extend_from_slice(&token.position.to_le_bytes());
+                // 0 encodes None
+                let origin = self.origin_t.map_or(0, |origin_t| origin_t + 1);
+                bytes.extend_from_slice(&origin.to_le_bytes());
+                for (previous_t, t_offset) in token.timestamp_states {
+                    bytes.extend_from_slice(&previous_t.to_le_bytes());
+                    bytes.extend_from_slice(&t_offset.to_le_bytes());
+                }
+                Ok(python
+                    .get_type_bound::<pyo3::types::PyLong>()
+                    .call_method1(
+                        "from_bytes",
+                        (pyo3::types::PyBytes::new_bound(python, &bytes), "little"),
+                    )?
+                    .unbind())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "state_token called after __exit__",
+            )),
+        }
+    }
+
+    fn resume_from(&mut self, token: &pyo3::Bound<'_, pyo3::types::PyAny>) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                let length = 16 + decoder.id_to_track.len() * 16;
+                let bytes = token
+                    .call_method1("to_bytes", (length, "little"))
+                    .and_then(|bytes| Ok(bytes.downcast_into::<pyo3::types::PyBytes>()?))
+                    .map_err(|_| {
+                        pyo3::exceptions::PyValueError::new_err(
+                            "the token was not returned by state_token for this file",
+                        )
+                    })?;
+                let words: Vec<u64> = bytes
+                    .as_bytes()
+                    .chunks_exact(8)
+                    .map(|chunk| u64::from_le_bytes(chunk.try_into().expect("eight bytes")))
+                    .collect();
+                decoder.resume_from(&decoder::StateToken {
+                    position: words[0],
+                    timestamp_states: words[2..]
+                        .chunks_exact(2)
+                        .map(|state| (state[0], state[1]))
+                        .collect(),
+                })?;
+                self.origin_t = words[1].checked_sub(1);
+                self.median_surfaces.clear();
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "resume_from called after __exit__",
+            )),
+        }
+    }
+
+    /// Skips, for each track, the packets that begin before t_us.
+    ///
+    /// t_us is relative to the origin if zero_origin is true (the origin is detected first if needed).
+    fn seek(&mut self, t_us: u64) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                let t = if self.zero_origin {
+                    if self.origin_t.is_none() {
+                        self.origin_t = decoder.first_t()?;
+                    }
+                    t_us.saturating_add(self.origin_t.unwrap_or(0))
+                } else {
+                    t_us
+                };
+                decoder.seek(t, self.unwrap_timestamps)?;
+                self.median_surfaces.clear();
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "seek called after __exit__",
+            )),
+        }
+    }
+
+    fn seek_to_start(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                decoder.seek_to_start()?;
+                self.median_surfaces.clear();
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "seek_to_start called after __exit__",
+            )),
+        }
+    }
+
+    /// Moves the decoder back to the first packet, as if the file had just been opened.
+    ///
+    /// Unlike seek_to_start, this also clears the timestamp origin (zero_origin) and the skipped packets count.
+    fn reset(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                decoder.seek_to_start()?;
+                self.origin_t = None;
+                self.skipped_packets = 0;
+                self.median_surfaces.clear();
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "reset called after __exit__",
+            )),
+        }
+    }
+
+    /// Number of complete packets in the file, regardless of skip_empty, polarity, and the decoder's position.
+    fn __len__(&mut self) -> PyResult<usize> {
+        match self.inner {
+            Some(ref mut decoder) => Ok(decoder.packet_count()?),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "__len__ called after __exit__",
+            )),
+        }
+    }
+
+    /// Returns a (height, width) array with the timestamp of each pixel's first event at or after after_t.
+    ///
+    /// Pixels without such events are set to 2^64 - 1. The whole file is scanned in a single pass,
+    /// on_overflow, polarity, and downsample are applied, and the decoder's position is restored afterwards
+    /// (a pending seek is discarded).
+    fn first_activation(&mut self, track_id: u32, after_t: u64) -> PyResult<PyObject> {
+        let unwrap_timestamps = self.unwrap_timestamps;
+        let polarity = self.polarity;
+        let mask = self.mask.clone();
+        let remap = self.remap.clone();
+        let median_denoise = self.median_denoise;
+        let downsample = self.downsample;
+        let on_overflow = self.on_overflow;
+        let track = self.selected_track("first_activation", track_id, "events")?;
+        let (width, height) =
+            track_dimensions(&track, downsample).expect("events tracks have dimensions");
+        let mut first_ts =
+            ndarray::Array2::<u64>::from_elem((height as usize, width as usize), u64::MAX);
+        let mut remaining = first_ts.len();
+        let mut median_surface = None;
+        self.scan("first_activation", |packet| {
+            if packet.track_id != track_id {
+                return Ok(true);
+            }
+            if let common::Track::Events {
+                dimensions,
+                ref mut previous_t,
+                ref mut t_offset,
+            } = packet.track
+            {
+                let mut median_denoise = median_denoise.map(|tolerance| {
+                    median_surface.get_or_insert_with(|| {
+                        utilities::MedianDenoise::new(*dimensions, tolerance)
+                    })
+                });
+                for event in packet_events(packet.buffer)? {
+                    let t = common::next_t(event.t(), previous_t, t_offset, unwrap_timestamps);
+                    let (x, y) = match on_overflow.apply(event.x(), event.y(), *dimensions)? {
+                        Some(coordinates) => coordinates,
+                        None => continue,
+                    };
+                    if t < after_t || polarity.is_some_and(|polarity| event.on() != polarity) {
+                        continue;
+                    }
+                    let (x, y) = match map_pixel(
+                        mask.as_deref(),
+                        remap.as_deref(),
+                        median_denoise.as_deref_mut(),
+                        t,
+                        x,
+                        y,
+                        *dimensions,
+                    ) {
+                        Some(coordinates) => coordinates,
+                        None => continue,
+                    };
+                    let first_t =
+                        &mut first_ts[[(y / downsample) as usize, (x / downsample) as usize]];
+                    if *first_t == u64::MAX {
+                        *first_t = t;
+                        remaining -= 1;
+                    }
+                }
+            }
+            Ok(remaining > 0)
+        })?;
+        Ok(Python::with_gil(|python| {
+            first_ts.to_pyarray_bound(python).to_object(python)
+        }))
+    }
+
+    /// Returns the events of an events track and the samples of an IMU track as a single array sorted by timestamp.
+    ///
+    /// kind is 0 for events and 1 for IMU samples. Events have NaN accelerometer and gyroscope values,
+    /// IMU samples have zero x, y, and on. Each record uses 38 bytes (three times the size of a DVS event),
+    /// and both tracks are loaded in memory before sorting, hence this function is only suitable for recordings
+    /// that fit in memory several times over. on_overflow, polarity, and downsample are applied to events, and the decoder's
+    /// position is restored afterwards (a pending seek is discarded).
+    fn collect_fused(&mut self, event_track_id: u32, imu_track_id: u32) -> PyResult<PyObject> {
+        const SIZE: usize = 38;
+        let unwrap_timestamps = self.unwrap_timestamps;
+        let polarity = self.polarity;
+        let mask = self.mask.clone();
+        let remap = self.remap.clone();
+        let median_denoise = self.median_denoise;
+        let downsample = self.downsample;
+        let on_overflow = self.on_overflow;
+        self.selected_track("collect_fused", event_track_id, "events")?;
+        self.selected_track("collect_fused", imu_track_id, "imus")?;
+        let mut records: Vec<[u8; SIZE]> = Vec::new();
+        let mut median_surface = None;
+        self.scan("collect_fused", |packet| {
+            if packet.track_id == event_track_id {
+                if let common::Track::Events {
+                    dimensions,
+                    ref mut previous_t,
+                    ref mut t_offset,
+                } = packet.track
+                {
+                    let mut median_denoise = median_denoise.map(|tolerance| {
+                        median_surface.get_or_insert_with(|| {
+                            utilities::MedianDenoise::new(*dimensions, tolerance)
+                        })
+                    });
+                    for event in packet_events(packet.buffer)? {
+                        let t = common::next_t(event.t(), previous_t, t_offset, unwrap_timestamps);
+                        let (x, y) = match on_overflow.apply(event.x(), event.y(), *dimensions)? {
+                            Some(coordinates) => coordinates,
+                            None => continue,
+                        };
+                        if polarity.is_some_and(|polarity| event.on() != polarity) {
+                            continue;
+                        }
+                        let (x, y) = match map_pixel(
+                            mask.as_deref(),
+                            remap.as_deref(),
+                            median_denoise.as_deref_mut(),
+                            t,
+                            x,
+                            y,
+                            *dimensions,
+                        ) {
+                            Some(coordinates) => coordinates,
+                            None => continue,
+                        };
+                        let mut record = [0u8; SIZE];
+                        record[0..8].copy_from_slice(&t.to_le_bytes());
+                        record[8] = 0;
+                        record[9..11].copy_from_slice(&(x / downsample).to_le_bytes());
+                        record[11..13].copy_from_slice(&(y / downsample).to_le_bytes());
+                        record[13] = if event.on() { 1 } else { 0 };
+                        for offset in (14..SIZE).step_by(4) {
+                            record[offset..offset + 4].copy_from_slice(&f32::NAN.to_le_bytes());
+                        }
+                        records.push(record);
+                    }
+                }
+            } else if packet.track_id == imu_track_id {
+                if let common::Track::Imus {
+                    ref mut previous_t,
+                    ref mut t_offset,
+                } = packet.track
+                {
+                    let imus = match common::imus_generated::size_prefixed_root_as_imu_packet(
+                        packet.buffer,
+                    ) {
+                        Ok(result) => result.elements().unwrap_or_default(),
+                        Err(_) => return Err(decoder::ReadError::MissingPacketSizePrefix.into()),
+                    };
+                    for imu in imus {
+                        let t = common::next_t(imu.t(), previous_t, t_offset, unwrap_timestamps);
+                        let mut record = [0u8; SIZE];
+                        record[0..8].copy_from_slice(&t.to_le_bytes());
+                        record[8] = 1;
+                        for (index, value) in [
+                            imu.accelerometer_x(),
+                            imu.accelerometer_y(),
+                            imu.accelerometer_z(),
+                            imu.gyroscope_x(),
+                            imu.gyroscope_y(),
+                            imu.gyroscope_z(),
+                        ]
+                        .iter()
+                        .enumerate()
+                        {
+                            record[14 + index * 4..18 + index * 4]
+                                .copy_from_slice(&value.to_le_bytes());
+                        }
+                        records.push(record);
+                    }
+                }
+            }
+            Ok(true)
+        })?;
+        // the sort is stable, records with identical timestamps keep their order in the file
+        records.sort_by_key(|record| u64::from_le_bytes(record[0..8].try_into().expect("8 bytes")));
+        Python::with_gil(|python| -> PyResult<PyObject> {
+            let array = types::ArrayType::AedatFused
+                .new_array(python, records.len() as numpy::npyffi::npy_intp);
+            unsafe {
+                for (index, record) in records.iter().enumerate() {
+                    let record_cell =
+                        types::array_at(python, array, index as numpy::npyffi::npy_intp);
+                    std::ptr::copy(record.as_ptr(), record_cell, record.len());
+                }
+                Ok(PyObject::from_owned_ptr(
+                    python,
+                    array as *mut pyo3::ffi::PyObject,
+                ))
+            }
+        })
+    }
+
+    /// Returns the triggers of the tracks selected by track_ids as a single array sorted by timestamp.
+    ///
+    /// Packets from other tracks are skipped without being read or decompressed, which is much faster
+    /// than iterating over the file when only triggers are needed. Timestamps are unwrapped if unwrap_timestamps
+    /// is true but not shifted by zero_origin, and the decoder's position is restored afterwards
+    /// (a pending seek is discarded).
+    fn triggers_only(&mut self) -> PyResult<PyObject> {
+        let triggers = self.collect_triggers("triggers_only")?;
+        Python::with_gil(|python| -> PyResult<PyObject> {
+            let array = types::ArrayType::AedatTrigger
+                .new_array(python, triggers.len() as numpy::npyffi::npy_intp);
+            unsafe {
+                for (index, (t, source)) in triggers.iter().enumerate() {
+                    let trigger_cell =
+                        types::array_at(python, array, index as numpy::npyffi::npy_intp);
+                    let mut trigger_array = [0u8; 9];
+                    trigger_array[0..8].copy_from_slice(&t.to_le_bytes());
+                    trigger_array[8] = *source;
+                    std::ptr::copy(trigger_array.as_ptr(), trigger_cell, trigger_array.len());
+                }
+                Ok(PyObject::from_owned_ptr(
+                    python,
+                    array as *mut pyo3::ffi::PyObject,
+                ))
+            }
+        })
+    }
+
+    /// Returns the timing and geometry of the frames of the selected tracks as a single array, in file order.
+    ///
+    /// Packets from other tracks are skipped without being read or decompressed, and pixels are not copied.
+    /// t is unwrapped if unwrap_timestamps is true, timestamps are not shifted by zero_origin,
+    /// and the decoder's position is restored afterwards (a pending seek is discarded).
+    fn frame_metadata(&mut self) -> PyResult<PyObject> {
+        const SIZE: usize = 48;
+        let unwrap_timestamps = self.unwrap_timestamps;
+        let mut records: Vec<[u8; SIZE]> = Vec::new();
+        self.scan_data_type("frame_metadata", "frame", |packet| {
+            if let common::Track::Frame {
+                ref mut previous_t,
+                ref mut t_offset,
+                ..
+            } = packet.track
+            {
+                let frame =
+                    match common::frame_generated::size_prefixed_root_as_frame(packet.buffer) {
+                        Ok(result) => result,
+                        Err(_) => return Err(decoder::ReadError::MissingPacketSizePrefix.into()),
+                    };
+                let t = common::next_t(frame.t(), previous_t, t_offset, unwrap_timestamps);
+                let mut record = [0u8; SIZE];
+                record[0..8].copy_from_slice(&t.to_le_bytes());
+                record[8..16].copy_from_slice(&frame.begin_t().to_le_bytes());
+                record[16..24].copy_from_slice(&frame.end_t().to_le_bytes());
+                record[24..32].copy_from_slice(&frame.exposure_begin_t().to_le_bytes());
+                record[32..40].copy_from_slice(&frame.exposure_end_t().to_le_bytes());
+                record[40..42].copy_from_slice(&(frame.width() as u16).to_le_bytes());
+                record[42..44].copy_from_slice(&(frame.height() as u16).to_le_bytes());
+                record[44..46].copy_from_slice(&frame.offset_x().to_le_bytes());
+                record[46..48].copy_from_slice(&frame.offset_y().to_le_bytes());
+                records.push(record);
+            }
+            Ok(true)
+        })?;
+        Python::with_gil(|python| -> PyResult<PyObject> {
+            let array = types::ArrayType::AedatFrameMetadata
+                .new_array(python, records.len() as numpy::npyffi::npy_intp);
+            unsafe {
+                for (index, record) in records.iter().enumerate() {
+                    let record_cell =
+                        types::array_at(python, array, index as numpy::npyffi::npy_intp);
+                    std::ptr::copy(record.as_ptr(), record_cell, record.len());
+                }
+                Ok(PyObject::from_owned_ptr(
+                    python,
+                    array as *mut pyo3::ffi::PyObject,
+                ))
+            }
+        })
+    }
+
+    /// Pairs rising edges with the next falling edge of the same kind (external signal or external generator).
+    ///
+    /// Each record has a start_t, an end_t, the source of the rising edge (1 or 4), and paired (true).
+    /// External signal pulses (source 3) are zero-length paired intervals. Edges without a match
+    /// (a falling edge before any rising edge, two consecutive rising edges, or a rising edge at the end of the file)
+    /// are zero-length intervals with paired set to false and their own source. Records are sorted by start_t.
+    /// Triggers are read like triggers_only.
+    fn trigger_intervals(&mut self) -> PyResult<PyObject> {
+        let triggers = self.collect_triggers("trigger_intervals")?;
+        let mut intervals: Vec<(u64, u64, u8, bool)> = Vec::new();
+        // pending rising edges, indexed by kind (0 for the external signal, 1 for the external generator)
+        let mut rising_edges: [Option<(u64, u8)>; 2] = [None, None];
+        for (t, source) in triggers {
+            match source {
+                1 | 4 => {
+                    let kind = (source == 4) as usize;
+                    if let Some((start_t, start_source)) = rising_edges[kind].replace((t, source)) {
+                        intervals.push((start_t, start_t, start_source, false));
+                    }
+                }
+                2 | 5 => {
+                    let kind = (source == 5) as usize;
+                    match rising_edges[kind].take() {
+                        Some((start_t, start_source)) => {
+                            intervals.push((start_t, t, start_source, true))
+                        }
+                        None => intervals.push((t, t, source, false)),
+                    }
+                }
+                3 => intervals.push((t, t, source, true)),
+                _ => (),
+            }
+        }
+        for (start_t, start_source) in rising_edges.into_iter().flatten() {
+            intervals.push((start_t, start_t, start_source, false));
+        }
+        intervals.sort_by_key(|interval| interval.0);
+        Python::with_gil(|python| -> PyResult<PyObject> {
+            let array = types::ArrayType::AedatTriggerInterval
+                .new_array(python, intervals.len() as numpy::npyffi::npy_intp);
+            unsafe {
+                for (index, (start_t, end_t, source, paired)) in intervals.iter().enumerate() {
+                    let interval_cell =
+                        types::array_at(python, array, index as numpy::npyffi::npy_intp);
+                    let mut interval_array = [0u8; 18];
+                    interval_array[0..8].copy_from_slice(&start_t.to_le_bytes());
+                    interval_array[8..16].copy_from_slice(&end_t.to_le_bytes());
+                    interval_array[16] = *source;
+                    interval_array[17] = if *paired { 1 } else { 0 };
+                    std::ptr::copy(interval_array.as_ptr(), interval_cell, interval_array.len());
+                }
+                Ok(PyObject::from_owned_ptr(
+                    python,
+                    array as *mut pyo3::ffi::PyObject,
+                ))
+            }
+        })
+    }
+
+    #[getter]
+    fn on_overflow(&self) -> &'static str {
+        self.on_overflow.to_str()
+    }
+
+    /// Width of the file's events track, raises if the file has zero or several events tracks.
+    #[getter]
+    fn width(&self) -> PyResult<u16> {
+        Ok(self.events_dimensions("width")?.0)
+    }
+
+    /// Height of the file's events track, raises if the file has zero or several events tracks.
+    #[getter]
+    fn height(&self) -> PyResult<u16> {
+        Ok(self.events_dimensions("height")?.1)
+    }
+
+    /// Whether the file is memory-mapped, false if mmap was not requested or if the source cannot be mapped.
+    #[getter]
+    fn mmap(&self) -> PyResult<bool> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.is_mapped()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "mmap called after __exit__",
+            )),
+        }
+    }
+
+    /// Returns the number of corrupt packets skipped so far (always 0 if lenient is false).
+    fn skipped_packets(&self) -> usize {
+        self.skipped_packets
+    }
+
+    /// Returns the timestamp subtracted from all the tracks if zero_origin is true,
+    /// None until a timestamp has been decoded (and always None if zero_origin is false).
+    ///
+    /// The origin is detected once and kept by seek and seek_to_start, resume_from restores the origin saved in the token
+    /// and reset clears it.
+    fn origin_t(&self) -> Option<u64> {
+        self.origin_t
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.inner.is_none()
+    }
+
     fn __enter__(slf: Py<Self>) -> Py<Self> {
         slf
     }
@@ -207,255 +1417,570 @@ impl Decoder {
         Ok(shell.into())
     }
 
+    /// Returns an iterator over (packet_index, (track, packet)) tuples.
+    ///
+    /// packet_index is the packet's position in the file. It counts the packets of all the tracks, including
+    /// the tracks excluded by track_ids, the packets skipped by seek, and the empty packets skipped by the decoder.
+    /// The iterator shares this decoder's file handle.
+    fn enumerate(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        if shell.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called enumerate after __exit__",
+            ));
+        }
+        let python = shell.py();
+        let decoder: Py<Decoder> = shell.into();
+        Py::new(python, EnumeratedDecoder { decoder })
+    }
+
     fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<(Track, PyObject)>> {
-        let packet = match shell.inner {
-            Some(ref mut decoder) => match decoder.next() {
-                Ok(result) => match result {
-                    Some(result) => result,
-                    None => return Ok(None),
+        let skip_empty = shell.skip_empty;
+        let unwrap_timestamps = shell.unwrap_timestamps;
+        let polarity = shell.polarity;
+        let mask = shell.mask.clone();
+        let remap = shell.remap.clone();
+        let median_denoise = shell.median_denoise;
+        let downsample = shell.downsample;
+        let imus_as_dict = shell.imus_as_dict;
+        let on_overflow = shell.on_overflow;
+        let zero_origin = shell.zero_origin;
+        let as_pil = shell.as_pil;
+        let composite_frames = shell.composite_frames;
+        let frame_background = shell.frame_background;
+        let python = shell.py();
+        loop {
+            let mut origin_t = shell.origin_t;
+            // the packet borrows the decoder, hence the surfaces are moved out of shell until the packet is parsed
+            let mut median_surfaces = std::mem::take(&mut shell.median_surfaces);
+            // reading and decompressing release the GIL
+            let packet = match shell.inner {
+                Some(ref mut decoder) => match python.allow_threads(move || decoder.next()) {
+                    Ok(result) => match result {
+                        Some(result) => result,
+                        None => return Ok(None),
+                    },
+                    Err(error) => {
+                        shell.median_surfaces = median_surfaces;
+                        shell.skip_packet(error)?;
+                        continue;
+                    }
                 },
-                Err(result) => return Err(result.into()),
-            },
-            None => {
-                return Err(pyo3::exceptions::PyException::new_err(
-                    "__next__ called after __exit__",
-                ))
-            }
-        };
-        Python::with_gil(|python| -> PyResult<Option<(Track, PyObject)>> {
-            let track = Track {
-                id: packet.track_id,
-                data_type: packet.track.to_data_type().to_owned(),
-                dimensions: packet.track.dimensions(),
+                None => {
+                    return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                        "__next__ called after __exit__",
+                    ))
+                }
             };
-            let packet = match packet.track {
-                common::Track::Events {
-                    dimensions,
-                    ref mut previous_t,
-                } => {
-                    use common::events_generated::size_prefixed_root_as_event_packet;
-                    let events = match size_prefixed_root_as_event_packet(packet.buffer) {
-                        Ok(result) => match result.elements() {
-                            Some(result) => result,
-                            None => return Err(decoder::ReadError::EmptyEventsPacket.into()),
-                        },
-                        Err(_) => return Err(decoder::ReadError::MissingPacketSizePrefix.into()),
-                    };
-                    let length = events.len() as numpy::npyffi::npy_intp;
-                    let array = types::ArrayType::Dvs.new_array(python, length);
-                    unsafe {
-                        for index in 0..length {
-                            let event_cell = types::array_at(python, array, index);
-                            let event = events.get(index as usize);
-                            let t = event.t().max(*previous_t as i64) as u64;
-                            *previous_t = t;
-                            let x = event.x();
-                            let y = event.y();
-                            if x < 0 || x >= dimensions.0 as i16 {
-                                return Err(decoder::ReadError::XOverflow {
-                                    x,
-                                    width: dimensions.0,
-                                }
-                                .into());
-                            }
-                            if y < 0 || y >= dimensions.1 as i16 {
-                                return Err(decoder::ReadError::YOverflow {
-                                    y,
-                                    height: dimensions.1,
-                                }
-                                .into());
+            let packet_index = packet.index;
+            let result = Python::with_gil(|python| -> Result<_, NextError> {
+                let track = Track {
+                    id: packet.track_id,
+                    data_type: packet.track.to_data_type().to_owned(),
+                    dimensions: track_dimensions(packet.track, downsample),
+                };
+                let packet = match packet.track {
+                    common::Track::Events {
+                        dimensions,
+                        ref mut previous_t,
+                        ref mut t_offset,
+                    } => {
+                        let dimensions = *dimensions;
+                        let median_denoise = median_denoise.map(|tolerance| {
+                            median_surfaces.entry(packet.track_id).or_insert_with(|| {
+                                utilities::MedianDenoise::new(dimensions, tolerance)
+                            })
+                        });
+                        // parsing does not require the GIL, which is only held to copy the events to the array
+                        let mut events = python.allow_threads(|| {
+                            parse_events(
+                                packet.buffer,
+                                dimensions,
+                                previous_t,
+                                t_offset,
+                                unwrap_timestamps,
+                                polarity,
+                                mask.as_deref(),
+                                remap.as_deref(),
+                                median_denoise,
+                                downsample,
+                                on_overflow,
+                            )
+                        })?;
+                        if events.is_empty() && skip_empty {
+                            return Ok(None);
+                        }
+                        for event in events.iter_mut() {
+                            event.t = shift_t(event.t, zero_origin, &mut origin_t);
+                        }
+                        let array = types::ArrayType::Dvs
+                            .new_array(python, events.len() as numpy::npyffi::npy_intp);
+                        unsafe {
+                            if !events.is_empty() {
+                                std::ptr::copy(
+                                    events.as_ptr() as *const u8,
+                                    types::array_at(python, array, 0),
+                                    std::mem::size_of_val(events.as_slice()),
+                                );
                             }
-                            let mut event_array = [0u8; 13];
-                            event_array[0..8].copy_from_slice(&t.to_le_bytes());
-                            event_array[8..10].copy_from_slice(&(x as u16).to_le_bytes());
-                            event_array[10..12].copy_from_slice(&(y as u16).to_le_bytes());
-                            event_array[12] = if event.on() { 1 } else { 0 };
-                            std::ptr::copy(event_array.as_ptr(), event_cell, event_array.len());
+                            PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
                         }
-                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
                     }
-                }
-                common::Track::Frame {
-                    ref mut previous_t, ..
-                } => {
-                    let frame =
-                        match common::frame_generated::size_prefixed_root_as_frame(packet.buffer) {
+                    common::Track::Frame {
+                        dimensions,
+                        ref mut previous_t,
+                        ref mut t_offset,
+                    } => {
+                        let frame = match common::frame_generated::size_prefixed_root_as_frame(
+                            packet.buffer,
+                        ) {
                             Ok(result) => result,
                             Err(_) => {
-                                return Err(PyErr::from(
-                                    decoder::ReadError::MissingPacketSizePrefix,
-                                ))
+                                return Err(decoder::ReadError::MissingPacketSizePrefix.into())
                             }
                         };
-                    let t = frame.t().max(*previous_t as i64) as u64;
-                    *previous_t = t;
-                    Frame {
-                        t,
-                        begin_t: frame.begin_t(),
-                        end_t: frame.end_t(),
-                        exposure_begin_t: frame.exposure_begin_t(),
-                        exposure_end_t: frame.exposure_end_t(),
-                        format: match frame.format() {
-                            common::frame_generated::FrameFormat::Gray => "L".to_owned(),
-                            common::frame_generated::FrameFormat::Bgr => "RGB".to_owned(),
-                            common::frame_generated::FrameFormat::Bgra => "RGBA".to_owned(),
-                            _ => return Err(PyErr::from(decoder::ReadError::UnknownFrameFormat)),
-                        },
-                        offset_x: frame.offset_x(),
-                        offset_y: frame.offset_y(),
-                        pixels: match frame.format() {
-                            common::frame_generated::FrameFormat::Gray => {
-                                let dimensions = [frame.height() as usize, frame.width() as usize]
-                                    .into_dimension();
-                                match frame.pixels() {
-                                    Some(result) => result
-                                        .bytes()
-                                        .to_pyarray_bound(python)
-                                        .reshape(dimensions)?
+                        let t = shift_t(
+                            common::next_t(frame.t(), previous_t, t_offset, unwrap_timestamps),
+                            zero_origin,
+                            &mut origin_t,
+                        );
+                        // the other timestamps are raw (not unwrapped) but they share the origin
+                        let offset = if zero_origin {
+                            origin_t.unwrap_or(0) as i64
+                        } else {
+                            0
+                        };
+                        let mut frame = Frame {
+                            t,
+                            begin_t: frame.begin_t() - offset,
+                            end_t: frame.end_t() - offset,
+                            exposure_begin_t: frame.exposure_begin_t() - offset,
+                            exposure_end_t: frame.exposure_end_t() - offset,
+                            format: match frame.format() {
+                                common::frame_generated::FrameFormat::Gray => "L".to_owned(),
+                                common::frame_generated::FrameFormat::Bgr => "RGB".to_owned(),
+                                common::frame_generated::FrameFormat::Bgra => "RGBA".to_owned(),
+                                _ => return Err(decoder::ReadError::UnknownFrameFormat.into()),
+                            },
+                            offset_x: frame.offset_x(),
+                            offset_y: frame.offset_y(),
+                            pixels: match frame.format() {
+                                common::frame_generated::FrameFormat::Gray => {
+                                    let dimensions =
+                                        [frame.height() as usize, frame.width() as usize]
+                                            .into_dimension();
+                                    match frame.pixels() {
+                                        Some(result) => result
+                                            .bytes()
+                                            .to_pyarray_bound(python)
+                                            .reshape(dimensions)?
+                                            .to_object(python),
+                                        None => numpy::array::PyArray2::<u8>::zeros_bound(
+                                            python, dimensions, false,
+                                        )
                                         .to_object(python),
-                                    None => numpy::array::PyArray2::<u8>::zeros_bound(
-                                        python, dimensions, false,
-                                    )
-                                    .to_object(python),
+                                    }
                                 }
-                            }
-                            common::frame_generated::FrameFormat::Bgr
-                            | common::frame_generated::FrameFormat::Bgra => {
-                                let channels = if frame.format()
-                                    == common::frame_generated::FrameFormat::Bgr
-                                {
-                                    3_usize
-                                } else {
-                                    4_usize
-                                };
-                                let dimensions =
-                                    [frame.height() as usize, frame.width() as usize, channels]
-                                        .into_dimension();
-                                match frame.pixels() {
-                                    Some(result) => {
-                                        let mut pixels = result.bytes().to_owned();
-                                        for index in 0..(pixels.len() / channels) {
-                                            pixels.swap(index * channels, index * channels + 2);
+                                common::frame_generated::FrameFormat::Bgr
+                                | common::frame_generated::FrameFormat::Bgra => {
+                                    let channels = if frame.format()
+                                        == common::frame_generated::FrameFormat::Bgr
+                                    {
+                                        3_usize
+                                    } else {
+                                        4_usize
+                                    };
+                                    let dimensions =
+                                        [frame.height() as usize, frame.width() as usize, channels]
+                                            .into_dimension();
+                                    match frame.pixels() {
+                                        Some(result) => {
+                                            let mut pixels = result.bytes().to_owned();
+                                            for index in 0..(pixels.len() / channels) {
+                                                pixels.swap(index * channels, index * channels + 2);
+                                            }
+                                            pixels
+                                                .to_pyarray_bound(python)
+                                                .reshape(dimensions)?
+                                                .to_object(python)
                                         }
-                                        pixels
-                                            .to_pyarray_bound(python)
-                                            .reshape(dimensions)?
-                                            .to_object(python)
+                                        None => numpy::array::PyArray3::<u8>::zeros_bound(
+                                            python, dimensions, false,
+                                        )
+                                        .to_object(python),
                                     }
-                                    None => numpy::array::PyArray3::<u8>::zeros_bound(
-                                        python, dimensions, false,
-                                    )
-                                    .to_object(python),
                                 }
-                            }
-                            _ => return Err(PyErr::from(decoder::ReadError::UnknownFrameFormat)),
-                        },
-                    }
-                    .into_py(python)
-                }
-                common::Track::Imus { ref mut previous_t } => {
-                    let imus = match common::imus_generated::size_prefixed_root_as_imu_packet(
-                        packet.buffer,
-                    ) {
-                        Ok(result) => match result.elements() {
-                            Some(result) => result,
-                            None => return Err(PyErr::from(decoder::ReadError::EmptyEventsPacket)),
-                        },
-                        Err(error) => {
-                            return Err(PyErr::from(decoder::ReadError::MissingPacketSizePrefix));
+                                _ => return Err(decoder::ReadError::UnknownFrameFormat.into()),
+                            },
+                        };
+                        if composite_frames {
+                            frame.pixels = composite_frame(
+                                python,
+                                &frame.pixels,
+                                frame.offset_x,
+                                frame.offset_y,
+                                *dimensions,
+                                frame_background,
+                            )?;
                         }
-                    };
-                    let length = imus.len() as numpy::npyffi::npy_intp;
-                    let array = types::ArrayType::AedatImu.new_array(python, length);
-                    unsafe {
-                        let mut index = 0;
-                        for imu in imus {
-                            let t = imu.t().max(*previous_t as i64) as u64;
-                            *previous_t = t;
-                            let imu_cell = types::array_at(python, array, index);
-                            let mut imu_array = [0u8; 48];
-                            imu_array[0..8].copy_from_slice(&t.to_le_bytes());
-                            imu_array[8..12].copy_from_slice(&(imu.temperature()).to_le_bytes());
-                            imu_array[12..16]
-                                .copy_from_slice(&(imu.accelerometer_x()).to_le_bytes());
-                            imu_array[16..20]
-                                .copy_from_slice(&(imu.accelerometer_y()).to_le_bytes());
-                            imu_array[20..24]
-                                .copy_from_slice(&(imu.accelerometer_z()).to_le_bytes());
-                            imu_array[24..28].copy_from_slice(&(imu.gyroscope_x()).to_le_bytes());
-                            imu_array[28..32].copy_from_slice(&(imu.gyroscope_y()).to_le_bytes());
-                            imu_array[32..36].copy_from_slice(&(imu.gyroscope_z()).to_le_bytes());
-                            imu_array[36..40]
-                                .copy_from_slice(&(imu.magnetometer_x()).to_le_bytes());
-                            imu_array[40..44]
-                                .copy_from_slice(&(imu.magnetometer_y()).to_le_bytes());
-                            imu_array[44..48]
-                                .copy_from_slice(&(imu.magnetometer_z()).to_le_bytes());
-                            std::ptr::copy(imu_array.as_ptr(), imu_cell, imu_array.len());
-                            index += 1;
+                        if as_pil {
+                            frame.to_pil(python)?
+                        } else {
+                            frame.into_py(python)
                         }
-                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
                     }
-                }
-                common::Track::Triggers { ref mut previous_t } => {
-                    let triggers =
-                        match common::triggers_generated::size_prefixed_root_as_trigger_packet(
+                    common::Track::Imus {
+                        ref mut previous_t,
+                        ref mut t_offset,
+                    } => {
+                        let imus = match common::imus_generated::size_prefixed_root_as_imu_packet(
                             packet.buffer,
                         ) {
-                            Ok(result) => match result.elements() {
-                                Some(result) => result,
-                                None => {
-                                    return Err(PyErr::from(decoder::ReadError::EmptyEventsPacket))
-                                }
-                            },
+                            Ok(result) => result.elements().unwrap_or_default(),
                             Err(_) => {
-                                return Err(PyErr::from(
-                                    decoder::ReadError::MissingPacketSizePrefix,
-                                ))
+                                return Err(decoder::ReadError::MissingPacketSizePrefix.into());
                             }
                         };
-                    let length = triggers.len() as numpy::npyffi::npy_intp;
-                    let array = types::ArrayType::AedatTrigger.new_array(python, length);
-                    unsafe {
-                        let mut index = 0;
-                        for trigger in triggers {
-                            let t = trigger.t().max(*previous_t as i64) as u64;
-                            *previous_t = t;
-                            let trigger_cell = types::array_at(python, array, index);
-                            let mut trigger_array = [0u8; 9];
-                            trigger_array[0..8].copy_from_slice(&t.to_le_bytes());
-                            use common::triggers_generated::TriggerSource;
-                            trigger_array[8] = match trigger.source() {
-                                TriggerSource::TimestampReset => 0_u8,
-                                TriggerSource::ExternalSignalRisingEdge => 1_u8,
-                                TriggerSource::ExternalSignalFallingEdge => 2_u8,
-                                TriggerSource::ExternalSignalPulse => 3_u8,
-                                TriggerSource::ExternalGeneratorRisingEdge => 4_u8,
-                                TriggerSource::ExternalGeneratorFallingEdge => 5_u8,
-                                TriggerSource::FrameBegin => 6_u8,
-                                TriggerSource::FrameEnd => 7_u8,
-                                TriggerSource::ExposureBegin => 8_u8,
-                                TriggerSource::ExposureEnd => 9_u8,
-                                _ => {
-                                    return Err(PyErr::from(
-                                        decoder::ReadError::UnknownTriggerSource,
-                                    ))
+                        if imus.is_empty() && skip_empty {
+                            return Ok(None);
+                        }
+                        if imus_as_dict {
+                            let mut ts = ndarray::Array1::<u64>::zeros(imus.len());
+                            let mut temperatures = ndarray::Array1::<f32>::zeros(imus.len());
+                            let mut accelerometers = ndarray::Array2::<f32>::zeros((imus.len(), 3));
+                            let mut gyroscopes = ndarray::Array2::<f32>::zeros((imus.len(), 3));
+                            let mut magnetometers = ndarray::Array2::<f32>::zeros((imus.len(), 3));
+                            for (index, imu) in imus.iter().enumerate() {
+                                ts[index] = shift_t(
+                                    common::next_t(
+                                        imu.t(),
+                                        previous_t,
+                                        t_offset,
+                                        unwrap_timestamps,
+                                    ),
+                                    zero_origin,
+                                    &mut origin_t,
+                                );
+                                temperatures[index] = imu.temperature();
+                                accelerometers.row_mut(index).assign(&ndarray::arr1(&[
+                                    imu.accelerometer_x(),
+                                    imu.accelerometer_y(),
+                                    imu.accelerometer_z(),
+                                ]));
+                                gyroscopes.row_mut(index).assign(&ndarray::arr1(&[
+                                    imu.gyroscope_x(),
+                                    imu.gyroscope_y(),
+                                    imu.gyroscope_z(),
+                                ]));
+                                magnetometers.row_mut(index).assign(&ndarray::arr1(&[
+                                    imu.magnetometer_x(),
+                                    imu.magnetometer_y(),
+                                    imu.magnetometer_z(),
+                                ]));
+                            }
+                            let dict = pyo3::types::PyDict::new_bound(python);
+                            dict.set_item("t", ts.to_pyarray_bound(python))?;
+                            dict.set_item("temperature", temperatures.to_pyarray_bound(python))?;
+                            dict.set_item(
+                                "accelerometer",
+                                accelerometers.to_pyarray_bound(python),
+                            )?;
+                            dict.set_item("gyroscope", gyroscopes.to_pyarray_bound(python))?;
+                            dict.set_item("magnetometer", magnetometers.to_pyarray_bound(python))?;
+                            return Ok(Some((track, dict.into())));
+                        }
+                        let length = imus.len() as numpy::npyffi::npy_intp;
+                        let array = types::ArrayType::AedatImu.new_array(python, length);
+                        unsafe {
+                            let mut index = 0;
+                            for imu in imus {
+                                let t = shift_t(
+                                    common::next_t(
+                                        imu.t(),
+                                        previous_t,
+                                        t_offset,
+                                        unwrap_timestamps,
+                                    ),
+                                    zero_origin,
+                                    &mut origin_t,
+                                );
+                                let imu_cell = types::array_at(python, array, index);
+                                let mut imu_array = [0u8; 48];
+                                imu_array[0..8].copy_from_slice(&t.to_le_bytes());
+                                imu_array[8..12]
+                                    .copy_from_slice(&(imu.temperature()).to_le_bytes());
+                                imu_array[12..16]
+                                    .copy_from_slice(&(imu.accelerometer_x()).to_le_bytes());
+                                imu_array[16..20]
+                                    .copy_from_slice(&(imu.accelerometer_y()).to_le_bytes());
+                                imu_array[20..24]
+                                    .copy_from_slice(&(imu.accelerometer_z()).to_le_bytes());
+                                imu_array[24..28]
+                                    .copy_from_slice(&(imu.gyroscope_x()).to_le_bytes());
+                                imu_array[28..32]
+                                    .copy_from_slice(&(imu.gyroscope_y()).to_le_bytes());
+                                imu_array[32..36]
+                                    .copy_from_slice(&(imu.gyroscope_z()).to_le_bytes());
+                                imu_array[36..40]
+                                    .copy_from_slice(&(imu.magnetometer_x()).to_le_bytes());
+                                imu_array[40..44]
+                                    .copy_from_slice(&(imu.magnetometer_y()).to_le_bytes());
+                                imu_array[44..48]
+                                    .copy_from_slice(&(imu.magnetometer_z()).to_le_bytes());
+                                std::ptr::copy(imu_array.as_ptr(), imu_cell, imu_array.len());
+                                index += 1;
+                            }
+                            PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+                        }
+                    }
+                    common::Track::Triggers {
+                        ref mut previous_t,
+                        ref mut t_offset,
+                    } => {
+                        let triggers =
+                            match common::triggers_generated::size_prefixed_root_as_trigger_packet(
+                                packet.buffer,
+                            ) {
+                                Ok(result) => result.elements().unwrap_or_default(),
+                                Err(_) => {
+                                    return Err(decoder::ReadError::MissingPacketSizePrefix.into())
                                 }
                             };
-                            std::ptr::copy(
-                                trigger_array.as_ptr(),
-                                trigger_cell,
-                                trigger_array.len(),
-                            );
-                            index += 1;
+                        if triggers.is_empty() && skip_empty {
+                            return Ok(None);
+                        }
+                        let length = triggers.len() as numpy::npyffi::npy_intp;
+                        let array = types::ArrayType::AedatTrigger.new_array(python, length);
+                        unsafe {
+                            let mut index = 0;
+                            for trigger in triggers {
+                                let t = shift_t(
+                                    common::next_t(
+                                        trigger.t(),
+                                        previous_t,
+                                        t_offset,
+                                        unwrap_timestamps,
+                                    ),
+                                    zero_origin,
+                                    &mut origin_t,
+                                );
+                                let trigger_cell = types::array_at(python, array, index);
+                                let mut trigger_array = [0u8; 9];
+                                trigger_array[0..8].copy_from_slice(&t.to_le_bytes());
+                                trigger_array[8] = trigger_source_to_u8(trigger.source())?;
+                                std::ptr::copy(
+                                    trigger_array.as_ptr(),
+                                    trigger_cell,
+                                    trigger_array.len(),
+                                );
+                                index += 1;
+                            }
+                            PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
                         }
-                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
                     }
+                };
+                Ok(Some((track, packet)))
+            });
+            shell.origin_t = origin_t;
+            shell.median_surfaces = median_surfaces;
+            match result {
+                Ok(Some(result)) => {
+                    shell.packet_index = packet_index;
+                    return Ok(Some(result));
                 }
-            };
-            Ok(Some((track, packet)))
+                Ok(None) => {}
+                Err(NextError::Packet(error)) => shell.skip_packet(error)?,
+                Err(NextError::Python(error)) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Iterates over a decoder's packets and yields (packet_index, (track, packet)) tuples.
+#[pyclass]
+pub struct EnumeratedDecoder {
+    decoder: Py<Decoder>,
+}
+
+#[pymethods]
+impl EnumeratedDecoder {
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(shell: PyRefMut<Self>) -> PyResult<Option<(usize, (Track, PyObject))>> {
+        let decoder = shell.decoder.bind(shell.py());
+        let packet = Decoder::__next__(decoder.try_borrow_mut()?)?;
+        Ok(packet.map(|packet| (decoder.borrow().packet_index, packet)))
+    }
+}
+
+/// Decoder for AEDAT 2.0 and AEDAT 3.1 files, which only yields DVS events.
+#[pyclass]
+pub struct LegacyDecoder {
+    inner: Option<legacy::Decoder>,
+    skip_empty: bool,
+}
+
+impl LegacyDecoder {
+    fn events_dimensions(&self, name: &str) -> PyResult<(u16, u16)> {
+        match self.inner {
+            Some(ref decoder) => single_events_dimensions(decoder.tracks.clone(), name),
+            None => Err(pyo3::exceptions::PyException::new_err(format!(
+                "{name} called after __exit__"
+            ))),
+        }
+    }
+}
+
+#[pymethods]
+impl LegacyDecoder {
+    #[new]
+    #[pyo3(signature = (path, skip_empty = true, buffer_size = utilities::BUFFER_SIZE))]
+    fn new(
+        path: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        skip_empty: bool,
+        buffer_size: usize,
+    ) -> Result<Self, PyErr> {
+        utilities::check_buffer_size(buffer_size)?;
+        Python::with_gil(|python| -> Result<Self, PyErr> {
+            match types::python_source(python, path) {
+                Ok(result) => match legacy::Decoder::new(result, buffer_size) {
+                    Ok(result) => Ok(LegacyDecoder {
+                        inner: Some(result),
+                        skip_empty,
+                    }),
+                    Err(error) => Err(PyErr::from(error)),
+                },
+                Err(error) => Err(error),
+            }
         })
     }
+
+    #[getter]
+    fn version(&self) -> PyResult<&'static str> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.version().to_str()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "version called after __exit__",
+            )),
+        }
+    }
+
+    /// Width of the file's events track, raises if the file has several tracks (AEDAT 3.1 sources).
+    #[getter]
+    fn width(&self) -> PyResult<u16> {
+        Ok(self.events_dimensions("width")?.0)
+    }
+
+    /// Height of the file's events track, raises if the file has several tracks (AEDAT 3.1 sources).
+    #[getter]
+    fn height(&self) -> PyResult<u16> {
+        Ok(self.events_dimensions("height")?.1)
+    }
+
+    fn tracks(&self) -> PyResult<Vec<Track>> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder
+                .tracks
+                .iter()
+                .map(|(id, dimensions)| Track {
+                    id: *id,
+                    data_type: "events".to_owned(),
+                    dimensions: Some(*dimensions),
+                })
+                .collect()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "tracks called after __exit__",
+            )),
+        }
+    }
+
+    /// Moves the decoder back to the first packet, as if the file had just been opened.
+    fn reset(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => Ok(decoder.reset()?),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "reset called after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        if self.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = self.inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<LegacyDecoder>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<(Track, PyObject)>> {
+        let skip_empty = shell.skip_empty;
+        loop {
+            let packet = match shell.inner {
+                Some(ref mut decoder) => match decoder.next() {
+                    Ok(result) => match result {
+                        Some(result) => result,
+                        None => return Ok(None),
+                    },
+                    Err(result) => return Err(result.into()),
+                },
+                None => {
+                    return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                        "__next__ called after __exit__",
+                    ))
+                }
+            };
+            if packet.events.is_empty() && skip_empty {
+                continue;
+            }
+            let events = packet.events;
+            return Python::with_gil(|python| -> PyResult<Option<(Track, PyObject)>> {
+                let length = events.len() as numpy::npyffi::npy_intp;
+                let array = types::ArrayType::Dvs.new_array(python, length);
+                let array = unsafe {
+                    for index in 0..length {
+                        let event_cell = types::array_at(python, array, index);
+                        std::ptr::copy(
+                            &events[index as usize]
+                                as *const neuromorphic_types::DvsEvent<u64, u16, u16>
+                                as *const u8,
+                            event_cell,
+                            std::mem::size_of::<neuromorphic_types::DvsEvent<u64, u16, u16>>(),
+                        );
+                    }
+                    PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+                };
+                Ok(Some((
+                    Track {
+                        id: packet.track_id,
+                        data_type: "events".to_owned(),
+                        dimensions: Some(packet.dimensions),
+                    },
+                    array,
+                )))
+            });
+        }
+    }
 }
 
 #[pyclass]
@@ -550,6 +2075,7 @@ impl Encoder {
                             common::Track::Events {
                                 dimensions,
                                 ref mut previous_t,
+                                ..
                             } => {
                                 let (array, length) =
                                     types::check_array(python, types::ArrayType::Dvs, packet)?;
@@ -595,6 +2121,7 @@ impl Encoder {
                             common::Track::Frame {
                                 dimensions,
                                 ref mut previous_t,
+                                ..
                             } => {
                                 let frame_bound: &pyo3::Bound<'_, Frame> = packet.downcast()?;
                                 let frame = frame_bound.borrow();
@@ -708,7 +2235,9 @@ impl Encoder {
                                     &self.frame_buffer,
                                 )?;
                             }
-                            common::Track::Imus { ref mut previous_t } => {
+                            common::Track::Imus {
+                                ref mut previous_t, ..
+                            } => {
                                 let (array, length) =
                                     types::check_array(python, types::ArrayType::AedatImu, packet)?;
                                 unsafe {
@@ -733,7 +2262,9 @@ impl Encoder {
                                     }),
                                 )?;
                             }
-                            common::Track::Triggers { ref mut previous_t } => {
+                            common::Track::Triggers {
+                                ref mut previous_t, ..
+                            } => {
                                 let (array, length) = types::check_array(
                                     python,
                                     types::ArrayType::AedatTrigger,
@@ -0,0 +1,87 @@
+#[derive(Debug, Clone, Copy)]
+pub enum Colormap {
+    Polarity,
+    Gray,
+    Viridis,
+    Jet,
+}
+
+// (position, color) stops, positions are increasing and span [0, 1]
+// OFF events are red and ON events green, on a gray background
+const POLARITY: [(f64, [u8; 3]); 3] = [
+    (0.0, [255, 0, 0]),
+    (0.5, [128, 128, 128]),
+    (1.0, [0, 255, 0]),
+];
+
+const GRAY: [(f64, [u8; 3]); 2] = [(0.0, [0, 0, 0]), (1.0, [255, 255, 255])];
+
+const VIRIDIS: [(f64, [u8; 3]); 9] = [
+    (0.0, [68, 1, 84]),
+    (0.125, [71, 44, 122]),
+    (0.25, [59, 82, 139]),
+    (0.375, [44, 113, 142]),
+    (0.5, [33, 145, 140]),
+    (0.625, [39, 173, 129]),
+    (0.75, [92, 200, 99]),
+    (0.875, [170, 220, 50]),
+    (1.0, [253, 231, 37]),
+];
+
+const JET: [(f64, [u8; 3]); 6] = [
+    (0.0, [0, 0, 128]),
+    (0.125, [0, 0, 255]),
+    (0.375, [0, 255, 255]),
+    (0.625, [255, 255, 0]),
+    (0.875, [255, 0, 0]),
+    (1.0, [128, 0, 0]),
+];
+
+impl Colormap {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "polarity" => Some(Colormap::Polarity),
+            "gray" => Some(Colormap::Gray),
+            "viridis" => Some(Colormap::Viridis),
+            "jet" => Some(Colormap::Jet),
+            _ => None,
+        }
+    }
+
+    fn stops(&self) -> &'static [(f64, [u8; 3])] {
+        match self {
+            Colormap::Polarity => &POLARITY,
+            Colormap::Gray => &GRAY,
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Jet => &JET,
+        }
+    }
+
+    /// Whether the colormap has a neutral middle that represents 0 in signed renders.
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Colormap::Polarity)
+    }
+
+    /// Maps a value in [0, 1] to a color, values outside the range are clamped.
+    pub fn rgb(&self, value: f64) -> [u8; 3] {
+        let value = if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(0.0, 1.0)
+        };
+        let stops = self.stops();
+        let index = stops
+            .iter()
+            .position(|(position, _)| *position >= value)
+            .unwrap_or(stops.len() - 1)
+            .max(1);
+        let (start, start_color) = stops[index - 1];
+        let (end, end_color) = stops[index];
+        let ratio = (value - start) / (end - start);
+        [0, 1, 2].map(|channel| {
+            (start_color[channel] as f64
+                + ratio * (end_color[channel] as f64 - start_color[channel] as f64))
+                .round() as u8
+        })
+    }
+}
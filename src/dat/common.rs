@@ -1,3 +1,12 @@
+/// Header line written by Encoder when the file ends with an index footer.
+///
+/// The footer is a list of (t, position) pairs (little-endian u64 each) followed by
+/// the number of pairs (little-endian u64) and INDEX_MAGIC. t is the (unwrapped) timestamp
+/// of the event at position, relative to the header's T0, and position is an absolute file offset.
+pub const INDEX_HEADER_LINE: &str = "% Index footer";
+
+pub const INDEX_MAGIC: &[u8; 8] = b"DATINDEX";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Version {
     Dat1,
@@ -82,3 +91,184 @@ impl Version {
         }
     }
 }
+
+/// Position of the event fields in a 64-bit little-endian word.
+///
+/// Each field is an (offset, width) pair in bits, counted from the least significant bit.
+/// Timestamps narrower than 64 bits wrap around and are unwrapped by the decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitLayout {
+    pub t: (u8, u8),
+    pub x: (u8, u8),
+    pub y: (u8, u8),
+    pub payload: (u8, u8),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BitLayoutError {
+    #[error(
+        "unknown bit layout \"{0}\" (must be \"dat1\", \"dat2\", \"dvs128\", or \"davis240\")"
+    )]
+    UnknownPreset(String),
+
+    #[error("unknown bit layout field \"{0}\" (must be \"t\", \"x\", \"y\", or \"payload\")")]
+    UnknownField(String),
+
+    #[error("the bit layout has no \"{0}\" field")]
+    MissingField(&'static str),
+
+    #[error("the \"{name}\" field must be between 1 and {maximum} bits wide (got {width})")]
+    Width {
+        name: &'static str,
+        width: u8,
+        maximum: u8,
+    },
+
+    #[error("the \"{name}\" field (offset {offset}, width {width}) does not fit in a 64-bit word")]
+    Word {
+        name: &'static str,
+        offset: u8,
+        width: u8,
+    },
+
+    #[error("the \"{0}\" and \"{1}\" fields overlap")]
+    Overlap(&'static str, &'static str),
+}
+
+impl BitLayout {
+    pub const DAT1: BitLayout = BitLayout {
+        t: (0, 32),
+        x: (32, 9),
+        y: (41, 8),
+        payload: (49, 4),
+    };
+
+    pub const DAT2: BitLayout = BitLayout {
+        t: (0, 32),
+        x: (32, 14),
+        y: (46, 14),
+        payload: (60, 4),
+    };
+
+    /// jAER DVS128 address (polarity, 7-bit x, 7-bit y) above a 32-bit timestamp.
+    pub const DVS128: BitLayout = BitLayout {
+        t: (0, 32),
+        x: (33, 7),
+        y: (40, 7),
+        payload: (32, 1),
+    };
+
+    /// jAER DAVIS240 address (polarity, 10-bit x, 9-bit y) above a 32-bit timestamp.
+    pub const DAVIS240: BitLayout = BitLayout {
+        t: (0, 32),
+        x: (44, 10),
+        y: (54, 9),
+        payload: (43, 1),
+    };
+
+    /// Checks that each field is between 1 and its maximum width bits wide, fits in the 64-bit word,
+    /// and does not overlap the other fields.
+    ///
+    /// The widths do not have to sum to 64, bits that belong to no field are ignored
+    /// (the DVS128 and DAVIS240 presets leave bits unused).
+    pub fn new(
+        t: (u8, u8),
+        x: (u8, u8),
+        y: (u8, u8),
+        payload: (u8, u8),
+    ) -> Result<Self, BitLayoutError> {
+        let layout = BitLayout { t, x, y, payload };
+        let fields = layout.fields();
+        for (name, (offset, width), maximum) in fields {
+            if width == 0 || width > maximum {
+                return Err(BitLayoutError::Width {
+                    name,
+                    width,
+                    maximum,
+                });
+            }
+            if offset as u16 + width as u16 > 64 {
+                return Err(BitLayoutError::Word {
+                    name,
+                    offset,
+                    width,
+                });
+            }
+        }
+        for (index, &(name, (offset, width), _)) in fields.iter().enumerate() {
+            for &(other_name, (other_offset, other_width), _) in fields[index + 1..].iter() {
+                if offset < other_offset + other_width && other_offset < offset + width {
+                    return Err(BitLayoutError::Overlap(name, other_name));
+                }
+            }
+        }
+        Ok(layout)
+    }
+
+    pub fn from_preset(name: &str) -> Result<Self, BitLayoutError> {
+        match name {
+            "dat1" => Ok(Self::DAT1),
+            "dat2" => Ok(Self::DAT2),
+            "dvs128" => Ok(Self::DVS128),
+            "davis240" => Ok(Self::DAVIS240),
+            name => Err(BitLayoutError::UnknownPreset(name.to_owned())),
+        }
+    }
+
+    /// Parses (name, (offset, width)) pairs, every field must be listed exactly once.
+    pub fn from_fields<'a>(
+        fields: impl Iterator<Item = (&'a str, (u8, u8))>,
+    ) -> Result<Self, BitLayoutError> {
+        let mut t = None;
+        let mut x = None;
+        let mut y = None;
+        let mut payload = None;
+        for (name, field) in fields {
+            match name {
+                "t" => t = Some(field),
+                "x" => x = Some(field),
+                "y" => y = Some(field),
+                "payload" => payload = Some(field),
+                name => return Err(BitLayoutError::UnknownField(name.to_owned())),
+            }
+        }
+        Self::new(
+            t.ok_or(BitLayoutError::MissingField("t"))?,
+            x.ok_or(BitLayoutError::MissingField("x"))?,
+            y.ok_or(BitLayoutError::MissingField("y"))?,
+            payload.ok_or(BitLayoutError::MissingField("payload"))?,
+        )
+    }
+
+    pub fn from_version(version: Version) -> Self {
+        match version {
+            Version::Dat1 => Self::DAT1,
+            Version::Dat2 => Self::DAT2,
+        }
+    }
+
+    /// Returns (name, (offset, width), maximum width) for each field.
+    pub fn fields(&self) -> [(&'static str, (u8, u8), u8); 4] {
+        [
+            ("t", self.t, 64),
+            ("x", self.x, 16),
+            ("y", self.y, 16),
+            ("payload", self.payload, 8),
+        ]
+    }
+
+    #[inline(always)]
+    pub fn extract(word: u64, (offset, width): (u8, u8)) -> u64 {
+        let word = word >> offset;
+        if width >= 64 {
+            word
+        } else {
+            word & ((1_u64 << width) - 1)
+        }
+    }
+
+    /// Timestamp period (2^width), None if the timestamp does not wrap around.
+    pub fn t_period(&self) -> Option<u64> {
+        (self.t.1 < 64).then(|| 1_u64 << self.t.1)
+    }
+}
@@ -7,12 +7,27 @@ use crate::utilities;
 pub struct Decoder {
     pub event_type: common::Type,
     version: common::Version,
-    file: std::fs::File,
+    layout: common::BitLayout,
+    header_lines: Vec<String>,
+    file: utilities::HeaderlessSource,
     raw_buffer: Vec<u8>,
     event_buffer: Vec<common::Event>,
     t: u64,
     offset: u64,
     t0: u64,
+    /// Absolute position of the first event, None if the source is not seekable.
+    data_start: Option<u64>,
+    /// Absolute position of the index footer, None if the file has no index.
+    data_end: Option<u64>,
+    /// Loaded by the first call to seek.
+    index: Option<Vec<IndexEntry>>,
+    seek_t: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    t: u64,
+    position: u64,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -34,18 +49,84 @@ pub enum Error {
 
     #[error("unknown version \"{0}\" (supports \"dat1\" and \"dat2\")")]
     UnknownVersion(String),
+
+    #[error("the index footer is malformed ({0})")]
+    Index(&'static str),
+}
+
+/// Reads the number of entries of the index footer (see common::INDEX_HEADER_LINE).
+///
+/// Returns the footer's absolute position and the number of entries.
+/// file's position is undefined after this function returns.
+fn read_index_trailer(
+    file: &mut std::io::BufReader<utilities::Source>,
+    data_start: u64,
+) -> Result<(u64, u64), Error> {
+    let length = file.seek(std::io::SeekFrom::End(0))?;
+    if length < data_start + 16 {
+        return Err(Error::Index("the file is too short"));
+    }
+    file.seek(std::io::SeekFrom::Start(length - 16))?;
+    let mut trailer = [0u8; 16];
+    file.read_exact(&mut trailer)?;
+    if &trailer[8..16] != common::INDEX_MAGIC {
+        return Err(Error::Index("the magic number is missing"));
+    }
+    let count = u64::from_le_bytes(trailer[0..8].try_into().expect("8 bytes"));
+    let data_end = count
+        .checked_mul(16)
+        .and_then(|size| (length - 16).checked_sub(size))
+        .filter(|data_end| *data_end >= data_start)
+        .ok_or(Error::Index("the entries overlap the header"))?;
+    if (data_end - data_start) % 8 != 0 {
+        return Err(Error::Index("the events size is not a multiple of 8"));
+    }
+    Ok((data_end, count))
+}
+
+/// Reads the entries of the index footer, see read_index_trailer.
+///
+/// file's position is undefined after this function returns.
+fn read_index(
+    file: &mut std::io::BufReader<utilities::Source>,
+    data_start: u64,
+    data_end: u64,
+) -> Result<Vec<IndexEntry>, Error> {
+    let length = file.seek(std::io::SeekFrom::End(0))?;
+    file.seek(std::io::SeekFrom::Start(data_end))?;
+    let mut bytes = vec![0u8; (length - 16 - data_end) as usize];
+    file.read_exact(&mut bytes)?;
+    let mut index: Vec<IndexEntry> = Vec::with_capacity(bytes.len() / 16);
+    for entry in bytes.chunks_exact(16) {
+        let entry = IndexEntry {
+            t: u64::from_le_bytes(entry[0..8].try_into().expect("8 bytes")),
+            position: u64::from_le_bytes(entry[8..16].try_into().expect("8 bytes")),
+        };
+        if entry.position < data_start
+            || entry.position >= data_end
+            || (entry.position - data_start) % 8 != 0
+        {
+            return Err(Error::Index("an entry does not point to an event"));
+        }
+        if let Some(previous_entry) = index.last() {
+            if entry.t < previous_entry.t || entry.position < previous_entry.position {
+                return Err(Error::Index("the entries are not sorted"));
+            }
+        }
+        index.push(entry);
+    }
+    Ok(index)
 }
 
 impl Decoder {
-    pub fn new<P: AsRef<std::path::Path>>(
-        path: P,
+    pub fn new(
+        source: utilities::Source,
         dimensions_fallback: Option<(u16, u16)>,
         version_fallback: Option<common::Version>,
+        bit_layout: Option<common::BitLayout>,
+        buffer_size: usize,
     ) -> Result<Self, Error> {
-        let header = utilities::read_prophesee_header(
-            &mut std::io::BufReader::new(std::fs::File::open(&path)?),
-            '%',
-        )?;
+        let (header, mut file) = utilities::read_prophesee_header(source, '%', buffer_size)?;
         let version = match header.version {
             Some(version) => match version.as_str() {
                 "1" => common::Version::Dat1,
@@ -57,8 +138,6 @@ impl Decoder {
                 None => return Err(Error::MissingVersion),
             },
         };
-        let mut file = std::fs::File::open(path)?;
-        file.seek(std::io::SeekFrom::Start(header.length))?;
         let event_type = {
             let mut type_and_size = [0u8; 2];
             file.read_exact(&mut type_and_size)?;
@@ -90,22 +169,111 @@ impl Decoder {
                 event_type => return Err(Error::UnsupportedType(event_type)),
             }
         };
+        // the cursor holds bytes that were read ahead while parsing the header
+        let (cursor, reader) = file.get_mut();
+        let data_start = reader
+            .stream_position()
+            .ok()
+            .map(|position| position - (cursor.get_ref().len() as u64 - cursor.position()));
+        // only files whose header announces the footer are probed
+        let data_end = match data_start {
+            Some(data_start)
+                if header
+                    .lines
+                    .iter()
+                    .any(|line| line == common::INDEX_HEADER_LINE) =>
+            {
+                let (data_end, _) = read_index_trailer(reader, data_start)?;
+                reader.seek(std::io::SeekFrom::Start(data_start))?;
+                cursor.set_position(cursor.get_ref().len() as u64);
+                Some(data_end)
+            }
+            _ => None,
+        };
         Ok(Decoder {
             event_type,
             version,
+            layout: bit_layout.unwrap_or_else(|| common::BitLayout::from_version(version)),
+            header_lines: header.lines,
             file,
-            raw_buffer: vec![0u8; utilities::BUFFER_SIZE],
+            raw_buffer: vec![0u8; buffer_size],
             event_buffer: Vec::new(),
             t: 0,
             offset: 0,
             t0: header.t0,
+            data_start,
+            data_end,
+            index: None,
+            seek_t: None,
         })
     }
 
+    /// Whether the file has an index footer, see common::INDEX_HEADER_LINE.
+    pub fn indexed(&self) -> bool {
+        self.data_end.is_some()
+    }
+
+    /// Moves to the first event whose timestamp is larger than or equal to t.
+    ///
+    /// Files with an index footer jump to the last indexed event strictly before t (binary search),
+    /// other files are scanned from the first event. In both cases, the events before t
+    /// are dropped by the next calls to next.
+    pub fn seek(&mut self, t: u64) -> Result<(), Error> {
+        let data_start = match self.data_start {
+            Some(data_start) => data_start,
+            None => return Err(self.file.get_ref().1.get_ref().seek_error().into()),
+        };
+        if let (Some(data_end), None) = (self.data_end, self.index.as_ref()) {
+            self.index = Some(read_index(self.file.get_mut().1, data_start, data_end)?);
+        }
+        let file_t = t.saturating_sub(self.t0);
+        let entry = self.index.as_ref().and_then(|index| {
+            let entry_index = index.partition_point(|entry| entry.t < file_t);
+            (entry_index > 0).then(|| index[entry_index - 1])
+        });
+        let (position, entry_t) = match entry {
+            Some(entry) => (entry.position, entry.t),
+            None => (data_start, 0),
+        };
+        let (cursor, reader) = self.file.get_mut();
+        reader.seek(std::io::SeekFrom::Start(position))?;
+        cursor.set_position(cursor.get_ref().len() as u64);
+        self.t = entry_t;
+        self.offset = match self.layout.t_period() {
+            Some(period) => entry_t & !(period - 1),
+            None => 0,
+        };
+        self.seek_t = Some(t);
+        Ok(())
+    }
+
+    /// Moves back to the first event and clears the timestamp state (overflows and pending seek).
+    pub fn reset(&mut self) -> Result<(), Error> {
+        let data_start = match self.data_start {
+            Some(data_start) => data_start,
+            None => return Err(self.file.get_ref().1.get_ref().seek_error().into()),
+        };
+        let (cursor, reader) = self.file.get_mut();
+        reader.seek(std::io::SeekFrom::Start(data_start))?;
+        cursor.set_position(cursor.get_ref().len() as u64);
+        self.t = 0;
+        self.offset = 0;
+        self.seek_t = None;
+        Ok(())
+    }
+
     pub fn version(&self) -> common::Version {
         self.version
     }
 
+    pub fn bit_layout(&self) -> common::BitLayout {
+        self.layout
+    }
+
+    pub fn header_lines(&self) -> &[String] {
+        &self.header_lines
+    }
+
     pub fn dimensions(&self) -> Option<(u16, u16)> {
         match self.event_type {
             common::Type::Event2d(width, height) => Some((width, height)),
@@ -115,91 +283,69 @@ impl Decoder {
     }
 
     pub fn next(&mut self) -> Result<Option<&Vec<common::Event>>, utilities::ReadError> {
-        let read = self.file.read(&mut self.raw_buffer)?;
+        let read = match self.data_end {
+            // the cursor is empty when the file has an index, see new
+            Some(data_end) => {
+                let position = self.file.get_mut().1.stream_position()?;
+                let length =
+                    (data_end.saturating_sub(position) as usize).min(self.raw_buffer.len());
+                utilities::read_words(&mut self.file, &mut self.raw_buffer[..length], 8)?
+            }
+            None => utilities::read_words(&mut self.file, &mut self.raw_buffer, 8)?,
+        };
         if read == 0 {
             return Ok(None);
         }
         self.event_buffer.clear();
         self.event_buffer.reserve(read / 8);
 
-        match self.version {
-            common::Version::Dat1 => {
-                for index in 0..read / 8 {
-                    let word = u64::from_le_bytes(
-                        self.raw_buffer[index * 8..(index + 1) * 8]
-                            .try_into()
-                            .expect("8 bytes"),
-                    );
-                    let mut candidate_t = (word & 0xFFFFFFFF_u64) + self.offset;
-                    if candidate_t < self.t {
-                        if self.t - candidate_t > (1_u64 << 31) {
-                            candidate_t += 1_u64 << 32;
-                            self.offset += 1_u64 << 32;
-                            self.t = candidate_t;
-                        }
-                    } else {
+        let layout = self.layout;
+        let t_period = layout.t_period();
+        for index in 0..read / 8 {
+            let word = u64::from_le_bytes(
+                self.raw_buffer[index * 8..(index + 1) * 8]
+                    .try_into()
+                    .expect("8 bytes"),
+            );
+            let mut candidate_t = common::BitLayout::extract(word, layout.t) + self.offset;
+            if candidate_t < self.t {
+                if let Some(period) = t_period {
+                    if self.t - candidate_t > period / 2 {
+                        candidate_t += period;
+                        self.offset += period;
                         self.t = candidate_t;
                     }
-                    let x = ((word >> 32) & 0b111111111_u64) as u16;
-                    let y = ((word >> 41) & 0b11111111_u64) as u16;
-                    match self.event_type {
-                        common::Type::Event2d(width, height)
-                        | common::Type::EventCd(width, height) => {
-                            if x >= width {
-                                return Err(utilities::ReadError::XOverflow { x, width });
-                            }
-                            if y >= height {
-                                return Err(utilities::ReadError::YOverflow { y, height });
-                            }
-                        }
-                        common::Type::EventExtTrigger => {}
-                    }
-                    self.event_buffer.push(common::Event {
-                        t: self.t + self.t0,
-                        x,
-                        y,
-                        payload: ((word >> 49) & 0b1111) as u8,
-                    });
                 }
+            } else {
+                self.t = candidate_t;
             }
-            common::Version::Dat2 => {
-                for index in 0..read / 8 {
-                    let word = u64::from_le_bytes(
-                        self.raw_buffer[index * 8..(index + 1) * 8]
-                            .try_into()
-                            .expect("8 bytes"),
-                    );
-                    let mut candidate_t = (word & 0xFFFFFFFF_u64) + self.offset;
-                    if candidate_t < self.t {
-                        if self.t - candidate_t > (1_u64 << 31) {
-                            candidate_t += 1_u64 << 32;
-                            self.offset += 1_u64 << 32;
-                            self.t = candidate_t;
-                        }
-                    } else {
-                        self.t = candidate_t;
+            let x = common::BitLayout::extract(word, layout.x) as u16;
+            let y = common::BitLayout::extract(word, layout.y) as u16;
+            match self.event_type {
+                common::Type::Event2d(width, height) | common::Type::EventCd(width, height) => {
+                    if x >= width {
+                        return Err(utilities::ReadError::XOverflow { x, width });
                     }
-                    let x = ((word >> 32) & 0b11111111111111_u64) as u16;
-                    let y = ((word >> 46) & 0b11111111111111_u64) as u16;
-                    match self.event_type {
-                        common::Type::Event2d(width, height)
-                        | common::Type::EventCd(width, height) => {
-                            if x >= width {
-                                return Err(utilities::ReadError::XOverflow { x, width });
-                            }
-                            if y >= height {
-                                return Err(utilities::ReadError::YOverflow { y, height });
-                            }
-                        }
-                        common::Type::EventExtTrigger => {}
+                    if y >= height {
+                        return Err(utilities::ReadError::YOverflow { y, height });
                     }
-                    self.event_buffer.push(common::Event {
-                        t: self.t + self.t0,
-                        x,
-                        y,
-                        payload: (word >> 60) as u8,
-                    });
                 }
+                common::Type::EventExtTrigger => {}
+            }
+            self.event_buffer.push(common::Event {
+                t: self.t + self.t0,
+                x,
+                y,
+                payload: common::BitLayout::extract(word, layout.payload) as u8,
+            });
+        }
+        if let Some(seek_t) = self.seek_t {
+            self.event_buffer.retain(|event| {
+                let t = event.t;
+                t >= seek_t
+            });
+            if !self.event_buffer.is_empty() {
+                self.seek_t = None;
             }
         }
         Ok(Some(&self.event_buffer))
@@ -1,3 +1,4 @@
+use std::io::Seek;
 use std::io::Write;
 
 use crate::dat::common;
@@ -8,6 +9,10 @@ pub struct Encoder {
     event_type: common::Type,
     previous_t: u64,
     t0: Option<u64>,
+    /// Number of events between index entries, None if the file has no index footer.
+    index_interval: Option<u64>,
+    index: Vec<(u64, u64)>,
+    event_count: u64,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -20,6 +25,9 @@ pub enum Error {
 
     #[error("the height must be smaller than {maximum} (got {value}")]
     Height { maximum: u16, value: u16 },
+
+    #[error("the index interval must be larger than 0")]
+    IndexInterval,
 }
 
 impl Encoder {
@@ -28,7 +36,11 @@ impl Encoder {
         version: common::Version,
         zero_t0: bool,
         event_type: common::Type,
+        index_interval: Option<u64>,
     ) -> Result<Self, Error> {
+        if index_interval == Some(0) {
+            return Err(Error::IndexInterval);
+        }
         match event_type {
             common::Type::Event2d(width, height) | common::Type::EventCd(width, height) => {
                 match version {
@@ -84,6 +96,9 @@ impl Encoder {
                     }
                     common::Type::EventExtTrigger => {}
                 }
+                if index_interval.is_some() {
+                    file.write_all(format!("{}\n", common::INDEX_HEADER_LINE).as_bytes())?;
+                }
                 if !zero_t0 {
                     file.write_all(&[
                         match event_type {
@@ -100,12 +115,30 @@ impl Encoder {
             event_type,
             previous_t: 0,
             t0: if zero_t0 { None } else { Some(0) },
+            index_interval,
+            index: Vec::new(),
+            event_count: 0,
         })
     }
 
     pub fn t0(&self) -> Option<u64> {
         self.t0
     }
+
+    /// Writes the index footer (if index_interval is not None) and flushes the file.
+    pub fn finish(mut self) -> Result<(), Error> {
+        if self.index_interval.is_some() {
+            for (t, position) in self.index.iter() {
+                self.file.write_all(&t.to_le_bytes())?;
+                self.file.write_all(&position.to_le_bytes())?;
+            }
+            self.file
+                .write_all(&(self.index.len() as u64).to_le_bytes())?;
+            self.file.write_all(common::INDEX_MAGIC)?;
+        }
+        self.file.flush()?;
+        Ok(())
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -167,6 +200,12 @@ impl Encoder {
         if event.payload >= 8 {
             return Err(PacketError::PayloadOverflow(event.payload));
         }
+        if let Some(index_interval) = self.index_interval {
+            if self.event_count % index_interval == 0 {
+                self.index
+                    .push((event.t - t0, self.file.stream_position()?));
+            }
+        }
         match self.version {
             common::Version::Dat1 => {
                 self.file.write_all(&u64::to_le_bytes(
@@ -186,6 +225,7 @@ impl Encoder {
             }
         }
         self.previous_t = event.t;
+        self.event_count += 1;
         Ok(())
     }
 }
@@ -3,6 +3,7 @@ mod decoder;
 mod encoder;
 
 use crate::types;
+use crate::utilities;
 
 use pyo3::prelude::*;
 
@@ -18,6 +19,12 @@ impl From<common::TypeError> for PyErr {
     }
 }
 
+impl From<common::BitLayoutError> for PyErr {
+    fn from(error: common::BitLayoutError) -> Self {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(error.to_string())
+    }
+}
+
 impl From<decoder::Error> for PyErr {
     fn from(error: decoder::Error) -> Self {
         PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string())
@@ -36,30 +43,65 @@ impl From<encoder::PacketError> for PyErr {
     }
 }
 
+/// Converts a preset name or a {name: (offset, width)} dict to a bit layout.
+fn python_bit_layout(
+    bit_layout: &pyo3::Bound<'_, pyo3::types::PyAny>,
+) -> PyResult<common::BitLayout> {
+    if let Ok(name) = bit_layout.extract::<String>() {
+        return Ok(common::BitLayout::from_preset(&name)?);
+    }
+    let fields = bit_layout
+        .downcast::<pyo3::types::PyDict>()
+        .map_err(|_| {
+            pyo3::exceptions::PyValueError::new_err(
+                "bit_layout must be a preset name or a dict of (offset, width) tuples",
+            )
+        })?
+        .iter()
+        .map(|(name, field)| Ok((name.extract::<String>()?, field.extract::<(u8, u8)>()?)))
+        .collect::<PyResult<Vec<(String, (u8, u8))>>>()?;
+    Ok(common::BitLayout::from_fields(
+        fields.iter().map(|(name, field)| (name.as_str(), *field)),
+    )?)
+}
+
 #[pyclass]
 pub struct Decoder {
     inner: Option<decoder::Decoder>,
+    skip_empty: bool,
+    /// Number of packets read since the decoder was opened, reset, or moved by seek, skipped empty packets included.
+    packet_index: u64,
 }
 
 #[pymethods]
 impl Decoder {
     #[new]
+    #[pyo3(signature = (path, dimensions_fallback = None, version_fallback = None, skip_empty = true, bit_layout = None, buffer_size = utilities::BUFFER_SIZE))]
     fn new(
         path: &pyo3::Bound<'_, pyo3::types::PyAny>,
         dimensions_fallback: Option<(u16, u16)>,
         version_fallback: Option<String>,
+        skip_empty: bool,
+        bit_layout: Option<&pyo3::Bound<'_, pyo3::types::PyAny>>,
+        buffer_size: usize,
     ) -> Result<Self, PyErr> {
+        utilities::check_buffer_size(buffer_size)?;
         Python::with_gil(|python| -> Result<Self, PyErr> {
-            match types::python_path_to_string(python, path) {
+            let bit_layout = bit_layout.map(python_bit_layout).transpose()?;
+            match types::python_source(python, path) {
                 Ok(result) => match decoder::Decoder::new(
                     result,
                     dimensions_fallback,
                     version_fallback
                         .map(|version| common::Version::from_string(&version))
                         .transpose()?,
+                    bit_layout,
+                    buffer_size,
                 ) {
                     Ok(result) => Ok(Decoder {
                         inner: Some(result),
+                        skip_empty,
+                        packet_index: 0,
                     }),
                     Err(error) => Err(PyErr::from(error)),
                 },
@@ -78,6 +120,22 @@ impl Decoder {
         }
     }
 
+    #[getter]
+    fn bit_layout(&self) -> PyResult<PyObject> {
+        match self.inner {
+            Some(ref decoder) => Python::with_gil(|python| -> PyResult<PyObject> {
+                let dict = pyo3::types::PyDict::new_bound(python);
+                for (name, field, _) in decoder.bit_layout().fields() {
+                    dict.set_item(name, field)?;
+                }
+                Ok(dict.into())
+            }),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called bit_layout after __exit__",
+            )),
+        }
+    }
+
     #[getter]
     fn event_type(&self) -> PyResult<String> {
         match self.inner {
@@ -98,6 +156,77 @@ impl Decoder {
         }
     }
 
+    #[getter]
+    fn width(&self) -> PyResult<Option<u16>> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.dimensions().map(|dimensions| dimensions.0)),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called width after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn height(&self) -> PyResult<Option<u16>> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.dimensions().map(|dimensions| dimensions.1)),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called height after __exit__",
+            )),
+        }
+    }
+
+    fn comment_lines(&self) -> PyResult<Vec<String>> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.header_lines().to_vec()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called comment_lines after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn indexed(&self) -> PyResult<bool> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.indexed()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called indexed after __exit__",
+            )),
+        }
+    }
+
+    /// Moves the decoder to the first event whose timestamp is larger than or equal to t_us.
+    ///
+    /// Packet indices returned by enumerate restart at 0 from the read position.
+    fn seek(&mut self, t_us: u64) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                decoder.seek(t_us)?;
+                self.packet_index = 0;
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called seek after __exit__",
+            )),
+        }
+    }
+
+    /// Moves the decoder back to the first event, as if the file had just been opened.
+    ///
+    /// Packet indices returned by enumerate restart at 0.
+    fn reset(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                decoder.reset()?;
+                self.packet_index = 0;
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called reset after __exit__",
+            )),
+        }
+    }
+
     fn __enter__(slf: Py<Self>) -> Py<Self> {
         slf
     }
@@ -121,19 +250,45 @@ impl Decoder {
         Ok(shell.into())
     }
 
+    /// Returns an iterator over (packet_index, packet) tuples.
+    ///
+    /// DAT files have no packets, the decoder reads buffer_size bytes at a time, hence packet_index depends on buffer_size.
+    /// It counts the reads since the decoder was opened, reset, or moved by seek, including the empty packets skipped
+    /// by the decoder. The iterator shares this decoder's file handle.
+    fn enumerate(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        if shell.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called enumerate after __exit__",
+            ));
+        }
+        let python = shell.py();
+        let decoder: Py<Decoder> = shell.into();
+        Py::new(python, EnumeratedDecoder { decoder })
+    }
+
     fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
-        let packet = match shell.inner {
-            Some(ref mut decoder) => match decoder.next() {
-                Ok(result) => match result {
-                    Some(result) => result,
-                    None => return Ok(None),
+        let shell = &mut *shell;
+        let skip_empty = shell.skip_empty;
+        let packet = loop {
+            match shell.inner {
+                Some(ref mut decoder) => match decoder.next() {
+                    Ok(result) => match result {
+                        Some(result) => {
+                            shell.packet_index += 1;
+                            if skip_empty && result.is_empty() {
+                                continue;
+                            }
+                            break result;
+                        }
+                        None => return Ok(None),
+                    },
+                    Err(result) => return Err(result.into()),
                 },
-                Err(result) => return Err(result.into()),
-            },
-            None => {
-                return Err(pyo3::exceptions::PyException::new_err(
-                    "called __next__ after __exit__",
-                ))
+                None => {
+                    return Err(pyo3::exceptions::PyException::new_err(
+                        "called __next__ after __exit__",
+                    ))
+                }
             }
         };
         Python::with_gil(|python| -> PyResult<Option<PyObject>> {
@@ -157,6 +312,25 @@ impl Decoder {
     }
 }
 
+/// Iterates over a decoder's packets and yields (packet_index, packet) tuples.
+#[pyclass]
+pub struct EnumeratedDecoder {
+    decoder: Py<Decoder>,
+}
+
+#[pymethods]
+impl EnumeratedDecoder {
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(shell: PyRefMut<Self>) -> PyResult<Option<(u64, PyObject)>> {
+        let decoder = shell.decoder.bind(shell.py());
+        let packet = Decoder::__next__(decoder.try_borrow_mut()?)?;
+        Ok(packet.map(|packet| (decoder.borrow().packet_index - 1, packet)))
+    }
+}
+
 #[pyclass]
 pub struct Encoder {
     inner: Option<encoder::Encoder>,
@@ -165,12 +339,14 @@ pub struct Encoder {
 #[pymethods]
 impl Encoder {
     #[new]
+    #[pyo3(signature = (path, version, event_type, zero_t0, dimensions, index_interval = None))]
     fn new(
         path: &pyo3::Bound<'_, pyo3::types::PyAny>,
         version: &str,
         event_type: &str,
         zero_t0: bool,
         dimensions: Option<(u16, u16)>,
+        index_interval: Option<u64>,
     ) -> Result<Self, PyErr> {
         Python::with_gil(|python| -> Result<Self, PyErr> {
             match types::python_path_to_string(python, path) {
@@ -179,6 +355,7 @@ impl Encoder {
                     common::Version::from_string(version)?,
                     zero_t0,
                     common::Type::new(event_type, dimensions)?,
+                    index_interval,
                 ) {
                     Ok(result) => Ok(Encoder {
                         inner: Some(result),
@@ -205,7 +382,9 @@ impl Encoder {
                 "multiple calls to __exit__",
             ));
         }
-        let _ = self.inner.take();
+        if let Some(encoder) = self.inner.take() {
+            encoder.finish()?;
+        }
         Ok(false)
     }
 
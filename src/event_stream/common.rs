@@ -10,6 +10,17 @@ pub enum Type {
     Color = 4,
 }
 
+impl Type {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Type::Generic => "generic",
+            Type::Dvs => "dvs",
+            Type::Atis => "atis",
+            Type::Color => "color",
+        }
+    }
+}
+
 #[repr(C)]
 pub struct OwnedGenericEvent {
     pub t: u64,
@@ -42,6 +42,8 @@ enum State {
         t: u64,
         index: usize,
         bytes_length: usize,
+        /// Number of bytes used to encode bytes_length.
+        size_length: usize,
         bytes: Vec<u8>,
         buffer: Vec<common::OwnedGenericEvent>,
     },
@@ -67,10 +69,48 @@ enum State {
 
 pub struct Decoder {
     pub version: [u8; 3],
+    /// Type used to parse the header's dimensions and the events, see new.
     pub event_type: common::Type,
-    file: std::fs::File,
+    /// Type read from the header.
+    pub declared_type: common::Type,
+    override_type: Option<common::Type>,
+    t0: u64,
+    file: utilities::Source,
     raw_buffer: Vec<u8>,
     state: State,
+    /// Events of masked pixels are dropped.
+    mask: Option<utilities::PixelMask>,
+    /// Applied after the mask, events mapped outside the sensor are dropped.
+    remap: Option<utilities::PixelRemap>,
+    /// Applied after the remap table, in sensor coordinates.
+    median_denoise: Option<utilities::MedianDenoise>,
+    statistics: Option<Statistics>,
+    /// Number of bytes (header included) read from the file.
+    offset: u64,
+    header_length: u64,
+    /// Number of buffer reads decoded since the file was opened, see packet_index.
+    packets_read: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Statistics {
+    pub duration: u64,
+    pub event_count: u64,
+}
+
+/// Aggregates computed by Decoder::summary.
+pub struct Summary {
+    pub event_count: u64,
+    /// ON and OFF counts include DVS events and ATIS change detection events (exposure measurements are not counted).
+    pub on_count: u64,
+    pub off_count: u64,
+    pub duration: u64,
+    /// Largest number of events in a millisecond (bins are aligned on multiples of 1000 µs).
+    pub peak_events_per_ms: u64,
+    /// Number of pixels with at least one event.
+    pub active_pixels: u64,
+    /// (x, y, width, height) of the smallest rectangle that contains every event, None if there are no events.
+    pub bounding_box: Option<(u16, u16, u16, u16)>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -86,11 +126,40 @@ pub enum Error {
 
     #[error("unsupported type {0}")]
     UnsupportedType(u8),
+
+    #[error(transparent)]
+    Read(#[from] utilities::ReadError),
+
+    #[error("offset {offset} is inside the header (events start at byte {header_length})")]
+    SeekInHeader { offset: u64, header_length: u64 },
+
+    #[error("offset {offset} is past the end of the file ({length} bytes)")]
+    SeekPastEnd { offset: u64, length: u64 },
+
+    #[error(
+        "the file cannot be decoded as {override_type} events (the header declares {declared_type} events and a {width}x{height} sensor)"
+    )]
+    OverrideType {
+        override_type: &'static str,
+        declared_type: &'static str,
+        width: u16,
+        height: u16,
+    },
 }
 
 impl Decoder {
-    pub fn new<P: AsRef<std::path::Path>>(path: P, t0: u64) -> Result<Self, Error> {
-        let mut file = std::fs::File::open(path)?;
+    /// Reads the file in chunks of buffer_size bytes.
+    ///
+    /// override_type forces the type of the events regardless of the header's type byte (mislabeled files).
+    /// The header is parsed as if it declared override_type (the dimensions follow the type byte),
+    /// and the dimensions must not be zero. Events are then decoded with override_type's layout,
+    /// hence coordinates outside the sensor and incomplete events raise errors as with any file.
+    pub fn new(
+        mut file: utilities::Source,
+        t0: u64,
+        buffer_size: usize,
+        override_type: Option<common::Type>,
+    ) -> Result<Self, Error> {
         {
             let mut magic_number_bytes = [0u8; common::MAGIC_NUMBER.len()];
             file.read_exact(&mut magic_number_bytes)?;
@@ -108,7 +177,7 @@ impl Decoder {
                 patch: version[2],
             });
         }
-        let event_type = {
+        let declared_type = {
             let mut version_byte = [0u8; 1];
             file.read_exact(&mut version_byte)?;
             match version_byte[0] {
@@ -119,6 +188,7 @@ impl Decoder {
                 _ => return Err(Error::UnsupportedType(version_byte[0])),
             }
         };
+        let event_type = override_type.unwrap_or(declared_type);
         let dimensions = match event_type {
             common::Type::Generic => None,
             _ => {
@@ -130,17 +200,42 @@ impl Decoder {
                 ))
             }
         };
+        if let (Some(override_type), Some((width, height))) = (override_type, dimensions) {
+            if width == 0 || height == 0 {
+                return Err(Error::OverrideType {
+                    override_type: override_type.to_str(),
+                    declared_type: declared_type.to_str(),
+                    width,
+                    height,
+                });
+            }
+        }
+        let header_length = (common::MAGIC_NUMBER.len()
+            + version.len()
+            + 1
+            + if dimensions.is_some() { 4 } else { 0 }) as u64;
         Ok(Decoder {
             version,
             event_type,
+            declared_type,
+            override_type,
+            t0,
             file,
-            raw_buffer: vec![0u8; utilities::BUFFER_SIZE],
+            raw_buffer: vec![0u8; buffer_size],
+            mask: None,
+            remap: None,
+            median_denoise: None,
+            statistics: None,
+            offset: header_length,
+            header_length,
+            packets_read: 0,
             state: match event_type {
                 common::Type::Generic => State::Generic {
                     inner: GenericState::Idle,
                     t: t0,
                     index: 0,
                     bytes_length: 0,
+                    size_length: 0,
                     bytes: Vec::new(),
                     buffer: Vec::new(),
                 },
@@ -187,6 +282,328 @@ impl Decoder {
         self.version.clone()
     }
 
+    /// Opens a new decoder at the beginning of the same file.
+    ///
+    /// This fails if the source is not a file.
+    pub fn restart(&self) -> Result<Decoder, Error> {
+        let mut decoder = Decoder::new(
+            self.file.reopen()?,
+            self.t0,
+            self.raw_buffer.len(),
+            self.override_type,
+        )?;
+        decoder.mask = self.mask.clone();
+        decoder.remap = self.remap.clone();
+        decoder.median_denoise = self.median_denoise.clone();
+        if let Some(median_denoise) = decoder.median_denoise.as_mut() {
+            median_denoise.reset();
+        }
+        Ok(decoder)
+    }
+
+    /// Moves the decoder back to the first packet (with a new file handle), cached statistics are kept.
+    ///
+    /// This fails if the source is not a file.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        let mut decoder = self.restart()?;
+        decoder.statistics = self.statistics;
+        *self = decoder;
+        Ok(())
+    }
+
+    /// Drops the events of masked pixels from the next packets, None disables masking.
+    ///
+    /// Cached statistics are discarded since they depend on the mask.
+    pub fn set_mask(&mut self, mask: Option<utilities::PixelMask>) {
+        self.mask = mask;
+        self.statistics = None;
+    }
+
+    /// Moves the events of the next packets with a per-pixel lookup table (in sensor coordinates), None disables remapping.
+    ///
+    /// Cached statistics are discarded since they depend on the remap.
+    pub fn set_remap(&mut self, remap: Option<utilities::PixelRemap>) {
+        self.remap = remap;
+        self.statistics = None;
+    }
+
+    /// Drops the events of the next packets that are far from the median of their neighbours' last timestamps,
+    /// None disables denoising.
+    ///
+    /// Cached statistics are discarded since they depend on the denoiser.
+    pub fn set_median_denoise(&mut self, median_denoise: Option<utilities::MedianDenoise>) {
+        self.median_denoise = median_denoise;
+        self.statistics = None;
+    }
+
+    /// Scans the whole file once (with a separate file handle) and caches the result.
+    ///
+    /// The position of this decoder is not modified.
+    pub fn statistics(&mut self) -> Result<Statistics, Error> {
+        if let Some(statistics) = self.statistics {
+            return Ok(statistics);
+        }
+        let mut scanner = self.restart()?;
+        let mut first_and_last_t: Option<(u64, u64)> = None;
+        let mut event_count = 0;
+        while let Some(packet) = scanner.next()? {
+            event_count += packet.len() as u64;
+            if let Some((packet_first_t, packet_last_t)) = packet.first_and_last_t() {
+                first_and_last_t = Some(match first_and_last_t {
+                    Some((first_t, _)) => (first_t, packet_last_t),
+                    None => (packet_first_t, packet_last_t),
+                });
+            }
+        }
+        let statistics = Statistics {
+            duration: first_and_last_t.map_or(0, |(first_t, last_t)| last_t - first_t),
+            event_count,
+        };
+        self.statistics = Some(statistics);
+        Ok(statistics)
+    }
+
+    /// Scans the whole file once (with a separate file handle) and computes per-polarity and per-pixel aggregates.
+    ///
+    /// Pixel aggregates are zero (and the bounding box is None) for generic streams, which have no coordinates.
+    /// The position of this decoder is not modified.
+    pub fn summary(&self) -> Result<Summary, Error> {
+        let (width, height) = self
+            .dimensions()
+            .map_or((0, 0), |(width, height)| (width as usize, height as usize));
+        let mut active = vec![false; width * height];
+        let mut first_and_last_t: Option<(u64, u64)> = None;
+        let mut bin: Option<(u64, u64)> = None;
+        let mut corners: Option<(u16, u16, u16, u16)> = None;
+        let mut summary = Summary {
+            event_count: 0,
+            on_count: 0,
+            off_count: 0,
+            duration: 0,
+            peak_events_per_ms: 0,
+            active_pixels: 0,
+            bounding_box: None,
+        };
+        let mut scanner = self.restart()?;
+        while let Some(packet) = scanner.next()? {
+            summary.event_count += packet.len() as u64;
+            packet.for_each_t(|t| {
+                first_and_last_t = Some(match first_and_last_t {
+                    Some((first_t, _)) => (first_t, t),
+                    None => (t, t),
+                });
+                let count = match bin {
+                    Some((index, count)) if index == t / 1000 => count + 1,
+                    _ => 1,
+                };
+                bin = Some((t / 1000, count));
+                summary.peak_events_per_ms = summary.peak_events_per_ms.max(count);
+            });
+            packet.for_each_xy(|x, y| {
+                if let Some(pixel) = active.get_mut(x as usize + y as usize * width) {
+                    *pixel = true;
+                }
+                corners = Some(match corners {
+                    Some((x_min, y_min, x_max, y_max)) => {
+                        (x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y))
+                    }
+                    None => (x, y, x, y),
+                });
+            });
+            match packet {
+                Packet::Dvs(events) => {
+                    for event in events.iter() {
+                        match event.polarity {
+                            neuromorphic_types::DvsPolarity::Off => summary.off_count += 1,
+                            neuromorphic_types::DvsPolarity::On => summary.on_count += 1,
+                        }
+                    }
+                }
+                Packet::Atis(events) => {
+                    for event in events.iter() {
+                        match event.polarity {
+                            neuromorphic_types::AtisPolarity::Off => summary.off_count += 1,
+                            neuromorphic_types::AtisPolarity::On => summary.on_count += 1,
+                            neuromorphic_types::AtisPolarity::ExposureStart
+                            | neuromorphic_types::AtisPolarity::ExposureEnd => (),
+                        }
+                    }
+                }
+                Packet::Generic(_) | Packet::Color(_) => (),
+            }
+        }
+        summary.duration = first_and_last_t.map_or(0, |(first_t, last_t)| last_t - first_t);
+        summary.active_pixels = active.iter().filter(|pixel| **pixel).count() as u64;
+        summary.bounding_box = corners.map(|(x_min, y_min, x_max, y_max)| {
+            (x_min, y_min, x_max - x_min + 1, y_max - y_min + 1)
+        });
+        Ok(summary)
+    }
+
+    /// Scans the whole file once (with a separate file handle) and counts events per time bin.
+    ///
+    /// Each row is (events, OFF events, ON events), ATIS exposure measurements and events without polarity
+    /// are only counted in the first column. The first bin starts at the first event's timestamp,
+    /// hence the number of bins is duration / bin_us + 1 (or 0 for files without events).
+    /// The position of this decoder is not modified.
+    pub fn time_histogram(&self, bin_us: u64) -> Result<Vec<[u64; 3]>, Error> {
+        let mut histogram: Vec<[u64; 3]> = Vec::new();
+        let mut first_t: Option<u64> = None;
+        let mut scanner = self.restart()?;
+        let mut row = |t: u64, column: Option<usize>| {
+            let index = ((t - *first_t.get_or_insert(t)) / bin_us) as usize;
+            if index >= histogram.len() {
+                histogram.resize(index + 1, [0; 3]);
+            }
+            histogram[index][0] += 1;
+            if let Some(column) = column {
+                histogram[index][column] += 1;
+            }
+        };
+        while let Some(packet) = scanner.next()? {
+            match packet {
+                Packet::Dvs(events) => {
+                    for event in events.iter() {
+                        row(
+                            event.t,
+                            Some(match event.polarity {
+                                neuromorphic_types::DvsPolarity::Off => 1,
+                                neuromorphic_types::DvsPolarity::On => 2,
+                            }),
+                        );
+                    }
+                }
+                Packet::Atis(events) => {
+                    for event in events.iter() {
+                        row(
+                            event.t,
+                            match event.polarity {
+                                neuromorphic_types::AtisPolarity::Off => Some(1),
+                                neuromorphic_types::AtisPolarity::On => Some(2),
+                                neuromorphic_types::AtisPolarity::ExposureStart
+                                | neuromorphic_types::AtisPolarity::ExposureEnd => None,
+                            },
+                        );
+                    }
+                }
+                packet => packet.for_each_t(|t| row(t, None)),
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// Scans the whole file once (with a separate file handle) and returns (t, gap) pairs,
+    /// where t is the timestamp of an event that follows the previous one by more than threshold µs.
+    ///
+    /// The position of this decoder is not modified.
+    pub fn clock_glitches(&self, threshold: u64) -> Result<Vec<(u64, u64)>, Error> {
+        let mut scanner = self.restart()?;
+        let mut previous_t: Option<u64> = None;
+        let mut glitches = Vec::new();
+        while let Some(packet) = scanner.next()? {
+            packet.for_each_t(|t| {
+                if let Some(previous_t) = previous_t {
+                    let gap = t - previous_t;
+                    if gap > threshold {
+                        glitches.push((t, gap));
+                    }
+                }
+                previous_t = Some(t);
+            });
+        }
+        Ok(glitches)
+    }
+
+    /// Scans the whole file once (with a separate file handle) and flags the pixels
+    /// whose event rate (over the file's duration) is strictly larger than threshold_hz.
+    ///
+    /// Returns None for generic streams, which have no coordinates.
+    /// The position of this decoder is not modified.
+    pub fn hot_pixels(&mut self, threshold_hz: f64) -> Result<Option<HotPixels>, Error> {
+        let dimensions = match self.dimensions() {
+            Some(dimensions) => dimensions,
+            None => return Ok(None),
+        };
+        let duration = self.statistics()?.duration.max(1);
+        let mut counts = vec![0u64; dimensions.0 as usize * dimensions.1 as usize];
+        let mut scanner = self.restart()?;
+        while let Some(packet) = scanner.next()? {
+            packet.for_each_xy(|x, y| {
+                if let Some(count) = counts.get_mut(x as usize + y as usize * dimensions.0 as usize)
+                {
+                    *count += 1;
+                }
+            });
+        }
+        let mut hot_pixels = HotPixels {
+            dimensions,
+            bitmap: vec![0u64; counts.len().div_ceil(64)],
+        };
+        for (index, count) in counts.iter().enumerate() {
+            if (*count as f64) * 1e6 / (duration as f64) > threshold_hz {
+                hot_pixels.bitmap[index / 64] |= 1 << (index % 64);
+            }
+        }
+        Ok(Some(hot_pixels))
+    }
+
+    /// Returns the number of bytes (header included) consumed by this decoder.
+    pub fn tell(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the index of the next packet, that is the number of buffer reads decoded since the file was opened.
+    ///
+    /// Packets are buffer-sized reads, hence the index depends on the buffer size.
+    /// seek_bytes counts the reads that it decodes to reach the offset.
+    pub fn packet_index(&self) -> u64 {
+        self.packets_read
+    }
+
+    /// Whether the file is gzip-compressed, tell and seek_bytes are disabled in this case.
+    pub fn is_gzip(&self) -> bool {
+        self.file.is_gzip()
+    }
+
+    /// Returns the size of the file in bytes (header included), the final value of tell.
+    ///
+    /// This fails if the source is not a file.
+    pub fn length(&self) -> Result<u64, Error> {
+        Ok(self.file.length()?)
+    }
+
+    /// Moves the decoder to a byte offset previously returned by tell.
+    ///
+    /// Event Stream timestamps are encoded as deltas, hence the decoder state (timestamp and partial event)
+    /// is rebuilt by decoding the file from the header to offset (with a separate file handle).
+    /// Offsets inside the header or past the end of the file are rejected.
+    pub fn seek_bytes(&mut self, offset: u64) -> Result<(), Error> {
+        if self.is_gzip() {
+            return Err(utilities::gzip_not_seekable_error().into());
+        }
+        if offset < self.header_length {
+            return Err(Error::SeekInHeader {
+                offset,
+                header_length: self.header_length,
+            });
+        }
+        let mut decoder = self.restart()?;
+        while decoder.offset < offset {
+            let length = (offset - decoder.offset).min(decoder.raw_buffer.len() as u64) as usize;
+            let read = decoder.file.read(&mut decoder.raw_buffer[0..length])?;
+            if read == 0 {
+                return Err(Error::SeekPastEnd {
+                    offset,
+                    length: decoder.offset,
+                });
+            }
+            decoder.decode(read)?;
+        }
+        decoder.statistics = self.statistics;
+        *self = decoder;
+        Ok(())
+    }
+
     pub fn dimensions(&self) -> Option<(u16, u16)> {
         match self.state {
             State::Generic { .. } => None,
@@ -195,6 +612,19 @@ impl Decoder {
             State::Color { dimensions, .. } => Some(dimensions),
         }
     }
+
+    /// Moves the events of the last DVS packet out of the decoder, without copying them.
+    ///
+    /// The next packet is decoded into a new buffer with the same capacity. Returns None for other streams.
+    pub fn take_dvs_events(&mut self) -> Option<Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>> {
+        match self.state {
+            State::Dvs { ref mut buffer, .. } => {
+                let capacity = buffer.capacity();
+                Some(std::mem::replace(buffer, Vec::with_capacity(capacity)))
+            }
+            _ => None,
+        }
+    }
 }
 
 pub enum Packet<'a> {
@@ -204,18 +634,559 @@ pub enum Packet<'a> {
     Color(&'a Vec<common::ColorEvent>),
 }
 
+impl<'a> Packet<'a> {
+    pub fn len(&self) -> usize {
+        match self {
+            Packet::Generic(events) => events.len(),
+            Packet::Dvs(events) => events.len(),
+            Packet::Atis(events) => events.len(),
+            Packet::Color(events) => events.len(),
+        }
+    }
+
+    pub fn first_and_last_t(&self) -> Option<(u64, u64)> {
+        match self {
+            Packet::Generic(events) => Some((events.first()?.t, events.last()?.t)),
+            Packet::Dvs(events) => Some((events.first()?.t, events.last()?.t)),
+            Packet::Atis(events) => Some((events.first()?.t, events.last()?.t)),
+            Packet::Color(events) => Some((events.first()?.t, events.last()?.t)),
+        }
+    }
+
+    pub fn for_each_t<F: FnMut(u64)>(&self, mut callback: F) {
+        match self {
+            Packet::Generic(events) => events.iter().for_each(|event| callback(event.t)),
+            Packet::Dvs(events) => events.iter().for_each(|event| callback(event.t)),
+            Packet::Atis(events) => events.iter().for_each(|event| callback(event.t)),
+            Packet::Color(events) => events.iter().for_each(|event| callback(event.t)),
+        }
+    }
+
+    /// Does nothing for generic packets, which have no coordinates.
+    pub fn for_each_xy<F: FnMut(u16, u16)>(&self, mut callback: F) {
+        match self {
+            Packet::Generic(_) => (),
+            Packet::Dvs(events) => events.iter().for_each(|event| callback(event.x, event.y)),
+            Packet::Atis(events) => events.iter().for_each(|event| callback(event.x, event.y)),
+            Packet::Color(events) => events.iter().for_each(|event| callback(event.x, event.y)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Packet::Generic(events) => events.is_empty(),
+            Packet::Dvs(events) => events.is_empty(),
+            Packet::Atis(events) => events.is_empty(),
+            Packet::Color(events) => events.is_empty(),
+        }
+    }
+}
+
+/// Rectangular region of interest, in sensor coordinates.
+#[derive(Debug, Clone, Copy)]
+pub struct Roi {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GeometryError {
+    #[error("downsample must be larger than 0")]
+    Downsample,
+
+    #[error("rotate must be 0, 90, 180, or 270 (got {0})")]
+    Rotation(u16),
+
+    #[error("flip_x, flip_y, and rotate require a stream with coordinates (got a generic stream)")]
+    MissingDimensions,
+}
+
+/// Clockwise rotation, in image coordinates (the y axis points down).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Zero,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+/// Coordinates transform applied when events are written to arrays, after the region of interest.
+///
+/// Coordinates are divided by downsample, then flipped, then rotated.
+#[derive(Debug, Clone, Copy)]
+pub struct Geometry {
+    /// Dimensions after downsampling and before rotation, (0, 0) for generic streams.
+    dimensions: (u16, u16),
+    downsample: u16,
+    flip_x: bool,
+    flip_y: bool,
+    rotation: Rotation,
+}
+
+impl Geometry {
+    /// dimensions are the stream's dimensions (or the region of interest's), None for generic streams.
+    pub fn new(
+        dimensions: Option<(u16, u16)>,
+        downsample: u16,
+        flip_x: bool,
+        flip_y: bool,
+        rotate: u16,
+    ) -> Result<Self, GeometryError> {
+        if downsample == 0 {
+            return Err(GeometryError::Downsample);
+        }
+        let rotation = match rotate {
+            0 => Rotation::Zero,
+            90 => Rotation::Quarter,
+            180 => Rotation::Half,
+            270 => Rotation::ThreeQuarters,
+            rotate => return Err(GeometryError::Rotation(rotate)),
+        };
+        let dimensions = match dimensions {
+            Some(dimensions) => (
+                dimensions.0.div_ceil(downsample),
+                dimensions.1.div_ceil(downsample),
+            ),
+            None => {
+                if flip_x || flip_y || rotation != Rotation::Zero {
+                    return Err(GeometryError::MissingDimensions);
+                }
+                (0, 0)
+            }
+        };
+        Ok(Geometry {
+            dimensions,
+            downsample,
+            flip_x,
+            flip_y,
+            rotation,
+        })
+    }
+
+    /// Returns the dimensions of the transformed stream (width and height are swapped by 90 and 270 rotations).
+    pub fn dimensions(&self) -> (u16, u16) {
+        match self.rotation {
+            Rotation::Zero | Rotation::Half => self.dimensions,
+            Rotation::Quarter | Rotation::ThreeQuarters => (self.dimensions.1, self.dimensions.0),
+        }
+    }
+
+    /// Returns true if apply does not change coordinates.
+    pub fn is_identity(&self) -> bool {
+        self.downsample == 1 && !self.flip_x && !self.flip_y && self.rotation == Rotation::Zero
+    }
+
+    pub fn apply(&self, x: u16, y: u16) -> (u16, u16) {
+        let (width, height) = self.dimensions;
+        let x = x / self.downsample;
+        let y = y / self.downsample;
+        let x = if self.flip_x { width - 1 - x } else { x };
+        let y = if self.flip_y { height - 1 - y } else { y };
+        match self.rotation {
+            Rotation::Zero => (x, y),
+            Rotation::Quarter => (height - 1 - y, x),
+            Rotation::Half => (width - 1 - x, height - 1 - y),
+            Rotation::ThreeQuarters => (y, width - 1 - x),
+        }
+    }
+}
+
+/// Events of a packet kept by a region of interest (with coordinates relative to the region's origin),
+/// a hot pixel filter, a refractory filter, or a background activity filter.
+pub enum CroppedPacket {
+    Dvs(Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>),
+    Atis(Vec<neuromorphic_types::AtisEvent<u64, u16, u16>>),
+    Color(Vec<common::ColorEvent>),
+}
+
+impl Roi {
+    fn translate(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        if x >= self.x && x - self.x < self.width && y >= self.y && y - self.y < self.height {
+            Some((x - self.x, y - self.y))
+        } else {
+            None
+        }
+    }
+
+    /// Returns None for generic packets, which have no coordinates.
+    pub fn crop(&self, packet: &Packet) -> Option<CroppedPacket> {
+        match packet {
+            Packet::Generic(_) => None,
+            Packet::Dvs(events) => Some(CroppedPacket::Dvs(
+                events
+                    .iter()
+                    .filter_map(|event| {
+                        self.translate(event.x, event.y)
+                            .map(|(x, y)| neuromorphic_types::DvsEvent { x, y, ..*event })
+                    })
+                    .collect(),
+            )),
+            Packet::Atis(events) => Some(CroppedPacket::Atis(
+                events
+                    .iter()
+                    .filter_map(|event| {
+                        self.translate(event.x, event.y)
+                            .map(|(x, y)| neuromorphic_types::AtisEvent { x, y, ..*event })
+                    })
+                    .collect(),
+            )),
+            Packet::Color(events) => Some(CroppedPacket::Color(
+                events
+                    .iter()
+                    .filter_map(|event| {
+                        self.translate(event.x, event.y)
+                            .map(|(x, y)| common::ColorEvent { x, y, ..*event })
+                    })
+                    .collect(),
+            )),
+        }
+    }
+}
+
+/// Pixels flagged by Decoder::hot_pixels, stored as a bitmap with one bit per pixel.
+pub struct HotPixels {
+    dimensions: (u16, u16),
+    bitmap: Vec<u64>,
+}
+
+impl HotPixels {
+    fn is_hot(&self, x: u16, y: u16) -> bool {
+        let index = x as usize + y as usize * self.dimensions.0 as usize;
+        self.bitmap
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    /// Returns the flagged pixels' coordinates, in row-major order.
+    pub fn coordinates(&self) -> Vec<(u16, u16)> {
+        let mut coordinates = Vec::new();
+        for y in 0..self.dimensions.1 {
+            for x in 0..self.dimensions.0 {
+                if self.is_hot(x, y) {
+                    coordinates.push((x, y));
+                }
+            }
+        }
+        coordinates
+    }
+
+    /// Returns None for generic packets, which have no coordinates.
+    pub fn filter(&self, packet: &Packet) -> Option<CroppedPacket> {
+        match packet {
+            Packet::Generic(_) => None,
+            Packet::Dvs(events) => Some(CroppedPacket::Dvs(
+                events
+                    .iter()
+                    .filter(|event| !self.is_hot(event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+            Packet::Atis(events) => Some(CroppedPacket::Atis(
+                events
+                    .iter()
+                    .filter(|event| !self.is_hot(event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+            Packet::Color(events) => Some(CroppedPacket::Color(
+                events
+                    .iter()
+                    .filter(|event| !self.is_hot(event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+        }
+    }
+}
+
+/// Per-pixel refractory period, events that follow the previous kept event of the same pixel
+/// by period or less are dropped.
+///
+/// The state persists between packets.
+pub struct Refractory {
+    dimensions: (u16, u16),
+    period: u64,
+    /// Timestamp of each pixel's last kept event, u64::MAX if the pixel has not fired yet.
+    last_ts: Vec<u64>,
+}
+
+impl Refractory {
+    pub fn new(dimensions: (u16, u16), period: u64) -> Self {
+        Refractory {
+            dimensions,
+            period,
+            last_ts: vec![u64::MAX; dimensions.0 as usize * dimensions.1 as usize],
+        }
+    }
+
+    /// Forgets the previous events, as if no pixel had fired yet.
+    pub fn reset(&mut self) {
+        self.last_ts.fill(u64::MAX);
+    }
+
+    fn keep(&mut self, t: u64, x: u16, y: u16) -> bool {
+        match self
+            .last_ts
+            .get_mut(x as usize + y as usize * self.dimensions.0 as usize)
+        {
+            Some(last_t) => {
+                if *last_t == u64::MAX || t > last_t.saturating_add(self.period) {
+                    *last_t = t;
+                    true
+                } else {
+                    false
+                }
+            }
+            None => true,
+        }
+    }
+
+    /// Returns None for generic packets, which have no coordinates.
+    pub fn filter(&mut self, packet: &Packet) -> Option<CroppedPacket> {
+        match packet {
+            Packet::Generic(_) => None,
+            Packet::Dvs(events) => Some(CroppedPacket::Dvs(
+                events
+                    .iter()
+                    .filter(|event| self.keep(event.t, event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+            Packet::Atis(events) => Some(CroppedPacket::Atis(
+                events
+                    .iter()
+                    .filter(|event| self.keep(event.t, event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+            Packet::Color(events) => Some(CroppedPacket::Color(
+                events
+                    .iter()
+                    .filter(|event| self.keep(event.t, event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+        }
+    }
+}
+
+/// Background activity filter, events are kept only if one of the 8 neighbouring pixels
+/// fired dt or less before them.
+///
+/// Every event (kept or not) updates its pixel's timestamp, and the state persists between packets.
+pub struct BackgroundActivity {
+    dimensions: (u16, u16),
+    dt: u64,
+    /// Timestamp of each pixel's last event, u64::MAX if the pixel has not fired yet.
+    last_ts: Vec<u64>,
+}
+
+impl BackgroundActivity {
+    pub fn new(dimensions: (u16, u16), dt: u64) -> Self {
+        BackgroundActivity {
+            dimensions,
+            dt,
+            last_ts: vec![u64::MAX; dimensions.0 as usize * dimensions.1 as usize],
+        }
+    }
+
+    /// Forgets the previous events, as if no pixel had fired yet.
+    pub fn reset(&mut self) {
+        self.last_ts.fill(u64::MAX);
+    }
+
+    fn keep(&mut self, t: u64, x: u16, y: u16) -> bool {
+        let (width, height) = (self.dimensions.0 as usize, self.dimensions.1 as usize);
+        let (x, y) = (x as usize, y as usize);
+        if x >= width || y >= height {
+            return true;
+        }
+        let mut supported = false;
+        'neighbours: for neighbour_y in y.saturating_sub(1)..(y + 2).min(height) {
+            for neighbour_x in x.saturating_sub(1)..(x + 2).min(width) {
+                if neighbour_x == x && neighbour_y == y {
+                    continue;
+                }
+                let last_t = self.last_ts[neighbour_x + neighbour_y * width];
+                if last_t != u64::MAX && t <= last_t.saturating_add(self.dt) {
+                    supported = true;
+                    break 'neighbours;
+                }
+            }
+        }
+        self.last_ts[x + y * width] = t;
+        supported
+    }
+
+    /// Returns None for generic packets, which have no coordinates.
+    pub fn filter(&mut self, packet: &Packet) -> Option<CroppedPacket> {
+        match packet {
+            Packet::Generic(_) => None,
+            Packet::Dvs(events) => Some(CroppedPacket::Dvs(
+                events
+                    .iter()
+                    .filter(|event| self.keep(event.t, event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+            Packet::Atis(events) => Some(CroppedPacket::Atis(
+                events
+                    .iter()
+                    .filter(|event| self.keep(event.t, event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+            Packet::Color(events) => Some(CroppedPacket::Color(
+                events
+                    .iter()
+                    .filter(|event| self.keep(event.t, event.x, event.y))
+                    .copied()
+                    .collect(),
+            )),
+        }
+    }
+}
+
+impl CroppedPacket {
+    /// Returns None for generic streams, which have no coordinates.
+    pub fn empty(event_type: common::Type) -> Option<Self> {
+        match event_type {
+            common::Type::Generic => None,
+            common::Type::Dvs => Some(CroppedPacket::Dvs(Vec::new())),
+            common::Type::Atis => Some(CroppedPacket::Atis(Vec::new())),
+            common::Type::Color => Some(CroppedPacket::Color(Vec::new())),
+        }
+    }
+
+    pub fn packet(&self) -> Packet<'_> {
+        match self {
+            CroppedPacket::Dvs(events) => Packet::Dvs(events),
+            CroppedPacket::Atis(events) => Packet::Atis(events),
+            CroppedPacket::Color(events) => Packet::Color(events),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.packet().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends the events of a packet with the same type (a file's packets all have the same type).
+    pub fn extend(&mut self, packet: &Packet) {
+        match (self, packet) {
+            (CroppedPacket::Dvs(events), Packet::Dvs(new_events)) => {
+                events.extend_from_slice(new_events)
+            }
+            (CroppedPacket::Atis(events), Packet::Atis(new_events)) => {
+                events.extend_from_slice(new_events)
+            }
+            (CroppedPacket::Color(events), Packet::Color(new_events)) => {
+                events.extend_from_slice(new_events)
+            }
+            _ => unreachable!("the packet type does not match the stream type"),
+        }
+    }
+
+    pub fn first_t(&self) -> Option<u64> {
+        self.packet().first_and_last_t().map(|(first_t, _)| first_t)
+    }
+
+    /// Returns the number of events whose timestamp is strictly smaller than t.
+    pub fn count_before(&self, t: u64) -> usize {
+        match self {
+            CroppedPacket::Dvs(events) => events.partition_point(|event| event.t < t),
+            CroppedPacket::Atis(events) => events.partition_point(|event| event.t < t),
+            CroppedPacket::Color(events) => events.partition_point(|event| event.t < t),
+        }
+    }
+
+    /// Removes the first count events and returns them.
+    pub fn split_front(&mut self, count: usize) -> CroppedPacket {
+        match self {
+            CroppedPacket::Dvs(events) => CroppedPacket::Dvs(events.drain(0..count).collect()),
+            CroppedPacket::Atis(events) => CroppedPacket::Atis(events.drain(0..count).collect()),
+            CroppedPacket::Color(events) => CroppedPacket::Color(events.drain(0..count).collect()),
+        }
+    }
+}
+
 impl Decoder {
     pub fn next(&mut self) -> Result<Option<Packet>, utilities::ReadError> {
         let read = self.file.read(&mut self.raw_buffer)?;
         if read == 0 {
-            return Ok(None);
+            return match self.partial_event() {
+                Some((available, expected)) => Err(utilities::ReadError::TruncatedFile {
+                    offset: self.offset - available,
+                    expected,
+                    available,
+                }),
+                None => Ok(None),
+            };
         }
+        self.decode(read).map(Some)
+    }
+
+    /// Returns the number of bytes read and the number of bytes expected for the current event,
+    /// or None if the decoder is between events.
+    ///
+    /// The expected size of generic events whose size is incomplete is a lower bound.
+    fn partial_event(&self) -> Option<(u64, u64)> {
+        let (available, expected) = match self.state {
+            State::Generic {
+                ref inner,
+                index,
+                bytes_length,
+                size_length,
+                ..
+            } => match inner {
+                GenericState::Idle => return None,
+                GenericState::Byte0 => (1 + index, 2 + index),
+                GenericState::DataByte => (1 + size_length + index, 1 + size_length + bytes_length),
+            },
+            State::Dvs { ref inner, .. } => match inner {
+                DvsState::Idle => return None,
+                DvsState::Byte0 => (1, 5),
+                DvsState::Byte1 => (2, 5),
+                DvsState::Byte2 => (3, 5),
+                DvsState::Byte3 => (4, 5),
+            },
+            State::Atis { ref inner, .. } => match inner {
+                AtisState::Idle => return None,
+                AtisState::Byte0 => (1, 5),
+                AtisState::Byte1 => (2, 5),
+                AtisState::Byte2 => (3, 5),
+                AtisState::Byte3 => (4, 5),
+            },
+            State::Color { ref inner, .. } => match inner {
+                ColorState::Idle => return None,
+                ColorState::Byte0 => (1, 8),
+                ColorState::Byte1 => (2, 8),
+                ColorState::Byte2 => (3, 8),
+                ColorState::Byte3 => (4, 8),
+                ColorState::Byte4 => (5, 8),
+                ColorState::Byte5 => (6, 8),
+                ColorState::Byte6 => (7, 8),
+            },
+        };
+        Some((available as u64, expected as u64))
+    }
+
+    /// Decodes the first read bytes of the raw buffer.
+    fn decode(&mut self, read: usize) -> Result<Packet<'_>, utilities::ReadError> {
+        self.offset += read as u64;
+        self.packets_read += 1;
         match self.state {
             State::Generic {
                 ref mut inner,
                 ref mut t,
                 ref mut index,
                 ref mut bytes_length,
+                ref mut size_length,
                 ref mut bytes,
                 ref mut buffer,
             } => {
@@ -239,6 +1210,7 @@ impl Decoder {
                             *bytes_length |= ((byte >> 1) as usize) << (7 * *index);
                             if ((*byte) & 1) == 0 {
                                 bytes.clear();
+                                *size_length = *index + 1;
                                 *index = 0;
                                 if *bytes_length == 0 {
                                     buffer.push(common::OwnedGenericEvent {
@@ -270,7 +1242,7 @@ impl Decoder {
                         }
                     }
                 }
-                Ok(Some(Packet::Generic(buffer)))
+                Ok(Packet::Generic(buffer))
             }
             State::Dvs {
                 ref mut inner,
@@ -323,12 +1295,21 @@ impl Decoder {
                                     height: dimensions.1,
                                 });
                             }
-                            buffer.push(*event);
+                            if let Some((x, y)) = utilities::map_pixel(
+                                self.mask.as_ref(),
+                                self.remap.as_ref(),
+                                self.median_denoise.as_mut(),
+                                event.t,
+                                event.x,
+                                event.y,
+                            ) {
+                                buffer.push(neuromorphic_types::DvsEvent { x, y, ..*event });
+                            }
                             DvsState::Idle
                         }
                     }
                 }
-                Ok(Some(Packet::Dvs(buffer)))
+                Ok(Packet::Dvs(buffer))
             }
             State::Atis {
                 ref mut inner,
@@ -381,12 +1362,21 @@ impl Decoder {
                                     height: dimensions.1,
                                 });
                             }
-                            buffer.push(*event);
+                            if let Some((x, y)) = utilities::map_pixel(
+                                self.mask.as_ref(),
+                                self.remap.as_ref(),
+                                self.median_denoise.as_mut(),
+                                event.t,
+                                event.x,
+                                event.y,
+                            ) {
+                                buffer.push(neuromorphic_types::AtisEvent { x, y, ..*event });
+                            }
                             AtisState::Idle
                         }
                     }
                 }
-                Ok(Some(Packet::Atis(buffer)))
+                Ok(Packet::Atis(buffer))
             }
             State::Color {
                 ref mut inner,
@@ -446,12 +1436,21 @@ impl Decoder {
                         }
                         ColorState::Byte6 => {
                             event.b = *byte;
-                            buffer.push(*event);
+                            if let Some((x, y)) = utilities::map_pixel(
+                                self.mask.as_ref(),
+                                self.remap.as_ref(),
+                                self.median_denoise.as_mut(),
+                                event.t,
+                                event.x,
+                                event.y,
+                            ) {
+                                buffer.push(common::ColorEvent { x, y, ..*event });
+                            }
                             ColorState::Idle
                         }
                     }
                 }
-                Ok(Some(Packet::Color(buffer)))
+                Ok(Packet::Color(buffer))
             }
         }
     }
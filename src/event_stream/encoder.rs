@@ -111,6 +111,18 @@ impl Encoder {
             Encoder::Color(encoder) => encoder.t0,
         }
     }
+
+    /// Flushes the buffered events, unlike drop, errors are reported.
+    pub fn finish(self) -> Result<(), Error> {
+        let mut file = match self {
+            Encoder::Generic(encoder) => encoder.file,
+            Encoder::Dvs(encoder) => encoder.file,
+            Encoder::Atis(encoder) => encoder.file,
+            Encoder::Color(encoder) => encoder.file,
+        };
+        file.flush()?;
+        Ok(())
+    }
 }
 
 fn open<P: AsRef<std::path::Path>>(
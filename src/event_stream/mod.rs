@@ -3,7 +3,9 @@ mod decoder;
 mod encoder;
 
 use crate::types;
+use crate::utilities;
 
+use numpy::convert::ToPyArray;
 use pyo3::prelude::*;
 
 impl From<decoder::Error> for PyErr {
@@ -12,73 +14,2229 @@ impl From<decoder::Error> for PyErr {
     }
 }
 
+impl From<decoder::GeometryError> for PyErr {
+    fn from(error: decoder::GeometryError) -> Self {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(error.to_string())
+    }
+}
+
 impl From<encoder::Error> for PyErr {
     fn from(error: encoder::Error) -> Self {
         PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string())
     }
 }
 
+/// The inner decoder is shared with the worker threads of the asynchronous iterators (see AsyncDecoder),
+/// hence the methods lock it instead of borrowing the Python object mutably.
+#[pyclass]
+pub struct Decoder {
+    inner: std::sync::Arc<std::sync::Mutex<Option<decoder::Decoder>>>,
+    skip_empty: bool,
+    ragged: bool,
+    split_atis: bool,
+    soa: bool,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    layout: Layout,
+    progress_callback: std::sync::Mutex<Option<PyObject>>,
+    workers: std::sync::Mutex<Vec<AsyncWorker>>,
+}
+
+/// Locks a decoder shared with worker threads.
+///
+/// The GIL is released while waiting since the thread that holds the lock may need the GIL to build a packet.
+fn lock_decoder<'a>(
+    python: Python,
+    inner: &'a std::sync::Mutex<Option<decoder::Decoder>>,
+) -> std::sync::MutexGuard<'a, Option<decoder::Decoder>> {
+    loop {
+        match inner.try_lock() {
+            Ok(guard) => return guard,
+            Err(std::sync::TryLockError::WouldBlock) => {
+                python.allow_threads(|| drop(inner.lock()));
+            }
+            Err(std::sync::TryLockError::Poisoned(_)) => {
+                panic!("the decoder mutex is not poisoned")
+            }
+        }
+    }
+}
+
+/// Packet conversion settings, copied from Decoder by __next__ and by the asynchronous iterators' workers.
+#[derive(Clone)]
+struct PacketOptions {
+    skip_empty: bool,
+    ragged: bool,
+    split_atis: bool,
+    soa: bool,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    layout: Layout,
+}
+
+/// Reads the next packet (skipping empty packets if requested) and converts it to a Python object.
+///
+/// The decoder stays locked while the packet is read and converted, hence concurrent readers take turns.
+fn read_packet(
+    python: Python,
+    inner: &std::sync::Mutex<Option<decoder::Decoder>>,
+    options: &PacketOptions,
+) -> PyResult<Option<(u64, PyObject)>> {
+    let mut inner = lock_decoder(python, inner);
+    let skip_empty = options.skip_empty;
+    let roi = options.roi;
+    let mut cropped_packet = None;
+    // reading, parsing, and cropping release the GIL, only the array construction requires it
+    let (index, packet) = loop {
+        match *inner {
+            Some(ref mut decoder) => {
+                let index = decoder.packet_index();
+                match python.allow_threads(move || decoder.next()) {
+                    Ok(result) => match result {
+                        Some(result) => {
+                            if skip_empty && result.is_empty() {
+                                continue;
+                            }
+                            // the region of interest is applied before building the array
+                            if let Some(cropped) =
+                                roi.and_then(|roi| python.allow_threads(|| roi.crop(&result)))
+                            {
+                                if skip_empty && cropped.packet().is_empty() {
+                                    continue;
+                                }
+                                break (index, cropped_packet.insert(cropped).packet());
+                            }
+                            break (index, result);
+                        }
+                        None => return Ok(None),
+                    },
+                    Err(result) => return Err(result.into()),
+                }
+            }
+            None => {
+                return Err(pyo3::exceptions::PyException::new_err(
+                    "called __next__ after __exit__",
+                ))
+            }
+        }
+    };
+    // DVS events are moved out of the decoder (or the cropped packet), the array is a view of their memory
+    if !options.soa && matches!(packet, decoder::Packet::Dvs(_)) {
+        let events = match cropped_packet {
+            Some(decoder::CroppedPacket::Dvs(events)) => events,
+            _ => inner
+                .as_mut()
+                .and_then(decoder::Decoder::take_dvs_events)
+                .expect("the packet has DVS events"),
+        };
+        return dvs_events_to_object(python, events, &options.geometry, &options.layout)
+            .map(|packet| Some((index, packet)));
+    }
+    packet_to_object(
+        python,
+        &packet,
+        options.ragged,
+        options.split_atis,
+        options.soa,
+        &options.geometry,
+        &options.layout,
+    )
+    .map(|packet| Some((index, packet)))
+}
+
+/// Maximum number of calls to the progress callback per scan (to_array, to_csv, and save_npy).
+const PROGRESS_CALLS: u64 = 100;
+
+/// Calls the progress callback with (bytes_processed, bytes_total) every bytes_total / PROGRESS_CALLS bytes.
+///
+/// The GIL is acquired only when the callback is invoked.
+struct Progress {
+    callback: Option<PyObject>,
+    total: u64,
+    step: u64,
+    next: u64,
+}
+
+impl Progress {
+    fn new(callback: Option<PyObject>, scanner: &decoder::Decoder) -> PyResult<Self> {
+        let total = if callback.is_some() {
+            scanner.length()?
+        } else {
+            0
+        };
+        let step = (total / PROGRESS_CALLS).max(1);
+        Ok(Progress {
+            callback,
+            total,
+            step,
+            next: step,
+        })
+    }
+
+    fn update(&mut self, bytes_processed: u64) -> PyResult<()> {
+        if bytes_processed < self.next {
+            return Ok(());
+        }
+        self.next = bytes_processed + self.step;
+        self.call(bytes_processed)
+    }
+
+    /// Reports the end of the scan, even if the last update was skipped by the throttle.
+    fn finish(&mut self, bytes_processed: u64) -> PyResult<()> {
+        if self.next == bytes_processed + self.step {
+            return Ok(());
+        }
+        self.next = bytes_processed + self.step;
+        self.call(bytes_processed)
+    }
+
+    fn call(&self, bytes_processed: u64) -> PyResult<()> {
+        match self.callback {
+            Some(ref callback) => Python::with_gil(|python| {
+                callback.call1(python, (bytes_processed, self.total))?;
+                Ok(())
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Maximum number of packets decoded ahead of the consumer by AsyncDecoder's worker thread.
+const ASYNC_QUEUE_SIZE: usize = 2;
+
+type AsyncPacket = PyResult<Option<PyObject>>;
+
+type AsyncReceiver = std::sync::Arc<std::sync::Mutex<std::sync::mpsc::Receiver<AsyncPacket>>>;
+
+/// Worker thread of an AsyncDecoder, stopped by Decoder.__exit__.
+struct AsyncWorker {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    receiver: AsyncReceiver,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl AsyncWorker {
+    fn join(self, python: Python) {
+        self.stop.store(true, std::sync::atomic::Ordering::Release);
+        // draining the queue unblocks a worker waiting to send, the iteration ends when the worker drops its sender
+        let receiver = self.receiver;
+        let thread = self.thread;
+        let (packets, result) = python.allow_threads(move || {
+            let packets: Vec<AsyncPacket> = receiver
+                .lock()
+                .expect("the receiver mutex is not poisoned")
+                .iter()
+                .collect();
+            (packets, thread.join())
+        });
+        drop(packets);
+        if let Err(payload) = result {
+            std::panic::resume_unwind(payload);
+        }
+    }
+}
+
+/// Asynchronous iterator returned by Decoder.__aiter__.
+///
+/// A worker thread reads packets like Decoder.__next__ and queues up to ASYNC_QUEUE_SIZE packets.
+/// Reading and parsing release the GIL, hence the worker does not block the event loop.
+/// The worker and Decoder.__next__ take turns on the decoder, packets go to whichever asks first.
+/// The worker stops at the end of the file, on error, when this iterator is dropped, or when the decoder is closed.
+#[pyclass]
+pub struct AsyncDecoder {
+    receiver: AsyncReceiver,
+}
+
+#[pymethods]
+impl AsyncDecoder {
+    fn __aiter__(shell: PyRef<Self>) -> Py<AsyncDecoder> {
+        shell.into()
+    }
+
+    fn __anext__(shell: &Bound<'_, Self>) -> PyResult<PyObject> {
+        let python = shell.py();
+        let event_loop = python
+            .import_bound("asyncio")?
+            .call_method0("get_running_loop")?;
+        Ok(event_loop
+            .call_method1(
+                "run_in_executor",
+                (python.None(), shell.getattr("next_packet")?),
+            )?
+            .unbind())
+    }
+
+    /// Waits for the next packet without holding the GIL, called on the event loop's executor by __anext__.
+    fn next_packet(&self, python: Python) -> PyResult<PyObject> {
+        // the worker drops its sender after the last packet, hence recv fails at the end of the file
+        match python.allow_threads(|| {
+            self.receiver
+                .lock()
+                .expect("the receiver mutex is not poisoned")
+                .recv()
+        }) {
+            Ok(Ok(Some(packet))) => Ok(packet),
+            Ok(Ok(None)) | Err(_) => Err(pyo3::exceptions::PyStopAsyncIteration::new_err(())),
+            Ok(Err(error)) => Err(error),
+        }
+    }
+}
+
+/// Size of the header written by save_npy, padded with spaces so that the event data is aligned on 64 bytes.
+const NPY_HEADER_SIZE: usize = 192;
+
+/// Returns a NumPy format 1.0 header for a one-dimensional array of DVS events.
+fn npy_header(length: u64) -> [u8; NPY_HEADER_SIZE] {
+    let description = format!(
+        "{{'descr': [('t', '<u8'), ('x', '<u2'), ('y', '<u2'), (('p', 'on'), '|b1')], 'fortran_order': False, 'shape': ({length},), }}"
+    );
+    let mut header = [b' '; NPY_HEADER_SIZE];
+    header[0..6].copy_from_slice(b"\x93NUMPY");
+    header[6] = 1;
+    header[7] = 0;
+    header[8..10].copy_from_slice(&((NPY_HEADER_SIZE - 10) as u16).to_le_bytes());
+    header[10..10 + description.len()].copy_from_slice(description.as_bytes());
+    header[NPY_HEADER_SIZE - 1] = b'\n';
+    header
+}
+
+/// Output field names and integer types, applied to the arrays built with the default layout.
+#[derive(Debug, Clone, Default)]
+struct Layout {
+    /// Maps default field names to output names, fields that are not listed keep their name.
+    names: std::collections::HashMap<String, String>,
+    signed_t: bool,
+    narrow_xy: bool,
+}
+
+impl Layout {
+    /// dimensions are the output dimensions (after the region of interest and the geometry transform).
+    fn new(
+        names: Option<std::collections::HashMap<String, String>>,
+        t_dtype: &str,
+        xy_dtype: &str,
+        event_type: common::Type,
+        dimensions: Option<(u16, u16)>,
+    ) -> PyResult<Self> {
+        let signed_t = match t_dtype {
+            "uint64" => false,
+            "int64" => true,
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown t_dtype \"{t_dtype}\" (expected \"uint64\" or \"int64\")"
+                )))
+            }
+        };
+        let narrow_xy = match xy_dtype {
+            "uint16" => false,
+            "uint8" => true,
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown xy_dtype \"{xy_dtype}\" (expected \"uint16\" or \"uint8\")"
+                )))
+            }
+        };
+        if narrow_xy {
+            match dimensions {
+                Some((width, height)) => {
+                    if width > 256 || height > 256 {
+                        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                            "xy_dtype=\"uint8\" cannot represent the coordinates of a {width}x{height} stream (the width and height must be at most 256)"
+                        )));
+                    }
+                }
+                None => return Err(pyo3::exceptions::PyValueError::new_err(
+                    "xy_dtype=\"uint8\" requires a stream with coordinates (got a generic stream)",
+                )),
+            }
+        }
+        let layout = Layout {
+            names: names.unwrap_or_default(),
+            signed_t,
+            narrow_xy,
+        };
+        let fields = event_type_to_array_type(event_type).fields();
+        let default_names: Vec<String> = fields.iter().map(|field| field.name()).collect();
+        if let Some(name) = layout
+            .names
+            .keys()
+            .find(|name| !default_names.contains(name))
+        {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown field \"{name}\" in field_names (expected one of \"{}\")",
+                default_names.join("\", \"")
+            )));
+        }
+        let mut output_names = std::collections::HashSet::new();
+        for name in default_names.iter() {
+            let name = layout.name(name);
+            if name.is_empty() || name.contains('\0') {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "field names must be non-empty and must not contain null characters",
+                ));
+            }
+            if !output_names.insert(name) {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "field_names maps several fields to \"{name}\""
+                )));
+            }
+        }
+        Ok(layout)
+    }
+
+    fn is_default(&self) -> bool {
+        self.names.is_empty() && !self.signed_t && !self.narrow_xy
+    }
+
+    fn name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.names.get(name).map_or(name, String::as_str)
+    }
+
+    /// Returns the fields of array_type with this layout's names and types.
+    ///
+    /// Titles (for instance "p" for "on") are aliases of the default names,
+    /// they are dropped if the field is renamed or if another field is renamed to the title.
+    fn fields(&self, array_type: types::ArrayType) -> Vec<types::NamedField> {
+        let fields = array_type.fields();
+        let names: Vec<String> = fields
+            .iter()
+            .map(|field| self.name(&field.name()).to_owned())
+            .collect();
+        fields
+            .iter()
+            .zip(names.iter())
+            .map(|(field, name)| {
+                let default_name = field.name();
+                types::NamedField {
+                    name: name.clone(),
+                    title: field
+                        .title
+                        .filter(|title| {
+                            *name == default_name && !names.iter().any(|name| name == title)
+                        })
+                        .map(str::to_owned),
+                    field_type: match default_name.as_str() {
+                        "t" if self.signed_t => types::FieldType::I64,
+                        "x" | "y" if self.narrow_xy => types::FieldType::U8,
+                        _ => field.field_type,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Converts an array with the default layout of array_type.
+    ///
+    /// Renaming fields returns a view, changing types copies the array.
+    fn apply(
+        &self,
+        python: Python,
+        array: PyObject,
+        array_type: types::ArrayType,
+    ) -> PyResult<PyObject> {
+        if self.is_default() {
+            return Ok(array);
+        }
+        let dtype = unsafe {
+            PyObject::from_owned_ptr(
+                python,
+                types::named_fields_dtype(python, &self.fields(array_type))
+                    as *mut pyo3::ffi::PyObject,
+            )
+        };
+        if !self.signed_t && !self.narrow_xy {
+            return array.call_method1(python, "view", (dtype,));
+        }
+        let array = array.bind(python);
+        let output = python
+            .import_bound("numpy")?
+            .call_method1("empty", (array.len()?, dtype))?;
+        for field in array_type.fields().iter() {
+            let name = field.name();
+            // coordinates fit in uint8 since new checks the dimensions
+            output.set_item(self.name(&name), array.get_item(&name)?)?;
+        }
+        Ok(output.unbind())
+    }
+}
+
+impl Decoder {
+    /// Reads the next packet and its index, see enumerate.
+    ///
+    /// The Python object is not borrowed while reading, hence other methods (and the asynchronous iterators' workers)
+    /// may run in the meantime.
+    fn next_enumerated(shell: PyRef<Self>) -> PyResult<Option<(u64, PyObject)>> {
+        let python = shell.py();
+        let inner = shell.inner.clone();
+        let options = shell.packet_options();
+        drop(shell);
+        read_packet(python, &inner, &options)
+    }
+
+    /// Shared by the constructors, applies the region of interest and the geometry to the decoder's dimensions.
+    #[allow(clippy::too_many_arguments)]
+    fn from_source(
+        source: utilities::Source,
+        t0: u64,
+        skip_empty: bool,
+        ragged: bool,
+        roi: Option<(u16, u16, u16, u16)>,
+        downsample: u16,
+        flip_x: bool,
+        flip_y: bool,
+        rotate: u16,
+        field_names: Option<std::collections::HashMap<String, String>>,
+        t_dtype: &str,
+        xy_dtype: &str,
+        split_atis: bool,
+        soa: bool,
+        mask_pixels: Option<Vec<(u16, u16)>>,
+        remap: Option<&pyo3::Bound<'_, pyo3::types::PyAny>>,
+        median_denoise: Option<u64>,
+        override_type: Option<&str>,
+        buffer_size: usize,
+    ) -> PyResult<Self> {
+        utilities::check_buffer_size(buffer_size)?;
+        let override_type = override_type
+            .map(|override_type| match override_type {
+                "dvs" => Ok(common::Type::Dvs),
+                "atis" => Ok(common::Type::Atis),
+                "color" => Ok(common::Type::Color),
+                override_type => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "override_type must be \"dvs\", \"atis\", or \"color\" (got \"{override_type}\")"
+                ))),
+            })
+            .transpose()?;
+        let mut decoder = decoder::Decoder::new(source, t0, buffer_size, override_type)?;
+        // masked pixels, the remap table, and the median denoiser are in sensor coordinates,
+        // before the region of interest and the geometry transform
+        if let Some(mask_pixels) = mask_pixels {
+            match decoder.dimensions() {
+                Some(dimensions) => {
+                    decoder.set_mask(Some(utilities::PixelMask::new(dimensions, &mask_pixels)?))
+                }
+                None => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "mask_pixels requires a stream with coordinates (got a generic stream)",
+                    ))
+                }
+            }
+        }
+        if let Some(remap) = remap {
+            match decoder.dimensions() {
+                Some(dimensions) => {
+                    decoder.set_remap(Some(utilities::PixelRemap::from_array(remap, dimensions)?))
+                }
+                None => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "remap requires a stream with coordinates (got a generic stream)",
+                    ))
+                }
+            }
+        }
+        if let Some(tolerance) = median_denoise {
+            match decoder.dimensions() {
+                Some(dimensions) => decoder
+                    .set_median_denoise(Some(utilities::MedianDenoise::new(dimensions, tolerance))),
+                None => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "median_denoise requires a stream with coordinates (got a generic stream)",
+                    ))
+                }
+            }
+        }
+        let roi = match roi {
+            Some((x, y, width, height)) => {
+                if width == 0 || height == 0 {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "the region of interest must have a non-zero width and height (got {width}x{height})"
+                    )));
+                }
+                if let Some(dimensions) = decoder.dimensions() {
+                    if x as u32 + width as u32 > dimensions.0 as u32
+                        || y as u32 + height as u32 > dimensions.1 as u32
+                    {
+                        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                            "the region of interest (x={x}, y={y}, width={width}, height={height}) does not fit in the sensor ({}x{})",
+                            dimensions.0, dimensions.1
+                        )));
+                    }
+                }
+                Some(decoder::Roi {
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+            }
+            None => None,
+        };
+        let geometry = decoder::Geometry::new(
+            match roi {
+                Some(roi) => decoder.dimensions().map(|_| (roi.width, roi.height)),
+                None => decoder.dimensions(),
+            },
+            downsample,
+            flip_x,
+            flip_y,
+            rotate,
+        )?;
+        let layout = Layout::new(
+            field_names,
+            t_dtype,
+            xy_dtype,
+            decoder.event_type,
+            decoder.dimensions().map(|_| geometry.dimensions()),
+        )?;
+        Ok(Decoder {
+            roi,
+            geometry,
+            layout,
+            inner: std::sync::Arc::new(std::sync::Mutex::new(Some(decoder))),
+            skip_empty,
+            ragged,
+            split_atis,
+            soa,
+            progress_callback: std::sync::Mutex::new(None),
+            workers: std::sync::Mutex::new(Vec::new()),
+        })
+    }
+
+    fn lock(&self, python: Python) -> std::sync::MutexGuard<'_, Option<decoder::Decoder>> {
+        lock_decoder(python, &self.inner)
+    }
+
+    fn progress_callback(&self) -> Option<PyObject> {
+        self.progress_callback
+            .lock()
+            .expect("the progress callback mutex is not poisoned")
+            .clone()
+    }
+
+    fn packet_options(&self) -> PacketOptions {
+        PacketOptions {
+            skip_empty: self.skip_empty,
+            ragged: self.ragged,
+            split_atis: self.split_atis,
+            soa: self.soa,
+            roi: self.roi,
+            geometry: self.geometry,
+            layout: self.layout.clone(),
+        }
+    }
+
+    /// Returns a decoder that reads the file from the start with a separate handle, if the file contains DVS events.
+    fn dvs_scanner(&self, python: Python, name: &str) -> PyResult<decoder::Decoder> {
+        match *self.lock(python) {
+            Some(ref decoder) => {
+                if !matches!(decoder.event_type, common::Type::Dvs) {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "{name} requires a file with DVS events"
+                    )));
+                }
+                Ok(decoder.restart()?)
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(format!(
+                "called {name} after __exit__"
+            ))),
+        }
+    }
+}
+
+#[pymethods]
+impl Decoder {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (path, t0, skip_empty = true, ragged = false, roi = None, downsample = 1, flip_x = false, flip_y = false, rotate = 0, field_names = None, t_dtype = "uint64", xy_dtype = "uint16", split_atis = false, soa = false, mask_pixels = None, remap = None, median_denoise = None, override_type = None, buffer_size = utilities::BUFFER_SIZE))]
+    fn new(
+        path: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        t0: u64,
+        skip_empty: bool,
+        ragged: bool,
+        roi: Option<(u16, u16, u16, u16)>,
+        downsample: u16,
+        flip_x: bool,
+        flip_y: bool,
+        rotate: u16,
+        field_names: Option<std::collections::HashMap<String, String>>,
+        t_dtype: &str,
+        xy_dtype: &str,
+        split_atis: bool,
+        soa: bool,
+        mask_pixels: Option<Vec<(u16, u16)>>,
+        remap: Option<&pyo3::Bound<'_, pyo3::types::PyAny>>,
+        median_denoise: Option<u64>,
+        override_type: Option<&str>,
+        buffer_size: usize,
+    ) -> Result<Self, PyErr> {
+        Python::with_gil(|python| -> Result<Self, PyErr> {
+            Self::from_source(
+                types::python_source(python, path)?,
+                t0,
+                skip_empty,
+                ragged,
+                roi,
+                downsample,
+                flip_x,
+                flip_y,
+                rotate,
+                field_names,
+                t_dtype,
+                xy_dtype,
+                split_atis,
+                soa,
+                mask_pixels,
+                remap,
+                median_denoise,
+                override_type,
+                buffer_size,
+            )
+        })
+    }
+
+    /// Decodes an in-memory Event Stream file (for instance received over a network), which is copied once.
+    ///
+    /// Unlike file-like objects, the buffer is seekable and supports duration, event_count, and to_array.
+    #[staticmethod]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (data, t0, skip_empty = true, ragged = false, roi = None, downsample = 1, flip_x = false, flip_y = false, rotate = 0, field_names = None, t_dtype = "uint64", xy_dtype = "uint16", split_atis = false, soa = false, mask_pixels = None, remap = None, median_denoise = None, override_type = None, buffer_size = utilities::BUFFER_SIZE))]
+    fn from_bytes(
+        data: &pyo3::Bound<'_, pyo3::types::PyBytes>,
+        t0: u64,
+        skip_empty: bool,
+        ragged: bool,
+        roi: Option<(u16, u16, u16, u16)>,
+        downsample: u16,
+        flip_x: bool,
+        flip_y: bool,
+        rotate: u16,
+        field_names: Option<std::collections::HashMap<String, String>>,
+        t_dtype: &str,
+        xy_dtype: &str,
+        split_atis: bool,
+        soa: bool,
+        mask_pixels: Option<Vec<(u16, u16)>>,
+        remap: Option<&pyo3::Bound<'_, pyo3::types::PyAny>>,
+        median_denoise: Option<u64>,
+        override_type: Option<&str>,
+        buffer_size: usize,
+    ) -> Result<Self, PyErr> {
+        use pyo3::types::PyBytesMethods;
+        Self::from_source(
+            utilities::Source::memory(data.as_bytes()),
+            t0,
+            skip_empty,
+            ragged,
+            roi,
+            downsample,
+            flip_x,
+            flip_y,
+            rotate,
+            field_names,
+            t_dtype,
+            xy_dtype,
+            split_atis,
+            soa,
+            mask_pixels,
+            remap,
+            median_denoise,
+            override_type,
+            buffer_size,
+        )
+    }
+
+    #[getter]
+    fn version(&self, python: Python) -> PyResult<String> {
+        match *self.lock(python) {
+            Some(ref decoder) => Ok({
+                let version = decoder.version();
+                format!("{}.{}.{}", version[0], version[1], version[2])
+            }),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called version after __exit__",
+            )),
+        }
+    }
+
+    /// Type of the decoded events, override_type if it was provided.
+    #[getter]
+    fn event_type(&self, python: Python) -> PyResult<String> {
+        match *self.lock(python) {
+            Some(ref decoder) => Ok(decoder.event_type.to_str().to_owned()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called event_type after __exit__",
+            )),
+        }
+    }
+
+    /// Type read from the file's header, which may differ from event_type if override_type was provided.
+    #[getter]
+    fn declared_type(&self, python: Python) -> PyResult<String> {
+        match *self.lock(python) {
+            Some(ref decoder) => Ok(decoder.declared_type.to_str().to_owned()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called declared_type after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn dimensions(&self, python: Python) -> PyResult<Option<(u16, u16)>> {
+        match *self.lock(python) {
+            Some(ref decoder) => Ok(decoder.dimensions().map(|_| self.geometry.dimensions())),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called dimensions after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn width(&self, python: Python) -> PyResult<Option<u16>> {
+        match *self.lock(python) {
+            Some(ref decoder) => Ok(decoder.dimensions().map(|_| self.geometry.dimensions().0)),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called width after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn height(&self, python: Python) -> PyResult<Option<u16>> {
+        match *self.lock(python) {
+            Some(ref decoder) => Ok(decoder.dimensions().map(|_| self.geometry.dimensions().1)),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called height after __exit__",
+            )),
+        }
+    }
+
+    fn duration(&self, python: Python) -> PyResult<u64> {
+        match *self.lock(python) {
+            Some(ref mut decoder) => Ok(decoder.statistics()?.duration),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called duration after __exit__",
+            )),
+        }
+    }
+
+    fn event_count(&self, python: Python) -> PyResult<u64> {
+        match *self.lock(python) {
+            Some(ref mut decoder) => Ok(decoder.statistics()?.event_count),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called event_count after __exit__",
+            )),
+        }
+    }
+
+    /// Scans the whole file once and returns aggregates (counts, rates, and active pixels) without building arrays.
+    ///
+    /// Like duration and event_count, the statistics describe the whole file in sensor coordinates
+    /// (the region of interest and the geometry are not applied).
+    fn statistics(&self, python: Python) -> PyResult<PyObject> {
+        match *self.lock(python) {
+            Some(ref decoder) => {
+                let summary = python.allow_threads(|| decoder.summary())?;
+                let statistics = pyo3::types::PyDict::new_bound(python);
+                statistics.set_item("event_count", summary.event_count)?;
+                statistics.set_item("on_count", summary.on_count)?;
+                statistics.set_item("off_count", summary.off_count)?;
+                statistics.set_item("duration", summary.duration)?;
+                statistics.set_item(
+                    "mean_rate",
+                    if summary.duration > 0 {
+                        summary.event_count as f64 * 1e6 / summary.duration as f64
+                    } else {
+                        0.0
+                    },
+                )?;
+                statistics.set_item("peak_events_per_ms", summary.peak_events_per_ms)?;
+                statistics.set_item("active_pixels", summary.active_pixels)?;
+                statistics.set_item("bounding_box", summary.bounding_box)?;
+                Ok(statistics.into())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called statistics after __exit__",
+            )),
+        }
+    }
+
+    /// Returns the current byte offset in the file, which can be passed to seek_bytes to resume decoding.
+    fn tell(&self, python: Python) -> PyResult<u64> {
+        match *self.lock(python) {
+            Some(ref decoder) => {
+                // offsets in the decompressed stream cannot be passed to seek_bytes
+                if decoder.is_gzip() {
+                    return Err(decoder::Error::from(utilities::gzip_not_seekable_error()).into());
+                }
+                Ok(decoder.tell())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called tell after __exit__",
+            )),
+        }
+    }
+
+    /// Resumes decoding at a byte offset previously returned by tell.
+    ///
+    /// The file is decoded from the start to offset (without building arrays) to restore the timestamp.
+    fn seek_bytes(&self, python: Python, offset: u64) -> PyResult<()> {
+        match *self.lock(python) {
+            Some(ref mut decoder) => Ok(decoder.seek_bytes(offset)?),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called seek_bytes after __exit__",
+            )),
+        }
+    }
+
+    /// Moves the decoder back to the first packet, as if the file had just been opened.
+    ///
+    /// Unlike seek_bytes, this also works with gzip-compressed files (they are reopened).
+    fn reset(&self, python: Python) -> PyResult<()> {
+        match *self.lock(python) {
+            Some(ref mut decoder) => Ok(decoder.reset()?),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called reset after __exit__",
+            )),
+        }
+    }
+
+    /// Counts events per bin_us µs bin over the whole file, starting at the first event.
+    ///
+    /// The result is a 1D int64 array, or a 2D array with OFF and ON columns if polarity is true
+    /// (DVS and ATIS streams only, ATIS exposure measurements are not counted).
+    #[pyo3(signature = (bin_us, polarity = false))]
+    fn time_histogram(&self, python: Python, bin_us: u64, polarity: bool) -> PyResult<PyObject> {
+        match *self.lock(python) {
+            Some(ref decoder) => {
+                if bin_us == 0 {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "bin_us must be larger than zero",
+                    ));
+                }
+                if polarity && !matches!(decoder.event_type, common::Type::Dvs | common::Type::Atis)
+                {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "time_histogram with polarity requires a file with DVS or ATIS events",
+                    ));
+                }
+                let histogram = python.allow_threads(|| decoder.time_histogram(bin_us))?;
+                if polarity {
+                    let mut array = ndarray::Array2::<i64>::zeros((histogram.len(), 2));
+                    for (index, row) in histogram.iter().enumerate() {
+                        array[[index, 0]] = row[1] as i64;
+                        array[[index, 1]] = row[2] as i64;
+                    }
+                    Ok(array.to_pyarray_bound(python).to_object(python))
+                } else {
+                    Ok(numpy::PyArray1::from_vec_bound(
+                        python,
+                        histogram.iter().map(|row| row[0] as i64).collect(),
+                    )
+                    .to_object(python))
+                }
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called time_histogram after __exit__",
+            )),
+        }
+    }
+
+    fn clock_glitches(&self, python: Python, threshold_us: u64) -> PyResult<PyObject> {
+        match *self.lock(python) {
+            Some(ref decoder) => {
+                let glitches = decoder.clock_glitches(threshold_us)?;
+                Python::with_gil(|python| -> PyResult<PyObject> {
+                    let mut array = ndarray::Array2::<u64>::zeros((glitches.len(), 2));
+                    for (index, (t, gap)) in glitches.iter().enumerate() {
+                        array[[index, 0]] = *t;
+                        array[[index, 1]] = *gap;
+                    }
+                    Ok(array.to_pyarray_bound(python).to_object(python))
+                })
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called clock_glitches after __exit__",
+            )),
+        }
+    }
+
+    /// Registers a callable invoked with (bytes_processed, bytes_total) during to_array, to_csv, and save_npy.
+    ///
+    /// The callback is called at most about 100 times per file, None removes it.
+    fn set_progress_callback(&self, python: Python, callback: Option<PyObject>) -> PyResult<()> {
+        if self.lock(python).is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called set_progress_callback after __exit__",
+            ));
+        }
+        if let Some(ref callback) = callback {
+            if !callback.bind(python).is_callable() {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "the progress callback must be callable or None",
+                ));
+            }
+        }
+        *self
+            .progress_callback
+            .lock()
+            .expect("the progress callback mutex is not poisoned") = callback;
+        Ok(())
+    }
+
+    fn to_array(&self, python: Python) -> PyResult<PyObject> {
+        match *self.lock(python) {
+            Some(ref mut decoder) => {
+                let length = decoder.statistics()?.event_count as numpy::npyffi::npy_intp;
+                let mut scanner = decoder.restart()?;
+                let mut progress = Progress::new(self.progress_callback(), &scanner)?;
+                let array_type = event_type_to_array_type(decoder.event_type);
+                Python::with_gil(|python| -> PyResult<PyObject> {
+                    let array = array_type.new_array(python, length);
+                    // the array owns the Python objects created by fill_array,
+                    // it must be released on error
+                    let array_object = unsafe {
+                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+                    };
+                    let mut offset = 0;
+                    // events read from the file, including those dropped by the region of interest
+                    let mut decoded_length = 0;
+                    while let Some(packet) = scanner.next()? {
+                        decoded_length += packet.len() as numpy::npyffi::npy_intp;
+                        if decoded_length > length {
+                            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                                "the file changed while it was being read",
+                            ));
+                        }
+                        let cropped_packet = self.roi.and_then(|roi| roi.crop(&packet));
+                        let packet = match cropped_packet {
+                            Some(ref cropped_packet) => cropped_packet.packet(),
+                            None => packet,
+                        };
+                        let packet_length = packet.len() as numpy::npyffi::npy_intp;
+                        unsafe {
+                            fill_array(python, array, offset, &packet, &self.geometry);
+                        }
+                        offset += packet_length;
+                        progress.update(scanner.tell())?;
+                    }
+                    progress.finish(scanner.tell())?;
+                    if decoded_length != length {
+                        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                            "the file changed while it was being read",
+                        ));
+                    }
+                    let array_object = if offset < length {
+                        // the region of interest dropped events
+                        array_object.call_method1(
+                            python,
+                            "__getitem__",
+                            (pyo3::types::PySlice::new_bound(python, 0, offset, 1),),
+                        )?
+                    } else {
+                        array_object
+                    };
+                    self.layout.apply(python, array_object, array_type)
+                })
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called to_array after __exit__",
+            )),
+        }
+    }
+
+    /// Writes the events to a CSV file with a "t,x,y,on" header, one packet at a time.
+    ///
+    /// Like to_array, the file is read from the start with a separate handle, the region of interest and the geometry are applied.
+    /// OFF events are written as -1 instead of 0 if signed_polarity is true.
+    #[pyo3(signature = (path, signed_polarity = false))]
+    fn to_csv(
+        &self,
+        python: Python,
+        path: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        signed_polarity: bool,
+    ) -> PyResult<()> {
+        use std::io::Write;
+        let mut scanner = self.dvs_scanner(python, "to_csv")?;
+        let mut progress = Progress::new(self.progress_callback(), &scanner)?;
+        let path = Python::with_gil(|python| types::python_path_to_string(python, path))?;
+        let mut output = std::io::BufWriter::new(std::fs::File::create(path)?);
+        output.write_all(b"t,x,y,on\n")?;
+        let off = if signed_polarity { "-1" } else { "0" };
+        let roi = self.roi;
+        let geometry = &self.geometry;
+        // the GIL is released while decoding, Progress reacquires it to call the callback
+        python.allow_threads(move || -> PyResult<()> {
+            while let Some(packet) = scanner.next()? {
+                let cropped_packet = roi.and_then(|roi| roi.crop(&packet));
+                let packet = match cropped_packet {
+                    Some(ref cropped_packet) => cropped_packet.packet(),
+                    None => packet,
+                };
+                if let decoder::Packet::Dvs(events) = packet {
+                    for event in events.iter() {
+                        let t = event.t;
+                        let (x, y) = geometry.apply(event.x, event.y);
+                        let on = match event.polarity {
+                            neuromorphic_types::DvsPolarity::Off => off,
+                            neuromorphic_types::DvsPolarity::On => "1",
+                        };
+                        writeln!(output, "{t},{x},{y},{on}")?;
+                    }
+                }
+                // memory usage does not depend on the file size
+                output.flush()?;
+                progress.update(scanner.tell())?;
+            }
+            progress.finish(scanner.tell())
+        })
+    }
+
+    /// Writes the events to a NumPy file (format 1.0) with the DVS dtype, which numpy.load reads directly.
+    ///
+    /// Events are written as they are decoded, the shape in the header is filled in at the end.
+    fn save_npy(&self, python: Python, path: &pyo3::Bound<'_, pyo3::types::PyAny>) -> PyResult<()> {
+        use std::io::Seek;
+        use std::io::Write;
+        let mut scanner = self.dvs_scanner(python, "save_npy")?;
+        let mut progress = Progress::new(self.progress_callback(), &scanner)?;
+        let path = Python::with_gil(|python| types::python_path_to_string(python, path))?;
+        let mut output = std::io::BufWriter::new(std::fs::File::create(path)?);
+        output.write_all(&[0u8; NPY_HEADER_SIZE])?;
+        let roi = self.roi;
+        let geometry = &self.geometry;
+        // the GIL is released while decoding, Progress reacquires it to call the callback
+        python.allow_threads(move || -> PyResult<()> {
+            let mut length: u64 = 0;
+            while let Some(packet) = scanner.next()? {
+                let cropped_packet = roi.and_then(|roi| roi.crop(&packet));
+                let packet = match cropped_packet {
+                    Some(ref cropped_packet) => cropped_packet.packet(),
+                    None => packet,
+                };
+                if let decoder::Packet::Dvs(events) = packet {
+                    for event in events.iter() {
+                        let (x, y) = geometry.apply(event.x, event.y);
+                        let event = neuromorphic_types::DvsEvent { x, y, ..*event };
+                        // DvsEvent is packed and has the same layout as the DVS dtype
+                        output.write_all(unsafe {
+                            std::slice::from_raw_parts(
+                                &event as *const neuromorphic_types::DvsEvent<u64, u16, u16>
+                                    as *const u8,
+                                std::mem::size_of::<neuromorphic_types::DvsEvent<u64, u16, u16>>(),
+                            )
+                        })?;
+                    }
+                    length += events.len() as u64;
+                }
+                progress.update(scanner.tell())?;
+            }
+            output.seek(std::io::SeekFrom::Start(0))?;
+            output.write_all(&npy_header(length))?;
+            output.flush()?;
+            progress.finish(scanner.tell())
+        })
+    }
+
+    /// Returns the events as a pandas DataFrame with the fields of to_array as columns.
+    ///
+    /// Color events have r, g, and b columns (uint8) and ATIS events have exposure and polarity columns instead of on.
+    /// pandas is imported by this function, it is not a dependency of faery.
+    fn to_dataframe(&self, python: Python) -> PyResult<PyObject> {
+        let (event_type, mut scanner) = match *self.lock(python) {
+            Some(ref decoder) => (decoder.event_type, decoder.restart()?),
+            None => {
+                return Err(pyo3::exceptions::PyException::new_err(
+                    "called to_dataframe after __exit__",
+                ))
+            }
+        };
+        if matches!(event_type, common::Type::Generic) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "to_dataframe does not support generic events",
+            ));
+        }
+        Python::with_gil(|python| -> PyResult<PyObject> {
+            let pandas = types::import_optional(python, "pandas", "pandas", "to_dataframe")?;
+            let mut ts = Vec::new();
+            let mut xs = Vec::new();
+            let mut ys = Vec::new();
+            // on (DVS), exposure and polarity (ATIS), or r, g, and b (color)
+            let mut channels: [Vec<u8>; 3] = [Vec::new(), Vec::new(), Vec::new()];
+            while let Some(packet) = scanner.next()? {
+                let cropped_packet = self.roi.and_then(|roi| roi.crop(&packet));
+                let packet = match cropped_packet {
+                    Some(ref cropped_packet) => cropped_packet.packet(),
+                    None => packet,
+                };
+                match packet {
+                    decoder::Packet::Generic(_) => (),
+                    decoder::Packet::Dvs(events) => {
+                        for event in events.iter() {
+                            let (x, y) = self.geometry.apply(event.x, event.y);
+                            ts.push(event.t);
+                            xs.push(x);
+                            ys.push(y);
+                            channels[0].push(match event.polarity {
+                                neuromorphic_types::DvsPolarity::Off => 0,
+                                neuromorphic_types::DvsPolarity::On => 1,
+                            });
+                        }
+                    }
+                    decoder::Packet::Atis(events) => {
+                        for event in events.iter() {
+                            let (x, y) = self.geometry.apply(event.x, event.y);
+                            ts.push(event.t);
+                            xs.push(x);
+                            ys.push(y);
+                            let (exposure, polarity) = match event.polarity {
+                                neuromorphic_types::AtisPolarity::Off => (0, 0),
+                                neuromorphic_types::AtisPolarity::On => (0, 1),
+                                neuromorphic_types::AtisPolarity::ExposureStart => (1, 0),
+                                neuromorphic_types::AtisPolarity::ExposureEnd => (1, 1),
+                            };
+                            channels[0].push(exposure);
+                            channels[1].push(polarity);
+                        }
+                    }
+                    decoder::Packet::Color(events) => {
+                        for event in events.iter() {
+                            let (x, y) = self.geometry.apply(event.x, event.y);
+                            ts.push(event.t);
+                            xs.push(x);
+                            ys.push(y);
+                            channels[0].push(event.r);
+                            channels[1].push(event.g);
+                            channels[2].push(event.b);
+                        }
+                    }
+                }
+            }
+            let columns = pyo3::types::PyDict::new_bound(python);
+            columns.set_item("t", numpy::PyArray1::from_vec_bound(python, ts))?;
+            columns.set_item("x", numpy::PyArray1::from_vec_bound(python, xs))?;
+            columns.set_item("y", numpy::PyArray1::from_vec_bound(python, ys))?;
+            let [first, second, third] = channels;
+            let to_bool = |channel: Vec<u8>| -> Vec<bool> {
+                channel.into_iter().map(|value| value == 1).collect()
+            };
+            match event_type {
+                common::Type::Generic => (),
+                common::Type::Dvs => {
+                    columns.set_item(
+                        "on",
+                        numpy::PyArray1::from_vec_bound(python, to_bool(first)),
+                    )?;
+                }
+                common::Type::Atis => {
+                    columns.set_item(
+                        "exposure",
+                        numpy::PyArray1::from_vec_bound(python, to_bool(first)),
+                    )?;
+                    columns.set_item(
+                        "polarity",
+                        numpy::PyArray1::from_vec_bound(python, to_bool(second)),
+                    )?;
+                }
+                common::Type::Color => {
+                    columns.set_item("r", numpy::PyArray1::from_vec_bound(python, first))?;
+                    columns.set_item("g", numpy::PyArray1::from_vec_bound(python, second))?;
+                    columns.set_item("b", numpy::PyArray1::from_vec_bound(python, third))?;
+                }
+            }
+            Ok(pandas.getattr("DataFrame")?.call1((columns,))?.unbind())
+        })
+    }
+
+    /// Returns the DVS events as a dict of torch tensors ("t" int64, "x" and "y" int16, and "polarity" bool).
+    ///
+    /// The tensors wrap NumPy arrays (torch.from_numpy) that are allocated by each call and never reused,
+    /// hence they remain valid after the next call. torch is imported by this function, it is not a dependency of faery.
+    fn to_torch(&self, python: Python) -> PyResult<PyObject> {
+        let mut scanner = self.dvs_scanner(python, "to_torch")?;
+        Python::with_gil(|python| -> PyResult<PyObject> {
+            let torch = types::import_optional(python, "torch", "torch", "to_torch")?;
+            let mut ts = Vec::new();
+            let mut xs = Vec::new();
+            let mut ys = Vec::new();
+            let mut polarities = Vec::new();
+            while let Some(packet) = scanner.next()? {
+                let cropped_packet = self.roi.and_then(|roi| roi.crop(&packet));
+                let packet = match cropped_packet {
+                    Some(ref cropped_packet) => cropped_packet.packet(),
+                    None => packet,
+                };
+                if let decoder::Packet::Dvs(events) = packet {
+                    for event in events.iter() {
+                        let (x, y) = self.geometry.apply(event.x, event.y);
+                        ts.push(event.t as i64);
+                        xs.push(x as i16);
+                        ys.push(y as i16);
+                        polarities.push(matches!(
+                            event.polarity,
+                            neuromorphic_types::DvsPolarity::On
+                        ));
+                    }
+                }
+            }
+            let from_numpy = torch.getattr("from_numpy")?;
+            let tensors = pyo3::types::PyDict::new_bound(python);
+            tensors.set_item(
+                "t",
+                from_numpy.call1((numpy::PyArray1::from_vec_bound(python, ts),))?,
+            )?;
+            tensors.set_item(
+                "x",
+                from_numpy.call1((numpy::PyArray1::from_vec_bound(python, xs),))?,
+            )?;
+            tensors.set_item(
+                "y",
+                from_numpy.call1((numpy::PyArray1::from_vec_bound(python, ys),))?,
+            )?;
+            tensors.set_item(
+                "polarity",
+                from_numpy.call1((numpy::PyArray1::from_vec_bound(python, polarities),))?,
+            )?;
+            Ok(tensors.into())
+        })
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    /// Stops the workers of the asynchronous iterators, then closes the file.
+    fn __exit__(
+        &self,
+        python: Python,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        let workers = std::mem::take(
+            &mut *self
+                .workers
+                .lock()
+                .expect("the workers mutex is not poisoned"),
+        );
+        for worker in workers {
+            worker.join(python);
+        }
+        let mut inner = self.lock(python);
+        if inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRef<Self>) -> PyResult<Py<Decoder>> {
+        Ok(shell.into())
+    }
+
+    /// Returns an AsyncDecoder that decodes packets on a worker thread.
+    ///
+    /// The synchronous iterator remains available, packets are yielded by whichever iterator calls __next__ first.
+    fn __aiter__(&self, python: Python) -> PyResult<Py<AsyncDecoder>> {
+        if self.lock(python).is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called __aiter__ after __exit__",
+            ));
+        }
+        let (sender, receiver) = std::sync::mpsc::sync_channel::<AsyncPacket>(ASYNC_QUEUE_SIZE);
+        let receiver = std::sync::Arc::new(std::sync::Mutex::new(receiver));
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let inner = self.inner.clone();
+        let options = self.packet_options();
+        let thread = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(std::sync::atomic::Ordering::Acquire) {
+                    let packet = Python::with_gil(|python| {
+                        read_packet(python, &inner, &options)
+                            .map(|packet| packet.map(|(_, packet)| packet))
+                    });
+                    let last = !matches!(packet, Ok(Some(_)));
+                    if let Err(error) = sender.send(packet) {
+                        Python::with_gil(|_| drop(error));
+                        break;
+                    }
+                    if last {
+                        break;
+                    }
+                }
+            })
+        };
+        let mut workers = self
+            .workers
+            .lock()
+            .expect("the workers mutex is not poisoned");
+        workers.retain(|worker| !worker.thread.is_finished());
+        workers.push(AsyncWorker {
+            stop,
+            receiver: receiver.clone(),
+            thread,
+        });
+        Py::new(python, AsyncDecoder { receiver })
+    }
+
+    fn __next__(shell: PyRef<Self>) -> PyResult<Option<PyObject>> {
+        Decoder::next_enumerated(shell).map(|packet| packet.map(|(_, packet)| packet))
+    }
+
+    /// Returns an iterator over (packet_index, packet) tuples.
+    ///
+    /// The decoder reads buffer_size bytes at a time, hence packet_index depends on buffer_size.
+    /// It counts the reads since the decoder was opened or reset, including the empty packets skipped by the decoder
+    /// and the reads of seek_bytes. The iterator shares this decoder's file handle.
+    fn enumerate(shell: PyRef<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        let python = shell.py();
+        if shell.lock(python).is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called enumerate after __exit__",
+            ));
+        }
+        Py::new(
+            python,
+            EnumeratedDecoder {
+                source: EnumeratedSource::Decoder(shell.into()),
+            },
+        )
+    }
+
+    /// Returns an iterator over the packets of this file, without the events of hot pixels.
+    ///
+    /// The file is scanned once (with a separate file handle) to flag pixels whose event rate is strictly
+    /// larger than threshold_hz. The iterator reads the file from the start, with a separate file handle.
+    fn hot_pixel_filter(&self, python: Python, threshold_hz: f64) -> PyResult<HotPixelFilter> {
+        if !threshold_hz.is_finite() || threshold_hz < 0.0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "threshold_hz must be a non-negative number (got {threshold_hz})"
+            )));
+        }
+        match *self.lock(python) {
+            Some(ref mut decoder) => match decoder.hot_pixels(threshold_hz)? {
+                Some(hot_pixels) => Ok(HotPixelFilter {
+                    inner: Some(decoder.restart()?),
+                    hot_pixels,
+                    skip_empty: self.skip_empty,
+                    roi: self.roi,
+                    geometry: self.geometry,
+                    layout: self.layout.clone(),
+                }),
+                None => Err(pyo3::exceptions::PyValueError::new_err(
+                    "hot_pixel_filter requires a stream with coordinates (got a generic stream)",
+                )),
+            },
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called hot_pixel_filter after __exit__",
+            )),
+        }
+    }
+
+    /// Returns an iterator over the packets of this file, without the events that fall within
+    /// the refractory period (period_us) of the same pixel's previous kept event.
+    ///
+    /// The iterator reads the file from the start, with a separate file handle.
+    fn refractory_filter(&self, python: Python, period_us: u64) -> PyResult<RefractoryFilter> {
+        match *self.lock(python) {
+            Some(ref mut decoder) => match decoder.dimensions() {
+                Some(dimensions) => Ok(RefractoryFilter {
+                    inner: Some(decoder.restart()?),
+                    refractory: decoder::Refractory::new(dimensions, period_us),
+                    skip_empty: self.skip_empty,
+                    roi: self.roi,
+                    geometry: self.geometry,
+                    layout: self.layout.clone(),
+                }),
+                None => Err(pyo3::exceptions::PyValueError::new_err(
+                    "refractory_filter requires a stream with coordinates (got a generic stream)",
+                )),
+            },
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called refractory_filter after __exit__",
+            )),
+        }
+    }
+
+    /// Returns an iterator over the packets of this file, without the events that have no neighbour
+    /// (8-connectivity) that fired within dt_us before them.
+    ///
+    /// The iterator reads the file from the start, with a separate file handle.
+    fn background_activity_filter(
+        &self,
+        python: Python,
+        dt_us: u64,
+    ) -> PyResult<BackgroundActivityFilter> {
+        match *self.lock(python) {
+            Some(ref mut decoder) => match decoder.dimensions() {
+                Some(dimensions) => Ok(BackgroundActivityFilter {
+                    inner: Some(decoder.restart()?),
+                    background_activity: decoder::BackgroundActivity::new(dimensions, dt_us),
+                    skip_empty: self.skip_empty,
+                    roi: self.roi,
+                    geometry: self.geometry,
+                    layout: self.layout.clone(),
+                }),
+                None => Err(pyo3::exceptions::PyValueError::new_err(
+                    "background_activity_filter requires a stream with coordinates (got a generic stream)",
+                )),
+            },
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called background_activity_filter after __exit__",
+            )),
+        }
+    }
+
+    /// Returns an iterator over fixed-duration chunks of this file, regardless of the packet boundaries.
+    ///
+    /// The first window starts at the first event's timestamp. Windows without events yield empty arrays.
+    fn chunks(&self, python: Python, duration_us: u64) -> PyResult<TimeChunks> {
+        if duration_us == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "duration_us must be larger than 0",
+            ));
+        }
+        match *self.lock(python) {
+            Some(ref decoder) => match decoder::CroppedPacket::empty(decoder.event_type) {
+                Some(carry) => Ok(TimeChunks {
+                    inner: Some(decoder.restart()?),
+                    carry,
+                    duration_us,
+                    window_start: None,
+                    roi: self.roi,
+                    geometry: self.geometry,
+                    layout: self.layout.clone(),
+                }),
+                None => Err(pyo3::exceptions::PyValueError::new_err(
+                    "chunks requires a stream with coordinates (got a generic stream)",
+                )),
+            },
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called chunks after __exit__",
+            )),
+        }
+    }
+
+    /// Returns an iterator over chunks of count events, regardless of the packet boundaries.
+    ///
+    /// The last chunk has fewer events if count does not divide the number of events.
+    fn chunks_by_count(&self, python: Python, count: usize) -> PyResult<CountChunks> {
+        if count == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "count must be larger than 0",
+            ));
+        }
+        match *self.lock(python) {
+            Some(ref decoder) => match decoder::CroppedPacket::empty(decoder.event_type) {
+                Some(carry) => Ok(CountChunks {
+                    inner: Some(decoder.restart()?),
+                    carry,
+                    count,
+                    roi: self.roi,
+                    geometry: self.geometry,
+                    layout: self.layout.clone(),
+                }),
+                None => Err(pyo3::exceptions::PyValueError::new_err(
+                    "chunks_by_count requires a stream with coordinates (got a generic stream)",
+                )),
+            },
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called chunks_by_count after __exit__",
+            )),
+        }
+    }
+
+    /// Returns an iterator over grayscale frames reconstructed from ATIS exposure measurements.
+    ///
+    /// Each pixel's intensity is min(255, k / interval), where interval is the time between
+    /// its latest exposure start and exposure end in µs. Pixels keep their value until the next measurement.
+    #[pyo3(signature = (frame_duration_us, k = 25500.0))]
+    fn atis_frames(&self, python: Python, frame_duration_us: u64, k: f64) -> PyResult<AtisFrames> {
+        if frame_duration_us == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "frame_duration_us must be larger than 0",
+            ));
+        }
+        if !(k.is_finite() && k > 0.0) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "k must be a finite number larger than 0 (got {k})"
+            )));
+        }
+        match *self.lock(python) {
+            Some(ref decoder) => {
+                if !matches!(decoder.event_type, common::Type::Atis) {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "atis_frames requires a file with ATIS events",
+                    ));
+                }
+                let (width, height) = self.geometry.dimensions();
+                let pixels = width as usize * height as usize;
+                Ok(AtisFrames {
+                    inner: Some(decoder.restart()?),
+                    carry: decoder::CroppedPacket::Atis(Vec::new()),
+                    frame_duration_us,
+                    k,
+                    window_start: None,
+                    roi: self.roi,
+                    geometry: self.geometry,
+                    exposure_starts: vec![None; pixels],
+                    frame: vec![0u8; pixels],
+                })
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called atis_frames after __exit__",
+            )),
+        }
+    }
+}
+
+/// Iterates over a file's packets and drops the events of flagged pixels.
+///
+/// Hot pixels are flagged in sensor coordinates, before the region of interest and the geometry transform are applied.
+#[pyclass]
+pub struct HotPixelFilter {
+    inner: Option<decoder::Decoder>,
+    hot_pixels: decoder::HotPixels,
+    skip_empty: bool,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    layout: Layout,
+}
+
+#[pymethods]
+impl HotPixelFilter {
+    #[getter]
+    fn hot_pixels(&self) -> Vec<(u16, u16)> {
+        self.hot_pixels.coordinates()
+    }
+
+    /// Moves the underlying decoder back to the first packet.
+    fn reset(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => Ok(decoder.reset()?),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called reset after __exit__",
+            )),
+        }
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        if self.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = self.inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<HotPixelFilter>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        shell
+            .next_enumerated()
+            .map(|packet| packet.map(|(_, packet)| packet))
+    }
+
+    /// Returns an iterator over (packet_index, packet) tuples, see Decoder.enumerate.
+    ///
+    /// packet_index counts the packets read by the underlying decoder, including the packets that are empty after filtering.
+    fn enumerate(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        if shell.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called enumerate after __exit__",
+            ));
+        }
+        let python = shell.py();
+        Py::new(
+            python,
+            EnumeratedDecoder {
+                source: EnumeratedSource::HotPixelFilter(shell.into()),
+            },
+        )
+    }
+}
+
+impl HotPixelFilter {
+    fn next_enumerated(&mut self) -> PyResult<Option<(u64, PyObject)>> {
+        let hot_pixels = &self.hot_pixels;
+        next_filtered(
+            &mut self.inner,
+            self.roi,
+            self.skip_empty,
+            &self.geometry,
+            &self.layout,
+            |packet| hot_pixels.filter(packet),
+        )
+    }
+}
+
+/// Iterates over a file's packets and drops the events that follow the previous kept event
+/// of the same pixel by period_us or less.
+///
+/// The refractory period is applied in sensor coordinates, before the region of interest and the geometry transform.
+#[pyclass]
+pub struct RefractoryFilter {
+    inner: Option<decoder::Decoder>,
+    refractory: decoder::Refractory,
+    skip_empty: bool,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    layout: Layout,
+}
+
+#[pymethods]
+impl RefractoryFilter {
+    /// Moves the underlying decoder back to the first packet and clears the filter's per-pixel state.
+    fn reset(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                decoder.reset()?;
+                self.refractory.reset();
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called reset after __exit__",
+            )),
+        }
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        if self.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = self.inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<RefractoryFilter>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        shell
+            .next_enumerated()
+            .map(|packet| packet.map(|(_, packet)| packet))
+    }
+
+    /// Returns an iterator over (packet_index, packet) tuples, see Decoder.enumerate.
+    ///
+    /// packet_index counts the packets read by the underlying decoder, including the packets that are empty after filtering.
+    fn enumerate(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        if shell.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called enumerate after __exit__",
+            ));
+        }
+        let python = shell.py();
+        Py::new(
+            python,
+            EnumeratedDecoder {
+                source: EnumeratedSource::RefractoryFilter(shell.into()),
+            },
+        )
+    }
+}
+
+impl RefractoryFilter {
+    fn next_enumerated(&mut self) -> PyResult<Option<(u64, PyObject)>> {
+        let refractory = &mut self.refractory;
+        next_filtered(
+            &mut self.inner,
+            self.roi,
+            self.skip_empty,
+            &self.geometry,
+            &self.layout,
+            |packet| refractory.filter(packet),
+        )
+    }
+}
+
+/// Iterates over a file's packets and drops the events that have no neighbour (8-connectivity)
+/// that fired within dt_us before them.
+///
+/// The filter is applied in sensor coordinates, before the region of interest and the geometry transform.
+#[pyclass]
+pub struct BackgroundActivityFilter {
+    inner: Option<decoder::Decoder>,
+    background_activity: decoder::BackgroundActivity,
+    skip_empty: bool,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    layout: Layout,
+}
+
+#[pymethods]
+impl BackgroundActivityFilter {
+    /// Moves the underlying decoder back to the first packet and clears the filter's per-pixel state.
+    fn reset(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                decoder.reset()?;
+                self.background_activity.reset();
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called reset after __exit__",
+            )),
+        }
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        if self.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = self.inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<BackgroundActivityFilter>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        shell
+            .next_enumerated()
+            .map(|packet| packet.map(|(_, packet)| packet))
+    }
+
+    /// Returns an iterator over (packet_index, packet) tuples, see Decoder.enumerate.
+    ///
+    /// packet_index counts the packets read by the underlying decoder, including the packets that are empty after filtering.
+    fn enumerate(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        if shell.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called enumerate after __exit__",
+            ));
+        }
+        let python = shell.py();
+        Py::new(
+            python,
+            EnumeratedDecoder {
+                source: EnumeratedSource::BackgroundActivityFilter(shell.into()),
+            },
+        )
+    }
+}
+
+impl BackgroundActivityFilter {
+    fn next_enumerated(&mut self) -> PyResult<Option<(u64, PyObject)>> {
+        let background_activity = &mut self.background_activity;
+        next_filtered(
+            &mut self.inner,
+            self.roi,
+            self.skip_empty,
+            &self.geometry,
+            &self.layout,
+            |packet| background_activity.filter(packet),
+        )
+    }
+}
+
+enum EnumeratedSource {
+    Decoder(Py<Decoder>),
+    HotPixelFilter(Py<HotPixelFilter>),
+    RefractoryFilter(Py<RefractoryFilter>),
+    BackgroundActivityFilter(Py<BackgroundActivityFilter>),
+}
+
+/// Iterates over a decoder's (or a filter's) packets and yields (packet_index, packet) tuples.
+#[pyclass]
+pub struct EnumeratedDecoder {
+    source: EnumeratedSource,
+}
+
+#[pymethods]
+impl EnumeratedDecoder {
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(shell: PyRef<Self>) -> PyResult<Option<(u64, PyObject)>> {
+        let python = shell.py();
+        match shell.source {
+            EnumeratedSource::Decoder(ref decoder) => {
+                Decoder::next_enumerated(decoder.bind(python).try_borrow()?)
+            }
+            EnumeratedSource::HotPixelFilter(ref filter) => {
+                filter.bind(python).try_borrow_mut()?.next_enumerated()
+            }
+            EnumeratedSource::RefractoryFilter(ref filter) => {
+                filter.bind(python).try_borrow_mut()?.next_enumerated()
+            }
+            EnumeratedSource::BackgroundActivityFilter(ref filter) => {
+                filter.bind(python).try_borrow_mut()?.next_enumerated()
+            }
+        }
+    }
+}
+
+/// Iterates over a file's events in windows of duration_us microseconds.
+///
+/// Events are buffered across packets, the region of interest is applied before buffering and the geometry transform after.
+#[pyclass]
+pub struct TimeChunks {
+    inner: Option<decoder::Decoder>,
+    carry: decoder::CroppedPacket,
+    duration_us: u64,
+    window_start: Option<u64>,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    layout: Layout,
+}
+
+#[pymethods]
+impl TimeChunks {
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        if self.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = self.inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<TimeChunks>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        let shell = &mut *shell;
+        let mut end_of_file = false;
+        loop {
+            if shell.window_start.is_none() {
+                shell.window_start = shell.carry.first_t();
+            }
+            match shell.window_start {
+                Some(window_start) => {
+                    if end_of_file && shell.carry.is_empty() {
+                        return Ok(None);
+                    }
+                    let window_end = window_start.saturating_add(shell.duration_us);
+                    let count = shell.carry.count_before(window_end);
+                    // the window is complete once an event past its end has been read
+                    if count < shell.carry.len() || end_of_file {
+                        shell.window_start = Some(window_end);
+                        let chunk = shell.carry.split_front(count);
+                        return Python::with_gil(|python| -> PyResult<Option<PyObject>> {
+                            packet_to_object(
+                                python,
+                                &chunk.packet(),
+                                false,
+                                false,
+                                false,
+                                &shell.geometry,
+                                &shell.layout,
+                            )
+                            .map(Some)
+                        });
+                    }
+                }
+                None => {
+                    if end_of_file {
+                        return Ok(None);
+                    }
+                }
+            }
+            end_of_file = !extend_carry(&mut shell.inner, shell.roi, &mut shell.carry)?;
+        }
+    }
+}
+
+/// Iterates over a file's events in chunks of count events.
+///
+/// Events are buffered across packets, the region of interest is applied before buffering and the geometry transform after.
 #[pyclass]
-pub struct Decoder {
+pub struct CountChunks {
     inner: Option<decoder::Decoder>,
+    carry: decoder::CroppedPacket,
+    count: usize,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    layout: Layout,
 }
 
 #[pymethods]
-impl Decoder {
-    #[new]
-    fn new(path: &pyo3::Bound<'_, pyo3::types::PyAny>, t0: u64) -> Result<Self, PyErr> {
-        Python::with_gil(|python| -> Result<Self, PyErr> {
-            match types::python_path_to_string(python, path) {
-                Ok(result) => match decoder::Decoder::new(result, t0) {
-                    Ok(result) => Ok(Decoder {
-                        inner: Some(result),
-                    }),
-                    Err(error) => Err(PyErr::from(error)),
-                },
-                Err(error) => Err(error),
-            }
+impl CountChunks {
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        if self.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = self.inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<CountChunks>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        let shell = &mut *shell;
+        let mut end_of_file = false;
+        while shell.carry.len() < shell.count && !end_of_file {
+            end_of_file = !extend_carry(&mut shell.inner, shell.roi, &mut shell.carry)?;
+        }
+        if shell.carry.is_empty() {
+            return Ok(None);
+        }
+        let chunk = shell.carry.split_front(shell.count.min(shell.carry.len()));
+        Python::with_gil(|python| -> PyResult<Option<PyObject>> {
+            packet_to_object(
+                python,
+                &chunk.packet(),
+                false,
+                false,
+                false,
+                &shell.geometry,
+                &shell.layout,
+            )
+            .map(Some)
         })
     }
+}
 
-    #[getter]
-    fn version(&self) -> PyResult<String> {
-        match self.inner {
-            Some(ref decoder) => Ok({
-                let version = decoder.version();
-                format!("{}.{}.{}", version[0], version[1], version[2])
-            }),
-            None => Err(pyo3::exceptions::PyException::new_err(
-                "called version after __exit__",
-            )),
+/// Iterates over a file's ATIS events and yields (t, frame) pairs every frame_duration_us.
+///
+/// t is the end of the frame's window and frame is a (height, width) uint8 array.
+/// Exposure starts and ends are paired per pixel after the region of interest and the geometry transform.
+#[pyclass]
+pub struct AtisFrames {
+    inner: Option<decoder::Decoder>,
+    carry: decoder::CroppedPacket,
+    frame_duration_us: u64,
+    k: f64,
+    window_start: Option<u64>,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    exposure_starts: Vec<Option<u64>>,
+    frame: Vec<u8>,
+}
+
+impl AtisFrames {
+    /// Pairs exposure events and updates the intensity of the pixels whose measurement ended.
+    fn integrate(&mut self, chunk: &decoder::CroppedPacket) {
+        let events = match chunk {
+            decoder::CroppedPacket::Atis(events) => events,
+            _ => unreachable!("AtisFrames only reads ATIS files"),
+        };
+        let width = self.geometry.dimensions().0 as usize;
+        for event in events.iter() {
+            let (x, y) = self.geometry.apply(event.x, event.y);
+            let index = x as usize + y as usize * width;
+            match event.polarity {
+                neuromorphic_types::AtisPolarity::ExposureStart => {
+                    self.exposure_starts[index] = Some(event.t);
+                }
+                neuromorphic_types::AtisPolarity::ExposureEnd => {
+                    // an end without a start (for instance at the beginning of the file) is ignored
+                    if let Some(start_t) = self.exposure_starts[index].take() {
+                        let interval = (event.t - start_t).max(1) as f64;
+                        self.frame[index] = (self.k / interval).round().min(255.0) as u8;
+                    }
+                }
+                neuromorphic_types::AtisPolarity::Off | neuromorphic_types::AtisPolarity::On => {}
+            }
         }
     }
+}
 
-    #[getter]
-    fn event_type(&self) -> PyResult<String> {
-        match self.inner {
-            Some(ref decoder) => Ok(match decoder.event_type {
-                common::Type::Generic => "generic",
-                common::Type::Dvs => "dvs",
-                common::Type::Atis => "atis",
-                common::Type::Color => "color",
+#[pymethods]
+impl AtisFrames {
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        if self.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            ));
+        }
+        let _ = self.inner.take();
+        Ok(false)
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<AtisFrames>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<(u64, PyObject)>> {
+        use numpy::PyArrayMethods;
+        let python = shell.py();
+        let shell = &mut *shell;
+        let mut end_of_file = false;
+        loop {
+            if shell.window_start.is_none() {
+                shell.window_start = shell.carry.first_t();
             }
-            .to_owned()),
-            None => Err(pyo3::exceptions::PyException::new_err(
-                "called event_type after __exit__",
-            )),
+            match shell.window_start {
+                Some(window_start) => {
+                    if end_of_file && shell.carry.is_empty() {
+                        return Ok(None);
+                    }
+                    let window_end = window_start.saturating_add(shell.frame_duration_us);
+                    let count = shell.carry.count_before(window_end);
+                    // the window is complete once an event past its end has been read
+                    if count < shell.carry.len() || end_of_file {
+                        shell.window_start = Some(window_end);
+                        let chunk = shell.carry.split_front(count);
+                        shell.integrate(&chunk);
+                        let (width, height) = shell.geometry.dimensions();
+                        let frame = numpy::PyArray1::from_vec_bound(python, shell.frame.clone())
+                            .reshape([height as usize, width as usize])?;
+                        return Ok(Some((window_end, frame.into_any().unbind())));
+                    }
+                }
+                None => {
+                    if end_of_file {
+                        return Ok(None);
+                    }
+                }
+            }
+            end_of_file = !extend_carry(&mut shell.inner, shell.roi, &mut shell.carry)?;
         }
     }
+}
 
-    #[getter]
-    fn dimensions(&self) -> PyResult<Option<(u16, u16)>> {
-        match self.inner {
-            Some(ref decoder) => Ok(decoder.dimensions()),
-            None => Err(pyo3::exceptions::PyException::new_err(
-                "called dimensions after __exit__",
-            )),
+/// One input of a Merge, with the events of its current packet (after the region of interest and the geometry transform).
+struct MergeInput {
+    decoder: decoder::Decoder,
+    roi: Option<decoder::Roi>,
+    geometry: decoder::Geometry,
+    events: Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>,
+    index: usize,
+}
+
+impl MergeInput {
+    /// Reads packets until an event is available, returns false at the end of the file.
+    fn fill(&mut self) -> Result<bool, utilities::ReadError> {
+        while self.index >= self.events.len() {
+            let packet = match self.decoder.next()? {
+                Some(packet) => packet,
+                None => return Ok(false),
+            };
+            let cropped_packet = self.roi.and_then(|roi| roi.crop(&packet));
+            let packet = match cropped_packet {
+                Some(ref cropped_packet) => cropped_packet.packet(),
+                None => packet,
+            };
+            if let decoder::Packet::Dvs(events) = packet {
+                self.events.clear();
+                self.events.extend(events.iter().map(|event| {
+                    let (x, y) = self.geometry.apply(event.x, event.y);
+                    neuromorphic_types::DvsEvent { x, y, ..*event }
+                }));
+                self.index = 0;
+            }
+        }
+        Ok(true)
+    }
+
+    fn front_t(&self) -> u64 {
+        self.events[self.index].t
+    }
+}
+
+/// Iterates over the events of several DVS files in timestamp order, see merge.
+#[pyclass]
+pub struct Merge {
+    inner: Option<Vec<MergeInput>>,
+    /// The first buffered event of each input that has not reached the end of its file, ordered by (t, camera ID).
+    heap: std::collections::BinaryHeap<std::cmp::Reverse<(u64, usize)>>,
+    #[pyo3(get)]
+    dimensions: (u16, u16),
+    packet_size: usize,
+}
+
+/// Merged events and their camera ID.
+type MergedEvents = Vec<(neuromorphic_types::DvsEvent<u64, u16, u16>, u16)>;
+
+/// Returns up to packet_size events (with their camera ID) in timestamp order,
+/// events with the same timestamp are sorted by camera ID.
+fn merge_next(
+    inputs: &mut [MergeInput],
+    heap: &mut std::collections::BinaryHeap<std::cmp::Reverse<(u64, usize)>>,
+    packet_size: usize,
+) -> Result<MergedEvents, utilities::ReadError> {
+    let mut events = Vec::with_capacity(packet_size.min(utilities::BUFFER_SIZE));
+    while events.len() < packet_size {
+        let camera_id = match heap.pop() {
+            Some(std::cmp::Reverse((_, camera_id))) => camera_id,
+            None => break,
+        };
+        let input = &mut inputs[camera_id];
+        events.push((input.events[input.index], camera_id as u16));
+        input.index += 1;
+        if input.fill()? {
+            heap.push(std::cmp::Reverse((input.front_t(), camera_id)));
         }
     }
+    Ok(events)
+}
 
+#[pymethods]
+impl Merge {
     fn __enter__(slf: Py<Self>) -> Py<Self> {
         slf
     }
@@ -98,115 +2256,503 @@ impl Decoder {
         Ok(false)
     }
 
-    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<Decoder>> {
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<Merge>> {
         Ok(shell.into())
     }
 
     fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
-        let packet = match shell.inner {
-            Some(ref mut decoder) => match decoder.next() {
-                Ok(result) => match result {
-                    Some(result) => result,
-                    None => return Ok(None),
-                },
-                Err(result) => return Err(result.into()),
-            },
+        let python = shell.py();
+        let shell = &mut *shell;
+        let inputs = match shell.inner {
+            Some(ref mut inputs) => inputs,
             None => {
                 return Err(pyo3::exceptions::PyException::new_err(
                     "called __next__ after __exit__",
                 ))
             }
         };
-        Python::with_gil(|python| -> PyResult<Option<PyObject>> {
-            Ok(Some(match packet {
-                decoder::Packet::Generic(events) => {
-                    let length = events.len() as numpy::npyffi::npy_intp;
-                    let array = types::ArrayType::EsGeneric.new_array(python, length);
-                    unsafe {
-                        for index in 0..length {
-                            let event_cell = types::array_at(python, array, index);
-                            let event = &events[index as usize];
-                            let mut event_array = [0u8; 8 + std::mem::size_of::<usize>()];
-                            event_array[0..8].copy_from_slice(&event.t.to_le_bytes());
-                            let pybytes = pyo3::ffi::PyBytes_FromStringAndSize(
-                                event.bytes.as_ptr() as *const i8,
-                                event.bytes.len() as pyo3::ffi::Py_ssize_t,
-                            );
-                            event_array[8..8 + std::mem::size_of::<usize>()]
-                                .copy_from_slice(&(pybytes as usize).to_ne_bytes());
-                            std::ptr::copy(event_array.as_ptr(), event_cell, event_array.len());
+        let heap = &mut shell.heap;
+        let packet_size = shell.packet_size;
+        let events = python.allow_threads(move || merge_next(inputs, heap, packet_size))?;
+        if events.is_empty() {
+            return Ok(None);
+        }
+        let array =
+            types::ArrayType::EsMerge.new_array(python, events.len() as numpy::npyffi::npy_intp);
+        unsafe {
+            for (index, (event, camera_id)) in events.iter().enumerate() {
+                let event_cell = types::array_at(python, array, index as numpy::npyffi::npy_intp);
+                let mut event_array = [0u8; 15];
+                // DvsEvent is packed and has the same layout as the first fields of the merge dtype
+                std::ptr::copy(
+                    event as *const neuromorphic_types::DvsEvent<u64, u16, u16> as *const u8,
+                    event_array.as_mut_ptr(),
+                    13,
+                );
+                event_array[13..15].copy_from_slice(&camera_id.to_le_bytes());
+                std::ptr::copy(event_array.as_ptr(), event_cell, event_array.len());
+            }
+            Ok(Some(PyObject::from_owned_ptr(
+                python,
+                array as *mut pyo3::ffi::PyObject,
+            )))
+        }
+    }
+}
+
+/// Merges the events of several DVS decoders in timestamp order, with a camera_id field (the decoder's index).
+///
+/// Each decoder is read from the start with a separate file handle, its region of interest and geometry are applied.
+/// Only the current packet of each file is held in memory. The merged stream's dimensions are the largest width
+/// and the largest height of the inputs, use camera_id to separate streams with different dimensions.
+#[pyfunction]
+#[pyo3(signature = (decoders, packet_size = 65536))]
+pub fn merge(decoders: Vec<PyRef<Decoder>>, packet_size: usize) -> PyResult<Merge> {
+    if decoders.is_empty() {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "merge requires at least one decoder",
+        ));
+    }
+    if decoders.len() > u16::MAX as usize + 1 {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "merge supports up to {} decoders (got {})",
+            u16::MAX as usize + 1,
+            decoders.len()
+        )));
+    }
+    if packet_size == 0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "packet_size must be larger than 0",
+        ));
+    }
+    let mut inputs = Vec::with_capacity(decoders.len());
+    let mut heap = std::collections::BinaryHeap::with_capacity(decoders.len());
+    let mut dimensions = (0, 0);
+    for (camera_id, decoder) in decoders.iter().enumerate() {
+        let mut input = MergeInput {
+            decoder: decoder.dvs_scanner(decoder.py(), "merge")?,
+            roi: decoder.roi,
+            geometry: decoder.geometry,
+            events: Vec::new(),
+            index: 0,
+        };
+        let input_dimensions = input.geometry.dimensions();
+        dimensions.0 = input_dimensions.0.max(dimensions.0);
+        dimensions.1 = input_dimensions.1.max(dimensions.1);
+        if input.fill()? {
+            heap.push(std::cmp::Reverse((input.front_t(), camera_id)));
+        }
+        inputs.push(input);
+    }
+    Ok(Merge {
+        inner: Some(inputs),
+        heap,
+        dimensions,
+        packet_size,
+    })
+}
+
+/// Decodes Event Stream files on a pool of threads and returns one array per file, in the order of paths.
+///
+/// Each worker decodes whole files with its own decoder and without the GIL, the threads only synchronize
+/// to pick the next file and when the results are collected. All the arrays are in memory at the same time,
+/// and each worker holds the events of the file that it is decoding, hence peak memory usage is about
+/// the size of the decoded events plus one file per worker. workers=0 starts one worker per CPU.
+/// Generic streams are not supported.
+#[pyfunction]
+#[pyo3(signature = (paths, workers = 0, t0 = 0))]
+pub fn decode_many(
+    python: Python,
+    paths: Vec<pyo3::Bound<'_, pyo3::types::PyAny>>,
+    workers: usize,
+    t0: u64,
+) -> PyResult<Vec<PyObject>> {
+    let paths = paths
+        .iter()
+        .map(|path| types::python_path_to_string(python, path))
+        .collect::<PyResult<Vec<String>>>()?;
+    let workers = if workers == 0 {
+        std::thread::available_parallelism().map_or(1, |workers| workers.get())
+    } else {
+        workers
+    }
+    .min(paths.len().max(1));
+    let next_index = std::sync::atomic::AtomicUsize::new(0);
+    let results = python.allow_threads(|| {
+        std::thread::scope(|scope| {
+            let handles = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut decoded = Vec::new();
+                        loop {
+                            let index =
+                                next_index.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            if index >= paths.len() {
+                                break decoded;
+                            }
+                            decoded.push((index, decode_file(&paths[index], t0)));
                         }
-                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
-                    }
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut results = (0..paths.len()).map(|_| None).collect::<Vec<_>>();
+            for handle in handles {
+                let decoded = handle
+                    .join()
+                    .unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+                for (index, result) in decoded {
+                    results[index] = Some(result);
                 }
-                decoder::Packet::Dvs(events) => {
-                    let length = events.len() as numpy::npyffi::npy_intp;
-                    let array = types::ArrayType::Dvs.new_array(python, length);
-                    unsafe {
-                        for index in 0..length {
-                            let event_cell = types::array_at(python, array, index);
-                            std::ptr::copy(
-                                &events[index as usize]
-                                    as *const neuromorphic_types::DvsEvent<u64, u16, u16>
-                                    as *const u8,
-                                event_cell,
-                                std::mem::size_of::<neuromorphic_types::DvsEvent<u64, u16, u16>>(),
-                            );
-                        }
-                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+            }
+            results
+        })
+    });
+    let geometry = decoder::Geometry::new(None, 1, false, false, 0)?;
+    results
+        .into_iter()
+        .map(|result| {
+            let events = result.expect("the workers decode every file")?;
+            packet_to_object(
+                python,
+                &events.packet(),
+                false,
+                false,
+                false,
+                &geometry,
+                &Layout::default(),
+            )
+        })
+        .collect()
+}
+
+/// Reads all the events of a DVS, ATIS, or color Event Stream file.
+fn decode_file(path: &str, t0: u64) -> PyResult<decoder::CroppedPacket> {
+    let source = utilities::Source::open(path)
+        .map_err(|error| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string()))?;
+    let mut decoder = decoder::Decoder::new(source, t0, utilities::BUFFER_SIZE, None)?;
+    let mut events = decoder::CroppedPacket::empty(decoder.event_type).ok_or_else(|| {
+        pyo3::exceptions::PyValueError::new_err(format!(
+            "decode_many does not support generic streams (\"{path}\")"
+        ))
+    })?;
+    while let Some(packet) = decoder.next()? {
+        events.extend(&packet);
+    }
+    Ok(events)
+}
+
+/// Reads the next packet and appends its events (in the region of interest) to carry.
+///
+/// Returns false at the end of the file.
+fn extend_carry(
+    inner: &mut Option<decoder::Decoder>,
+    roi: Option<decoder::Roi>,
+    carry: &mut decoder::CroppedPacket,
+) -> PyResult<bool> {
+    let decoder = match inner {
+        Some(ref mut decoder) => decoder,
+        None => {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called __next__ after __exit__",
+            ))
+        }
+    };
+    let packet = match decoder.next() {
+        Ok(Some(result)) => result,
+        Ok(None) => return Ok(false),
+        Err(result) => return Err(result.into()),
+    };
+    match roi.and_then(|roi| roi.crop(&packet)) {
+        Some(cropped_packet) => carry.extend(&cropped_packet.packet()),
+        None => carry.extend(&packet),
+    }
+    Ok(true)
+}
+
+/// Reads the next packet, applies filter, the region of interest, and the geometry transform.
+///
+/// filter must return Some for streams with coordinates.
+fn next_filtered<F: FnMut(&decoder::Packet) -> Option<decoder::CroppedPacket>>(
+    inner: &mut Option<decoder::Decoder>,
+    roi: Option<decoder::Roi>,
+    skip_empty: bool,
+    geometry: &decoder::Geometry,
+    layout: &Layout,
+    mut filter: F,
+) -> PyResult<Option<(u64, PyObject)>> {
+    loop {
+        let decoder = match inner {
+            Some(ref mut decoder) => decoder,
+            None => {
+                return Err(pyo3::exceptions::PyException::new_err(
+                    "called __next__ after __exit__",
+                ))
+            }
+        };
+        let index = decoder.packet_index();
+        let packet = match decoder.next() {
+            Ok(Some(result)) => result,
+            Ok(None) => return Ok(None),
+            Err(result) => return Err(result.into()),
+        };
+        let filtered_packet =
+            filter(&packet).expect("filters only exist for streams with coordinates");
+        let cropped_packet = roi.and_then(|roi| roi.crop(&filtered_packet.packet()));
+        let packet = match cropped_packet {
+            Some(ref cropped_packet) => cropped_packet.packet(),
+            None => filtered_packet.packet(),
+        };
+        if skip_empty && packet.is_empty() {
+            continue;
+        }
+        return Python::with_gil(|python| -> PyResult<Option<(u64, PyObject)>> {
+            packet_to_object(python, &packet, false, false, false, geometry, layout)
+                .map(|packet| Some((index, packet)))
+        });
+    }
+}
+
+fn packet_to_object(
+    python: Python,
+    packet: &decoder::Packet,
+    ragged: bool,
+    split_atis: bool,
+    soa: bool,
+    geometry: &decoder::Geometry,
+    layout: &Layout,
+) -> PyResult<PyObject> {
+    if let (true, decoder::Packet::Atis(events)) = (split_atis, packet) {
+        // change detection and exposure measurement events are returned as two DVS arrays,
+        // on is true for ON events in "cd" and for exposure ends in "exposure"
+        let mut cd_events = Vec::new();
+        let mut exposure_events = Vec::new();
+        for event in events.iter() {
+            let (events, on) = match event.polarity {
+                neuromorphic_types::AtisPolarity::Off => (&mut cd_events, false),
+                neuromorphic_types::AtisPolarity::On => (&mut cd_events, true),
+                neuromorphic_types::AtisPolarity::ExposureStart => (&mut exposure_events, false),
+                neuromorphic_types::AtisPolarity::ExposureEnd => (&mut exposure_events, true),
+            };
+            events.push(neuromorphic_types::DvsEvent {
+                t: event.t,
+                x: event.x,
+                y: event.y,
+                polarity: if on {
+                    neuromorphic_types::DvsPolarity::On
+                } else {
+                    neuromorphic_types::DvsPolarity::Off
+                },
+            });
+        }
+        let python_packet = pyo3::types::PyDict::new_bound(python);
+        for (name, events) in [("cd", &cd_events), ("exposure", &exposure_events)] {
+            python_packet.set_item(
+                name,
+                packet_to_object(
+                    python,
+                    &decoder::Packet::Dvs(events),
+                    false,
+                    false,
+                    soa,
+                    geometry,
+                    layout,
+                )?,
+            )?;
+        }
+        return Ok(python_packet.into());
+    }
+    if let (true, decoder::Packet::Generic(events)) = (ragged, packet) {
+        // Arrow-style variable-length layout, the bytes of event i are bytes[offsets[i]:offsets[i + 1]]
+        let mut ts = Vec::with_capacity(events.len());
+        let mut offsets = Vec::with_capacity(events.len() + 1);
+        let mut bytes = Vec::new();
+        offsets.push(0u64);
+        for event in events.iter() {
+            ts.push(event.t);
+            bytes.extend_from_slice(&event.bytes);
+            offsets.push(bytes.len() as u64);
+        }
+        let python_packet = pyo3::types::PyDict::new_bound(python);
+        if layout.signed_t {
+            let ts: Vec<i64> = ts.into_iter().map(|t| t as i64).collect();
+            python_packet.set_item(layout.name("t"), ts.to_pyarray_bound(python))?;
+        } else {
+            python_packet.set_item(layout.name("t"), ts.to_pyarray_bound(python))?;
+        }
+        python_packet.set_item("offsets", offsets.to_pyarray_bound(python))?;
+        python_packet.set_item(layout.name("bytes"), bytes.to_pyarray_bound(python))?;
+        return Ok(python_packet.into());
+    }
+    if let (true, decoder::Packet::Dvs(events)) = (soa, packet) {
+        // structure of arrays, each field is a contiguous 1D array
+        let mut xs = Vec::with_capacity(events.len());
+        let mut ys = Vec::with_capacity(events.len());
+        for event in events.iter() {
+            let (x, y) = geometry.apply(event.x, event.y);
+            xs.push(x);
+            ys.push(y);
+        }
+        let python_packet = pyo3::types::PyDict::new_bound(python);
+        if layout.signed_t {
+            let ts: Vec<i64> = events.iter().map(|event| event.t as i64).collect();
+            python_packet.set_item(layout.name("t"), ts.to_pyarray_bound(python))?;
+        } else {
+            let ts: Vec<u64> = events.iter().map(|event| event.t).collect();
+            python_packet.set_item(layout.name("t"), ts.to_pyarray_bound(python))?;
+        }
+        if layout.narrow_xy {
+            // coordinates fit in uint8 since Layout::new checks the dimensions
+            let xs: Vec<u8> = xs.into_iter().map(|x| x as u8).collect();
+            let ys: Vec<u8> = ys.into_iter().map(|y| y as u8).collect();
+            python_packet.set_item(layout.name("x"), xs.to_pyarray_bound(python))?;
+            python_packet.set_item(layout.name("y"), ys.to_pyarray_bound(python))?;
+        } else {
+            python_packet.set_item(layout.name("x"), xs.to_pyarray_bound(python))?;
+            python_packet.set_item(layout.name("y"), ys.to_pyarray_bound(python))?;
+        }
+        let ons: Vec<bool> = events
+            .iter()
+            .map(|event| matches!(event.polarity, neuromorphic_types::DvsPolarity::On))
+            .collect();
+        python_packet.set_item(layout.name("on"), ons.to_pyarray_bound(python))?;
+        return Ok(python_packet.into());
+    }
+    let array = array_type(packet).new_array(python, packet.len() as numpy::npyffi::npy_intp);
+    let array = unsafe {
+        fill_array(python, array, 0, packet, geometry);
+        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+    };
+    layout.apply(python, array, array_type(packet))
+}
+
+/// Converts DVS events to an array without copying them, the array owns the events' memory.
+///
+/// Coordinates are transformed in place by geometry.
+fn dvs_events_to_object(
+    python: Python,
+    mut events: Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>,
+    geometry: &decoder::Geometry,
+    layout: &Layout,
+) -> PyResult<PyObject> {
+    if !geometry.is_identity() {
+        for event in events.iter_mut() {
+            let (x, y) = geometry.apply(event.x, event.y);
+            event.x = x;
+            event.y = y;
+        }
+    }
+    let array = unsafe {
+        // DvsEvent is packed and matches the DVS dtype (t, x, y, on)
+        let array = types::new_array_from_vec(python, types::ArrayType::Dvs.dtype(python), events)?;
+        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+    };
+    layout.apply(python, array, types::ArrayType::Dvs)
+}
+
+fn event_type_to_array_type(event_type: common::Type) -> types::ArrayType {
+    match event_type {
+        common::Type::Generic => types::ArrayType::EsGeneric,
+        common::Type::Dvs => types::ArrayType::Dvs,
+        common::Type::Atis => types::ArrayType::EsAtis,
+        common::Type::Color => types::ArrayType::EsColor,
+    }
+}
+
+fn array_type(packet: &decoder::Packet) -> types::ArrayType {
+    match packet {
+        decoder::Packet::Generic(_) => types::ArrayType::EsGeneric,
+        decoder::Packet::Dvs(_) => types::ArrayType::Dvs,
+        decoder::Packet::Atis(_) => types::ArrayType::EsAtis,
+        decoder::Packet::Color(_) => types::ArrayType::EsColor,
+    }
+}
+
+/// Copies the packet's events into the array, starting at the given offset.
+///
+/// Coordinates are transformed by geometry (downsampling may map several events to the same coordinates).
+///
+/// # Safety
+///
+/// The array must have the dtype returned by array_type and at least offset + packet.len() elements.
+unsafe fn fill_array(
+    python: Python,
+    array: *mut numpy::npyffi::PyArrayObject,
+    offset: numpy::npyffi::npy_intp,
+    packet: &decoder::Packet,
+    geometry: &decoder::Geometry,
+) {
+    match packet {
+        decoder::Packet::Generic(events) => {
+            for (index, event) in events.iter().enumerate() {
+                let event_cell =
+                    types::array_at(python, array, offset + index as numpy::npyffi::npy_intp);
+                let mut event_array = [0u8; 8 + std::mem::size_of::<usize>()];
+                event_array[0..8].copy_from_slice(&event.t.to_le_bytes());
+                let pybytes = pyo3::ffi::PyBytes_FromStringAndSize(
+                    event.bytes.as_ptr() as *const i8,
+                    event.bytes.len() as pyo3::ffi::Py_ssize_t,
+                );
+                event_array[8..8 + std::mem::size_of::<usize>()]
+                    .copy_from_slice(&(pybytes as usize).to_ne_bytes());
+                std::ptr::copy(event_array.as_ptr(), event_cell, event_array.len());
+            }
+        }
+        decoder::Packet::Dvs(events) => {
+            for (index, event) in events.iter().enumerate() {
+                let event_cell =
+                    types::array_at(python, array, offset + index as numpy::npyffi::npy_intp);
+                let (x, y) = geometry.apply(event.x, event.y);
+                let event = neuromorphic_types::DvsEvent { x, y, ..*event };
+                std::ptr::copy(
+                    &event as *const neuromorphic_types::DvsEvent<u64, u16, u16> as *const u8,
+                    event_cell,
+                    std::mem::size_of::<neuromorphic_types::DvsEvent<u64, u16, u16>>(),
+                );
+            }
+        }
+        decoder::Packet::Atis(events) => {
+            for (index, event) in events.iter().enumerate() {
+                let event_cell =
+                    types::array_at(python, array, offset + index as numpy::npyffi::npy_intp);
+                let mut event_array = [0u8; 14];
+                event_array[0..8].copy_from_slice(&event.t.to_le_bytes());
+                let (x, y) = geometry.apply(event.x, event.y);
+                event_array[8..10].copy_from_slice(&x.to_le_bytes());
+                event_array[10..12].copy_from_slice(&y.to_le_bytes());
+                match event.polarity {
+                    neuromorphic_types::AtisPolarity::Off => {
+                        event_array[12] = 0;
+                        event_array[13] = 0;
                     }
-                }
-                decoder::Packet::Atis(events) => {
-                    let length = events.len() as numpy::npyffi::npy_intp;
-                    let array = types::ArrayType::EsAtis.new_array(python, length);
-                    unsafe {
-                        for index in 0..length {
-                            let event_cell = types::array_at(python, array, index);
-                            let event = events[index as usize];
-                            let mut event_array = [0u8; 14];
-                            event_array[0..8].copy_from_slice(&event.t.to_le_bytes());
-                            event_array[8..10].copy_from_slice(&event.x.to_le_bytes());
-                            event_array[10..12].copy_from_slice(&event.y.to_le_bytes());
-                            match event.polarity {
-                                neuromorphic_types::AtisPolarity::Off => {
-                                    event_array[12] = 0;
-                                    event_array[13] = 0;
-                                }
-                                neuromorphic_types::AtisPolarity::On => {
-                                    event_array[12] = 0;
-                                    event_array[13] = 1;
-                                }
-                                neuromorphic_types::AtisPolarity::ExposureStart => {
-                                    event_array[12] = 1;
-                                    event_array[13] = 0;
-                                }
-                                neuromorphic_types::AtisPolarity::ExposureEnd => {
-                                    event_array[12] = 1;
-                                    event_array[13] = 1;
-                                }
-                            }
-                            std::ptr::copy(event_array.as_ptr(), event_cell, event_array.len());
-                        }
-                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+                    neuromorphic_types::AtisPolarity::On => {
+                        event_array[12] = 0;
+                        event_array[13] = 1;
                     }
-                }
-                decoder::Packet::Color(events) => {
-                    let length = events.len() as numpy::npyffi::npy_intp;
-                    let array = types::ArrayType::EsColor.new_array(python, length);
-                    unsafe {
-                        for index in 0..length {
-                            let event_cell = types::array_at(python, array, index);
-                            std::ptr::copy(
-                                &events[index as usize] as *const common::ColorEvent as *const u8,
-                                event_cell,
-                                std::mem::size_of::<common::ColorEvent>(),
-                            );
-                        }
-                        PyObject::from_owned_ptr(python, array as *mut pyo3::ffi::PyObject)
+                    neuromorphic_types::AtisPolarity::ExposureStart => {
+                        event_array[12] = 1;
+                        event_array[13] = 0;
+                    }
+                    neuromorphic_types::AtisPolarity::ExposureEnd => {
+                        event_array[12] = 1;
+                        event_array[13] = 1;
                     }
                 }
-            }))
-        })
+                std::ptr::copy(event_array.as_ptr(), event_cell, event_array.len());
+            }
+        }
+        decoder::Packet::Color(events) => {
+            for (index, event) in events.iter().enumerate() {
+                let event_cell =
+                    types::array_at(python, array, offset + index as numpy::npyffi::npy_intp);
+                let (x, y) = geometry.apply(event.x, event.y);
+                let event = common::ColorEvent { x, y, ..*event };
+                std::ptr::copy(
+                    &event as *const common::ColorEvent as *const u8,
+                    event_cell,
+                    std::mem::size_of::<common::ColorEvent>(),
+                );
+            }
+        }
     }
 }
 
@@ -264,13 +2810,15 @@ impl Encoder {
         _value: Option<PyObject>,
         _traceback: Option<PyObject>,
     ) -> PyResult<bool> {
-        if self.inner.is_none() {
-            return Err(pyo3::exceptions::PyException::new_err(
+        match self.inner.take() {
+            Some(encoder) => {
+                encoder.finish()?;
+                Ok(false)
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
                 "multiple calls to __exit__",
-            ));
+            )),
         }
-        let _ = self.inner.take();
-        Ok(false)
     }
 
     fn t0(&mut self) -> PyResult<Option<u64>> {
@@ -282,6 +2830,23 @@ impl Encoder {
         }
     }
 
+    fn close(&mut self) -> PyResult<()> {
+        match self.inner.take() {
+            Some(encoder) => {
+                encoder.finish()?;
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "close called after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.inner.is_none()
+    }
+
     fn write(&mut self, packet: &pyo3::Bound<'_, pyo3::types::PyAny>) -> PyResult<()> {
         Python::with_gil(|python| -> PyResult<()> {
             match self.inner.as_mut() {
@@ -1,4 +1,3 @@
-use std::io::Read;
 use std::io::Seek;
 
 use crate::evt::common;
@@ -26,12 +25,15 @@ enum State {
 
 pub struct Decoder {
     pub dimensions: (u16, u16),
-    file: std::fs::File,
+    pub dimensions_source: utilities::DimensionsSource,
+    file: utilities::HeaderlessSource,
     raw_buffer: Vec<u8>,
     event_buffer: Vec<neuromorphic_types::DvsEvent<u64, u16, u16>>,
     trigger_buffer: Vec<neuromorphic_types::TriggerEvent<u64, u8>>,
     state: State,
     polarity: neuromorphic_types::DvsPolarity,
+    /// Absolute position of the first word, None if the source is not seekable.
+    data_start: Option<u64>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -43,7 +45,7 @@ pub enum Error {
     Version(#[from] common::Error),
 
     #[error(
-        "the header has no size information (width and height) and no size fallback was provided"
+        "the header has no size information (width and height or a known sensor model) and no size fallback was provided"
     )]
     MissingSize,
 
@@ -54,25 +56,67 @@ pub enum Error {
     UnknownVersion(String),
 }
 
+impl State {
+    fn new(version: common::Version, t0: u64) -> Self {
+        match version {
+            common::Version::Evt2 => State::Evt2 {
+                t: 0,
+                t_high: 0,
+                t_offset: 0,
+                t_without_offset: 0,
+                t0,
+            },
+            common::Version::Evt21 => State::Evt21 {},
+            common::Version::Evt3 => State::Evt3 {
+                t: 0,
+                overflows: 0,
+                previous_msb_t: 0,
+                previous_lsb_t: 0,
+                x: 0,
+                y: 0,
+                t0,
+            },
+        }
+    }
+
+    fn t0(&self) -> u64 {
+        match self {
+            State::Evt2 { t0, .. } | State::Evt3 { t0, .. } => *t0,
+            State::Evt21 {} => 0,
+        }
+    }
+}
+
 impl Decoder {
-    pub fn new<P: AsRef<std::path::Path>>(
-        path: P,
+    pub fn new(
+        source: utilities::Source,
         dimensions_fallback: Option<(u16, u16)>,
         version_fallback: Option<common::Version>,
+        buffer_size: usize,
     ) -> Result<Self, Error> {
-        let header = utilities::read_prophesee_header(
-            &mut std::io::BufReader::new(std::fs::File::open(&path)?),
-            '%',
-        )?;
-        let dimensions = match header.dimensions {
-            Some(dimensions) => dimensions,
-            None => match dimensions_fallback {
-                Some(size) => size,
-                None => return Err(Error::MissingSize),
+        let (header, mut file) = utilities::read_prophesee_header(source, '%', buffer_size)?;
+        // the cursor holds bytes that were read ahead while parsing the header
+        let data_start = {
+            let (cursor, reader) = file.get_mut();
+            reader
+                .stream_position()
+                .ok()
+                .map(|position| position - (cursor.get_ref().len() as u64 - cursor.position()))
+        };
+        let (dimensions, dimensions_source) = match header.dimensions {
+            Some(dimensions) => (dimensions, utilities::DimensionsSource::Header),
+            None => match header
+                .models
+                .iter()
+                .find_map(|model| utilities::model_to_dimensions(model))
+            {
+                Some(dimensions) => (dimensions, utilities::DimensionsSource::Database),
+                None => match dimensions_fallback {
+                    Some(size) => (size, utilities::DimensionsSource::Fallback),
+                    None => return Err(Error::MissingSize),
+                },
             },
         };
-        let mut file = std::fs::File::open(path)?;
-        file.seek(std::io::SeekFrom::Start(header.length))?;
         let version = match header.version {
             Some(version) => match version.as_str() {
                 "2" => common::Version::Evt2,
@@ -87,33 +131,31 @@ impl Decoder {
         };
         Ok(Decoder {
             dimensions,
+            dimensions_source,
             file,
-            raw_buffer: vec![0u8; utilities::BUFFER_SIZE],
+            raw_buffer: vec![0u8; buffer_size],
             event_buffer: Vec::new(),
             trigger_buffer: Vec::new(),
-            state: match version {
-                common::Version::Evt2 => State::Evt2 {
-                    t: 0,
-                    t_high: 0,
-                    t_offset: 0,
-                    t_without_offset: 0,
-                    t0: header.t0,
-                },
-                common::Version::Evt21 => State::Evt21 {},
-                common::Version::Evt3 => State::Evt3 {
-                    t: 0,
-                    overflows: 0,
-                    previous_msb_t: 0,
-                    previous_lsb_t: 0,
-                    x: 0,
-                    y: 0,
-                    t0: header.t0,
-                },
-            },
+            state: State::new(version, header.t0),
             polarity: neuromorphic_types::DvsPolarity::Off,
+            data_start,
         })
     }
 
+    /// Moves back to the first word and clears the timestamp state (time high words and overflows).
+    pub fn reset(&mut self) -> Result<(), Error> {
+        let data_start = match self.data_start {
+            Some(data_start) => data_start,
+            None => return Err(self.file.get_ref().1.get_ref().seek_error().into()),
+        };
+        let (cursor, reader) = self.file.get_mut();
+        reader.seek(std::io::SeekFrom::Start(data_start))?;
+        cursor.set_position(cursor.get_ref().len() as u64);
+        self.state = State::new(self.version(), self.state.t0());
+        self.polarity = neuromorphic_types::DvsPolarity::Off;
+        Ok(())
+    }
+
     pub fn version(&self) -> common::Version {
         match self.state {
             State::Evt2 { .. } => common::Version::Evt2,
@@ -132,7 +174,8 @@ impl Decoder {
         )>,
         utilities::ReadError,
     > {
-        let read = self.file.read(&mut self.raw_buffer)?;
+        // EVT 2.0 words are 4 bytes long, EVT 3.0 words are 2 bytes long
+        let read = utilities::read_words(&mut self.file, &mut self.raw_buffer, 4)?;
         if read == 0 {
             return Ok(None);
         }
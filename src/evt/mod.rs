@@ -3,6 +3,7 @@ mod decoder;
 mod encoder;
 
 use crate::types;
+use crate::utilities;
 
 use pyo3::prelude::*;
 
@@ -27,27 +28,37 @@ impl From<common::Error> for PyErr {
 #[pyclass]
 pub struct Decoder {
     inner: Option<decoder::Decoder>,
+    skip_empty: bool,
+    /// Number of buffer_size reads since the decoder was opened or reset, including the empty packets skipped by __next__.
+    packet_index: u64,
 }
 
 #[pymethods]
 impl Decoder {
     #[new]
+    #[pyo3(signature = (path, dimensions_fallback = None, version_fallback = None, skip_empty = true, buffer_size = utilities::BUFFER_SIZE))]
     fn new(
         path: &pyo3::Bound<'_, pyo3::types::PyAny>,
         dimensions_fallback: Option<(u16, u16)>,
         version_fallback: Option<String>,
+        skip_empty: bool,
+        buffer_size: usize,
     ) -> Result<Self, PyErr> {
+        utilities::check_buffer_size(buffer_size)?;
         Python::with_gil(|python| -> Result<Self, PyErr> {
-            match types::python_path_to_string(python, path) {
+            match types::python_source(python, path) {
                 Ok(result) => match decoder::Decoder::new(
                     result,
                     dimensions_fallback,
                     version_fallback
                         .map(|version| common::Version::from_string(&version))
                         .transpose()?,
+                    buffer_size,
                 ) {
                     Ok(result) => Ok(Decoder {
                         inner: Some(result),
+                        skip_empty,
+                        packet_index: 0,
                     }),
                     Err(error) => Err(PyErr::from(error)),
                 },
@@ -76,6 +87,52 @@ impl Decoder {
         }
     }
 
+    #[getter]
+    fn width(&self) -> PyResult<u16> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.dimensions.0),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called width after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn height(&self) -> PyResult<u16> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.dimensions.1),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called height after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn dimensions_source(&self) -> PyResult<&'static str> {
+        match self.inner {
+            Some(ref decoder) => Ok(decoder.dimensions_source.to_str()),
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called dimensions_source after __exit__",
+            )),
+        }
+    }
+
+    /// Moves the decoder back to the first packet, as if the file had just been opened.
+    ///
+    /// Packet indices returned by enumerate restart at 0.
+    fn reset(&mut self) -> PyResult<()> {
+        match self.inner {
+            Some(ref mut decoder) => {
+                decoder.reset()?;
+                self.packet_index = 0;
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "called reset after __exit__",
+            )),
+        }
+    }
+
     fn __enter__(slf: Py<Self>) -> Py<Self> {
         slf
     }
@@ -99,19 +156,45 @@ impl Decoder {
         Ok(shell.into())
     }
 
+    /// Returns an iterator over (packet_index, packet) tuples.
+    ///
+    /// EVT files have no packets, the decoder reads buffer_size bytes at a time, hence packet_index depends on buffer_size.
+    /// It counts the reads since the decoder was opened or reset, including the empty packets skipped by the decoder.
+    /// The iterator shares this decoder's file handle.
+    fn enumerate(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        if shell.inner.is_none() {
+            return Err(pyo3::exceptions::PyException::new_err(
+                "called enumerate after __exit__",
+            ));
+        }
+        let python = shell.py();
+        let decoder: Py<Decoder> = shell.into();
+        Py::new(python, EnumeratedDecoder { decoder })
+    }
+
     fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
-        let packet = match shell.inner {
-            Some(ref mut decoder) => match decoder.next() {
-                Ok(result) => match result {
-                    Some(result) => result,
-                    None => return Ok(None),
+        let shell = &mut *shell;
+        let skip_empty = shell.skip_empty;
+        let packet = loop {
+            match shell.inner {
+                Some(ref mut decoder) => match decoder.next() {
+                    Ok(result) => match result {
+                        Some(result) => {
+                            shell.packet_index += 1;
+                            if skip_empty && result.0.is_empty() && result.1.is_empty() {
+                                continue;
+                            }
+                            break result;
+                        }
+                        None => return Ok(None),
+                    },
+                    Err(result) => return Err(result.into()),
                 },
-                Err(result) => return Err(result.into()),
-            },
-            None => {
-                return Err(pyo3::exceptions::PyException::new_err(
-                    "used decoder after __exit__",
-                ))
+                None => {
+                    return Err(pyo3::exceptions::PyException::new_err(
+                        "used decoder after __exit__",
+                    ))
+                }
             }
         };
         Python::with_gil(|python| -> PyResult<Option<PyObject>> {
@@ -157,6 +240,25 @@ impl Decoder {
     }
 }
 
+/// Iterates over a decoder's packets and yields (packet_index, packet) tuples.
+#[pyclass]
+pub struct EnumeratedDecoder {
+    decoder: Py<Decoder>,
+}
+
+#[pymethods]
+impl EnumeratedDecoder {
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<EnumeratedDecoder>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(shell: PyRefMut<Self>) -> PyResult<Option<(u64, PyObject)>> {
+        let decoder = shell.decoder.bind(shell.py());
+        let packet = Decoder::__next__(decoder.try_borrow_mut()?)?;
+        Ok(packet.map(|packet| (decoder.borrow().packet_index - 1, packet)))
+    }
+}
+
 #[pyclass]
 pub struct Encoder {
     inner: Option<encoder::Encoder>,
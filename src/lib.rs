@@ -2,12 +2,15 @@ use pyo3::prelude::*;
 extern crate ndarray;
 
 mod aedat;
+mod colormap;
 mod dat;
 mod event_stream;
 mod evt;
 mod render;
 mod types;
 mod utilities;
+#[cfg(feature = "video")]
+mod video;
 
 #[pymodule]
 fn faery(python: Python, module: &pyo3::Bound<'_, pyo3::types::PyModule>) -> PyResult<()> {
@@ -15,7 +18,9 @@ fn faery(python: Python, module: &pyo3::Bound<'_, pyo3::types::PyModule>) -> PyR
         let submodule = PyModule::new_bound(python, "aedat")?;
         submodule.add_class::<aedat::Decoder>()?;
         submodule.add_class::<aedat::Encoder>()?;
+        submodule.add_class::<aedat::EnumeratedDecoder>()?;
         submodule.add_class::<aedat::Frame>()?;
+        submodule.add_class::<aedat::LegacyDecoder>()?;
         submodule.add_class::<aedat::Track>()?;
         submodule.add("LZ4_FASTEST", ("lz4", utilities::LZ4_MINIMUM_LEVEL))?;
         submodule.add("LZ4_DEFAULT", ("lz4", utilities::LZ4_DEFAULT_LEVEL))?;
@@ -28,25 +33,51 @@ fn faery(python: Python, module: &pyo3::Bound<'_, pyo3::types::PyModule>) -> PyR
     {
         let submodule = PyModule::new_bound(python, "dat")?;
         submodule.add_class::<dat::Decoder>()?;
+        submodule.add_class::<dat::EnumeratedDecoder>()?;
         submodule.add_class::<dat::Encoder>()?;
         module.add_submodule(&submodule)?;
     }
     {
         let submodule = PyModule::new_bound(python, "event_stream")?;
+        submodule.add_class::<event_stream::AsyncDecoder>()?;
+        submodule.add_class::<event_stream::AtisFrames>()?;
         submodule.add_class::<event_stream::Decoder>()?;
         submodule.add_class::<event_stream::Encoder>()?;
+        submodule.add_class::<event_stream::EnumeratedDecoder>()?;
+        submodule.add_class::<event_stream::HotPixelFilter>()?;
+        submodule.add_class::<event_stream::RefractoryFilter>()?;
+        submodule.add_class::<event_stream::BackgroundActivityFilter>()?;
+        submodule.add_class::<event_stream::TimeChunks>()?;
+        submodule.add_class::<event_stream::CountChunks>()?;
+        submodule.add_class::<event_stream::Merge>()?;
+        submodule.add_function(wrap_pyfunction!(event_stream::decode_many, &submodule)?)?;
+        submodule.add_function(wrap_pyfunction!(event_stream::merge, &submodule)?)?;
         module.add_submodule(&submodule)?;
     }
     {
         let submodule = PyModule::new_bound(python, "evt")?;
         submodule.add_class::<evt::Decoder>()?;
+        submodule.add_class::<evt::EnumeratedDecoder>()?;
         submodule.add_class::<evt::Encoder>()?;
         module.add_submodule(&submodule)?;
     }
     {
         let submodule = PyModule::new_bound(python, "render")?;
         submodule.add_class::<render::RenderIterator>()?;
+        submodule.add_class::<render::TimeSurfaceIterator>()?;
+        submodule.add_class::<render::CountImageIterator>()?;
         module.add_submodule(&submodule)?;
     }
+    #[cfg(feature = "video")]
+    {
+        let submodule = PyModule::new_bound(python, "video")?;
+        submodule.add_class::<video::VideoEncoder>()?;
+        module.add_submodule(&submodule)?;
+    }
+    module.add("VIDEO_AVAILABLE", cfg!(feature = "video"))?;
+    module.add(
+        "TruncatedFileError",
+        python.get_type_bound::<utilities::TruncatedFileError>(),
+    )?;
     Ok(())
 }
@@ -1,8 +1,11 @@
 use pyo3::prelude::*;
 
+use crate::colormap;
 use crate::types;
 
 struct BufferedArray {
+    // keeps the array alive while array is in use
+    #[allow(dead_code)]
     inner: PyObject,
     array: *mut numpy::npyffi::PyArrayObject,
     length: isize,
@@ -11,14 +14,89 @@ struct BufferedArray {
 
 unsafe impl Send for BufferedArray {}
 
+/// Reads events from a Python iterator of DVS arrays, one at a time.
+struct EventReader {
+    parent: PyObject,
+    dimensions: (u16, u16),
+    buffered_array: Option<BufferedArray>,
+}
+
+impl EventReader {
+    fn new(parent: &pyo3::Bound<'_, pyo3::types::PyAny>, dimensions: (u16, u16)) -> Self {
+        EventReader {
+            parent: parent.clone().unbind(),
+            dimensions,
+            buffered_array: None,
+        }
+    }
+
+    /// Returns the next event if its timestamp is strictly smaller than frame_t.
+    ///
+    /// Returns None if the next event belongs to a later frame (it is kept for the next call) or if the parent is exhausted.
+    fn next_before(
+        &mut self,
+        python: Python,
+        frame_t: u64,
+    ) -> PyResult<Option<neuromorphic_types::DvsEvent<u64, u16, u16>>> {
+        loop {
+            let mut buffered_array = match self.buffered_array.take() {
+                Some(buffered_array) => buffered_array,
+                None => match self.parent.call_method0(python, "__next__") {
+                    Ok(packet) => {
+                        let (array, length) =
+                            types::check_array(python, types::ArrayType::Dvs, packet.bind(python))?;
+                        BufferedArray {
+                            inner: packet,
+                            array,
+                            length,
+                            index: 0,
+                        }
+                    }
+                    Err(error) => {
+                        if error.is_instance_of::<pyo3::exceptions::PyStopIteration>(python) {
+                            // the parent is exhausted, the remaining frames only decay
+                            return Ok(None);
+                        }
+                        return Err(error);
+                    }
+                },
+            };
+            if buffered_array.index < buffered_array.length {
+                let event: neuromorphic_types::DvsEvent<u64, u16, u16> =
+                    unsafe { *types::array_at(python, buffered_array.array, buffered_array.index) };
+                if event.t >= frame_t {
+                    self.buffered_array = Some(buffered_array);
+                    return Ok(None);
+                }
+                if event.x >= self.dimensions.0 || event.y >= self.dimensions.1 {
+                    return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                        "event out of bounds (x={}, y={}, width={}, height={})",
+                        { event.x },
+                        { event.y },
+                        self.dimensions.0,
+                        self.dimensions.1
+                    )));
+                }
+                buffered_array.index += 1;
+                self.buffered_array = Some(buffered_array);
+                return Ok(Some(event));
+            }
+        }
+    }
+}
+
 struct Inner {
+    reader: EventReader,
+    dimensions: (u16, u16),
     next_frame_t: u64,
     frame_duration: u64,
     frame_index: u64,
     frame_count: u64,
     decay: Decay,
-    buffered_array: Option<BufferedArray>,
-    ts_and_polarities: Vec<(u64, neuromorphic_types::DvsPolarity)>,
+    ignore_polarity: bool,
+    colormap: Option<colormap::Colormap>,
+    ts_and_polarities: Vec<Option<(u64, neuromorphic_types::DvsPolarity)>>,
+    staleness: bool,
 }
 
 #[pyclass]
@@ -31,11 +109,35 @@ enum Decay {
     Exponential(f64),
     Linear(f64),
     Step(u64),
+    /// Time-encoded image, see render.
+    TimeEncoded(u64),
+}
+
+impl Decay {
+    /// Weight in [0, 1] of an event that happened elapsed µs before the frame.
+    fn value(self, elapsed: u64) -> f64 {
+        match self {
+            Decay::Exponential(coefficient) => (coefficient * elapsed as f64).exp(),
+            Decay::Linear(coefficient) => (1.0 + coefficient * elapsed as f64).max(0.0),
+            Decay::Step(tau) => {
+                if elapsed < tau {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Decay::TimeEncoded(_) => {
+                unreachable!("time-encoded values depend on the other pixels, see render")
+            }
+        }
+    }
 }
 
 #[pymethods]
 impl RenderIterator {
     #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (parent, dimensions, next_frame_t, frame_duration, frame_count, decay, tau, ignore_polarity = false, staleness = false, colormap = None))]
     fn new(
         parent: &pyo3::Bound<'_, pyo3::types::PyAny>,
         dimensions: (u16, u16),
@@ -44,95 +146,432 @@ impl RenderIterator {
         frame_count: u64,
         decay: String,
         tau: u64,
+        ignore_polarity: bool,
+        staleness: bool,
+        colormap: Option<&str>,
     ) -> Result<Self, PyErr> {
-        Python::with_gil(|python| -> Result<Self, PyErr> {
-            let decay = match decay.as_str() {
-                "exponential" => Decay::Exponential(-1.0 / (tau as f64)),
-                "linear" => Decay::Linear(-1.0 / (tau as f64)),
-                "step" => Decay::Step(tau),
-                decay => {
-                    return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
-                        "unknown decay \"{decay}\" (expected \"exponential\", \"linear\", or \"step\")"
-                    )));
+        // exponential and linear decays divide by tau
+        if tau == 0 && matches!(decay.as_str(), "exponential" | "linear") {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "tau must be larger than 0 with the \"{decay}\" decay"
+            )));
+        }
+        let decay = match decay.as_str() {
+            "exponential" => Decay::Exponential(-1.0 / (tau as f64)),
+            "linear" => Decay::Linear(-1.0 / (tau as f64)),
+            "step" => Decay::Step(tau),
+            "time_encoded" => Decay::TimeEncoded(tau),
+            decay => {
+                return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                    "unknown decay \"{decay}\" (expected \"exponential\", \"linear\", \"step\", or \"time_encoded\")"
+                )));
+            }
+        };
+        let colormap = match colormap {
+            Some(name) => Some(colormap::Colormap::from_name(name).ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                    "unknown colormap \"{name}\" (expected \"polarity\", \"gray\", \"viridis\", or \"jet\")"
+                ))
+            })?),
+            None => None,
+        };
+        Ok(RenderIterator {
+            inner: Some(Inner {
+                reader: EventReader::new(parent, dimensions),
+                dimensions,
+                next_frame_t,
+                frame_duration,
+                frame_index: 0,
+                frame_count,
+                decay,
+                ignore_polarity,
+                colormap,
+                ts_and_polarities: vec![None; dimensions.0 as usize * dimensions.1 as usize],
+                staleness,
+            }),
+        })
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<RenderIterator>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        Python::with_gil(|python| -> PyResult<Option<PyObject>> {
+            let inner = match shell.inner {
+                Some(ref mut inner) => inner,
+                None => {
+                    return Err(pyo3::exceptions::PyException::new_err(
+                        "__next__ called after __exit__",
+                    ))
                 }
             };
-            Ok(RenderIterator {
-                inner: Some(Inner {
-                    next_frame_t,
-                    frame_duration,
-                    frame_index: 0,
-                    frame_count,
-                    decay,
-                    buffered_array: None,
-                    ts_and_polarities: vec![
-                        (0, neuromorphic_types::DvsPolarity::Off);
-                        dimensions.0 as usize * dimensions.1 as usize
-                    ],
-                }),
-            })
+            if inner.frame_index >= inner.frame_count {
+                return Ok(None);
+            }
+            while let Some(event) = inner.reader.next_before(python, inner.next_frame_t)? {
+                inner.ts_and_polarities
+                    [event.x as usize + event.y as usize * inner.dimensions.0 as usize] =
+                    Some((event.t, event.polarity));
+            }
+            inner.frame(python).map(Some)
         })
     }
 
-    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<RenderIterator>> {
+    fn close(&mut self) {
+        let _ = self.inner.take();
+    }
+}
+
+impl Inner {
+    /// Renders the frame at next_frame_t and moves to the next frame.
+    ///
+    /// The result is a (height, width) float64 array, or a (height, width, 3) uint8 array if a colormap is set.
+    /// It is wrapped in a (pixels, staleness) tuple if staleness is enabled, see render_staleness.
+    fn frame(&mut self, python: Python) -> PyResult<PyObject> {
+        let pixels = render(
+            python,
+            self.dimensions,
+            &self.ts_and_polarities,
+            self.next_frame_t,
+            self.decay,
+            self.ignore_polarity,
+            self.colormap,
+        )?;
+        let result = if self.staleness {
+            (
+                pixels,
+                render_staleness(
+                    python,
+                    self.dimensions,
+                    &self.ts_and_polarities,
+                    self.next_frame_t,
+                )?,
+            )
+                .to_object(python)
+        } else {
+            pixels
+        };
+        self.next_frame_t += self.frame_duration;
+        self.frame_index += 1;
+        Ok(result)
+    }
+}
+
+struct TimeSurfaceInner {
+    reader: EventReader,
+    dimensions: (u16, u16),
+    next_frame_t: u64,
+    frame_duration: u64,
+    frame_index: u64,
+    frame_count: u64,
+    tau: f64,
+    split_polarities: bool,
+    /// Timestamps of the last OFF event and of the last ON event of each pixel.
+    ts: Vec<[Option<u64>; 2]>,
+}
+
+/// Renders exponential time surfaces (HOTS), exp(-(frame_t - t) / tau) where t is the timestamp of each pixel's last event.
+#[pyclass]
+pub struct TimeSurfaceIterator {
+    inner: Option<TimeSurfaceInner>,
+}
+
+#[pymethods]
+impl TimeSurfaceIterator {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (parent, dimensions, next_frame_t, frame_duration, frame_count, tau, split_polarities = true))]
+    fn new(
+        parent: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        dimensions: (u16, u16),
+        next_frame_t: u64,
+        frame_duration: u64,
+        frame_count: u64,
+        tau: u64,
+        split_polarities: bool,
+    ) -> Result<Self, PyErr> {
+        if tau == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "tau must be larger than 0",
+            ));
+        }
+        Ok(TimeSurfaceIterator {
+            inner: Some(TimeSurfaceInner {
+                reader: EventReader::new(parent, dimensions),
+                dimensions,
+                next_frame_t,
+                frame_duration,
+                frame_index: 0,
+                frame_count,
+                tau: tau as f64,
+                split_polarities,
+                ts: vec![[None; 2]; dimensions.0 as usize * dimensions.1 as usize],
+            }),
+        })
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<TimeSurfaceIterator>> {
         Ok(shell.into())
     }
 
-    /*
     fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
         Python::with_gil(|python| -> PyResult<Option<PyObject>> {
-            match shell.inner {
-                Some(ref mut decoder) => loop {
-                    if let Some(buffered_array) = decoder.buffered_array.take() {
-                        while buffered_array.index < buffered_array.length {
-                            unsafe {
-                                let event_cell: *mut neuromorphic_types::DvsEvent<u64, u16, u16> =
-                                    types::array_at(
-                                        python,
-                                        buffered_array.array,
-                                        buffered_array.index,
-                                    );
-                                if (*event_cell).t >= decoder.next_frame_t {
-                                    decoder.buffered_array = Some(buffered_array);
-
-                                    // @DEV render frame
-                                    return Ok(Some());
-                                }
-                            }
-                        }
-                    }
-                },
-                None => Err(pyo3::exceptions::PyException::new_err(
-                    "__next__ called after __exit__",
-                )),
+            let inner = match shell.inner {
+                Some(ref mut inner) => inner,
+                None => {
+                    return Err(pyo3::exceptions::PyException::new_err(
+                        "__next__ called after __exit__",
+                    ))
+                }
+            };
+            if inner.frame_index >= inner.frame_count {
+                return Ok(None);
+            }
+            while let Some(event) = inner.reader.next_before(python, inner.next_frame_t)? {
+                inner.ts[event.x as usize + event.y as usize * inner.dimensions.0 as usize]
+                    [event.polarity as usize] = Some(event.t);
+            }
+            inner.frame(python).map(Some)
+        })
+    }
+
+    fn close(&mut self) {
+        let _ = self.inner.take();
+    }
+}
+
+impl TimeSurfaceInner {
+    /// Renders the frame at next_frame_t and moves to the next frame.
+    ///
+    /// The result is a (2, height, width) float64 array (OFF then ON) if split_polarities is true,
+    /// and a (height, width) float64 array with the most recent event of each pixel otherwise.
+    /// Pixels never touched by an event are 0.
+    fn frame(&mut self, python: Python) -> PyResult<PyObject> {
+        use numpy::convert::ToPyArray;
+        use numpy::prelude::*;
+        let frame_t = self.next_frame_t;
+        let tau = self.tau;
+        let value = |t: Option<u64>| -> f64 {
+            t.map_or(0.0, |t| (-(frame_t.saturating_sub(t) as f64) / tau).exp())
+        };
+        let result = if self.split_polarities {
+            (0..2)
+                .flat_map(|polarity| self.ts.iter().map(move |ts| value(ts[polarity])))
+                .collect::<Vec<f64>>()
+                .to_pyarray_bound(python)
+                .reshape([2, self.dimensions.1 as usize, self.dimensions.0 as usize])?
+                .to_object(python)
+        } else {
+            self.ts
+                .iter()
+                .map(|ts| value(ts[0].max(ts[1])))
+                .collect::<Vec<f64>>()
+                .to_pyarray_bound(python)
+                .reshape([self.dimensions.1 as usize, self.dimensions.0 as usize])?
+                .to_object(python)
+        };
+        self.next_frame_t += self.frame_duration;
+        self.frame_index += 1;
+        Ok(result)
+    }
+}
+
+struct CountImageInner {
+    reader: EventReader,
+    dimensions: (u16, u16),
+    next_frame_t: u64,
+    frame_duration: u64,
+    frame_index: u64,
+    frame_count: u64,
+    split_polarities: bool,
+    /// Number of OFF events and number of ON events of each pixel since the previous frame.
+    counts: Vec<[i32; 2]>,
+}
+
+/// Counts the events of each pixel in each frame window, counts are reset after each frame.
+#[pyclass]
+pub struct CountImageIterator {
+    inner: Option<CountImageInner>,
+}
+
+#[pymethods]
+impl CountImageIterator {
+    #[new]
+    #[pyo3(signature = (parent, dimensions, next_frame_t, frame_duration, frame_count, split_polarities = false))]
+    fn new(
+        parent: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        dimensions: (u16, u16),
+        next_frame_t: u64,
+        frame_duration: u64,
+        frame_count: u64,
+        split_polarities: bool,
+    ) -> Self {
+        CountImageIterator {
+            inner: Some(CountImageInner {
+                reader: EventReader::new(parent, dimensions),
+                dimensions,
+                next_frame_t,
+                frame_duration,
+                frame_index: 0,
+                frame_count,
+                split_polarities,
+                counts: vec![[0; 2]; dimensions.0 as usize * dimensions.1 as usize],
+            }),
+        }
+    }
+
+    fn __iter__(shell: PyRefMut<Self>) -> PyResult<Py<CountImageIterator>> {
+        Ok(shell.into())
+    }
+
+    fn __next__(mut shell: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        Python::with_gil(|python| -> PyResult<Option<PyObject>> {
+            let inner = match shell.inner {
+                Some(ref mut inner) => inner,
+                None => {
+                    return Err(pyo3::exceptions::PyException::new_err(
+                        "__next__ called after __exit__",
+                    ))
+                }
+            };
+            if inner.frame_index >= inner.frame_count {
+                return Ok(None);
             }
+            while let Some(event) = inner.reader.next_before(python, inner.next_frame_t)? {
+                let count = &mut inner.counts
+                    [event.x as usize + event.y as usize * inner.dimensions.0 as usize]
+                    [event.polarity as usize];
+                *count = count.saturating_add(1);
+            }
+            inner.frame(python).map(Some)
         })
     }
-     */
 
     fn close(&mut self) {
         let _ = self.inner.take();
     }
 }
 
+impl CountImageInner {
+    /// Returns the counts of the frame that ends at next_frame_t and moves to the next frame.
+    ///
+    /// The result is a (height, width, 2) int32 array (OFF then ON) if split_polarities is true,
+    /// and a (height, width) int32 array otherwise.
+    fn frame(&mut self, python: Python) -> PyResult<PyObject> {
+        use numpy::convert::ToPyArray;
+        use numpy::prelude::*;
+        let result = if self.split_polarities {
+            self.counts
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<i32>>()
+                .to_pyarray_bound(python)
+                .reshape([self.dimensions.1 as usize, self.dimensions.0 as usize, 2])?
+                .to_object(python)
+        } else {
+            self.counts
+                .iter()
+                .map(|counts| counts[0].saturating_add(counts[1]))
+                .collect::<Vec<i32>>()
+                .to_pyarray_bound(python)
+                .reshape([self.dimensions.1 as usize, self.dimensions.0 as usize])?
+                .to_object(python)
+        };
+        self.counts.fill([0; 2]);
+        self.next_frame_t += self.frame_duration;
+        self.frame_index += 1;
+        Ok(result)
+    }
+}
+
+/// Pixels never touched by an event are 0, others are the decayed weight of their last event,
+/// negated for OFF events unless ignore_polarity is true.
+///
+/// Time-encoded images do not decay, the last events in [frame_t - tau, frame_t) are mapped linearly
+/// from the window's earliest event (darkest, close to 0) to its latest event (1), older events are 0.
 fn render(
     python: Python,
     dimensions: (u16, u16),
-    ts_and_polarities: &Vec<(u64, neuromorphic_types::DvsPolarity)>,
+    ts_and_polarities: &[Option<(u64, neuromorphic_types::DvsPolarity)>],
     frame_t: u64,
     decay: Decay,
-) {
-    /*
-    unsafe {
-        let array = numpy::PyArray2::<f64>::new_bound(
-            python,
-            (dimensions.0 as usize, dimensions.1 as usize),
-            false,
-        );
-        for y in 0..dimensions.1 as isize {
-            for x in 0..dimensions.0 as isize {
-                array
+    ignore_polarity: bool,
+    colormap: Option<colormap::Colormap>,
+) -> PyResult<PyObject> {
+    use numpy::convert::ToPyArray;
+    use numpy::prelude::*;
+    let window = match decay {
+        Decay::TimeEncoded(tau) => ts_and_polarities
+            .iter()
+            .flatten()
+            .map(|(t, _)| *t)
+            .filter(|t| frame_t.saturating_sub(*t) < tau)
+            .fold(None, |window: Option<(u64, u64)>, t| match window {
+                Some((first_t, last_t)) => Some((first_t.min(t), last_t.max(t))),
+                None => Some((t, t)),
+            }),
+        _ => None,
+    };
+    let values = ts_and_polarities
+        .iter()
+        .map(|t_and_polarity| match t_and_polarity {
+            Some((t, polarity)) => {
+                let value = match decay {
+                    Decay::TimeEncoded(tau) => match window {
+                        Some((first_t, last_t)) if frame_t.saturating_sub(*t) < tau => {
+                            (*t - first_t + 1) as f64 / (last_t - first_t + 1) as f64
+                        }
+                        _ => 0.0,
+                    },
+                    decay => decay.value(frame_t.saturating_sub(*t)),
+                };
+                if ignore_polarity || *polarity == neuromorphic_types::DvsPolarity::On {
+                    value
+                } else {
+                    -value
+                }
             }
-        }
+            None => 0.0,
+        });
+    match colormap {
+        // values are in [0, 1] if ignore_polarity is true and in [-1, 1] otherwise,
+        // signed colormaps always map [-1, 1] so that untouched pixels are neutral
+        Some(colormap) => Ok(values
+            .flat_map(|value| {
+                colormap.rgb(if ignore_polarity && !colormap.is_signed() {
+                    value
+                } else {
+                    (value + 1.0) / 2.0
+                })
+            })
+            .collect::<Vec<u8>>()
+            .to_pyarray_bound(python)
+            .reshape([dimensions.1 as usize, dimensions.0 as usize, 3])?
+            .to_object(python)),
+        None => Ok(values
+            .collect::<Vec<f64>>()
+            .to_pyarray_bound(python)
+            .reshape([dimensions.1 as usize, dimensions.0 as usize])?
+            .to_object(python)),
     }
-     */
+}
+
+/// Pixels that never received an event report u64::MAX.
+fn render_staleness(
+    python: Python,
+    dimensions: (u16, u16),
+    ts_and_polarities: &[Option<(u64, neuromorphic_types::DvsPolarity)>],
+    frame_t: u64,
+) -> PyResult<PyObject> {
+    use numpy::convert::ToPyArray;
+    use numpy::prelude::*;
+    Ok(ts_and_polarities
+        .iter()
+        .map(|t_and_polarity| t_and_polarity.map_or(u64::MAX, |(t, _)| frame_t.saturating_sub(t)))
+        .collect::<Vec<u64>>()
+        .to_pyarray_bound(python)
+        .reshape([dimensions.1 as usize, dimensions.0 as usize])?
+        .to_object(python))
 }
@@ -2,6 +2,23 @@ use numpy::prelude::*;
 use numpy::Element;
 use pyo3::prelude::*;
 
+use crate::utilities;
+
+/// Converts a path, "-" (stdin), or a file-like object with a read method to a decoder source.
+pub fn python_source(
+    python: Python,
+    path: &pyo3::Bound<'_, pyo3::types::PyAny>,
+) -> PyResult<utilities::Source> {
+    if !path.is_instance_of::<pyo3::types::PyString>()
+        && !path.is_instance_of::<pyo3::types::PyBytes>()
+        && path.hasattr("read")?
+    {
+        return Ok(utilities::Source::Python(path.clone().unbind()));
+    }
+    utilities::Source::open(python_path_to_string(python, path)?)
+        .map_err(|error| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string()))
+}
+
 pub fn python_path_to_string(
     python: Python,
     path: &pyo3::Bound<'_, pyo3::types::PyAny>,
@@ -23,6 +40,26 @@ pub fn python_path_to_string(
     Ok(fspath_as_bytes.to_string())
 }
 
+/// Imports a module that faery does not depend on, with an explicit message if it is not installed.
+///
+/// package is the name passed to pip, which differs from the module name for some libraries (PIL is installed with pillow).
+pub fn import_optional<'py>(
+    python: Python<'py>,
+    name: &str,
+    package: &str,
+    method: &str,
+) -> PyResult<Bound<'py, pyo3::types::PyModule>> {
+    match python.import_bound(name) {
+        Ok(module) => Ok(module),
+        Err(error) if error.is_instance_of::<pyo3::exceptions::PyImportError>(python) => {
+            Err(pyo3::exceptions::PyImportError::new_err(format!(
+                "{method} requires {name} (install it with \"pip install {package}\")"
+            )))
+        }
+        Err(error) => Err(error),
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum CheckArrayError {
     #[error("the object is not a numpy array")]
@@ -206,10 +243,14 @@ pub enum ArrayType {
     Dvs,
     AedatImu,
     AedatTrigger,
+    AedatTriggerInterval,
+    AedatFrameMetadata,
+    AedatFused,
     Dat,
     EsGeneric,
     EsAtis,
     EsColor,
+    EsMerge,
     EvtTrigger,
 }
 
@@ -220,10 +261,28 @@ pub enum FieldType {
     F32,
     U8,
     U16,
+    I16,
     U64,
+    I64,
     Object,
 }
 
+impl FieldType {
+    pub fn num(self, python: Python) -> core::ffi::c_int {
+        match self {
+            FieldType::Empty => panic!("FieldType::num called on an empty field"),
+            FieldType::Bool => bool::get_dtype_bound(python).num(),
+            FieldType::F32 => f32::get_dtype_bound(python).num(),
+            FieldType::U8 => u8::get_dtype_bound(python).num(),
+            FieldType::U16 => u16::get_dtype_bound(python).num(),
+            FieldType::I16 => i16::get_dtype_bound(python).num(),
+            FieldType::U64 => u64::get_dtype_bound(python).num(),
+            FieldType::I64 => i64::get_dtype_bound(python).num(),
+            FieldType::Object => numpy::PyArrayDescr::object_bound(python).num(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Field {
     pub null_terminated_name: &'static str,
@@ -251,7 +310,9 @@ impl Field {
             FieldType::F32 => 4,
             FieldType::U8 => 1,
             FieldType::U16 => 2,
+            FieldType::I16 => 2,
             FieldType::U64 => 8,
+            FieldType::I64 => 8,
             FieldType::Object => std::mem::size_of::<usize>(),
         }
     }
@@ -261,15 +322,7 @@ impl Field {
     }
 
     pub fn num(&self, python: Python) -> core::ffi::c_int {
-        match self.field_type {
-            FieldType::Empty => panic!("Field::num called on an empty field"),
-            FieldType::Bool => bool::get_dtype_bound(python).num(),
-            FieldType::F32 => f32::get_dtype_bound(python).num(),
-            FieldType::U8 => u8::get_dtype_bound(python).num(),
-            FieldType::U16 => u16::get_dtype_bound(python).num(),
-            FieldType::U64 => u64::get_dtype_bound(python).num(),
-            FieldType::Object => numpy::PyArrayDescr::object_bound(python).num(),
-        }
+        self.field_type.num(python)
     }
 
     pub fn dtype(&self, python: Python) -> *mut numpy::npyffi::PyArray_Descr {
@@ -332,6 +385,45 @@ impl ArrayType {
                 EMPTY,
                 EMPTY,
             ],
+            ArrayType::AedatTriggerInterval => [
+                Field::new("start_t\0", None, FieldType::U64),
+                Field::new("end_t\0", None, FieldType::U64),
+                Field::new("source\0", None, FieldType::U8),
+                Field::new("paired\0", None, FieldType::Bool),
+                EMPTY,
+                EMPTY,
+                EMPTY,
+                EMPTY,
+                EMPTY,
+                EMPTY,
+                EMPTY,
+            ],
+            ArrayType::AedatFrameMetadata => [
+                Field::new("t\0", None, FieldType::U64),
+                Field::new("begin_t\0", None, FieldType::I64),
+                Field::new("end_t\0", None, FieldType::I64),
+                Field::new("exposure_begin_t\0", None, FieldType::I64),
+                Field::new("exposure_end_t\0", None, FieldType::I64),
+                Field::new("width\0", None, FieldType::U16),
+                Field::new("height\0", None, FieldType::U16),
+                Field::new("offset_x\0", None, FieldType::I16),
+                Field::new("offset_y\0", None, FieldType::I16),
+                EMPTY,
+                EMPTY,
+            ],
+            ArrayType::AedatFused => [
+                Field::new("t\0", None, FieldType::U64),
+                Field::new("kind\0", None, FieldType::U8),
+                Field::new("x\0", None, FieldType::U16),
+                Field::new("y\0", None, FieldType::U16),
+                Field::new("on\0", Some("p"), FieldType::Bool),
+                Field::new("accelerometer_x\0", None, FieldType::F32),
+                Field::new("accelerometer_y\0", None, FieldType::F32),
+                Field::new("accelerometer_z\0", None, FieldType::F32),
+                Field::new("gyroscope_x\0", None, FieldType::F32),
+                Field::new("gyroscope_y\0", None, FieldType::F32),
+                Field::new("gyroscope_z\0", None, FieldType::F32),
+            ],
             ArrayType::Dat => [
                 Field::new("t\0", None, FieldType::U64),
                 Field::new("x\0", None, FieldType::U16),
@@ -384,6 +476,19 @@ impl ArrayType {
                 EMPTY,
                 EMPTY,
             ],
+            ArrayType::EsMerge => [
+                Field::new("t\0", None, FieldType::U64),
+                Field::new("x\0", None, FieldType::U16),
+                Field::new("y\0", None, FieldType::U16),
+                Field::new("on\0", Some("p"), FieldType::Bool),
+                Field::new("camera_id\0", None, FieldType::U16),
+                EMPTY,
+                EMPTY,
+                EMPTY,
+                EMPTY,
+                EMPTY,
+                EMPTY,
+            ],
             ArrayType::EvtTrigger => [
                 Field::new("t\0", None, FieldType::U64),
                 Field::new("source\0", None, FieldType::U8),
@@ -400,7 +505,6 @@ impl ArrayType {
         })
     }
 
-    #[allow(unused)]
     pub fn dtype(self, python: Python) -> *mut numpy::npyffi::PyArray_Descr {
         self.fields().dtype(python)
     }
@@ -466,36 +570,119 @@ impl Fields {
                     field.num(python),
                 );
             }
-            let mut dtype: *mut numpy::npyffi::PyArray_Descr = std::ptr::null_mut();
-            if numpy::PY_ARRAY_API.PyArray_DescrConverter(python, dtype_as_list, &mut dtype) < 0 {
-                panic!("PyArray_DescrConverter failed");
-            }
-            pyo3::ffi::Py_DECREF(dtype_as_list);
-            dtype
+            list_to_dtype(python, dtype_as_list)
         }
     }
 
     pub fn new_array(
         &self,
         python: Python,
-        mut length: numpy::npyffi::npy_intp,
+        length: numpy::npyffi::npy_intp,
     ) -> *mut numpy::npyffi::PyArrayObject {
-        let dtype = self.dtype(python);
-        unsafe {
-            numpy::PY_ARRAY_API.PyArray_NewFromDescr(
+        new_array_from_dtype(python, self.dtype(python), length)
+    }
+}
+
+/// A field whose name is chosen at runtime, for instance to rename the fields of a built-in layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedField {
+    pub name: String,
+    pub title: Option<String>,
+    pub field_type: FieldType,
+}
+
+/// Returns a structured dtype with the given fields, which must have distinct names and titles.
+pub fn named_fields_dtype(
+    python: Python,
+    fields: &[NamedField],
+) -> *mut numpy::npyffi::PyArray_Descr {
+    unsafe {
+        let dtype_as_list = pyo3::ffi::PyList_New(fields.len() as pyo3::ffi::Py_ssize_t);
+        for (index, field) in fields.iter().enumerate() {
+            set_dtype_as_list_field(
                 python,
-                numpy::PY_ARRAY_API
-                    .get_type_object(python, numpy::npyffi::array::NpyTypes::PyArray_Type),
-                dtype,
-                1_i32,
-                &mut length,
-                std::ptr::null_mut(),
-                std::ptr::null_mut(),
-                0_i32,
-                std::ptr::null_mut(),
-            ) as *mut numpy::npyffi::PyArrayObject
+                dtype_as_list,
+                index,
+                &format!("{}\0", field.name),
+                field.title.as_deref(),
+                field.field_type.num(python),
+            );
         }
+        list_to_dtype(python, dtype_as_list)
+    }
+}
+
+/// Converts a list of (name, type) tuples to a dtype and releases the list.
+unsafe fn list_to_dtype(
+    python: Python,
+    dtype_as_list: *mut pyo3::ffi::PyObject,
+) -> *mut numpy::npyffi::PyArray_Descr {
+    let mut dtype: *mut numpy::npyffi::PyArray_Descr = std::ptr::null_mut();
+    if numpy::PY_ARRAY_API.PyArray_DescrConverter(python, dtype_as_list, &mut dtype) < 0 {
+        panic!("PyArray_DescrConverter failed");
+    }
+    pyo3::ffi::Py_DECREF(dtype_as_list);
+    dtype
+}
+
+/// Returns a one-dimensional array with the given dtype, which the array steals.
+pub fn new_array_from_dtype(
+    python: Python,
+    dtype: *mut numpy::npyffi::PyArray_Descr,
+    mut length: numpy::npyffi::npy_intp,
+) -> *mut numpy::npyffi::PyArrayObject {
+    unsafe {
+        numpy::PY_ARRAY_API.PyArray_NewFromDescr(
+            python,
+            numpy::PY_ARRAY_API
+                .get_type_object(python, numpy::npyffi::array::NpyTypes::PyArray_Type),
+            dtype,
+            1_i32,
+            &mut length,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0_i32,
+            std::ptr::null_mut(),
+        ) as *mut numpy::npyffi::PyArrayObject
+    }
+}
+
+/// Returns a one-dimensional array with the given dtype that points to the vector's memory, without copying it.
+///
+/// The array steals dtype and owns the vector (through a capsule base), which is freed with the array.
+///
+/// # Safety
+///
+/// The memory layout of T must match dtype (same item size and field offsets).
+pub unsafe fn new_array_from_vec<T: Send + 'static>(
+    python: Python,
+    dtype: *mut numpy::npyffi::PyArray_Descr,
+    mut vec: Vec<T>,
+) -> PyResult<*mut numpy::npyffi::PyArrayObject> {
+    let mut length = vec.len() as numpy::npyffi::npy_intp;
+    // the heap buffer does not move when the vector is moved into the capsule
+    let data = vec.as_mut_ptr() as *mut core::ffi::c_void;
+    let capsule = pyo3::types::PyCapsule::new_bound(python, vec, None)?;
+    let array = numpy::PY_ARRAY_API.PyArray_NewFromDescr(
+        python,
+        numpy::PY_ARRAY_API.get_type_object(python, numpy::npyffi::array::NpyTypes::PyArray_Type),
+        dtype,
+        1_i32,
+        &mut length,
+        std::ptr::null_mut(),
+        data,
+        numpy::npyffi::NPY_ARRAY_WRITEABLE,
+        std::ptr::null_mut(),
+    ) as *mut numpy::npyffi::PyArrayObject;
+    if array.is_null() {
+        return Err(PyErr::fetch(python));
+    }
+    // PyArray_SetBaseObject steals the capsule reference, even on failure
+    if numpy::PY_ARRAY_API.PyArray_SetBaseObject(python, array, capsule.into_ptr()) < 0 {
+        pyo3::ffi::Py_DECREF(array as *mut pyo3::ffi::PyObject);
+        return Err(PyErr::fetch(python));
     }
+    Ok(array)
 }
 
 unsafe fn set_dtype_as_list_field(
@@ -1,6 +1,9 @@
 use std::io::BufRead;
+use std::io::Read;
 
 pub const BUFFER_SIZE: usize = 65536;
+/// Smaller read buffers issue one system call per few events, which is slower without saving meaningful memory.
+pub const MINIMUM_BUFFER_SIZE: usize = 1024;
 pub const LZ4_MINIMUM_LEVEL: u8 = 1;
 pub const LZ4_DEFAULT_LEVEL: u8 = 1;
 pub const LZ4_MAXIMUM_LEVEL: u8 = 12;
@@ -18,12 +21,59 @@ pub enum ReadError {
 
     #[error("y overflow (y={y} should be strictly smaller than height={height})")]
     YOverflow { y: u16, height: u16 },
+
+    #[error("truncated file (the event at byte {offset} has {available} of {expected} bytes)")]
+    TruncatedFile {
+        offset: u64,
+        expected: u64,
+        available: u64,
+    },
 }
 
+pyo3::create_exception!(
+    faery,
+    TruncatedFileError,
+    pyo3::exceptions::PyEOFError,
+    "The file ends in the middle of a packet or an event, the data before the truncation point was decoded."
+);
+
 impl From<ReadError> for pyo3::PyErr {
     fn from(error: ReadError) -> Self {
-        pyo3::PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string())
+        match error {
+            ReadError::TruncatedFile { .. } => TruncatedFileError::new_err(error.to_string()),
+            _ => pyo3::PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string()),
+        }
+    }
+}
+
+/// Reads until buffer is full or the end of the file is reached, and returns the number of bytes read.
+pub fn read_up_to<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) => break,
+            Ok(size) => read += size,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
     }
+    Ok(read)
+}
+
+/// Reads at least one word (unless the end of the file is reached) and returns the number of bytes read.
+///
+/// Reads may end in the middle of a word, for instance at the end of the bytes read ahead while parsing a header,
+/// the last word is completed before returning. buffer must be at least one word long.
+pub fn read_words<R: Read>(
+    reader: &mut R,
+    buffer: &mut [u8],
+    word_size: usize,
+) -> Result<usize, std::io::Error> {
+    let capacity = buffer.len() / word_size * word_size;
+    let buffer = &mut buffer[..capacity];
+    let read = reader.read(buffer)?;
+    let end = read.div_ceil(word_size) * word_size;
+    Ok(read + read_up_to(reader, &mut buffer[read..end])?)
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -52,36 +102,435 @@ impl From<WriteError> for pyo3::PyErr {
     }
 }
 
+#[derive(thiserror::Error, Debug)]
+#[error("the masked pixel ({x}, {y}) is outside the sensor ({width}x{height})")]
+pub struct MaskError {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl From<MaskError> for pyo3::PyErr {
+    fn from(error: MaskError) -> Self {
+        pyo3::exceptions::PyValueError::new_err(error.to_string())
+    }
+}
+
+/// Pixels whose events are dropped while decoding (for instance known dead pixels),
+/// stored as a bitmap with one bit per pixel.
+#[derive(Debug, Clone)]
+pub struct PixelMask {
+    dimensions: (u16, u16),
+    bitmap: Vec<u64>,
+}
+
+impl PixelMask {
+    pub fn new(dimensions: (u16, u16), coordinates: &[(u16, u16)]) -> Result<Self, MaskError> {
+        let mut bitmap = vec![0u64; (dimensions.0 as usize * dimensions.1 as usize).div_ceil(64)];
+        for &(x, y) in coordinates {
+            if x >= dimensions.0 || y >= dimensions.1 {
+                return Err(MaskError {
+                    x,
+                    y,
+                    width: dimensions.0,
+                    height: dimensions.1,
+                });
+            }
+            let index = x as usize + y as usize * dimensions.0 as usize;
+            bitmap[index / 64] |= 1 << (index % 64);
+        }
+        Ok(PixelMask { dimensions, bitmap })
+    }
+
+    /// Returns false for coordinates outside the mask's dimensions.
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        if x >= self.dimensions.0 || y >= self.dimensions.1 {
+            return false;
+        }
+        let index = x as usize + y as usize * self.dimensions.0 as usize;
+        self.bitmap[index / 64] & (1 << (index % 64)) != 0
+    }
+}
+
+/// Per-pixel lookup table that moves events to new coordinates while decoding (for instance calibration remaps).
+#[derive(Debug, Clone)]
+pub struct PixelRemap {
+    dimensions: (u16, u16),
+    /// Destination of each source pixel (row-major), None if the destination is outside the sensor.
+    table: Vec<Option<(u16, u16)>>,
+}
+
+impl PixelRemap {
+    /// Converts an integer array with shape (height, width, 2), array[y, x] is the destination (x, y) of the pixel (x, y).
+    pub fn from_array(
+        array: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        dimensions: (u16, u16),
+    ) -> Result<Self, pyo3::PyErr> {
+        use numpy::PyArrayMethods;
+        use pyo3::types::PyAnyMethods;
+        let shape: Vec<usize> = array.getattr("shape")?.extract()?;
+        if shape != [dimensions.1 as usize, dimensions.0 as usize, 2] {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "the remap array must have the shape ({}, {}, 2) (got {:?})",
+                dimensions.1, dimensions.0, shape
+            )));
+        }
+        let kind: char = array.getattr("dtype")?.getattr("kind")?.extract()?;
+        if kind != 'i' && kind != 'u' {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "the remap array must have an integer dtype (got kind \"{kind}\")"
+            )));
+        }
+        let array = array
+            .call_method1("astype", ("int64",))?
+            .downcast_into::<numpy::PyArray3<i64>>()?
+            .readonly();
+        let array = array.as_array();
+        let mut table = Vec::with_capacity(dimensions.0 as usize * dimensions.1 as usize);
+        for y in 0..dimensions.1 as usize {
+            for x in 0..dimensions.0 as usize {
+                let (destination_x, destination_y) = (array[[y, x, 0]], array[[y, x, 1]]);
+                table.push(
+                    ((0..dimensions.0 as i64).contains(&destination_x)
+                        && (0..dimensions.1 as i64).contains(&destination_y))
+                    .then_some((destination_x as u16, destination_y as u16)),
+                );
+            }
+        }
+        Ok(PixelRemap { dimensions, table })
+    }
+
+    /// Returns None if the destination is outside the sensor or if (x, y) is outside the table's dimensions.
+    pub fn get(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        if x >= self.dimensions.0 || y >= self.dimensions.1 {
+            return None;
+        }
+        self.table[x as usize + y as usize * self.dimensions.0 as usize]
+    }
+}
+
+/// Median time-surface denoiser, events are kept only if their timestamp is within tolerance
+/// of the median of the 8 neighbouring pixels' last timestamps.
+///
+/// Every event (kept or not) updates its pixel's timestamp, and the state persists between packets.
+/// Neighbours outside the sensor and pixels that have not fired yet count as infinitely old,
+/// hence isolated events are dropped. The surface uses 8 bytes per pixel, and each event costs
+/// 8 reads and a sort of 8 values (about 5 times slower than the background activity filter).
+#[derive(Debug, Clone)]
+pub struct MedianDenoise {
+    dimensions: (u16, u16),
+    tolerance: u64,
+    /// Timestamp of each pixel's last event, u64::MAX if the pixel has not fired yet.
+    last_ts: Vec<u64>,
+}
+
+impl MedianDenoise {
+    pub fn new(dimensions: (u16, u16), tolerance: u64) -> Self {
+        MedianDenoise {
+            dimensions,
+            tolerance,
+            last_ts: vec![u64::MAX; dimensions.0 as usize * dimensions.1 as usize],
+        }
+    }
+
+    /// Forgets the previous events, as if no pixel had fired yet.
+    pub fn reset(&mut self) {
+        self.last_ts.fill(u64::MAX);
+    }
+
+    pub fn keep(&mut self, t: u64, x: u16, y: u16) -> bool {
+        let (width, height) = (self.dimensions.0 as usize, self.dimensions.1 as usize);
+        let (x, y) = (x as usize, y as usize);
+        if x >= width || y >= height {
+            return true;
+        }
+        // None sorts before Some, missing neighbours are older than any timestamp
+        let mut neighbours = [None; 8];
+        let mut index = 0;
+        for neighbour_y in [y.wrapping_sub(1), y, y + 1] {
+            for neighbour_x in [x.wrapping_sub(1), x, x + 1] {
+                if neighbour_x == x && neighbour_y == y {
+                    continue;
+                }
+                if neighbour_x < width && neighbour_y < height {
+                    let last_t = self.last_ts[neighbour_x + neighbour_y * width];
+                    if last_t != u64::MAX {
+                        neighbours[index] = Some(last_t);
+                    }
+                }
+                index += 1;
+            }
+        }
+        neighbours.sort_unstable();
+        self.last_ts[x + y * width] = t;
+        match (neighbours[3], neighbours[4]) {
+            // t - (low + high) / 2 <= tolerance, without rounding or overflow
+            (Some(low), Some(high)) => {
+                2 * t as u128 <= low as u128 + high as u128 + 2 * self.tolerance as u128
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Applies the mask, the remap table, and the median denoiser, returns None if the event is dropped.
+pub fn map_pixel(
+    mask: Option<&PixelMask>,
+    remap: Option<&PixelRemap>,
+    median_denoise: Option<&mut MedianDenoise>,
+    t: u64,
+    x: u16,
+    y: u16,
+) -> Option<(u16, u16)> {
+    if mask.is_some_and(|mask| mask.contains(x, y)) {
+        return None;
+    }
+    let (x, y) = match remap {
+        Some(remap) => remap.get(x, y)?,
+        None => (x, y),
+    };
+    match median_denoise {
+        Some(median_denoise) => median_denoise.keep(t, x, y).then_some((x, y)),
+        None => Some((x, y)),
+    }
+}
+
+/// First bytes of gzip-compressed files.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decoder input, files and in-memory buffers can seek whereas stdin ("-"), Python file-like objects,
+/// and gzip-compressed files are read forward-only.
+pub enum Source {
+    File {
+        path: std::path::PathBuf,
+        file: std::fs::File,
+    },
+    /// Files that start with the gzip magic number are decompressed transparently.
+    Gzip {
+        path: std::path::PathBuf,
+        decoder: Box<flate2::read::MultiGzDecoder<std::fs::File>>,
+    },
+    Stdin(std::io::Stdin),
+    Python(pyo3::PyObject),
+    /// Reopened sources share the buffer.
+    Memory(std::io::Cursor<std::sync::Arc<[u8]>>),
+}
+
+pub fn not_seekable_error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "this feature requires a seekable source (a file path or bytes, not \"-\" or a file-like object)",
+    )
+}
+
+pub fn gzip_not_seekable_error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "gzip-compressed files cannot seek (decompress the file to use seek and tell)",
+    )
+}
+
+impl Source {
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, std::io::Error> {
+        if path.as_ref() == std::path::Path::new("-") {
+            return Ok(Source::Stdin(std::io::stdin()));
+        }
+        let mut file = std::fs::File::open(&path)?;
+        let mut magic = [0u8; GZIP_MAGIC.len()];
+        let gzip = read_up_to(&mut file, &mut magic)? == magic.len() && magic == GZIP_MAGIC;
+        std::io::Seek::rewind(&mut file)?;
+        let path = path.as_ref().to_path_buf();
+        Ok(if gzip {
+            Source::Gzip {
+                path,
+                decoder: Box::new(flate2::read::MultiGzDecoder::new(file)),
+            }
+        } else {
+            Source::File { path, file }
+        })
+    }
+
+    pub fn memory(data: &[u8]) -> Self {
+        Source::Memory(std::io::Cursor::new(data.into()))
+    }
+
+    /// Opens the same file again, with an independent position.
+    pub fn reopen(&self) -> Result<Self, std::io::Error> {
+        match self {
+            Source::File { path, .. } | Source::Gzip { path, .. } => Source::open(path),
+            Source::Memory(cursor) => Ok(Source::Memory(std::io::Cursor::new(
+                cursor.get_ref().clone(),
+            ))),
+            Source::Stdin(_) | Source::Python(_) => Err(not_seekable_error()),
+        }
+    }
+
+    /// Returns the total number of bytes in the source, regardless of the current position.
+    pub fn length(&self) -> Result<u64, std::io::Error> {
+        match self {
+            Source::File { file, .. } => Ok(file.metadata()?.len()),
+            Source::Memory(cursor) => Ok(cursor.get_ref().len() as u64),
+            Source::Gzip { .. } => Err(gzip_not_seekable_error()),
+            Source::Stdin(_) | Source::Python(_) => Err(not_seekable_error()),
+        }
+    }
+
+    /// Whether seek is supported, forward-only sources skip bytes by reading them.
+    pub fn is_seekable(&self) -> bool {
+        matches!(self, Source::File { .. } | Source::Memory(_))
+    }
+
+    pub fn is_gzip(&self) -> bool {
+        matches!(self, Source::Gzip { .. })
+    }
+
+    /// The error to report when a feature requires seeking and this source cannot seek.
+    pub fn seek_error(&self) -> std::io::Error {
+        if self.is_gzip() {
+            gzip_not_seekable_error()
+        } else {
+            not_seekable_error()
+        }
+    }
+}
+
+impl std::io::Read for Source {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Source::File { file, .. } => file.read(buffer),
+            Source::Gzip { decoder, .. } => decoder.read(buffer),
+            Source::Stdin(stdin) => stdin.read(buffer),
+            Source::Memory(cursor) => cursor.read(buffer),
+            Source::Python(object) => pyo3::Python::with_gil(|python| {
+                use pyo3::types::PyBytesMethods;
+                let result = object
+                    .call_method1(python, "read", (buffer.len(),))
+                    .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))?;
+                let bytes = result
+                    .downcast_bound::<pyo3::types::PyBytes>(python)
+                    .map_err(|_| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "read must return bytes (the file-like object must be opened in binary mode)",
+                        )
+                    })?
+                    .as_bytes();
+                if bytes.len() > buffer.len() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "read returned {} bytes (expected at most {})",
+                            bytes.len(),
+                            buffer.len()
+                        ),
+                    ));
+                }
+                buffer[0..bytes.len()].copy_from_slice(bytes);
+                Ok(bytes.len())
+            }),
+        }
+    }
+}
+
+impl std::io::Seek for Source {
+    fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Source::File { file, .. } => file.seek(position),
+            Source::Memory(cursor) => cursor.seek(position),
+            Source::Gzip { .. } => Err(gzip_not_seekable_error()),
+            Source::Stdin(_) | Source::Python(_) => Err(not_seekable_error()),
+        }
+    }
+}
+
+/// Checks the buffer_size argument of the decoder constructors.
+///
+/// Each decoder allocates buffer_size bytes (twice for readers that parse headers through a buffer), larger buffers
+/// reduce the number of system calls, which matters on slow or networked filesystems, at the cost of memory.
+pub fn check_buffer_size(buffer_size: usize) -> Result<(), pyo3::PyErr> {
+    if buffer_size < MINIMUM_BUFFER_SIZE {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "buffer_size must be at least {MINIMUM_BUFFER_SIZE} bytes (got {buffer_size})"
+        )));
+    }
+    Ok(())
+}
+
+/// A source positioned after a Prophesee header, see read_prophesee_header.
+pub type HeaderlessSource = std::io::Chain<std::io::Cursor<Vec<u8>>, std::io::BufReader<Source>>;
+
 pub struct Header {
     pub dimensions: Option<(u16, u16)>,
     pub version: Option<String>,
-    pub length: u64,
     pub t0: u64,
+    pub models: Vec<String>,
+    /// Header lines, including the marker and without the line terminator.
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DimensionsSource {
+    Header,
+    Database,
+    Fallback,
 }
 
+impl DimensionsSource {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            DimensionsSource::Header => "header",
+            DimensionsSource::Database => "database",
+            DimensionsSource::Fallback => "fallback",
+        }
+    }
+}
+
+// more specific names must come first since models are matched by substring
+const MODEL_TO_DIMENSIONS: [(&str, (u16, u16)); 7] = [
+    ("genx320", (320, 320)),
+    ("imx636", (1280, 720)),
+    ("imx637", (640, 512)),
+    ("gen41", (1280, 720)),
+    ("gen4", (1280, 720)),
+    ("gen31", (640, 480)),
+    ("gen3", (640, 480)),
+];
+
+pub fn model_to_dimensions(model: &str) -> Option<(u16, u16)> {
+    let model = model.to_lowercase().replace(['-', '_', '.', ' '], "");
+    MODEL_TO_DIMENSIONS
+        .iter()
+        .find(|(name, _)| model.contains(name))
+        .map(|(_, dimensions)| *dimensions)
+}
+
+/// Reads header lines until the first line that does not start with marker.
+///
+/// The returned source yields the data that follows the header, without seeking.
+/// Its buffer holds buffer_size bytes.
 pub fn read_prophesee_header(
-    file: &mut std::io::BufReader<std::fs::File>,
+    source: Source,
     marker: char,
-) -> Result<Header, std::io::Error> {
-    let mut buffer = String::new();
+    buffer_size: usize,
+) -> Result<(Header, HeaderlessSource), std::io::Error> {
+    let mut file = std::io::BufReader::with_capacity(buffer_size, source);
+    let mut line = Vec::new();
     let mut width: Option<u16> = None;
     let mut height: Option<u16> = None;
     let mut version: Option<String> = None;
     let mut t0: Option<u64> = None;
-    let mut length = 0;
+    let mut models = Vec::new();
+    let mut lines = Vec::new();
     loop {
-        buffer.clear();
-        let bytes_read = match file.read_line(&mut buffer) {
-            Ok(bytes_read) => bytes_read,
-            Err(error) => match error.kind() {
-                std::io::ErrorKind::InvalidData => 0,
-                _ => return Err(error),
-            },
+        line.clear();
+        let bytes_read = file.read_until(b'\n', &mut line)?;
+        let buffer = match std::str::from_utf8(&line) {
+            Ok(buffer) if bytes_read > 0 && buffer.starts_with(marker) => buffer,
+            // the line belongs to the data, it is consumed from file and must be decoded first
+            _ => break,
         };
-        if bytes_read == 0 || !buffer.starts_with(marker) {
-            break;
-        }
-        length += bytes_read as u64;
+        lines.push(buffer.trim_end_matches(['\r', '\n']).to_owned());
         let words: Vec<&str> = buffer[1..]
             .trim()
             .split(&[' ', ';'])
@@ -162,6 +611,12 @@ pub fn read_prophesee_header(
                         }
                     }
                 }
+                "sensor_name" | "plugin_name" => {
+                    models.push(words[1].to_owned());
+                }
+                "sensor_generation" => {
+                    models.push(format!("gen{}", words[1]));
+                }
                 "evt" => {
                     version = Some(match words[1] {
                         "2" | "2.0" => "2".to_owned(),
@@ -174,20 +629,17 @@ pub fn read_prophesee_header(
             }
         }
     }
-    if let Some(width) = width {
-        if let Some(height) = height {
-            return Ok(Header {
-                dimensions: Some((width, height)),
-                version,
-                length,
-                t0: t0.unwrap_or(0),
-            });
-        }
-    }
-    Ok(Header {
-        dimensions: None,
-        version,
-        length,
-        t0: t0.unwrap_or(0),
-    })
+    Ok((
+        Header {
+            dimensions: match (width, height) {
+                (Some(width), Some(height)) => Some((width, height)),
+                _ => None,
+            },
+            version,
+            t0: t0.unwrap_or(0),
+            models,
+            lines,
+        },
+        std::io::Cursor::new(line).chain(file),
+    ))
 }
@@ -0,0 +1,657 @@
+use std::io::Write;
+
+mod x264 {
+    #![allow(
+        non_upper_case_globals,
+        non_camel_case_types,
+        non_snake_case,
+        dead_code
+    )]
+    include!(concat!(env!("OUT_DIR"), "/x264_bindings.rs"));
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Mp4(#[from] mp4::Error),
+
+    #[error("the width and height must be even and larger than zero (got {width}x{height})")]
+    Dimensions { width: u16, height: u16 },
+
+    #[error("the frame rate must be finite and larger than zero (got {0})")]
+    FrameRate(f64),
+
+    #[error("{0} failed")]
+    X264(&'static str),
+
+    #[error("unknown preset \"{0}\" (must be \"ultrafast\", \"superfast\", \"veryfast\", \"faster\", \"fast\", \"medium\", \"slow\", \"slower\", \"veryslow\", or \"placebo\")")]
+    UnknownPreset(String),
+
+    #[error("crf and bitrate_kbps cannot be both set")]
+    RateControlConflict,
+
+    #[error("the constant rate factor must be in the range [0, 51] (got {0})")]
+    ConstantRateFactor(f32),
+
+    #[error("the bitrate must be larger than zero")]
+    Bitrate,
+
+    #[error("chunks must have at least 2 frames (got {0})")]
+    ChunkFrames(usize),
+
+    #[error("expected a frame with shape ({height}, {width}), ({height}, {width}, 3), or ({height}, {width}, 4) (got {shape:?})")]
+    Shape {
+        width: u16,
+        height: u16,
+        shape: Vec<usize>,
+    },
+}
+
+const TRACK_ID: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Ultrafast,
+    Superfast,
+    Veryfast,
+    Faster,
+    Fast,
+    Medium,
+    Slow,
+    Slower,
+    Veryslow,
+    Placebo,
+}
+
+impl Preset {
+    pub fn from_string(string: &str) -> Result<Self, Error> {
+        match string {
+            "ultrafast" => Ok(Self::Ultrafast),
+            "superfast" => Ok(Self::Superfast),
+            "veryfast" => Ok(Self::Veryfast),
+            "faster" => Ok(Self::Faster),
+            "fast" => Ok(Self::Fast),
+            "medium" => Ok(Self::Medium),
+            "slow" => Ok(Self::Slow),
+            "slower" => Ok(Self::Slower),
+            "veryslow" => Ok(Self::Veryslow),
+            "placebo" => Ok(Self::Placebo),
+            string => Err(Error::UnknownPreset(string.to_owned())),
+        }
+    }
+
+    fn to_c_str(self) -> &'static std::ffi::CStr {
+        match self {
+            Self::Ultrafast => c"ultrafast",
+            Self::Superfast => c"superfast",
+            Self::Veryfast => c"veryfast",
+            Self::Faster => c"faster",
+            Self::Fast => c"fast",
+            Self::Medium => c"medium",
+            Self::Slow => c"slow",
+            Self::Slower => c"slower",
+            Self::Veryslow => c"veryslow",
+            Self::Placebo => c"placebo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateControl {
+    ConstantRateFactor(f32),
+    Bitrate { kbps: u32 },
+}
+
+impl RateControl {
+    /// Defaults to a constant rate factor of 23 (x264's default) if neither crf nor bitrate_kbps is set.
+    pub fn new(crf: Option<f32>, bitrate_kbps: Option<u32>) -> Result<Self, Error> {
+        match (crf, bitrate_kbps) {
+            (Some(_), Some(_)) => Err(Error::RateControlConflict),
+            (Some(crf), None) => {
+                if (0.0..=51.0).contains(&crf) {
+                    Ok(Self::ConstantRateFactor(crf))
+                } else {
+                    Err(Error::ConstantRateFactor(crf))
+                }
+            }
+            (None, Some(0)) => Err(Error::Bitrate),
+            (None, Some(kbps)) => Ok(Self::Bitrate { kbps }),
+            (None, None) => Ok(Self::ConstantRateFactor(23.0)),
+        }
+    }
+}
+
+/// x264 parameters, copied by the chunk workers.
+///
+/// The string fields are null or point to static strings (see x264_param_default).
+#[derive(Clone, Copy)]
+struct Parameters(x264::x264_param_t);
+
+unsafe impl Send for Parameters {}
+
+impl Parameters {
+    fn new(
+        dimensions: (u16, u16),
+        frame_rate_numerator: u32,
+        sample_duration: u32,
+        rate_control: RateControl,
+        preset: Preset,
+        chunk_frames: Option<usize>,
+    ) -> Result<Self, Error> {
+        unsafe {
+            let mut parameters = std::mem::MaybeUninit::<x264::x264_param_t>::uninit();
+            if x264::x264_param_default_preset(
+                parameters.as_mut_ptr(),
+                preset.to_c_str().as_ptr(),
+                std::ptr::null(),
+            ) < 0
+            {
+                return Err(Error::X264("x264_param_default_preset"));
+            }
+            let mut parameters = parameters.assume_init();
+            parameters.i_width = dimensions.0 as std::os::raw::c_int;
+            parameters.i_height = dimensions.1 as std::os::raw::c_int;
+            parameters.i_csp = x264::X264_CSP_I420 as std::os::raw::c_int;
+            parameters.i_log_level = x264::X264_LOG_ERROR as std::os::raw::c_int;
+            parameters.i_fps_num = frame_rate_numerator;
+            parameters.i_fps_den = sample_duration;
+            match rate_control {
+                RateControl::ConstantRateFactor(crf) => {
+                    parameters.rc.i_rc_method = x264::X264_RC_CRF as std::os::raw::c_int;
+                    parameters.rc.f_rf_constant = crf;
+                }
+                RateControl::Bitrate { kbps } => {
+                    parameters.rc.i_rc_method = x264::X264_RC_ABR as std::os::raw::c_int;
+                    parameters.rc.i_bitrate =
+                        kbps.min(std::os::raw::c_int::MAX as u32) as std::os::raw::c_int;
+                }
+            }
+            parameters.b_vfr_input = 0;
+            // MP4 samples carry length-prefixed NAL units, SPS and PPS go in the avcC box
+            parameters.b_annexb = 0;
+            parameters.b_repeat_headers = 0;
+            // without B-frames, the decoding order is the presentation order (no composition offsets)
+            parameters.i_bframe = 0;
+            if let Some(chunk_frames) = chunk_frames {
+                // each chunk is encoded by a single-threaded x264 instance and starts with an IDR frame,
+                // scene cuts are disabled so that a chunk never ends with an IDR frame
+                // (consecutive IDR frames from different instances would share the same idr_pic_id)
+                parameters.i_threads = 1;
+                parameters.i_keyint_max =
+                    chunk_frames.min(std::os::raw::c_int::MAX as usize) as std::os::raw::c_int;
+                parameters.i_scenecut_threshold = 0;
+            }
+            if x264::x264_param_apply_profile(&mut parameters, c"high".as_ptr()) < 0 {
+                return Err(Error::X264("x264_param_apply_profile"));
+            }
+            Ok(Parameters(parameters))
+        }
+    }
+}
+
+/// Encoded frame (length-prefixed NAL units).
+struct Sample {
+    bytes: Vec<u8>,
+    is_sync: bool,
+}
+
+// owns the x264 encoder and the input picture, both are released on drop
+struct Handle {
+    encoder: *mut x264::x264_t,
+    picture: x264::x264_picture_t,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe {
+            x264::x264_encoder_close(self.encoder);
+            x264::x264_picture_clean(&mut self.picture);
+        }
+    }
+}
+
+unsafe impl Send for Handle {}
+
+impl Handle {
+    fn new(parameters: &Parameters) -> Result<Self, Error> {
+        // x264_encoder_open copies the parameters
+        let mut parameters = parameters.0;
+        unsafe {
+            let mut picture = std::mem::MaybeUninit::<x264::x264_picture_t>::uninit();
+            if x264::x264_picture_alloc(
+                picture.as_mut_ptr(),
+                x264::X264_CSP_I420 as std::os::raw::c_int,
+                parameters.i_width,
+                parameters.i_height,
+            ) < 0
+            {
+                return Err(Error::X264("x264_picture_alloc"));
+            }
+            let mut picture = picture.assume_init();
+            let encoder = x264::x264_encoder_open(&mut parameters);
+            if encoder.is_null() {
+                x264::x264_picture_clean(&mut picture);
+                return Err(Error::X264("x264_encoder_open"));
+            }
+            Ok(Handle { encoder, picture })
+        }
+    }
+
+    /// Returns the sequence parameter set and the picture parameter set.
+    fn headers(&self) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let mut seq_param_set = Vec::new();
+        let mut pic_param_set = Vec::new();
+        unsafe {
+            let mut nals: *mut x264::x264_nal_t = std::ptr::null_mut();
+            let mut nals_count: std::os::raw::c_int = 0;
+            if x264::x264_encoder_headers(self.encoder, &mut nals, &mut nals_count) < 0 {
+                return Err(Error::X264("x264_encoder_headers"));
+            }
+            for nal in std::slice::from_raw_parts(nals, nals_count as usize) {
+                // skip the 4-bytes length prefix
+                let payload =
+                    &std::slice::from_raw_parts(nal.p_payload, nal.i_payload as usize)[4..];
+                match nal.i_type as x264::nal_unit_type_e {
+                    x264::nal_unit_type_e_NAL_SPS => seq_param_set = payload.to_vec(),
+                    x264::nal_unit_type_e_NAL_PPS => pic_param_set = payload.to_vec(),
+                    _ => (),
+                }
+            }
+        }
+        Ok((seq_param_set, pic_param_set))
+    }
+
+    /// Copies an I420 frame (see to_i420) to the input picture.
+    fn load(&mut self, frame: &[u8], dimensions: (u16, u16), pts: i64) {
+        let width = dimensions.0 as usize;
+        let height = dimensions.1 as usize;
+        let image = &self.picture.img;
+        let mut offset = 0;
+        for (plane, (plane_width, plane_height)) in [
+            (width, height),
+            (width / 2, height / 2),
+            (width / 2, height / 2),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            for y in 0..plane_height {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(
+                        frame[offset..offset + plane_width].as_ptr(),
+                        image.plane[plane].add(y * image.i_stride[plane] as usize),
+                        plane_width,
+                    );
+                }
+                offset += plane_width;
+            }
+        }
+        self.picture.i_pts = pts;
+    }
+
+    /// Encodes the input picture, or one of the frames buffered by x264 if flush is true.
+    fn encode(&mut self, flush: bool) -> Result<Option<Sample>, Error> {
+        let picture: *mut x264::x264_picture_t = if flush {
+            std::ptr::null_mut()
+        } else {
+            &mut self.picture
+        };
+        let mut nals: *mut x264::x264_nal_t = std::ptr::null_mut();
+        let mut nals_count: std::os::raw::c_int = 0;
+        let mut output_picture = std::mem::MaybeUninit::<x264::x264_picture_t>::uninit();
+        let size = unsafe {
+            x264::x264_encoder_encode(
+                self.encoder,
+                &mut nals,
+                &mut nals_count,
+                picture,
+                output_picture.as_mut_ptr(),
+            )
+        };
+        if size < 0 {
+            return Err(Error::X264("x264_encoder_encode"));
+        }
+        if size == 0 {
+            return Ok(None);
+        }
+        // x264 guarantees that the payloads of a frame's NAL units are contiguous
+        unsafe {
+            Ok(Some(Sample {
+                bytes: std::slice::from_raw_parts((*nals).p_payload, size as usize).to_vec(),
+                is_sync: output_picture.assume_init().b_keyframe != 0,
+            }))
+        }
+    }
+
+    fn has_delayed_frames(&self) -> bool {
+        unsafe { x264::x264_encoder_delayed_frames(self.encoder) > 0 }
+    }
+}
+
+/// Encodes the frames of a chunk with a new x264 instance, the first frame is an IDR frame.
+fn encode_chunk(
+    parameters: Parameters,
+    dimensions: (u16, u16),
+    frames: Vec<Vec<u8>>,
+) -> Result<Vec<Sample>, Error> {
+    let mut handle = Handle::new(&parameters)?;
+    let mut samples = Vec::with_capacity(frames.len());
+    for (index, frame) in frames.iter().enumerate() {
+        handle.load(frame, dimensions, index as i64);
+        samples.extend(handle.encode(false)?);
+    }
+    while handle.has_delayed_frames() {
+        samples.extend(handle.encode(true)?);
+    }
+    Ok(samples)
+}
+
+/// Converts a grayscale, RGB, or RGBA frame to I420 (the luma plane, then the U and V planes at half resolution).
+///
+/// The conversion uses BT.601 limited range, chroma is the average of each 2x2 block.
+fn to_i420(dimensions: (u16, u16), channels: usize, pixels: &[u8], frame: &mut Vec<u8>) {
+    let width = dimensions.0 as usize;
+    let height = dimensions.1 as usize;
+    let rgb = |x: usize, y: usize| -> (i32, i32, i32) {
+        let index = (x + y * width) * channels;
+        if channels == 1 {
+            let value = pixels[index] as i32;
+            (value, value, value)
+        } else {
+            (
+                pixels[index] as i32,
+                pixels[index + 1] as i32,
+                pixels[index + 2] as i32,
+            )
+        }
+    };
+    frame.clear();
+    frame.reserve(width * height + width * height / 2);
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = rgb(x, y);
+            frame.push((((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8);
+        }
+    }
+    let luma_length = frame.len();
+    let chroma_length = (width / 2) * (height / 2);
+    frame.resize(luma_length + chroma_length * 2, 0);
+    let (u_plane, v_plane) = frame[luma_length..].split_at_mut(chroma_length);
+    for y in 0..height / 2 {
+        for x in 0..width / 2 {
+            let mut r = 0;
+            let mut g = 0;
+            let mut b = 0;
+            for (offset_x, offset_y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let (pixel_r, pixel_g, pixel_b) = rgb(x * 2 + offset_x, y * 2 + offset_y);
+                r += pixel_r;
+                g += pixel_g;
+                b += pixel_b;
+            }
+            r = (r + 2) / 4;
+            g = (g + 2) / 4;
+            b = (b + 2) / 4;
+            u_plane[x + y * (width / 2)] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+            v_plane[x + y * (width / 2)] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+        }
+    }
+}
+
+/// Chunks are encoded in parallel by worker threads and written in order.
+struct Chunks {
+    parameters: Parameters,
+    chunk_frames: usize,
+    workers: usize,
+    /// Frames of the chunk being filled.
+    pending: Vec<Vec<u8>>,
+    /// Chunks being encoded, in presentation order.
+    running: std::collections::VecDeque<std::thread::JoinHandle<Result<Vec<Sample>, Error>>>,
+}
+
+enum Mode {
+    /// Frames are encoded as they are written by a single x264 instance.
+    Sequential {
+        handle: Handle,
+        frame: Vec<u8>,
+    },
+    Chunked(Chunks),
+}
+
+pub struct Encoder {
+    mode: Mode,
+    writer: mp4::Mp4Writer<std::io::BufWriter<std::fs::File>>,
+    dimensions: (u16, u16),
+    sample_duration: u32,
+    frame_index: i64,
+    sample_index: u64,
+}
+
+impl Encoder {
+    /// chunk_frames enables parallel encoding, frames are grouped in chunks of chunk_frames frames
+    /// (the last chunk may be shorter) encoded by up to workers threads (0 uses the available parallelism).
+    /// Each chunk starts with a keyframe, hence shorter chunks produce larger files.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<P: AsRef<std::path::Path>>(
+        path: P,
+        dimensions: (u16, u16),
+        frame_rate: f64,
+        rate_control: RateControl,
+        preset: Preset,
+        chunk_frames: Option<usize>,
+        workers: usize,
+    ) -> Result<Self, Error> {
+        if dimensions.0 == 0 || dimensions.1 == 0 || dimensions.0 % 2 == 1 || dimensions.1 % 2 == 1
+        {
+            return Err(Error::Dimensions {
+                width: dimensions.0,
+                height: dimensions.1,
+            });
+        }
+        if !frame_rate.is_finite() || frame_rate <= 0.0 {
+            return Err(Error::FrameRate(frame_rate));
+        }
+        if let Some(chunk_frames) = chunk_frames {
+            if chunk_frames < 2 {
+                return Err(Error::ChunkFrames(chunk_frames));
+            }
+        }
+        // the frame rate is stored as a fraction with denominator 1000 (29.97 becomes 29970 / 1000)
+        // the MP4 track timescale is the numerator so that each sample lasts exactly 1000 ticks
+        let sample_duration = 1000u32;
+        let frame_rate_numerator = (frame_rate * sample_duration as f64).round() as u32;
+        if frame_rate_numerator == 0 {
+            return Err(Error::FrameRate(frame_rate));
+        }
+        let parameters = Parameters::new(
+            dimensions,
+            frame_rate_numerator,
+            sample_duration,
+            rate_control,
+            preset,
+            chunk_frames,
+        )?;
+        // chunk workers use the same parameters, hence the same headers
+        let handle = Handle::new(&parameters)?;
+        let (seq_param_set, pic_param_set) = handle.headers()?;
+        let mut writer = mp4::Mp4Writer::write_start(
+            std::io::BufWriter::new(std::fs::File::create(path)?),
+            &mp4::Mp4Config {
+                major_brand: "isom".parse().expect("isom is a valid FourCC"),
+                minor_version: 512,
+                compatible_brands: ["isom", "iso2", "avc1", "mp41"]
+                    .iter()
+                    .map(|brand| brand.parse().expect("brands are valid FourCCs"))
+                    .collect(),
+                timescale: 1000,
+            },
+        )?;
+        writer.add_track(&mp4::TrackConfig {
+            track_type: mp4::TrackType::Video,
+            timescale: frame_rate_numerator,
+            language: "und".to_owned(),
+            media_conf: mp4::MediaConfig::AvcConfig(mp4::AvcConfig {
+                width: dimensions.0,
+                height: dimensions.1,
+                seq_param_set,
+                pic_param_set,
+            }),
+        })?;
+        let mode = match chunk_frames {
+            Some(chunk_frames) => Mode::Chunked(Chunks {
+                parameters,
+                chunk_frames,
+                workers: if workers == 0 {
+                    std::thread::available_parallelism().map_or(1, |workers| workers.get())
+                } else {
+                    workers
+                },
+                pending: Vec::with_capacity(chunk_frames),
+                running: std::collections::VecDeque::new(),
+            }),
+            None => Mode::Sequential {
+                handle,
+                frame: Vec::new(),
+            },
+        };
+        Ok(Encoder {
+            mode,
+            writer,
+            dimensions,
+            sample_duration,
+            frame_index: 0,
+            sample_index: 0,
+        })
+    }
+
+    /// Encodes a grayscale (height, width), RGB (height, width, 3), or RGBA (height, width, 4) frame.
+    ///
+    /// pixels must be in row-major order. The alpha channel is ignored.
+    pub fn write_frame(&mut self, shape: &[usize], pixels: &[u8]) -> Result<(), Error> {
+        let width = self.dimensions.0 as usize;
+        let height = self.dimensions.1 as usize;
+        let channels = match shape {
+            [frame_height, frame_width] if *frame_height == height && *frame_width == width => 1,
+            [frame_height, frame_width, channels]
+                if *frame_height == height
+                    && *frame_width == width
+                    && (*channels == 3 || *channels == 4) =>
+            {
+                *channels
+            }
+            _ => {
+                return Err(Error::Shape {
+                    width: self.dimensions.0,
+                    height: self.dimensions.1,
+                    shape: shape.to_vec(),
+                })
+            }
+        };
+        assert_eq!(pixels.len(), width * height * channels);
+        let sample = match self.mode {
+            Mode::Sequential {
+                ref mut handle,
+                ref mut frame,
+            } => {
+                to_i420(self.dimensions, channels, pixels, frame);
+                handle.load(frame, self.dimensions, self.frame_index);
+                handle.encode(false)?
+            }
+            Mode::Chunked(ref mut chunks) => {
+                let mut frame = Vec::new();
+                to_i420(self.dimensions, channels, pixels, &mut frame);
+                chunks.pending.push(frame);
+                None
+            }
+        };
+        self.frame_index += 1;
+        if let Some(sample) = sample {
+            self.write_sample(sample)?;
+        }
+        if let Mode::Chunked(ref chunks) = self.mode {
+            if chunks.pending.len() == chunks.chunk_frames {
+                self.start_chunk()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes the frames buffered by x264 (or waits for the chunk workers) and writes the MP4 index (moov box).
+    pub fn finish(mut self) -> Result<(), Error> {
+        loop {
+            let sample = match self.mode {
+                Mode::Sequential { ref mut handle, .. } if handle.has_delayed_frames() => {
+                    handle.encode(true)?
+                }
+                _ => break,
+            };
+            if let Some(sample) = sample {
+                self.write_sample(sample)?;
+            }
+        }
+        if let Mode::Chunked(ref chunks) = self.mode {
+            if !chunks.pending.is_empty() {
+                self.start_chunk()?;
+            }
+        }
+        while self.join_chunk()? {}
+        self.writer.write_end()?;
+        self.writer.into_writer().flush()?;
+        Ok(())
+    }
+
+    /// Sends the pending frames to a new worker, after writing the oldest chunk if all the workers are busy.
+    fn start_chunk(&mut self) -> Result<(), Error> {
+        if let Mode::Chunked(ref chunks) = self.mode {
+            if chunks.running.len() >= chunks.workers {
+                self.join_chunk()?;
+            }
+        }
+        if let Mode::Chunked(ref mut chunks) = self.mode {
+            let parameters = chunks.parameters;
+            let dimensions = self.dimensions;
+            let frames =
+                std::mem::replace(&mut chunks.pending, Vec::with_capacity(chunks.chunk_frames));
+            chunks.running.push_back(std::thread::spawn(move || {
+                encode_chunk(parameters, dimensions, frames)
+            }));
+        }
+        Ok(())
+    }
+
+    /// Waits for the oldest chunk and writes its samples, returns false if no chunk is running.
+    fn join_chunk(&mut self) -> Result<bool, Error> {
+        let handle = match self.mode {
+            Mode::Chunked(ref mut chunks) => match chunks.running.pop_front() {
+                Some(handle) => handle,
+                None => return Ok(false),
+            },
+            Mode::Sequential { .. } => return Ok(false),
+        };
+        let samples = handle
+            .join()
+            .unwrap_or_else(|payload| std::panic::resume_unwind(payload))?;
+        for sample in samples {
+            self.write_sample(sample)?;
+        }
+        Ok(true)
+    }
+
+    /// Samples are written in presentation order, their timestamps increase by one frame duration.
+    fn write_sample(&mut self, sample: Sample) -> Result<(), Error> {
+        self.writer.write_sample(
+            TRACK_ID,
+            &mp4::Mp4Sample {
+                start_time: self.sample_index * self.sample_duration as u64,
+                duration: self.sample_duration,
+                rendering_offset: 0,
+                is_sync: sample.is_sync,
+                bytes: mp4::Bytes::from(sample.bytes),
+            },
+        )?;
+        self.sample_index += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use pyo3::prelude::*;
+
+use crate::types;
+
+mod encoder;
+
+impl From<encoder::Error> for PyErr {
+    fn from(error: encoder::Error) -> Self {
+        match error {
+            encoder::Error::UnknownPreset(_)
+            | encoder::Error::RateControlConflict
+            | encoder::Error::ConstantRateFactor(_)
+            | encoder::Error::Bitrate
+            | encoder::Error::ChunkFrames(_) => {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(error.to_string())
+            }
+            error => PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(error.to_string()),
+        }
+    }
+}
+
+#[pyclass]
+pub struct VideoEncoder {
+    inner: Option<encoder::Encoder>,
+}
+
+#[pymethods]
+impl VideoEncoder {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (path, dimensions, frame_rate, crf = None, bitrate_kbps = None, preset = "medium", chunk_frames = None, workers = 0))]
+    fn new(
+        path: &pyo3::Bound<'_, pyo3::types::PyAny>,
+        dimensions: (u16, u16),
+        frame_rate: f64,
+        crf: Option<f32>,
+        bitrate_kbps: Option<u32>,
+        preset: &str,
+        chunk_frames: Option<usize>,
+        workers: usize,
+    ) -> Result<Self, PyErr> {
+        Python::with_gil(|python| -> Result<Self, PyErr> {
+            Ok(VideoEncoder {
+                inner: Some(encoder::Encoder::new(
+                    types::python_path_to_string(python, path)?,
+                    dimensions,
+                    frame_rate,
+                    encoder::RateControl::new(crf, bitrate_kbps)?,
+                    encoder::Preset::from_string(preset)?,
+                    chunk_frames,
+                    workers,
+                )?),
+            })
+        })
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exception_type: Option<PyObject>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        match self.inner.take() {
+            Some(encoder) => {
+                encoder.finish()?;
+                Ok(false)
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "multiple calls to __exit__",
+            )),
+        }
+    }
+
+    fn write_frame(&mut self, frame: numpy::PyReadonlyArrayDyn<'_, u8>) -> PyResult<()> {
+        match self.inner.as_mut() {
+            Some(encoder) => {
+                let frame = frame.as_array();
+                let frame = frame.as_standard_layout();
+                encoder.write_frame(
+                    frame.shape(),
+                    frame
+                        .as_slice()
+                        .expect("standard layout arrays are contiguous"),
+                )?;
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "write_frame called after __exit__",
+            )),
+        }
+    }
+
+    fn close(&mut self) -> PyResult<()> {
+        match self.inner.take() {
+            Some(encoder) => {
+                encoder.finish()?;
+                Ok(())
+            }
+            None => Err(pyo3::exceptions::PyException::new_err(
+                "close called after __exit__",
+            )),
+        }
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.inner.is_none()
+    }
+}
@@ -0,0 +1,1009 @@
+import pathlib
+import struct
+import warnings
+
+import faery
+import numpy
+
+import assets
+
+data_generated = pathlib.Path(__file__).resolve().parent / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+
+def packet_to_bytes(packet) -> bytes:
+    if isinstance(packet, faery.aedat.Frame):
+        return packet.pixels.tobytes()
+    return packet.tobytes()
+
+
+for file in assets.files:
+    if file.format != "aedat":
+        continue
+
+    # resume from a saved token
+    print(f"faery.aedat.Decoder.resume_from ({file.path.name})")
+    resume_index = 10
+    token = None
+    expected = []
+    with faery.aedat.Decoder(file.path) as decoder:
+        index = 0
+        while True:
+            if index == resume_index:
+                token = decoder.state_token()
+            try:
+                track, packet = next(decoder)
+            except StopIteration:
+                break
+            if index >= resume_index:
+                expected.append((track.id, packet_to_bytes(packet)))
+            index += 1
+    assert token is not None
+    with faery.aedat.Decoder(file.path) as decoder:
+        try:
+            decoder.resume_from(token + 1)
+            raise AssertionError("resume_from accepted a token within a packet")
+        except RuntimeError:
+            pass
+        decoder.resume_from(token)
+        resumed = [(track.id, packet_to_bytes(packet)) for track, packet in decoder]
+    assert resumed == expected
+
+    # memory-mapped files decode to the same packets as buffered files
+    print(f"faery.aedat.Decoder mmap ({file.path.name})")
+    with faery.aedat.Decoder(file.path, mmap=True) as decoder:
+        assert decoder.mmap
+        decoder.resume_from(token)
+        mapped = [(track.id, packet_to_bytes(packet)) for track, packet in decoder]
+        decoder.resume_from(token)
+        assert mapped == [
+            (track.id, packet_to_bytes(packet)) for track, packet in decoder
+        ]
+    assert mapped == expected
+    with faery.aedat.Decoder(file.path.read_bytes(), mmap=True) as decoder:
+        assert not decoder.mmap
+
+    # enumerate yields the packet's position in the file, resume_from restores it
+    print(f"faery.aedat.Decoder.enumerate ({file.path.name})")
+    with faery.aedat.Decoder(file.path, skip_empty=False) as decoder:
+        indices = [index for index, _ in decoder.enumerate()]
+        assert indices == list(range(len(decoder))), f"{indices=}"
+    with faery.aedat.Decoder(file.path) as decoder:
+        indices = [index for index, _ in decoder.enumerate()]
+        decoder.resume_from(token)
+        resumed_indices = [index for index, _ in decoder.enumerate()]
+    assert resumed_indices == indices[resume_index:], f"{resumed_indices=}"
+
+# empty packets are skipped by default and emitted as empty arrays on demand
+print("faery.aedat.Decoder skip_empty")
+path = data_generated / "empty_packets.aedat4"
+events = numpy.zeros(3, dtype=faery.DVS_DTYPE)
+events["t"] = [0, 1, 2]
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[faery.aedat.Track(0, "events", (16, 16))],
+    compression=None,
+) as encoder:
+    encoder.write(0, events[0:1])
+    encoder.write(0, numpy.zeros(0, dtype=faery.DVS_DTYPE))
+    encoder.write(0, events[1:3])
+with faery.aedat.Decoder(path) as decoder:
+    assert [len(packet) for _, packet in decoder] == [1, 2]
+with faery.aedat.Decoder(path, skip_empty=False) as decoder:
+    assert [len(packet) for _, packet in decoder] == [1, 0, 2]
+with faery.aedat.Decoder(path) as decoder:
+    assert [index for index, _ in decoder.enumerate()] == [0, 2]
+
+# APS frames are uniformly stale
+for file in assets.files:
+    if file.format != "aedat":
+        continue
+    print(f"faery.aedat.Frame.staleness ({file.path.name})")
+    with faery.aedat.Decoder(file.path) as decoder:
+        for track, packet in decoder:
+            if track.data_type == "frame":
+                staleness = packet.staleness(packet.t + 1000)
+                assert staleness.shape == packet.pixels.shape[0:2]
+                assert numpy.all(staleness == 1000)
+                assert numpy.all(packet.staleness(0) == 0)
+                break
+
+# frame_metadata matches the attributes of the decoded frames
+print("faery.aedat.Decoder.frame_metadata")
+for file in assets.files:
+    if file.format != "aedat":
+        continue
+    with faery.aedat.Decoder(file.path) as decoder:
+        metadata = decoder.frame_metadata()
+        frames = [packet for track, packet in decoder if track.data_type == "frame"]
+    assert len(metadata) == len(frames)
+    for record, frame in zip(metadata, frames):
+        for name in (
+            "t",
+            "begin_t",
+            "end_t",
+            "exposure_begin_t",
+            "exposure_end_t",
+            "offset_x",
+            "offset_y",
+        ):
+            assert record[name] == getattr(frame, name), name
+        assert (record["height"], record["width"]) == frame.pixels.shape[0:2]
+
+# as_pil yields frames as PIL images with the frame's mode (PIL is optional)
+print("faery.aedat.Decoder as_pil")
+try:
+    import PIL.Image
+except ImportError:
+    PIL = None
+for file in assets.files:
+    if file.format != "aedat":
+        continue
+    if PIL is None:
+        try:
+            faery.aedat.Decoder(file.path, as_pil=True)
+            raise AssertionError("as_pil succeeded without PIL")
+        except ImportError:
+            pass
+        break
+    with faery.aedat.Decoder(file.path) as decoder:
+        frames = [packet for track, packet in decoder if track.data_type == "frame"]
+    with faery.aedat.Decoder(file.path, as_pil=True) as decoder:
+        assert decoder.as_pil
+        images = [packet for track, packet in decoder if track.data_type == "frame"]
+    assert len(images) == len(frames)
+    for frame, image in zip(frames, images):
+        assert isinstance(image, PIL.Image.Image)
+        assert image.mode == frame.format
+        assert image.info["t"] == frame.t
+        assert numpy.array_equal(numpy.asarray(image), frame.pixels)
+        assert numpy.array_equal(numpy.asarray(frame.to_pil()), frame.pixels)
+
+# composite_frames places each frame at its offset in a sensor-sized image
+print("faery.aedat.Decoder composite_frames")
+for file in assets.files:
+    if file.format != "aedat":
+        continue
+    with faery.aedat.Decoder(file.path) as decoder:
+        frames = [packet for track, packet in decoder if track.data_type == "frame"]
+    with faery.aedat.Decoder(
+        file.path, composite_frames=True, frame_background=7
+    ) as decoder:
+        assert decoder.composite_frames and decoder.frame_background == 7
+        composites = [
+            (track, packet) for track, packet in decoder if track.data_type == "frame"
+        ]
+    assert len(composites) == len(frames)
+    for frame, (track, composite) in zip(frames, composites):
+        assert composite.pixels.shape[0:2] == (track.dimensions[1], track.dimensions[0])
+        assert composite.pixels.shape[2:] == frame.pixels.shape[2:]
+        height, width = frame.pixels.shape[0:2]
+        inside = numpy.zeros(composite.pixels.shape[0:2], dtype=bool)
+        inside[
+            frame.offset_y : frame.offset_y + height,
+            frame.offset_x : frame.offset_x + width,
+        ] = True
+        assert numpy.array_equal(
+            composite.pixels[inside],
+            frame.pixels.reshape(height * width, *frame.pixels.shape[2:]),
+        )
+        assert numpy.all(composite.pixels[~inside] == 7)
+
+# round trip with several event tracks
+print("faery.aedat.Encoder round trip")
+path = data_generated / "round_trip.aedat4"
+id_to_events = {}
+for id, (width, height) in ((0, (16, 16)), (3, (640, 480))):
+    events = numpy.zeros(1000, dtype=faery.DVS_DTYPE)
+    events["t"] = numpy.arange(1000) * (id + 1)
+    events["x"] = numpy.arange(1000) % width
+    events["y"] = numpy.arange(1000) % height
+    events["on"] = numpy.arange(1000) % 3 == 0
+    id_to_events[id] = events
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[
+        faery.aedat.Track(0, "events", (16, 16)),
+        faery.aedat.Track(3, "events", (640, 480)),
+    ],
+    compression=faery.aedat.ZSTD_DEFAULT,
+) as encoder:
+    for offset in range(0, 1000, 100):
+        for id, events in id_to_events.items():
+            encoder.write(id, events[offset : offset + 100])
+    for id, events, message in (
+        (0, id_to_events[3][999:], "x overflow"),
+        (3, id_to_events[3][0:1], "non-monotonic timestamps"),
+    ):
+        try:
+            encoder.write(id, events)
+            raise AssertionError(f"write accepted {message}")
+        except RuntimeError:
+            pass
+id_to_decoded_events = {0: [], 3: []}
+with faery.aedat.Decoder(path) as decoder:
+    assert [(track.id, track.dimensions) for track in decoder.tracks()] == [
+        (0, (16, 16)),
+        (3, (640, 480)),
+    ]
+    for track, packet in decoder:
+        id_to_decoded_events[track.id].append(packet)
+for id, events in id_to_events.items():
+    assert numpy.array_equal(numpy.concatenate(id_to_decoded_events[id]), events)
+try:
+    faery.aedat.Encoder(
+        path=path,
+        description_or_tracks=[faery.aedat.Track(0, "events", (0, 16))],
+        compression=None,
+    )
+    raise AssertionError("Encoder accepted zero dimensions")
+except RuntimeError:
+    pass
+
+# the context manager closes the file
+print("faery.aedat.Decoder closed")
+with faery.aedat.Decoder(path) as decoder:
+    assert not decoder.closed
+assert decoder.closed
+try:
+    next(decoder)
+    raise AssertionError("__next__ succeeded after __exit__")
+except RuntimeError as error:
+    assert str(error) == "__next__ called after __exit__"
+
+# seek skips, for each track, the packets that begin before the given timestamp
+print("faery.aedat.Decoder.seek")
+with faery.aedat.Decoder(path) as decoder:
+    decoder.seek(500)
+    id_to_first_ts = {0: [], 3: []}
+    for track, packet in decoder:
+        id_to_first_ts[track.id].append(int(packet["t"][0]))
+    assert id_to_first_ts == {
+        0: [500, 600, 700, 800, 900],
+        3: [800, 1200, 1600, 2000, 2400, 2800, 3200, 3600],
+    }, f"{id_to_first_ts=}"
+    decoder.seek(4000)
+    assert next(decoder, None) is None
+    decoder.seek_to_start()
+    for id, events in id_to_events.items():
+        id_to_decoded_events[id] = []
+    for track, packet in decoder:
+        id_to_decoded_events[track.id].append(packet)
+for id, events in id_to_events.items():
+    assert numpy.array_equal(numpy.concatenate(id_to_decoded_events[id]), events)
+
+# each track unwraps its own 32-bit timestamps
+print("faery.aedat.Decoder unwrap_timestamps")
+path = data_generated / "wrapped_timestamps.aedat4"
+id_to_events = {}
+for id, wrap_index in ((0, 300), (3, 700)):
+    events = numpy.zeros(1000, dtype=faery.DVS_DTYPE)
+    events["t"] = numpy.arange(1000) * 10 + (1 << 32) - wrap_index * 10
+    id_to_events[id] = events
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[
+        faery.aedat.Track(0, "events", (16, 16)),
+        faery.aedat.Track(3, "events", (16, 16)),
+    ],
+    compression=None,
+) as encoder:
+    for offset in range(0, 1000, 100):
+        for id, events in id_to_events.items():
+            encoder.write(id, events[offset : offset + 100])
+# the encoder rejects non-monotonic timestamps, the wraps are patched in the uncompressed file
+data = path.read_bytes()
+for events in id_to_events.values():
+    for t in events["t"]:
+        if t >= 1 << 32:
+            data = data.replace(
+                int(t).to_bytes(8, "little"), int(t - (1 << 32)).to_bytes(8, "little")
+            )
+path.write_bytes(data)
+for unwrap_timestamps in (False, True):
+    id_to_decoded_events = {0: [], 3: []}
+    with faery.aedat.Decoder(path, unwrap_timestamps=unwrap_timestamps) as decoder:
+        assert decoder.unwrap_timestamps == unwrap_timestamps
+        for track, packet in decoder:
+            id_to_decoded_events[track.id].append(packet)
+    for id, events in id_to_events.items():
+        decoded_ts = numpy.concatenate(id_to_decoded_events[id])["t"]
+        if unwrap_timestamps:
+            assert numpy.array_equal(decoded_ts, events["t"]), f"{id=}"
+        else:
+            # without unwrapping, timestamps are clamped to the last value before the wrap
+            assert numpy.all(decoded_ts < 1 << 32), f"{id=}"
+            assert numpy.all(numpy.diff(decoded_ts.astype(numpy.int64)) >= 0), f"{id=}"
+
+# seek compares the unwrapped timestamps, relative to the origin if zero_origin is true
+print("faery.aedat.Decoder.seek unwrap_timestamps")
+origin = int(id_to_events[0]["t"][0])
+for zero_origin in (False, True):
+    with faery.aedat.Decoder(
+        path, unwrap_timestamps=True, zero_origin=zero_origin
+    ) as decoder:
+        decoder.seek((1 << 32) + 1000 - (origin if zero_origin else 0))
+        id_to_decoded_events = {0: [], 3: []}
+        for track, packet in decoder:
+            id_to_decoded_events[track.id].append(packet)
+        if zero_origin:
+            assert decoder.origin_t() == origin
+    for id, index in ((0, 400), (3, 800)):
+        decoded_ts = numpy.concatenate(id_to_decoded_events[id])["t"]
+        expected_ts = id_to_events[id]["t"][index:] - (origin if zero_origin else 0)
+        assert numpy.array_equal(decoded_ts, expected_ts), f"{id=}, {zero_origin=}"
+
+# the token carries the unwrapping state, resuming after the wraps yields the same timestamps
+print("faery.aedat.Decoder.resume_from unwrap_timestamps")
+with faery.aedat.Decoder(path, unwrap_timestamps=True) as decoder:
+    for _ in range(16):
+        next(decoder)
+    token = decoder.state_token()
+    expected = [(track.id, packet.tobytes()) for track, packet in decoder]
+    decoder.resume_from(token)
+    assert [(track.id, packet.tobytes()) for track, packet in decoder] == expected
+with faery.aedat.Decoder(path, unwrap_timestamps=True) as decoder:
+    decoder.resume_from(token)
+    assert [(track.id, packet.tobytes()) for track, packet in decoder] == expected
+    try:
+        decoder.resume_from(token | (1 << (64 * 8)))
+        raise AssertionError("resume_from accepted a token with too many tracks")
+    except ValueError:
+        pass
+
+
+# flatbuffers are decoded regardless of their byte order
+print("faery.aedat.Decoder big-endian")
+
+
+def handmade_aedat_bytes(byte_order: str) -> bytes:
+    """Builds an uncompressed AEDAT4 file by hand with an events track (0) and a triggers track (1)."""
+
+    def pack(format: str, *values) -> bytes:
+        return struct.pack(f"{byte_order}{format}", *values)
+
+    description = (
+        '<dv version="2.0">\n'
+        '    <node name="outInfo" path="/mainloop/Recorder/outInfo/">\n'
+        '        <node name="0" path="/mainloop/Recorder/outInfo/0/">\n'
+        '            <attr key="compression" type="string">NONE</attr>\n'
+        '            <attr key="typeIdentifier" type="string">EVTS</attr>\n'
+        '            <node name="info" path="/mainloop/Recorder/outInfo/0/info/">\n'
+        '                <attr key="sizeX" type="int">32</attr>\n'
+        '                <attr key="sizeY" type="int">16</attr>\n'
+        "            </node>\n"
+        "        </node>\n"
+        '        <node name="1" path="/mainloop/Recorder/outInfo/1/">\n'
+        '            <attr key="compression" type="string">NONE</attr>\n'
+        '            <attr key="typeIdentifier" type="string">TRIG</attr>\n'
+        "        </node>\n"
+        "    </node>\n"
+        "</dv>\n"
+    ).encode()
+    # header (offsets are relative to the end of the size prefix)
+    header = bytearray()
+    header += pack("I", 24)  # root table offset
+    header += b"IOHE"
+    header += pack("HHHHH", 10, 20, 4, 8, 16) + bytes(6)  # vtable
+    header += pack("i", 16)  # table, vtable offset
+    header += pack("i", 0)  # compression (none)
+    header += pack("q", -1)  # file data position
+    header += pack("I", 4)  # description offset
+    header += pack("I", len(description)) + description + b"\x00"
+    header += bytes(-len(header) % 4)
+    result = bytearray(b"#!AER-DAT4.0\r\n")
+    result += pack("I", len(header)) + header
+
+    def packet(track_id: int, content: bytearray):
+        nonlocal result
+        content = pack("I", len(content)) + content
+        result += struct.pack("<II", track_id, len(content)) + content
+
+    for events in (
+        [(1000000, 0, 0, True), (1000010, 31, 15, False)],
+        [(1000020, 7, 3, True), (1000020, 8, 4, True), (1000100, 1, 2, False)],
+    ):
+        content = bytearray()
+        content += pack("I", 16)  # root table offset
+        content += b"EVTS"
+        content += pack("HHH", 6, 8, 4) + bytes(2)  # vtable
+        content += pack("i", 8)  # table, vtable offset
+        content += pack("I", 4)  # elements offset
+        content += pack("I", len(events))
+        for t, x, y, on in events:
+            content += pack("qhh?", t, x, y, on) + bytes(3)
+        packet(0, content)
+    triggers = [(1000005, 6), (1000015, 7), (1000050, 1)]
+    content = bytearray()
+    content += pack("I", 16)  # root table offset
+    content += b"TRIG"
+    content += pack("HHH", 6, 8, 4) + bytes(2)  # packet vtable
+    content += pack("i", 8)  # packet table, vtable offset
+    content += pack("I", 4)  # elements offset
+    content += pack("I", len(triggers))
+    elements_position = len(content) + 4  # includes the size prefix
+    vtable_position = elements_position + 4 * len(triggers)
+    first_table_position = vtable_position + 8
+    first_table_position += -first_table_position % 8
+    for index in range(len(triggers)):
+        content += pack(
+            "I", first_table_position + 16 * index - (elements_position + 4 * index)
+        )
+    content += pack("HHHH", 8, 16, 8, 4)  # vtable shared by the triggers
+    content += bytes(first_table_position - 4 - len(content))
+    for index, (t, source) in enumerate(triggers):
+        content += pack(
+            "ib", first_table_position + 16 * index - vtable_position, source
+        )
+        content += bytes(3) + pack("q", t)
+    packet(1, content)
+    return bytes(result)
+
+
+
+expected_events = numpy.array(
+    [
+        (1000000, 0, 0, True),
+        (1000010, 31, 15, False),
+        (1000020, 7, 3, True),
+        (1000020, 8, 4, True),
+        (1000100, 1, 2, False),
+    ],
+    dtype=faery.DVS_DTYPE,
+)
+expected_triggers = [(1000005, 6), (1000015, 7), (1000050, 1)]
+for name, byte_order in (("little_endian", "<"), ("big_endian", ">")):
+    path = data_generated / f"{name}.aedat4"
+    path.write_bytes(handmade_aedat_bytes(byte_order))
+    with faery.aedat.Decoder(path) as decoder:
+        assert [(track.id, track.dimensions) for track in decoder.tracks()] == [
+            (0, (32, 16)),
+            (1, None),
+        ], f"{name=}"
+        id_to_packets = {0: [], 1: []}
+        for track, packet in decoder:
+            id_to_packets[track.id].append(packet)
+    events = numpy.concatenate(id_to_packets[0])
+    for field in ("t", "x", "y", "on"):
+        assert numpy.array_equal(events[field], expected_events[field]), f"{name=}"
+    triggers = numpy.concatenate(id_to_packets[1])
+    assert [
+        (int(trigger["t"]), int(trigger["source"])) for trigger in triggers
+    ] == expected_triggers, f"{name=}"
+
+# triggers_only skips the events packets and restores the decoder's position
+print("faery.aedat.Decoder.triggers_only")
+for name in ("little_endian", "big_endian"):
+    path = data_generated / f"{name}.aedat4"
+    with faery.aedat.Decoder(path) as decoder:
+        track_ids = [next(decoder)[0].id]
+        triggers = decoder.triggers_only()
+        track_ids += [track.id for track, _ in decoder]
+    with faery.aedat.Decoder(path) as decoder:
+        assert [track.id for track, _ in decoder] == track_ids, f"{name=}"
+    assert [
+        (int(trigger["t"]), int(trigger["source"])) for trigger in triggers
+    ] == expected_triggers, f"{name=}"
+    with faery.aedat.Decoder(path, track_ids=[0]) as decoder:
+        assert len(decoder.triggers_only()) == 0
+
+# trigger_intervals pairs rising and falling edges, unmatched edges are flagged
+print("faery.aedat.Decoder.trigger_intervals")
+path = data_generated / "trigger_intervals.aedat4"
+triggers = numpy.array(
+    [(5, 2), (10, 1), (12, 4), (20, 2), (25, 3), (30, 5), (40, 1), (50, 1), (55, 4)],
+    dtype=[("t", "<u8"), ("source", "u1")],
+)
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[faery.aedat.Track(0, "triggers", None)],
+    compression=None,
+) as encoder:
+    encoder.write(0, triggers)
+with faery.aedat.Decoder(path) as decoder:
+    intervals = decoder.trigger_intervals()
+assert intervals.dtype.names == ("start_t", "end_t", "source", "paired")
+assert [tuple(interval.tolist()) for interval in intervals] == [
+    (5, 5, 2, False),
+    (10, 20, 1, True),
+    (12, 30, 4, True),
+    (25, 25, 3, True),
+    (40, 40, 1, False),
+    (50, 50, 1, False),
+    (55, 55, 4, False),
+]
+
+print("faery.aedat.Decoder polarity")
+path = data_generated / "little_endian.aedat4"
+for polarity in (None, True, False):
+    with faery.aedat.Decoder(path, polarity=polarity) as decoder:
+        assert decoder.polarity == polarity
+        packets = [packet for track, packet in decoder if track.id == 0]
+    events = numpy.concatenate(packets)
+    if polarity is None:
+        expected = expected_events
+    else:
+        expected = expected_events[expected_events["on"] == polarity]
+    for field in ("t", "x", "y", "on"):
+        assert numpy.array_equal(events[field], expected[field]), f"{polarity=}"
+
+# downsampling divides event coordinates and rescales the events track (rounded up)
+print("faery.aedat.Decoder downsample")
+path = data_generated / "little_endian.aedat4"
+for downsample, dimensions in ((1, (32, 16)), (3, (11, 6)), (32, (1, 1))):
+    with faery.aedat.Decoder(path, downsample=downsample) as decoder:
+        assert decoder.downsample == downsample
+        assert [(track.id, track.dimensions) for track in decoder.tracks()] == [
+            (0, dimensions),
+            (1, None),
+        ], f"{downsample=}"
+        packets = []
+        for track, packet in decoder:
+            if track.id == 0:
+                assert track.dimensions == dimensions, f"{downsample=}"
+                packets.append(packet)
+    events = numpy.concatenate(packets)
+    assert events["x"].tolist() == (expected_events["x"] // downsample).tolist()
+    assert events["y"].tolist() == (expected_events["y"] // downsample).tolist()
+    assert numpy.all(events["x"] < dimensions[0]) and numpy.all(
+        events["y"] < dimensions[1]
+    ), f"{downsample=}"
+
+# mask_pixels drops the events of the given pixels in every events track
+print("faery.aedat.Decoder mask_pixels")
+path = data_generated / "little_endian.aedat4"
+masked_pixels = [(10, 20), (25, 25)]
+with faery.aedat.Decoder(path, mask_pixels=masked_pixels) as decoder:
+    packets = [packet for track, packet in decoder if track.id == 0]
+events = numpy.concatenate(packets)
+kept = numpy.array(
+    [
+        (x, y) not in masked_pixels
+        for x, y in zip(expected_events["x"], expected_events["y"])
+    ]
+)
+for field in ("t", "x", "y", "on"):
+    assert numpy.array_equal(events[field], expected_events[kept][field])
+try:
+    faery.aedat.Decoder(path, mask_pixels=[(0, 16)])
+    raise AssertionError("a pixel outside the sensor must raise ValueError")
+except ValueError:
+    pass
+
+# reset rewinds the decoder and clears the timestamp origin
+print("faery.aedat.Decoder.reset")
+path = data_generated / "little_endian.aedat4"
+with faery.aedat.Decoder(path, zero_origin=True) as decoder:
+    packets = [packet for track, packet in decoder if track.id == 0]
+    origin_t = decoder.origin_t()
+    decoder.reset()
+    assert decoder.origin_t() is None
+    reset_packets = [packet for track, packet in decoder if track.id == 0]
+    assert decoder.origin_t() == origin_t
+assert numpy.array_equal(numpy.concatenate(reset_packets), numpy.concatenate(packets))
+
+# __len__ counts complete packets without decoding them
+print("faery.aedat.Decoder __len__")
+path = data_generated / "round_trip.aedat4"
+tokens = []
+with faery.aedat.Decoder(path, skip_empty=False) as decoder:
+    length = len(decoder)
+    while True:
+        tokens.append(decoder.state_token())
+        try:
+            next(decoder)
+        except StopIteration:
+            break
+    assert len(decoder) == length
+assert length == len(tokens) - 1
+with faery.aedat.Decoder(path, skip_empty=False) as decoder:
+    assert len(decoder) == length
+    assert sum(1 for _ in decoder) == length
+truncated_path = data_generated / "truncated.aedat4"
+truncated_path.write_bytes(path.read_bytes()[: tokens[-2] + 10])
+with faery.aedat.Decoder(truncated_path, skip_empty=False) as decoder:
+    assert len(decoder) == length - 1
+    for _ in range(length - 1):
+        next(decoder)
+    try:
+        next(decoder)
+        raise AssertionError("__next__ did not report the truncated packet")
+    except RuntimeError:
+        pass
+
+# first_activation returns each pixel's first timestamp after a trigger
+print("faery.aedat.Decoder.first_activation")
+path = data_generated / "first_activation.aedat4"
+before = numpy.zeros(16 * 8, dtype=faery.DVS_DTYPE)
+before["t"] = numpy.arange(16 * 8)
+before["x"] = numpy.arange(16 * 8) % 16
+before["y"] = numpy.arange(16 * 8) // 16
+region = numpy.zeros(3 * 4 * 2, dtype=faery.DVS_DTYPE)
+region["t"] = 1000 + numpy.arange(3 * 4 * 2) * 10
+region["x"] = 4 + numpy.arange(3 * 4 * 2) % 4
+region["y"] = 2 + (numpy.arange(3 * 4 * 2) // 4) % 3
+region["on"] = True
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[
+        faery.aedat.Track(0, "events", (16, 8)),
+        faery.aedat.Track(1, "triggers", None),
+    ],
+    compression=None,
+) as encoder:
+    encoder.write(0, before)
+    for offset in range(0, len(region), 5):
+        encoder.write(0, region[offset : offset + 5])
+expected = numpy.full((8, 16), 2**64 - 1, dtype=numpy.uint64)
+for index in range(3 * 4):
+    expected[region["y"][index], region["x"][index]] = region["t"][index]
+with faery.aedat.Decoder(path) as decoder:
+    first_packet = next(decoder)[1]
+    first_activation = decoder.first_activation(0, 1000)
+    assert first_activation.dtype == numpy.uint64
+    assert first_activation.shape == (8, 16)
+    assert numpy.array_equal(first_activation, expected)
+    assert numpy.array_equal(first_packet, before)
+    remaining = numpy.concatenate([packet for _, packet in decoder])
+    assert numpy.array_equal(remaining, region)
+    assert numpy.array_equal(
+        decoder.first_activation(0, 0), numpy.reshape(before["t"], (8, 16))
+    )
+    assert numpy.all(decoder.first_activation(0, 2000) == 2**64 - 1)
+    for track_id in (1, 2):
+        try:
+            decoder.first_activation(track_id, 0)
+            raise AssertionError(f"first_activation accepted track {track_id}")
+        except ValueError:
+            pass
+
+# track_ids skips the packets of the other tracks
+print("faery.aedat.Decoder track_ids")
+path = data_generated / "round_trip.aedat4"
+with faery.aedat.Decoder(path) as decoder:
+    all_packets = [(track.id, packet) for track, packet in decoder]
+assert set(id for id, _ in all_packets) == {0, 3}
+for track_ids in ([0], [3], [0, 3]):
+    with faery.aedat.Decoder(path, track_ids=track_ids) as decoder:
+        assert [track.id for track in decoder.tracks()] == [0, 3]
+        packets = [(track.id, packet) for track, packet in decoder]
+    expected_packets = [(id, packet) for id, packet in all_packets if id in track_ids]
+    assert len(packets) == len(expected_packets), f"{track_ids=}"
+    for (id, packet), (expected_id, expected_packet) in zip(packets, expected_packets):
+        assert id == expected_id, f"{track_ids=}"
+        assert numpy.array_equal(packet, expected_packet), f"{track_ids=}"
+    # enumerate counts the packets of the other tracks
+    with faery.aedat.Decoder(path, track_ids=track_ids) as decoder:
+        indices = [index for index, _ in decoder.enumerate()]
+    expected_indices = [
+        index for index, (id, _) in enumerate(all_packets) if id in track_ids
+    ]
+    assert indices == expected_indices, f"{track_ids=}, {indices=}"
+path = data_generated / "little_endian.aedat4"
+with faery.aedat.Decoder(path, track_ids=[1]) as decoder:
+    packets = [(track.data_type, packet) for track, packet in decoder]
+assert len(packets) > 0 and all(data_type == "triggers" for data_type, _ in packets)
+try:
+    faery.aedat.Decoder(path, track_ids=[0, 5])
+    raise AssertionError("Decoder accepted an unknown track ID")
+except RuntimeError:
+    pass
+
+# collect_fused merges an events track and an IMU track
+print("faery.aedat.Decoder.collect_fused")
+path = data_generated / "fused.aedat4"
+imu_dtype = numpy.dtype(
+    [("t", "<u8")]
+    + [
+        (name, "<f4")
+        for name in (
+            "temperature",
+            "accelerometer_x",
+            "accelerometer_y",
+            "accelerometer_z",
+            "gyroscope_x",
+            "gyroscope_y",
+            "gyroscope_z",
+            "magnetometer_x",
+            "magnetometer_y",
+            "magnetometer_z",
+        )
+    ]
+)
+events = numpy.zeros(40, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(40) * 10
+events["x"] = numpy.arange(40) % 16
+events["y"] = numpy.arange(40) % 8
+events["on"] = numpy.arange(40) % 2 == 0
+imus = numpy.zeros(8, dtype=imu_dtype)
+imus["t"] = numpy.arange(8) * 50 + 5
+for index, name in enumerate(imu_dtype.names[1:]):
+    imus[name] = numpy.arange(8) + index * 100
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[
+        faery.aedat.Track(0, "events", (16, 8)),
+        faery.aedat.Track(1, "imus", None),
+        faery.aedat.Track(2, "triggers", None),
+    ],
+    compression=None,
+) as encoder:
+    for offset in range(0, 40, 10):
+        encoder.write(0, events[offset : offset + 10])
+        encoder.write(1, imus[offset // 5 : offset // 5 + 2])
+with faery.aedat.Decoder(path) as decoder:
+    fused = decoder.collect_fused(0, 1)
+    assert numpy.array_equal(next(decoder)[1], events[0:10])
+assert fused.dtype.names == (
+    "t",
+    "kind",
+    "x",
+    "y",
+    "on",
+    "accelerometer_x",
+    "accelerometer_y",
+    "accelerometer_z",
+    "gyroscope_x",
+    "gyroscope_y",
+    "gyroscope_z",
+)
+assert len(fused) == len(events) + len(imus)
+assert numpy.all(numpy.diff(fused["t"].astype(numpy.int64)) >= 0)
+event_mask = fused["kind"] == 0
+imu_mask = fused["kind"] == 1
+assert numpy.count_nonzero(event_mask) == len(events)
+assert numpy.count_nonzero(imu_mask) == len(imus)
+for name in ("t", "x", "y", "on"):
+    assert numpy.array_equal(fused[name][event_mask], events[name])
+assert numpy.array_equal(fused["t"][imu_mask], imus["t"])
+for name in fused.dtype.names[5:]:
+    assert numpy.all(numpy.isnan(fused[name][event_mask]))
+    assert numpy.array_equal(fused[name][imu_mask], imus[name])
+assert numpy.all(fused["x"][imu_mask] == 0) and numpy.all(fused["y"][imu_mask] == 0)
+assert not numpy.any(fused["on"][imu_mask])
+with faery.aedat.Decoder(path) as decoder:
+    for event_track_id, imu_track_id in ((1, 1), (0, 2), (0, 3)):
+        try:
+            decoder.collect_fused(event_track_id, imu_track_id)
+            raise AssertionError(
+                f"collect_fused accepted ({event_track_id}, {imu_track_id})"
+            )
+        except ValueError:
+            pass
+
+# imus_as_dict returns IMU packets as dicts of arrays
+print("faery.aedat.Decoder imus_as_dict")
+with faery.aedat.Decoder(path, track_ids=[1], imus_as_dict=True) as decoder:
+    assert decoder.imus_as_dict
+    packets = [packet for _, packet in decoder]
+assert all(
+    set(packet.keys())
+    == {"t", "temperature", "accelerometer", "gyroscope", "magnetometer"}
+    for packet in packets
+)
+assert numpy.array_equal(
+    numpy.concatenate([packet["t"] for packet in packets]), imus["t"]
+)
+assert numpy.array_equal(
+    numpy.concatenate([packet["temperature"] for packet in packets]),
+    imus["temperature"],
+)
+for name in ("accelerometer", "gyroscope", "magnetometer"):
+    values = numpy.concatenate([packet[name] for packet in packets])
+    assert values.dtype == numpy.float32 and values.shape == (len(imus), 3)
+    for axis, suffix in enumerate(("x", "y", "z")):
+        assert numpy.array_equal(values[:, axis], imus[f"{name}_{suffix}"])
+with faery.aedat.Decoder(path, track_ids=[1]) as decoder:
+    assert numpy.array_equal(numpy.concatenate([packet for _, packet in decoder]), imus)
+
+# parsed_description extracts the camera information from the description
+print("faery.aedat.Decoder.parsed_description")
+for file in assets.files:
+    if file.path.name == "davis346.aedat4":
+        with faery.aedat.Decoder(file.path) as decoder:
+            assert decoder.parsed_description() == {
+                "device_name": "DAVIS346",
+                "serial_number": "00000002",
+                "sources": {id: "DAVIS346_00000002" for id in range(4)},
+            }
+with faery.aedat.Decoder(data_generated / "round_trip.aedat4") as decoder:
+    assert decoder.parsed_description() == {}
+
+# AEDAT 2.0 and 3.1 files are read with LegacyDecoder (DVS events only)
+print("faery.aedat.LegacyDecoder")
+legacy_events = numpy.array(
+    [(10, 1, 2, True), (4294967000, 127, 0, False), (5 + (1 << 32), 3, 127, True)],
+    dtype=faery.DVS_DTYPE,
+)
+path = data_generated / "legacy_dvs128.aedat"
+with open(path, "wb") as output:
+    output.write(b"#!AER-DAT2.0\r\n# This is a raw AE data file\r\n")
+    for event in legacy_events:
+        address = (int(event["y"]) << 8) | (int(event["x"]) << 1) | int(event["on"])
+        output.write(struct.pack(">II", address, int(event["t"]) & 0xFFFFFFFF))
+assert faery.FileType.guess(path) == faery.FileType.AEDAT
+with faery.aedat.LegacyDecoder(path) as decoder:
+    assert decoder.version == "2.0"
+    assert [(track.id, track.dimensions) for track in decoder.tracks()] == [
+        (0, (128, 128))
+    ]
+    packets = [packet for _, packet in decoder]
+assert numpy.array_equal(numpy.concatenate(packets), legacy_events)
+stream = faery.stream_from_file(path)
+assert stream.dimensions() == (128, 128)
+assert numpy.array_equal(numpy.concatenate(list(stream)), legacy_events)
+
+path = data_generated / "legacy_davis346.aedat"
+with open(path, "wb") as output:
+    output.write(
+        b"#!AER-DAT3.1\r\n#Format: RAW\r\n#Source 1: DAVIS346\r\n#!END-HEADER\r\n"
+    )
+
+    def polarity_packet(overflow: int, events: numpy.ndarray) -> bytes:
+        result = struct.pack("<hhiiiiii", 1, 1, 8, 4, overflow, *(len(events),) * 3)
+        for event in events:
+            data = (
+                1
+                | (int(event["on"]) << 1)
+                | (int(event["y"]) << 2)
+                | (int(event["x"]) << 17)
+            )
+            result += struct.pack("<Ii", data, int(event["t"]) & 0x7FFFFFFF)
+        return result
+
+    legacy_events = numpy.array(
+        [(1, 345, 0, True), (2, 0, 259, False), ((1 << 31) + 3, 100, 50, True)],
+        dtype=faery.DVS_DTYPE,
+    )
+    output.write(polarity_packet(0, legacy_events[:2]))
+    # frame packets are skipped
+    output.write(struct.pack("<hhiiiiii", 2, 1, 4, 4, 0, 3, 3, 3) + bytes(12))
+    output.write(polarity_packet(1, legacy_events[2:]))
+with faery.aedat.LegacyDecoder(path) as decoder:
+    assert decoder.version == "3.1"
+    assert [(track.id, track.dimensions) for track in decoder.tracks()] == [
+        (1, (346, 260))
+    ]
+    packets = [packet for _, packet in decoder]
+assert len(packets) == 2
+assert numpy.array_equal(numpy.concatenate(packets), legacy_events)
+stream = faery.stream_from_file(path)
+assert stream.dimensions() == (346, 260)
+assert numpy.array_equal(numpy.concatenate(list(stream)), legacy_events)
+
+# a file cut off in the middle of a packet raises TruncatedFileError after the complete packets
+print("faery.aedat.Decoder TruncatedFileError")
+for file in assets.files:
+    if file.format != "aedat":
+        continue
+    complete_packets = 3
+    with faery.aedat.Decoder(file.path) as decoder:
+        expected = []
+        for _ in range(complete_packets):
+            track, packet = next(decoder)
+            expected.append((track.id, packet_to_bytes(packet)))
+        # the lower 64 bits of the token are the byte offset of the next packet
+        position = decoder.state_token() & ((1 << 64) - 1)
+    path = data_generated / f"truncated_{file.path.name}"
+    with open(path, "wb") as output:
+        output.write(file.path.read_bytes()[: position + 20])
+    packets = []
+    try:
+        with faery.aedat.Decoder(path) as decoder:
+            for track, packet in decoder:
+                packets.append((track.id, packet_to_bytes(packet)))
+        raise AssertionError("the truncated file did not raise TruncatedFileError")
+    except faery.TruncatedFileError as error:
+        assert isinstance(error, EOFError)
+    assert packets == expected
+
+# lenient skips corrupt packets with a warning instead of raising an error
+print("faery.aedat.Decoder lenient")
+path = data_generated / "corrupt.aedat4"
+events = numpy.zeros(30, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(30)
+events["x"] = numpy.arange(30) % 16
+events["y"] = numpy.arange(30) % 8
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[faery.aedat.Track(0, "events", (16, 16))],
+    compression=None,
+) as encoder:
+    for offset in range(0, 30, 10):
+        encoder.write(0, events[offset : offset + 10])
+with faery.aedat.Decoder(path) as decoder:
+    next(decoder)
+    position = decoder.state_token() & ((1 << 64) - 1)
+data = bytearray(path.read_bytes())
+# the flatbuffer identifier follows the packet header (8 bytes), size prefix, and root offset
+data[position + 16 : position + 20] = b"XXXX"
+path.write_bytes(data)
+try:
+    with faery.aedat.Decoder(path) as decoder:
+        list(decoder)
+    raise AssertionError("the corrupt packet did not raise an error")
+except RuntimeError:
+    pass
+with warnings.catch_warnings(record=True) as caught_warnings:
+    warnings.simplefilter("always")
+    with faery.aedat.Decoder(path, lenient=True) as decoder:
+        packets = [packet for _, packet in decoder]
+        assert decoder.skipped_packets() == 1
+assert len(caught_warnings) == 1
+assert issubclass(caught_warnings[0].category, RuntimeWarning)
+assert numpy.array_equal(
+    numpy.concatenate(packets), numpy.concatenate([events[:10], events[20:]])
+)
+
+# on_overflow handles events whose coordinates are outside the track's dimensions
+print("faery.aedat.Decoder on_overflow")
+path = data_generated / "overflow.aedat4"
+events = numpy.array(
+    [(1, 1, 2, True), (2, 18, 4, False), (3, 3, 5, True)], dtype=faery.DVS_DTYPE
+)
+with faery.aedat.Encoder(
+    path=path,
+    description_or_tracks=[faery.aedat.Track(0, "events", (20, 16))],
+    compression=None,
+) as encoder:
+    encoder.write(0, events)
+# shrinks the track in the description without changing the header's size
+path.write_bytes(
+    path.read_bytes().replace(
+        b'<attr key="sizeX" type="int">20</attr>',
+        b'<attr key="sizeX" type="int">16</attr>',
+    )
+)
+try:
+    with faery.aedat.Decoder(path) as decoder:
+        list(decoder)
+    raise AssertionError("the overflow did not raise an error")
+except RuntimeError as error:
+    assert "x overflow" in str(error)
+with faery.aedat.Decoder(path, on_overflow="clamp") as decoder:
+    assert decoder.on_overflow == "clamp"
+    (packet,) = [packet for _, packet in decoder]
+assert packet["x"].tolist() == [1, 15, 3]
+assert packet["t"].tolist() == [1, 2, 3]
+with faery.aedat.Decoder(path, on_overflow="drop") as decoder:
+    (packet,) = [packet for _, packet in decoder]
+assert numpy.array_equal(packet, events[[0, 2]])
+with faery.aedat.Decoder(path, on_overflow="drop", polarity=False) as decoder:
+    assert [len(packet) for _, packet in decoder] == []
+with faery.aedat.Decoder(path, on_overflow="drop") as decoder:
+    first_activation = decoder.first_activation(0, 0)
+assert first_activation.shape == (16, 16)
+assert first_activation[2, 1] == 1 and first_activation[5, 3] == 3
+assert (first_activation != numpy.iinfo(numpy.uint64).max).sum() == 2
+with faery.aedat.Decoder(path, on_overflow="clamp") as decoder:
+    assert decoder.first_activation(0, 0)[4, 15] == 2
+try:
+    with faery.aedat.Decoder(path) as decoder:
+        decoder.first_activation(0, 0)
+    raise AssertionError("first_activation ignored the overflow")
+except RuntimeError as error:
+    assert "x overflow" in str(error)
+try:
+    faery.aedat.Decoder(path, on_overflow="wrap")
+    raise AssertionError("on_overflow accepted an unknown policy")
+except ValueError:
+    pass
+
+# zero_origin shifts all the tracks by the first decoded timestamp
+print("faery.aedat.Decoder zero_origin")
+path = data_generated / "little_endian.aedat4"
+with faery.aedat.Decoder(path) as decoder:
+    list(decoder)
+    assert decoder.origin_t() is None
+with faery.aedat.Decoder(path, zero_origin=True) as decoder:
+    assert decoder.zero_origin
+    assert decoder.origin_t() is None
+    id_to_packets = {0: [], 1: []}
+    for track, packet in decoder:
+        id_to_packets[track.id].append(packet)
+    assert decoder.origin_t() == 1000000
+    decoder.seek_to_start()
+    _, packet = next(decoder)
+    assert packet["t"].tolist() == [0, 10]
+assert numpy.concatenate(id_to_packets[0])["t"].tolist() == [0, 10, 20, 20, 100]
+assert numpy.concatenate(id_to_packets[1])["t"].tolist() == [5, 15, 50]
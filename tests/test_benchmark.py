@@ -0,0 +1,15 @@
+import faery
+
+import assets
+
+for file in assets.files:
+    if file.format in ("es-color", "es-generic"):
+        continue
+    print(f"faery.benchmark ({file.path.name})")
+    report = faery.benchmark(file.path)
+    events = faery.stream_from_file(file.path).to_array()
+    assert report["events"] == len(events), f"{report=}, {len(events)=}"
+    assert report["packets"] > 0
+    assert report["largest_packet_bytes"] > 0
+    assert report["events_per_second"] > 0.0
+    assert report["megabytes_per_second"] > 0.0
@@ -0,0 +1,145 @@
+import pathlib
+import struct
+import typing
+
+import faery
+import numpy
+
+data_generated = pathlib.Path(__file__).resolve().parent / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+# the encoder writes an index footer (one entry every index_interval events) announced by a header line
+print("faery.dat.Decoder seek")
+scanned_path = data_generated / "scanned.dat"
+indexed_path = data_generated / "indexed.dat"
+events = numpy.zeros(
+    3000,
+    dtype=[("t", "<u8"), ("x", "<u2"), ("y", "<u2"), ("payload", "u1")],
+)
+# timestamps wrap around 2^32 twice
+events["t"] = numpy.arange(3000) * 3000000
+events["x"] = numpy.arange(3000) % 32
+events["y"] = (numpy.arange(3000) // 32) % 16
+events["payload"] = numpy.arange(3000) % 2
+for path, index_interval in ((scanned_path, None), (indexed_path, 100)):
+    with faery.dat.Encoder(
+        path=path,
+        version="dat2",
+        event_type="cd",
+        zero_t0=False,
+        dimensions=(32, 16),
+        index_interval=index_interval,
+    ) as encoder:
+        encoder.write(events)
+data = indexed_path.read_bytes()
+assert b"% Index footer\n" in data
+assert data.endswith(struct.pack("<Q", 30) + b"DATINDEX")
+
+
+def decode(path: pathlib.Path, seek_t: typing.Optional[int]) -> numpy.ndarray:
+    with faery.dat.Decoder(path) as decoder:
+        assert decoder.indexed == (path == indexed_path)
+        if seek_t is not None:
+            decoder.seek(seek_t)
+        packets = [packet for packet in decoder]
+    if len(packets) == 0:
+        return numpy.zeros(0, dtype=events.dtype)
+    return numpy.concatenate(packets)
+
+
+for seek_t in (None, 0, 1, 299999999, 4294967296, 4500000000, 8997000000, 9000000000):
+    expected_events = events if seek_t is None else events[events["t"] >= seek_t]
+    for path in (scanned_path, indexed_path):
+        decoded_events = decode(path, seek_t)
+        for field in ("t", "x", "y", "payload"):
+            assert numpy.array_equal(
+                decoded_events[field], expected_events[field]
+            ), f"{path.name=}, {seek_t=}"
+
+# bit layouts map (offset, width) pairs to the fields of each 64-bit word
+print("faery.dat.Decoder bit_layout")
+dvs128_path = data_generated / "dvs128.dat"
+dvs128_events = events[:256].copy()
+dvs128_events["x"] = numpy.arange(256) % 128
+dvs128_events["y"] = (numpy.arange(256) * 7) % 128
+dvs128_events["t"] = numpy.arange(256) * 1000
+with open(dvs128_path, "wb") as output:
+    output.write(b"% Version 2\n% Width 128\n% Height 128\n\x0c\x08")
+    for event in dvs128_events:
+        address = (
+            int(event["payload"]) | (int(event["x"]) << 1) | (int(event["y"]) << 8)
+        )
+        output.write(struct.pack("<II", int(event["t"]), address))
+for bit_layout in (
+    "dvs128",
+    {"t": (0, 32), "payload": (32, 1), "x": (33, 7), "y": (40, 7)},
+):
+    with faery.dat.Decoder(dvs128_path, bit_layout=bit_layout) as decoder:
+        assert decoder.bit_layout["x"] == (33, 7)
+        decoded_events = numpy.concatenate([packet for packet in decoder])
+    for field in ("t", "x", "y", "payload"):
+        assert numpy.array_equal(decoded_events[field], dvs128_events[field])
+for bit_layout in (
+    "dvs256",
+    {"t": (0, 32), "x": (32, 9), "y": (41, 8)},
+    {"t": (0, 32), "x": (32, 9), "y": (40, 8), "payload": (49, 4)},
+    {"t": (0, 32), "x": (32, 9), "y": (41, 8), "payload": (60, 8)},
+):
+    try:
+        faery.dat.Decoder(dvs128_path, bit_layout=bit_layout)
+        raise AssertionError(f"{bit_layout=} should have raised")
+    except ValueError:
+        pass
+
+# comment_lines returns the raw header lines, including the "%" marker
+print("faery.dat.Decoder header")
+with faery.dat.Decoder(dvs128_path, bit_layout="dvs128") as decoder:
+    assert decoder.version == "dat2"
+    assert (decoder.width, decoder.height) == (128, 128)
+    assert decoder.comment_lines() == ["% Version 2", "% Width 128", "% Height 128"]
+
+# reset rewinds the decoder and clears the timestamp overflows
+print("faery.dat.Decoder.reset")
+for path in (scanned_path, indexed_path):
+    with faery.dat.Decoder(path) as decoder:
+        next(decoder)
+        decoder.seek(4500000000)
+        next(decoder)
+        decoder.reset()
+        decoded_events = numpy.concatenate([packet for packet in decoder])
+    for field in ("t", "x", "y", "payload"):
+        assert numpy.array_equal(decoded_events[field], events[field]), f"{path.name=}"
+
+# enumerate counts buffer_size reads, including the empty packets skipped after seek
+print("faery.dat.Decoder.enumerate")
+for path in (scanned_path, indexed_path):
+    read_counts = []
+    for buffer_size in (1024, 2048):
+        with faery.dat.Decoder(
+            path, skip_empty=False, buffer_size=buffer_size
+        ) as decoder:
+            decoder.seek(4500000000)
+            non_empty_indices = [
+                index for index, packet in enumerate(decoder) if len(packet) > 0
+            ]
+            decoder.reset()
+            read_counts.append(len(list(decoder.enumerate())))
+        with faery.dat.Decoder(path, buffer_size=buffer_size) as decoder:
+            decoder.seek(4500000000)
+            indices = [index for index, packet in decoder.enumerate()]
+        assert (
+            indices == non_empty_indices
+        ), f"{path.name=}, {indices=}, {non_empty_indices=}"
+        if path == scanned_path:
+            assert indices[0] > 0, f"{path.name=}, {indices=}"
+    assert read_counts[0] > read_counts[1], f"{path.name=}, {read_counts=}"
+
+# a header that announces a missing footer is reported
+print("faery.dat.Decoder malformed index")
+truncated_path = data_generated / "truncated_index.dat"
+truncated_path.write_bytes(indexed_path.read_bytes()[:-8])
+try:
+    faery.dat.Decoder(truncated_path)
+    raise AssertionError("the missing footer did not raise an error")
+except RuntimeError as error:
+    assert "index footer" in str(error), f"{error=}"
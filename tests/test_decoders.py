@@ -29,6 +29,19 @@ def validate(file: assets.File):
                     assert (
                         track.dimensions == file_track.dimensions
                     ), f"{tracks=}, {file.tracks=}"
+                # width and height require a single events track
+                events_tracks = [
+                    track for track in tracks if track.data_type == "events"
+                ]
+                if len(events_tracks) == 1:
+                    dimensions = events_tracks[0].dimensions
+                    assert (decoder.width, decoder.height) == dimensions
+                else:
+                    try:
+                        decoder.width
+                        raise AssertionError("width did not raise")
+                    except ValueError:
+                        pass
                 field_to_hasher = file.field_to_hasher()
                 for track, packet in decoder:
                     if track.data_type == "events":
@@ -62,6 +75,7 @@ def validate(file: assets.File):
                 assert decoder.version == "dat2"
                 assert decoder.event_type == "cd"
                 assert decoder.dimensions == file.dimensions
+                assert (decoder.width, decoder.height) == file.dimensions
                 field_to_hasher = file.field_to_hasher()
                 for packet in decoder:
                     field_to_hasher["t"].update(packet["t"].tobytes())
@@ -82,6 +96,7 @@ def validate(file: assets.File):
                 assert decoder.version == "2.0.0"
                 assert decoder.event_type == "atis"
                 assert decoder.dimensions == file.dimensions
+                assert (decoder.width, decoder.height) == file.dimensions
                 assert decoder.dimensions is not None
                 field_to_hasher = file.field_to_hasher(
                     [
@@ -120,6 +135,7 @@ def validate(file: assets.File):
                 assert decoder.version == "2.0.0"
                 assert decoder.event_type == "color"
                 assert decoder.dimensions == file.dimensions
+                assert (decoder.width, decoder.height) == file.dimensions
                 assert decoder.dimensions is not None
                 field_to_hasher = file.field_to_hasher()
                 for packet in decoder:
@@ -146,6 +162,7 @@ def validate(file: assets.File):
                 assert decoder.version == "2.0.0"
                 assert decoder.event_type == "dvs"
                 assert decoder.dimensions == file.dimensions
+                assert (decoder.width, decoder.height) == file.dimensions
                 assert decoder.dimensions is not None
                 field_to_hasher = file.field_to_hasher()
                 for packet in decoder:
@@ -171,6 +188,7 @@ def validate(file: assets.File):
                 assert decoder.version == "2.0.0"
                 assert decoder.event_type == "generic"
                 assert decoder.dimensions == file.dimensions
+                assert decoder.width is None and decoder.height is None
                 assert file.content_lines is not None
                 field_to_hasher = file.field_to_hasher()
                 index = 0
@@ -202,6 +220,7 @@ def validate(file: assets.File):
             with faery.evt.Decoder(file.path, file.dimensions) as decoder:
                 assert decoder.version == "evt2"
                 assert decoder.dimensions == file.dimensions
+                assert (decoder.width, decoder.height) == file.dimensions
                 field_to_hasher = file.field_to_hasher()
                 for packet in decoder:
                     if "events" in packet:
@@ -218,6 +237,7 @@ def validate(file: assets.File):
             with faery.evt.Decoder(file.path) as decoder:
                 assert decoder.version == "evt3"
                 assert decoder.dimensions == file.dimensions
+                assert (decoder.width, decoder.height) == file.dimensions
                 field_to_hasher = file.field_to_hasher()
                 for packet in decoder:
                     if "events" in packet:
@@ -3,6 +3,7 @@ import shutil
 import time
 
 import faery
+import numpy
 
 import assets
 import test_decoders
@@ -241,3 +242,24 @@ for file in assets.files:
                     t0=t0,
                 )
             )
+
+
+# split a file by polarity and merge the two outputs
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    on_path = data_generated / f"{file.path.stem}-on.es"
+    off_path = data_generated / f"{file.path.stem}-off.es"
+    print(f"faery.split_by_polarity ({file.path.name} -> {on_path.name}, {off_path.name})")
+    faery.split_by_polarity(file.path, on_path, off_path)
+    on_events = faery.stream_from_file(on_path).to_array()
+    off_events = faery.stream_from_file(off_path).to_array()
+    assert numpy.all(on_events["on"])
+    assert not numpy.any(off_events["on"])
+    events = faery.stream_from_file(file.path).to_array()
+    merged_events = numpy.concatenate((on_events, off_events))
+    order = numpy.lexsort((events["on"], events["y"], events["x"], events["t"]))
+    merged_order = numpy.lexsort(
+        (merged_events["on"], merged_events["y"], merged_events["x"], merged_events["t"])
+    )
+    assert numpy.array_equal(events[order], merged_events[merged_order])
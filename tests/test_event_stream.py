@@ -0,0 +1,1093 @@
+import asyncio
+import gzip
+import pathlib
+
+import faery
+import numpy
+
+import assets
+
+data_generated = pathlib.Path(__file__).resolve().parent / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+
+async def decode_async(path: pathlib.Path) -> list[numpy.ndarray]:
+    with faery.event_stream.Decoder(path, t0=0) as decoder:
+        return [packet async for packet in decoder]
+
+
+async def decode_mixed(
+    path: pathlib.Path,
+) -> tuple[list[numpy.ndarray], list[numpy.ndarray]]:
+    synchronous_packets = []
+    asynchronous_packets = []
+    with faery.event_stream.Decoder(path, t0=0, buffer_size=1024) as decoder:
+        synchronous_packets.append(next(decoder))
+        async for packet in decoder:
+            asynchronous_packets.append(packet)
+            try:
+                synchronous_packets.append(next(decoder))
+            except StopIteration:
+                pass
+    return synchronous_packets, asynchronous_packets
+
+
+async def exit_async_early(path: pathlib.Path):
+    with faery.event_stream.Decoder(path, t0=0, buffer_size=1024) as decoder:
+        iterator = decoder.__aiter__()
+        async for _ in iterator:
+            break
+    # __exit__ stops the worker, the iterator ends and the decoder is closed
+    try:
+        await iterator.__anext__()
+        raise AssertionError("the iterator yielded a packet after __exit__")
+    except StopAsyncIteration:
+        pass
+    try:
+        next(decoder)
+        raise AssertionError("next succeeded after __exit__")
+    except Exception as error:
+        assert "__exit__" in str(error), f"{error=}"
+
+
+for file in assets.files:
+    if not file.format.startswith("es-"):
+        continue
+
+    # duration and event count are computed without moving the decoder
+    print(f"faery.event_stream.Decoder.duration/event_count ({file.path.name})")
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        duration = decoder.duration()
+        event_count = decoder.event_count()
+        assert decoder.duration() == duration
+        assert decoder.event_count() == event_count
+        first_t = None
+        last_t = None
+        count = 0
+        for packet in decoder:
+            count += len(packet)
+            if len(packet) > 0:
+                if first_t is None:
+                    first_t = int(packet["t"][0])
+                last_t = int(packet["t"][-1])
+    assert event_count == count, f"{event_count=}, {count=}"
+    assert first_t is not None and last_t is not None
+    assert duration == last_t - first_t, f"{duration=}, {first_t=}, {last_t=}"
+
+    # to_array matches the concatenated packets, generic events hold bytes objects
+    print(f"faery.event_stream.Decoder.to_array ({file.path.name})")
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        events = decoder.to_array()
+        packets = [packet for packet in decoder]
+    assert len(events) == event_count, f"{len(events)=}, {event_count=}"
+    if len(packets) > 0:
+        expected_events = numpy.concatenate(packets)
+        assert events.dtype == expected_events.dtype
+        assert numpy.array_equal(events, expected_events)
+
+    # DVS packets own the decoded events (the array's base keeps them alive), later packets do not overwrite them
+    if file.format == "es-dvs":
+        print(f"faery.event_stream.Decoder zero-copy ({file.path.name})")
+        for packet in packets:
+            assert packet.base is not None
+            assert packet.flags["WRITEABLE"]
+
+    # async for decodes on a worker thread and yields the same packets
+    print(f"faery.event_stream.Decoder async for ({file.path.name})")
+    async_packets = asyncio.run(decode_async(file.path))
+    assert len(async_packets) == len(packets)
+    for async_packet, packet in zip(async_packets, packets):
+        assert numpy.array_equal(async_packet, packet)
+
+    # next and async for take turns on the decoder, each packet is yielded once
+    print(f"faery.event_stream.Decoder next and async for ({file.path.name})")
+    with faery.event_stream.Decoder(file.path, t0=0, buffer_size=1024) as decoder:
+        small_packets = [packet for packet in decoder]
+    synchronous_packets, asynchronous_packets = asyncio.run(decode_mixed(file.path))
+    assert len(synchronous_packets) > 0 and len(asynchronous_packets) > 0
+    assert sorted(
+        packet["t"].tobytes() for packet in synchronous_packets + asynchronous_packets
+    ) == sorted(packet["t"].tobytes() for packet in small_packets)
+    asyncio.run(exit_async_early(file.path))
+
+    # the ragged layout reproduces each generic event's bytes
+    if file.format == "es-generic":
+        print(f"faery.event_stream.Decoder ragged ({file.path.name})")
+        with faery.event_stream.Decoder(file.path, t0=0, ragged=True) as decoder:
+            ragged_packets = [packet for packet in decoder]
+        assert len(ragged_packets) == len(packets)
+        for ragged_packet, packet in zip(ragged_packets, packets):
+            assert numpy.array_equal(ragged_packet["t"], packet["t"])
+            offsets = ragged_packet["offsets"]
+            assert len(offsets) == len(packet) + 1 and offsets[0] == 0
+            assert offsets[-1] == len(ragged_packet["bytes"])
+            for index, event in enumerate(packet):
+                assert (
+                    ragged_packet["bytes"][offsets[index] : offsets[index + 1]].tobytes()
+                    == event["bytes"]
+                )
+
+    # the structure-of-arrays layout holds the same DVS events in contiguous columns
+    if file.format == "es-dvs":
+        print(f"faery.event_stream.Decoder soa ({file.path.name})")
+        with faery.event_stream.Decoder(file.path, t0=0, soa=True) as decoder:
+            soa_packets = [packet for packet in decoder]
+        assert len(soa_packets) == len(packets)
+        for soa_packet, packet in zip(soa_packets, packets):
+            assert list(soa_packet.keys()) == ["t", "x", "y", "on"]
+            for name, column in soa_packet.items():
+                assert column.ndim == 1 and column.flags["C_CONTIGUOUS"]
+                assert column.dtype == packet.dtype[name]
+                assert numpy.array_equal(column, packet[name])
+        with faery.event_stream.Decoder(
+            file.path,
+            t0=0,
+            soa=True,
+            field_names={"on": "p"},
+            t_dtype="int64",
+        ) as decoder:
+            soa_packet = next(decoder)
+        assert list(soa_packet.keys()) == ["t", "x", "y", "p"]
+        assert soa_packet["t"].dtype == numpy.int64
+        assert numpy.array_equal(soa_packet["p"], packets[0]["on"])
+
+# clock glitches are reported as (t, gap) rows, gaps equal to the threshold are not glitches
+print("faery.event_stream.Decoder.clock_glitches")
+path = data_generated / "clock_glitches.es"
+events = numpy.zeros(1000, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(1000) * 10
+events["t"][500:] += 1000000
+events["t"][800:] += 100
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(16, 16),
+) as encoder:
+    encoder.write(events)
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    glitches = decoder.clock_glitches(threshold_us=110)
+    assert decoder.clock_glitches(threshold_us=2000000).shape == (0, 2)
+assert glitches.dtype == numpy.uint64
+assert glitches.tolist() == [[5000 + 1000000, 1000010]], f"{glitches=}"
+
+# the region of interest (x, y, width, height) drops outside events and moves its origin to (0, 0)
+print("faery.event_stream.Decoder roi")
+path = data_generated / "roi.es"
+events = numpy.zeros(1000, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(1000)
+events["x"] = numpy.arange(1000) % 32
+events["y"] = (numpy.arange(1000) // 32) % 16
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(32, 16),
+) as encoder:
+    encoder.write(events)
+inside = numpy.logical_and(
+    numpy.logical_and(events["x"] >= 4, events["x"] < 14),
+    numpy.logical_and(events["y"] >= 2, events["y"] < 7),
+)
+expected_events = events[inside]
+expected_events["x"] -= 4
+expected_events["y"] -= 2
+with faery.event_stream.Decoder(path, t0=0, roi=(4, 2, 10, 5)) as decoder:
+    assert decoder.dimensions == (10, 5)
+    decoded_events = numpy.concatenate([packet for packet in decoder])
+    assert numpy.array_equal(decoder.to_array(), expected_events)
+assert numpy.array_equal(decoded_events, expected_events)
+for roi in ((0, 0, 0, 5), (30, 0, 4, 4)):
+    try:
+        faery.event_stream.Decoder(path, t0=0, roi=roi)
+        raise AssertionError(f"Decoder accepted {roi=}")
+    except ValueError:
+        pass
+
+# hot pixels fire more than threshold_hz events per second over the file's duration
+print("faery.event_stream.Decoder.hot_pixel_filter")
+path = data_generated / "hot_pixels.es"
+events = numpy.zeros(2000, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(2000) * 500
+events["x"] = numpy.arange(2000) % 32
+events["y"] = (numpy.arange(2000) // 32) % 16
+events["x"][::4] = 3
+events["y"][::4] = 5
+events["x"][1::8] = 30
+events["y"][1::8] = 1
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(32, 16),
+) as encoder:
+    encoder.write(events)
+# the file lasts about 1 s, (3, 5) fires 504 times and (30, 1) fires 254 times
+hot = numpy.logical_or(
+    numpy.logical_and(events["x"] == 3, events["y"] == 5),
+    numpy.logical_and(events["x"] == 30, events["y"] == 1),
+)
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    with decoder.hot_pixel_filter(threshold_hz=100.0) as hot_pixel_filter:
+        assert hot_pixel_filter.hot_pixels == [(30, 1), (3, 5)]
+        filtered_events = numpy.concatenate([packet for packet in hot_pixel_filter])
+    assert numpy.array_equal(filtered_events, events[numpy.logical_not(hot)])
+    with decoder.hot_pixel_filter(threshold_hz=300.0) as hot_pixel_filter:
+        assert hot_pixel_filter.hot_pixels == [(3, 5)]
+    # the filter scans the file with its own handle, the decoder still starts at the first packet
+    assert numpy.array_equal(numpy.concatenate([packet for packet in decoder]), events)
+    try:
+        decoder.hot_pixel_filter(threshold_hz=-1.0)
+        raise AssertionError("hot_pixel_filter accepted a negative threshold")
+    except ValueError:
+        pass
+
+# the refractory period drops events that follow the pixel's previous kept event by period_us or less
+print("faery.event_stream.Decoder.refractory_filter")
+path = data_generated / "refractory.es"
+events = numpy.zeros(100000, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(100000) * 10
+events["x"] = numpy.arange(100000) % 2
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(2, 1),
+) as encoder:
+    encoder.write(events)
+pixel_to_last_t = {}
+keep = numpy.zeros(len(events), dtype=bool)
+for index, (t, x) in enumerate(zip(events["t"].tolist(), events["x"].tolist())):
+    if x not in pixel_to_last_t or t > pixel_to_last_t[x] + 25:
+        pixel_to_last_t[x] = t
+        keep[index] = True
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    with decoder.refractory_filter(period_us=25) as refractory_filter:
+        packets = [packet for packet in refractory_filter]
+# the state persists across packets
+assert len(packets) > 1
+assert numpy.array_equal(numpy.concatenate(packets), events[keep])
+# the background activity filter keeps events whose 8-neighbourhood fired within dt_us
+print("faery.event_stream.Decoder.background_activity_filter")
+path = data_generated / "background_activity.es"
+random_generator = numpy.random.default_rng(0)
+events = numpy.zeros(100000, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(100000) * 10
+events["x"] = random_generator.integers(0, 4, size=len(events))
+events["y"] = random_generator.integers(0, 3, size=len(events))
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(4, 3),
+) as encoder:
+    encoder.write(events)
+pixel_to_last_t = {}
+keep = numpy.zeros(len(events), dtype=bool)
+for index, (t, x, y) in enumerate(
+    zip(events["t"].tolist(), events["x"].tolist(), events["y"].tolist())
+):
+    keep[index] = any(
+        (x + dx, y + dy) in pixel_to_last_t
+        and t <= pixel_to_last_t[(x + dx, y + dy)] + 50
+        for dx in (-1, 0, 1)
+        for dy in (-1, 0, 1)
+        if dx != 0 or dy != 0
+    )
+    pixel_to_last_t[(x, y)] = t
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    with decoder.background_activity_filter(dt_us=50) as background_activity_filter:
+        packets = [packet for packet in background_activity_filter]
+# the state persists across packets
+assert len(packets) > 1
+assert 0 < numpy.count_nonzero(keep) < len(events)
+assert numpy.array_equal(numpy.concatenate(packets), events[keep])
+
+for file in assets.files:
+    if file.format not in ("es-atis", "es-color", "es-dvs"):
+        continue
+    print(f"faery.event_stream.Decoder.refractory_filter ({file.path.name})")
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        assert decoder.dimensions is not None
+        width = decoder.dimensions[0]
+        events = decoder.to_array()
+        with decoder.refractory_filter(period_us=1000) as refractory_filter:
+            filtered_events = numpy.concatenate([packet for packet in refractory_filter])
+    assert filtered_events.dtype == events.dtype
+    # every pixel keeps its first event and kept events are spaced by more than the period
+    pixels = filtered_events["x"] + filtered_events["y"].astype(numpy.uint64) * width
+    order = numpy.lexsort((filtered_events["t"], pixels))
+    same_pixel = numpy.diff(pixels[order]) == 0
+    gaps = numpy.diff(filtered_events["t"][order].astype(numpy.int64))
+    assert numpy.all(gaps[same_pixel] > 1000), f"{file.path.name=}"
+    assert numpy.array_equal(
+        numpy.unique(pixels),
+        numpy.unique(events["x"] + events["y"].astype(numpy.uint64) * width),
+    ), f"{file.path.name=}"
+
+# downsampling divides coordinates (events that collapse onto the same pixel are all kept)
+print("faery.event_stream.Decoder downsample")
+path = data_generated / "downsample.es"
+events = numpy.zeros(6, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(6)
+events["x"] = [0, 2, 3, 4, 30, 31]
+events["y"] = [0, 1, 2, 3, 14, 15]
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(32, 16),
+) as encoder:
+    encoder.write(events)
+for downsample, dimensions, expected_xs, expected_ys in (
+    (1, (32, 16), [0, 2, 3, 4, 30, 31], [0, 1, 2, 3, 14, 15]),
+    (3, (11, 6), [0, 0, 1, 1, 10, 10], [0, 0, 0, 1, 4, 5]),
+    (4, (8, 4), [0, 0, 0, 1, 7, 7], [0, 0, 0, 0, 3, 3]),
+):
+    with faery.event_stream.Decoder(path, t0=0, downsample=downsample) as decoder:
+        assert decoder.dimensions == dimensions, f"{downsample=}"
+        decoded_events = numpy.concatenate([packet for packet in decoder])
+        assert numpy.array_equal(decoder.to_array(), decoded_events), f"{downsample=}"
+    assert decoded_events["x"].tolist() == expected_xs, f"{downsample=}"
+    assert decoded_events["y"].tolist() == expected_ys, f"{downsample=}"
+    assert numpy.array_equal(decoded_events["t"], events["t"]), f"{downsample=}"
+try:
+    faery.event_stream.Decoder(path, t0=0, downsample=0)
+    raise AssertionError("Decoder accepted downsample=0")
+except ValueError:
+    pass
+
+print("faery.event_stream.Decoder flip_x, flip_y, and rotate")
+path = data_generated / "corners.es"
+events = numpy.zeros(4, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(4)
+events["x"] = [0, 31, 0, 31]
+events["y"] = [0, 0, 15, 15]
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(32, 16),
+) as encoder:
+    encoder.write(events)
+for flip_x, flip_y, rotate, dimensions, expected_corners in (
+    (False, False, 0, (32, 16), [(0, 0), (31, 0), (0, 15), (31, 15)]),
+    (True, False, 0, (32, 16), [(31, 0), (0, 0), (31, 15), (0, 15)]),
+    (False, True, 0, (32, 16), [(0, 15), (31, 15), (0, 0), (31, 0)]),
+    (True, True, 0, (32, 16), [(31, 15), (0, 15), (31, 0), (0, 0)]),
+    (False, False, 90, (16, 32), [(15, 0), (15, 31), (0, 0), (0, 31)]),
+    (False, False, 180, (32, 16), [(31, 15), (0, 15), (31, 0), (0, 0)]),
+    (False, False, 270, (16, 32), [(0, 31), (0, 0), (15, 31), (15, 0)]),
+    (True, False, 90, (16, 32), [(15, 31), (15, 0), (0, 31), (0, 0)]),
+    (False, True, 270, (16, 32), [(15, 31), (15, 0), (0, 31), (0, 0)]),
+):
+    parameters = f"{flip_x=}, {flip_y=}, {rotate=}"
+    with faery.event_stream.Decoder(
+        path,
+        t0=0,
+        flip_x=flip_x,
+        flip_y=flip_y,
+        rotate=rotate,
+    ) as decoder:
+        assert decoder.dimensions == dimensions, parameters
+        decoded_events = numpy.concatenate([packet for packet in decoder])
+        assert numpy.array_equal(decoder.to_array(), decoded_events), parameters
+    assert (
+        list(zip(decoded_events["x"].tolist(), decoded_events["y"].tolist()))
+        == expected_corners
+    ), parameters
+with faery.event_stream.Decoder(
+    path, t0=0, downsample=2, flip_x=True, rotate=90
+) as decoder:
+    assert decoder.dimensions == (8, 16)
+    decoded_events = numpy.concatenate([packet for packet in decoder])
+assert list(zip(decoded_events["x"].tolist(), decoded_events["y"].tolist())) == [
+    (7, 15),
+    (7, 0),
+    (0, 15),
+    (0, 0),
+]
+try:
+    faery.event_stream.Decoder(path, t0=0, rotate=45)
+    raise AssertionError("Decoder accepted rotate=45")
+except ValueError:
+    pass
+
+# to_csv writes one line per event, OFF events are 0 or -1
+print("faery.event_stream.Decoder.to_csv")
+events = numpy.zeros(3, dtype=faery.DVS_DTYPE)
+events["t"] = [1, 5, 1000000]
+events["x"] = [0, 31, 7]
+events["y"] = [15, 0, 3]
+events["on"] = [True, False, True]
+path = data_generated / "to_csv.es"
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(32, 16),
+) as encoder:
+    encoder.write(events)
+for signed_polarity, off in ((False, "0"), (True, "-1")):
+    csv_path = data_generated / f"to_csv_{signed_polarity}.csv"
+    with faery.event_stream.Decoder(path, t0=0) as decoder:
+        decoder.to_csv(csv_path, signed_polarity=signed_polarity)
+        assert len(list(decoder)) > 0
+    assert csv_path.read_text().splitlines() == [
+        "t,x,y,on",
+        "1,0,15,1",
+        f"5,31,0,{off}",
+        "1000000,7,3,1",
+    ], f"{signed_polarity=}"
+with faery.event_stream.Decoder(path, t0=0, flip_x=True) as decoder:
+    decoder.to_csv(data_generated / "to_csv_flip_x.csv")
+assert (data_generated / "to_csv_flip_x.csv").read_text().splitlines()[1] == "1,31,15,1"
+
+# save_npy writes a file that numpy.load reads with the DVS dtype
+print("faery.event_stream.Decoder.save_npy")
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        decoder.save_npy(data_generated / f"{file.path.stem}.npy")
+        events = decoder.to_array()
+    loaded_events = numpy.load(data_generated / f"{file.path.stem}.npy")
+    assert loaded_events.dtype == faery.DVS_DTYPE
+    assert numpy.array_equal(loaded_events, events)
+with faery.event_stream.Decoder(path, t0=0, flip_x=True) as decoder:
+    decoder.save_npy(data_generated / "save_npy_flip_x.npy")
+    assert numpy.array_equal(
+        numpy.load(data_generated / "save_npy_flip_x.npy"), decoder.to_array()
+    )
+
+# the progress callback is throttled and ends with (bytes_total, bytes_total)
+print("faery.event_stream.Decoder.set_progress_callback")
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    for method in ("to_array", "to_csv", "save_npy"):
+        calls = []
+        with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+            decoder.set_progress_callback(lambda *arguments: calls.append(arguments))
+            if method == "to_array":
+                decoder.to_array()
+            else:
+                getattr(decoder, method)(data_generated / f"progress.{method}")
+        bytes_total = file.path.stat().st_size
+        assert 0 < len(calls) <= 101, f"{method=}, {len(calls)=}"
+        assert calls[-1] == (bytes_total, bytes_total)
+        assert all(a[0] < b[0] for a, b in zip(calls, calls[1:]))
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    try:
+        decoder.set_progress_callback(1)
+        raise AssertionError("set_progress_callback accepted a non-callable")
+    except ValueError:
+        pass
+    decoder.set_progress_callback(None)
+    decoder.to_array()
+
+# statistics aggregates the whole file in a single scan
+print("faery.event_stream.Decoder.statistics")
+events = numpy.zeros(5, dtype=faery.DVS_DTYPE)
+events["t"] = [1000, 1200, 1999, 2000, 1001000]
+events["x"] = [3, 7, 3, 5, 4]
+events["y"] = [2, 9, 2, 4, 6]
+events["on"] = [True, False, True, True, False]
+path = data_generated / "statistics.es"
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(32, 16),
+) as encoder:
+    encoder.write(events)
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    statistics = decoder.statistics()
+    assert len(list(decoder)) > 0
+assert statistics == {
+    "event_count": 5,
+    "on_count": 3,
+    "off_count": 2,
+    "duration": 1000000,
+    "mean_rate": 5.0,
+    "peak_events_per_ms": 3,
+    "active_pixels": 4,
+    "bounding_box": (3, 2, 5, 8),
+}, statistics
+
+# time_histogram bins start at the first event, the polarity columns are OFF and ON
+print("faery.event_stream.Decoder.time_histogram")
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    histogram = decoder.time_histogram(1000)
+    polarity_histogram = decoder.time_histogram(500000, polarity=True)
+    try:
+        decoder.time_histogram(0)
+        raise AssertionError("time_histogram accepted bin_us=0")
+    except ValueError:
+        pass
+assert histogram.dtype == numpy.int64
+assert histogram.shape == (1001,)
+assert histogram[:2].tolist() == [3, 1] and histogram[-1] == 1 and histogram.sum() == 5
+assert polarity_histogram.tolist() == [[1, 3], [0, 0], [1, 0]]
+
+# from_bytes decodes an in-memory file, metadata scans use the same buffer
+print("faery.event_stream.Decoder.from_bytes")
+for file in assets.files:
+    if not file.format.startswith("es-"):
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        event_count = decoder.event_count()
+        duration = decoder.duration()
+        packets = list(decoder)
+    with faery.event_stream.Decoder.from_bytes(file.path.read_bytes(), t0=0) as decoder:
+        assert decoder.event_count() == event_count
+        assert decoder.duration() == duration
+        assert len(decoder.to_array()) == event_count
+        memory_packets = list(decoder)
+    assert len(memory_packets) == len(packets)
+    if file.format != "es-generic":
+        for packet, memory_packet in zip(packets, memory_packets):
+            assert numpy.array_equal(packet, memory_packet)
+
+# to_dataframe has the same columns as to_array (pandas is optional)
+print("faery.event_stream.Decoder.to_dataframe")
+try:
+    import pandas
+except ImportError:
+    pandas = None
+for file in assets.files:
+    if file.format not in ("es-atis", "es-color", "es-dvs"):
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        events = decoder.to_array()
+        if pandas is None:
+            try:
+                decoder.to_dataframe()
+                raise AssertionError("to_dataframe succeeded without pandas")
+            except ImportError:
+                pass
+            continue
+        dataframe = decoder.to_dataframe()
+    assert len(dataframe) == len(events)
+    for name in events.dtype.names:
+        expected = events[name]
+        if name in ("r", "g", "b"):
+            # to_array stores the color bytes in boolean fields
+            expected = expected.view(numpy.uint8)
+        assert numpy.array_equal(dataframe[name].to_numpy(), expected), name
+
+# to_torch returns one tensor per field (torch is optional)
+print("faery.event_stream.Decoder.to_torch")
+try:
+    import torch
+except ImportError:
+    torch = None
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        events = decoder.to_array()
+        if torch is None:
+            try:
+                decoder.to_torch()
+                raise AssertionError("to_torch succeeded without torch")
+            except ImportError:
+                pass
+            continue
+        tensors = decoder.to_torch()
+    assert tensors["t"].dtype == torch.int64
+    assert tensors["x"].dtype == torch.int16 and tensors["y"].dtype == torch.int16
+    assert tensors["polarity"].dtype == torch.bool
+    for name, field in (("t", "t"), ("x", "x"), ("y", "y"), ("polarity", "on")):
+        assert numpy.array_equal(tensors[name].numpy(), events[field]), name
+
+# chunks yields one array per window, including empty windows
+print("faery.event_stream.Decoder.chunks")
+events = numpy.zeros(5, dtype=faery.DVS_DTYPE)
+events["t"] = [100, 110, 119, 125, 170]
+path = data_generated / "chunks.es"
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(32, 16),
+) as encoder:
+    encoder.write(events)
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    chunks = list(decoder.chunks(20))
+assert [chunk["t"].tolist() for chunk in chunks] == [
+    [100, 110, 119],
+    [125],
+    [],
+    [170],
+], [chunk["t"].tolist() for chunk in chunks]
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        events = decoder.to_array()
+        chunks = list(decoder.chunks(10000))
+    assert numpy.array_equal(numpy.concatenate(chunks), events)
+    for index, chunk in enumerate(chunks):
+        window_start = int(events["t"][0]) + index * 10000
+        assert numpy.all(chunk["t"] >= window_start)
+        assert numpy.all(chunk["t"] < window_start + 10000)
+try:
+    faery.event_stream.Decoder(path, t0=0).chunks(0)
+    raise AssertionError("chunks accepted duration_us=0")
+except ValueError:
+    pass
+
+# chunks_by_count splits packets, only the last chunk is shorter
+print("faery.event_stream.Decoder.chunks_by_count")
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        events = decoder.to_array()
+        # count must not divide the number of events
+        count = 997 if len(events) % 997 != 0 else 991
+        chunks = list(decoder.chunks_by_count(count))
+    assert [len(chunk) for chunk in chunks] == [count] * (len(events) // count) + [
+        len(events) % count
+    ]
+    assert numpy.array_equal(numpy.concatenate(chunks), events)
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    assert [chunk["t"].tolist() for chunk in decoder.chunks_by_count(2)] == [
+        [100, 110],
+        [119, 125],
+        [170],
+    ]
+
+# seek_bytes resumes decoding at an offset returned by tell
+print("faery.event_stream.Decoder.tell/seek_bytes")
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        header_offset = decoder.tell()
+        next(decoder)
+        offset = decoder.tell()
+        assert offset > header_offset
+        remaining = list(decoder)
+        end_offset = decoder.tell()
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        decoder.seek_bytes(offset)
+        assert decoder.tell() == offset
+        resumed = list(decoder)
+    assert numpy.array_equal(numpy.concatenate(resumed), numpy.concatenate(remaining))
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        for invalid_offset in (header_offset - 1, end_offset + 1):
+            try:
+                decoder.seek_bytes(invalid_offset)
+                raise AssertionError(f"seek_bytes accepted offset={invalid_offset}")
+            except RuntimeError:
+                pass
+
+# gzip-compressed files are decompressed transparently, tell and seek_bytes are disabled
+print("faery.event_stream.Decoder gzip")
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    gzip_path = data_generated / f"{file.path.name}.gz"
+    gzip_path.write_bytes(gzip.compress(file.path.read_bytes()))
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        events = decoder.to_array()
+    with faery.event_stream.Decoder(gzip_path, t0=0) as decoder:
+        assert numpy.array_equal(numpy.concatenate(list(decoder)), events)
+        assert numpy.array_equal(decoder.to_array(), events)
+        for call in (decoder.tell, lambda: decoder.seek_bytes(0)):
+            try:
+                call()
+                raise AssertionError("a gzip decoder accepted tell or seek_bytes")
+            except RuntimeError as error:
+                assert "gzip" in str(error)
+    assert numpy.array_equal(faery.stream_from_file(gzip_path).to_array(), events)
+
+# a file cut off in the middle of an event raises TruncatedFileError after the complete events
+print("faery.event_stream.Decoder TruncatedFileError")
+for file in assets.files:
+    if file.format != "es-dvs":
+        continue
+    with faery.event_stream.Decoder(file.path, t0=0) as decoder:
+        events = decoder.to_array()
+    path = data_generated / f"truncated_{file.path.name}"
+    with open(path, "wb") as output:
+        output.write(file.path.read_bytes()[:-1])
+    packets = []
+    try:
+        with faery.event_stream.Decoder(path, t0=0) as decoder:
+            for packet in decoder:
+                packets.append(packet)
+        raise AssertionError("the truncated file did not raise TruncatedFileError")
+    except faery.TruncatedFileError as error:
+        assert isinstance(error, EOFError)
+    assert numpy.array_equal(numpy.concatenate(packets), events[:-1])
+
+# split_atis returns change detection and exposure events as two DVS arrays
+print("faery.event_stream.Decoder split_atis")
+atis_path = next(file.path for file in assets.files if file.format == "es-atis")
+with faery.event_stream.Decoder(atis_path, t0=0) as decoder:
+    events = decoder.to_array()
+with faery.event_stream.Decoder(atis_path, t0=0, split_atis=True) as decoder:
+    split_packets = [packet for packet in decoder]
+for name, exposure in (("cd", False), ("exposure", True)):
+    split_events = numpy.concatenate([packet[name] for packet in split_packets])
+    expected_events = events[events["exposure"] == exposure]
+    assert split_events.dtype == faery.DVS_DTYPE
+    for field in ("t", "x", "y"):
+        assert numpy.array_equal(split_events[field], expected_events[field])
+    assert numpy.array_equal(split_events["on"], expected_events["polarity"])
+
+# atis_frames maps exposure intervals to k / interval, pixels keep their value between measurements
+print("faery.event_stream.Decoder.atis_frames")
+atis_events = numpy.zeros(5, dtype=events.dtype)
+atis_events["t"] = [0, 50, 100, 1050, 1200]
+atis_events["x"] = [1, 2, 1, 2, 3]
+atis_events["y"] = [0, 1, 0, 1, 1]
+atis_events["exposure"] = [True, True, True, True, False]
+atis_events["polarity"] = [False, False, True, True, True]
+with faery.event_stream.Encoder(
+    path=data_generated / "atis_frames.es",
+    event_type="atis",
+    zero_t0=False,
+    dimensions=(4, 2),
+) as encoder:
+    encoder.write(atis_events)
+with faery.event_stream.Decoder(data_generated / "atis_frames.es", t0=0) as decoder:
+    with decoder.atis_frames(frame_duration_us=500) as atis_frames:
+        frames = [(t, frame) for t, frame in atis_frames]
+assert [t for t, _ in frames] == [500, 1000, 1500]
+for _, frame in frames:
+    assert frame.shape == (2, 4) and frame.dtype == numpy.uint8
+assert frames[0][1].tolist() == [[0, 255, 0, 0], [0, 0, 0, 0]]
+assert numpy.array_equal(frames[1][1], frames[0][1])
+assert frames[2][1].tolist() == [[0, 255, 0, 0], [0, 0, 26, 0]]
+
+# close flushes the file, decoding it returns the written events byte for byte
+print("faery.event_stream.Encoder.close")
+dvs_events = numpy.zeros(1000, dtype=faery.DVS_DTYPE)
+dvs_events["t"] = numpy.arange(1000) * 7 // 3
+dvs_events["x"] = numpy.arange(1000) % 320
+dvs_events["y"] = numpy.arange(1000) % 240
+dvs_events["on"] = numpy.arange(1000) % 3 == 0
+encoder = faery.event_stream.Encoder(
+    path=data_generated / "close.es",
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(320, 240),
+)
+encoder.write(dvs_events[:400])
+encoder.write(dvs_events[400:])
+encoder.close()
+assert encoder.closed
+with faery.event_stream.Decoder(data_generated / "close.es", t0=0) as decoder:
+    assert decoder.dimensions == (320, 240)
+    assert decoder.to_array().tobytes() == dvs_events.tobytes()
+with faery.event_stream.Encoder(
+    path=data_generated / "close.es",
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(320, 240),
+) as encoder:
+    try:
+        encoder.write(dvs_events[::-1])
+        raise AssertionError("decreasing timestamps did not raise")
+    except RuntimeError:
+        pass
+
+# decode_many returns the same arrays as to_array, in the order of paths
+print("faery.event_stream.decode_many")
+paths = [
+    file.path
+    for file in assets.files
+    if file.format in ("es-atis", "es-color", "es-dvs")
+]
+paths = paths + paths[::-1]
+expected = []
+for file_path in paths:
+    with faery.event_stream.Decoder(file_path, t0=0) as decoder:
+        expected.append(decoder.to_array())
+for workers in (0, 1, 3):
+    arrays = faery.event_stream.decode_many(paths, workers=workers)
+    assert len(arrays) == len(expected)
+    for array, expected_array in zip(arrays, expected):
+        assert array.dtype == expected_array.dtype
+        assert numpy.array_equal(array, expected_array)
+
+# field_names renames the fields, t_dtype and xy_dtype change their types
+print("faery.event_stream.Decoder field_names, t_dtype, and xy_dtype")
+events = numpy.zeros(4, dtype=faery.DVS_DTYPE)
+events["t"] = [1, 2, 3, 4]
+events["x"] = [0, 255, 7, 100]
+events["y"] = [0, 1, 2, 3]
+events["on"] = [True, False, True, False]
+for dimensions in ((256, 4), (257, 4)):
+    with faery.event_stream.Encoder(
+        path=data_generated / f"layout_{dimensions[0]}.es",
+        event_type="dvs",
+        zero_t0=False,
+        dimensions=dimensions,
+    ) as encoder:
+        encoder.write(events)
+path = data_generated / "layout_256.es"
+with faery.event_stream.Decoder(
+    path, t0=0, field_names={"t": "ts", "on": "p"}
+) as decoder:
+    packet = numpy.concatenate([packet for packet in decoder])
+    array = decoder.to_array()
+assert packet.dtype.names == ("ts", "x", "y", "p")
+assert array.dtype == packet.dtype
+assert numpy.array_equal(packet["ts"], events["t"])
+assert numpy.array_equal(packet["p"], events["on"])
+with faery.event_stream.Decoder(
+    path, t0=0, field_names={"on": "polarity"}, t_dtype="int64", xy_dtype="uint8"
+) as decoder:
+    packet = numpy.concatenate([packet for packet in decoder])
+    array = decoder.to_array()
+assert packet.dtype == numpy.dtype(
+    [("t", "<i8"), ("x", "u1"), ("y", "u1"), ("polarity", "?")]
+)
+assert numpy.array_equal(array, packet)
+assert packet["x"].tolist() == [0, 255, 7, 100]
+assert packet["t"].tolist() == [1, 2, 3, 4]
+for path, options in (
+    (data_generated / "layout_257.es", {"xy_dtype": "uint8"}),
+    (path, {"xy_dtype": "uint32"}),
+    (path, {"field_names": {"exposure": "e"}}),
+    (path, {"field_names": {"t": "x"}}),
+):
+    try:
+        faery.event_stream.Decoder(path, t0=0, **options)
+        raise AssertionError(f"Decoder accepted {options}")
+    except ValueError:
+        pass
+
+# merge interleaves the events of several files in timestamp order
+print("faery.event_stream.merge")
+camera_events = []
+for camera_id, (dimensions, ts) in enumerate(
+    (((32, 16), [0, 2, 2, 5, 9]), ((8, 24), [1, 2, 3, 4, 10, 11, 12]))
+):
+    events = numpy.zeros(len(ts), dtype=faery.DVS_DTYPE)
+    events["t"] = ts
+    events["x"] = numpy.arange(len(ts)) % 8
+    events["y"] = numpy.arange(len(ts)) % 16
+    events["on"] = numpy.arange(len(ts)) % 2 == 0
+    with faery.event_stream.Encoder(
+        path=data_generated / f"merge_{camera_id}.es",
+        event_type="dvs",
+        zero_t0=False,
+        dimensions=dimensions,
+    ) as encoder:
+        encoder.write(events[:3])
+        encoder.write(events[3:])
+    camera_events.append(events)
+decoders = [
+    faery.event_stream.Decoder(data_generated / f"merge_{camera_id}.es", t0=0)
+    for camera_id in range(2)
+]
+with faery.event_stream.merge(decoders, packet_size=4) as merged:
+    assert merged.dimensions == (32, 24)
+    packets = [packet for packet in merged]
+assert [len(packet) for packet in packets] == [4, 4, 4]
+merged_events = numpy.concatenate(packets)
+assert merged_events["t"].tolist() == [0, 1, 2, 2, 2, 3, 4, 5, 9, 10, 11, 12]
+assert merged_events["camera_id"].tolist() == [0, 1, 0, 0, 1, 1, 1, 0, 0, 1, 1, 1]
+for camera_id, events in enumerate(camera_events):
+    camera_merged_events = merged_events[merged_events["camera_id"] == camera_id]
+    for field in ("t", "x", "y", "on"):
+        assert numpy.array_equal(camera_merged_events[field], events[field])
+for decoder in decoders:
+    decoder.__exit__(None, None, None)
+
+# mask_pixels drops the events of the given pixels, pixels outside the sensor raise an error
+print("faery.event_stream.Decoder mask_pixels")
+masked_pixels = [(1, 1), (3, 3)]
+with faery.event_stream.Decoder(
+    data_generated / "merge_0.es", t0=0, mask_pixels=masked_pixels
+) as decoder:
+    masked_events = numpy.concatenate([packet for packet in decoder])
+events = camera_events[0]
+kept = numpy.array(
+    [(x, y) not in masked_pixels for x, y in zip(events["x"], events["y"])]
+)
+for field in ("t", "x", "y", "on"):
+    assert numpy.array_equal(masked_events[field], events[kept][field])
+try:
+    faery.event_stream.Decoder(data_generated / "merge_0.es", t0=0, mask_pixels=[(32, 0)])
+    raise AssertionError("a pixel outside the sensor must raise ValueError")
+except ValueError:
+    pass
+
+# remap moves each event to the pixel given by the table, events mapped outside the sensor are dropped
+print("faery.event_stream.Decoder remap")
+events = camera_events[0]
+identity = numpy.stack(numpy.meshgrid(numpy.arange(32), numpy.arange(16)), axis=-1)
+with faery.event_stream.Decoder(
+    data_generated / "merge_0.es", t0=0, remap=identity
+) as decoder:
+    remapped_events = numpy.concatenate([packet for packet in decoder])
+for field in ("t", "x", "y", "on"):
+    assert numpy.array_equal(remapped_events[field], events[field])
+swap = identity[:, :, ::-1]
+with faery.event_stream.Decoder(
+    data_generated / "merge_0.es", t0=0, remap=swap
+) as decoder:
+    remapped_events = numpy.concatenate([packet for packet in decoder])
+inside = events["x"] < 16
+assert numpy.array_equal(remapped_events["x"], events["y"][inside])
+assert numpy.array_equal(remapped_events["y"], events["x"][inside])
+try:
+    faery.event_stream.Decoder(data_generated / "merge_0.es", t0=0, remap=swap[:, :, :1])
+    raise AssertionError("a table with the wrong shape must raise ValueError")
+except ValueError:
+    pass
+
+# median_denoise keeps events close to the median of their neighbours' last timestamps
+print("faery.event_stream.Decoder median_denoise")
+path = data_generated / "median_denoise.es"
+events = numpy.zeros(5 * 64 + 16, dtype=faery.DVS_DTYPE)
+# a textured patch moving in place, every pixel of an 8 x 8 square fires every 100 µs
+events["t"][: 5 * 64] = numpy.repeat(numpy.arange(5) * 100 + 1000, 64)
+events["x"][: 5 * 64] = numpy.tile(numpy.repeat(numpy.arange(8), 8), 5)
+events["y"][: 5 * 64] = numpy.tile(numpy.arange(8), 5 * 8)
+# isolated noise far from the patch
+events["t"][5 * 64 :] = numpy.arange(16) * 50 + 1025
+events["x"][5 * 64 :] = numpy.arange(16) * 2 + 16
+events["y"][5 * 64 :] = 20
+events = events[numpy.argsort(events["t"], kind="stable")]
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(64, 64),
+) as encoder:
+    encoder.write(events)
+with faery.event_stream.Decoder(path, t0=0, median_denoise=200) as decoder:
+    denoised_events = numpy.concatenate([packet for packet in decoder])
+assert numpy.all(denoised_events["x"] < 8)
+# the first pass primes the surface, corners have only 3 neighbours in the patch
+assert len(denoised_events) == 4 * (64 - 4), f"{len(denoised_events)=}"
+with faery.event_stream.Decoder(path, t0=0, median_denoise=200) as decoder:
+    # reset clears the surface, the second read keeps the same events
+    first_events = numpy.concatenate([packet for packet in decoder])
+    decoder.reset()
+    assert numpy.array_equal(numpy.concatenate([packet for packet in decoder]), first_events)
+
+# reset rewinds the decoder and clears the filters' per-pixel state
+print("faery.event_stream.Decoder.reset")
+path = data_generated / "refractory.es"
+with faery.event_stream.Decoder(path, t0=0) as decoder:
+    expected_events = numpy.concatenate([packet for packet in decoder])
+    decoder.reset()
+    events = numpy.concatenate([packet for packet in decoder])
+    assert numpy.array_equal(events, expected_events)
+    with decoder.refractory_filter(period_us=25) as refractory_filter:
+        expected_events = numpy.concatenate([packet for packet in refractory_filter])
+        refractory_filter.reset()
+        events = numpy.concatenate([packet for packet in refractory_filter])
+    assert numpy.array_equal(events, expected_events)
+
+# enumerate counts buffer_size reads, including the packets skipped because they are empty after filtering
+print("faery.event_stream.Decoder.enumerate")
+path = data_generated / "enumerate.es"
+events = numpy.zeros(100000, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(100000) * 10
+# (0, 0) fires alone during the first half, hence filters and the region of interest empty the first packets
+events["x"][50000:] = numpy.arange(50000) % 2
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="dvs",
+    zero_t0=False,
+    dimensions=(2, 1),
+) as encoder:
+    encoder.write(events)
+
+
+def enumerate_filters(
+    decoder: faery.event_stream.Decoder,
+) -> list[list[tuple[int, int]]]:
+    with decoder.hot_pixel_filter(threshold_hz=50000.0) as hot_pixel_filter:
+        hot_pixel_indices = [
+            (index, len(packet)) for index, packet in hot_pixel_filter.enumerate()
+        ]
+    with decoder.refractory_filter(period_us=25) as refractory_filter:
+        refractory_indices = [
+            (index, len(packet)) for index, packet in refractory_filter.enumerate()
+        ]
+    with decoder.background_activity_filter(dt_us=50) as background_activity_filter:
+        background_activity_indices = [
+            (index, len(packet))
+            for index, packet in background_activity_filter.enumerate()
+        ]
+    return [hot_pixel_indices, refractory_indices, background_activity_indices]
+
+
+read_counts = []
+for buffer_size in (1024, 4096):
+    with faery.event_stream.Decoder(
+        path, t0=0, skip_empty=False, buffer_size=buffer_size
+    ) as decoder:
+        indices = [index for index, _ in decoder.enumerate()]
+        assert indices == list(range(len(indices))), f"{indices=}"
+        read_counts.append(len(indices))
+        expected_filter_indices = [
+            [index for index, length in indices if length > 0]
+            for indices in enumerate_filters(decoder)
+        ]
+    with faery.event_stream.Decoder(path, t0=0, buffer_size=buffer_size) as decoder:
+        filter_indices = [
+            [index for index, _ in indices] for indices in enumerate_filters(decoder)
+        ]
+    assert filter_indices == expected_filter_indices, f"{buffer_size=}"
+    # the hot pixel and background activity filters drop every event of the first half
+    assert filter_indices[0][0] > 0, f"{filter_indices[0]=}"
+    assert filter_indices[2][0] > 0, f"{filter_indices[2]=}"
+    with faery.event_stream.Decoder(
+        path, t0=0, skip_empty=False, roi=(1, 0, 1, 1), buffer_size=buffer_size
+    ) as decoder:
+        expected_roi_indices = [
+            index for index, packet in decoder.enumerate() if len(packet) > 0
+        ]
+    with faery.event_stream.Decoder(
+        path, t0=0, roi=(1, 0, 1, 1), buffer_size=buffer_size
+    ) as decoder:
+        roi_indices = [index for index, _ in decoder.enumerate()]
+    assert roi_indices == expected_roi_indices, f"{roi_indices=}"
+    assert roi_indices[0] > 0, f"{roi_indices=}"
+    # seek_bytes counts the reads that it decodes
+    with faery.event_stream.Decoder(
+        path, t0=0, skip_empty=False, buffer_size=buffer_size
+    ) as decoder:
+        next(decoder)
+        offset = decoder.tell()
+        decoder.seek_bytes(offset)
+        assert next(decoder.enumerate())[0] == 1
+assert read_counts[0] > read_counts[1], f"{read_counts=}"
+
+# override_type decodes mislabeled files, declared_type reports the header's type
+print("faery.event_stream.Decoder override_type")
+data = bytearray((data_generated / "merge_0.es").read_bytes())
+assert data[15] == 1
+data[15] = 0
+with faery.event_stream.Decoder.from_bytes(bytes(data), t0=0, override_type="dvs") as decoder:
+    assert decoder.event_type == "dvs"
+    assert decoder.declared_type == "generic"
+    assert decoder.dimensions == (32, 16)
+    events = numpy.concatenate([packet for packet in decoder])
+assert numpy.array_equal(events, camera_events[0])
+with faery.event_stream.Decoder(data_generated / "merge_0.es", t0=0) as decoder:
+    assert decoder.event_type == decoder.declared_type == "dvs"
+try:
+    faery.event_stream.Decoder.from_bytes(
+        b"Event Stream\x02\x00\x00\x00\x00\x00\x00\x00", t0=0, override_type="dvs"
+    )
+    raise AssertionError("an override without dimensions must raise an error")
+except RuntimeError:
+    pass
+try:
+    faery.event_stream.Decoder.from_bytes(bytes(data), t0=0, override_type="generic")
+    raise AssertionError("override_type must be dvs, atis, or color")
+except ValueError:
+    pass
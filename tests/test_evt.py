@@ -0,0 +1,122 @@
+import pathlib
+
+import faery
+import numpy
+
+import assets
+
+data_generated = pathlib.Path(__file__).resolve().parent / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+# sensor models resolve to dimensions when the header has no geometry
+for file in assets.files:
+    if file.format not in ("evt2", "evt3"):
+        continue
+    print(f"faery.evt.Decoder dimensions_source ({file.path.name})")
+    with faery.evt.Decoder(file.path) as decoder:
+        assert decoder.dimensions == file.dimensions
+        assert decoder.dimensions_source == (
+            "database" if file.format == "evt2" else "header"
+        ), f"{decoder.dimensions_source=}"
+    if file.format == "evt3":
+        # remove the explicit geometry, the model lines (IMX636, generation 4.2) remain
+        data = file.path.read_bytes()
+        lines = []
+        offset = 0
+        while data[offset : offset + 1] == b"%":
+            end = data.index(b"\n", offset) + 1
+            line = data[offset:end]
+            if not line.startswith(b"% geometry") and not line.startswith(
+                b"% format"
+            ):
+                lines.append(line)
+            offset = end
+        path = data_generated / f"{file.path.stem}-model-only.raw"
+        path.write_bytes(b"".join(lines) + data[offset:])
+        with faery.evt.Decoder(path) as decoder:
+            assert decoder.dimensions == (1280, 720)
+            assert decoder.dimensions_source == "database"
+        with faery.evt.Decoder(
+            path, dimensions_fallback=(640, 480)
+        ) as decoder:
+            assert decoder.dimensions == (1280, 720)
+
+# EVT3 time high rollovers carry over between packets (the padding spans several read buffers)
+print("faery.evt.Decoder EVT3 time high rollover")
+words = [0x8800, 0x8FFF, 0x6005, 0x0001, 0x2000 | (1 << 11) | 3]
+words += [0x7000] * 40000
+words += [0x8000, 0x6002, 0x0002, 0x2004, 0x3000 | (1 << 11), 0x5005]
+path = data_generated / "evt3_rollover.raw"
+path.write_bytes(
+    b"% evt 3.0\n% format EVT3;height=4;width=16\n"
+    + b"".join(word.to_bytes(2, "little") for word in words)
+)
+with faery.evt.Decoder(path) as decoder:
+    assert decoder.dimensions == (16, 4)
+    events = numpy.concatenate(
+        [packet["events"] for packet in decoder if "events" in packet]
+    )
+assert events.tolist() == [
+    ((4095 << 12) | 5, 3, 1, True),
+    ((1 << 24) | 2, 4, 2, False),
+    ((1 << 24) | 2, 0, 2, True),
+    ((1 << 24) | 2, 2, 2, True),
+], events.tolist()
+
+# enumerate yields the index of the buffer_size read, skipped empty packets included
+print("faery.evt.Decoder.enumerate")
+# the padding spans several read buffers, hence the decoder reads empty packets
+words = [0x8000, 0x6005, 0x0001, 0x2000 | 3]
+words += [0x7000] * 100000
+words += [0x8001, 0x6002, 0x0002, 0x2004]
+path = data_generated / "evt3_enumerate.raw"
+path.write_bytes(
+    b"% evt 3.0\n% format EVT3;height=4;width=16\n"
+    + b"".join(word.to_bytes(2, "little") for word in words)
+)
+with faery.evt.Decoder(path, skip_empty=False) as decoder:
+    non_empty_indices = [
+        index for index, packet in enumerate(decoder) if len(packet) > 0
+    ]
+with faery.evt.Decoder(path) as decoder:
+    indices = [index for index, packet in decoder.enumerate()]
+assert non_empty_indices[-1] >= len(non_empty_indices)
+assert indices == non_empty_indices, f"{indices=}, {non_empty_indices=}"
+
+# EVT2 CD and external trigger words share the running time high
+print("faery.evt.Decoder EVT2 triggers")
+words = [
+    (0b1000 << 28) | 1,
+    (0b0001 << 28) | (5 << 22) | (3 << 11) | 2,
+    (0b1010 << 28) | (7 << 22) | (4 << 8) | 1,
+    (0b1000 << 28) | 2,
+    (0b0000 << 28) | (1 << 11),
+    (0b1010 << 28) | (1 << 22) | (4 << 8),
+]
+path = data_generated / "evt2_triggers.raw"
+path.write_bytes(
+    b"% evt 2.0\n% format EVT2;height=4;width=8\n"
+    + b"".join(word.to_bytes(4, "little") for word in words)
+)
+with faery.evt.Decoder(path) as decoder:
+    packets = list(decoder)
+events = numpy.concatenate([packet["events"] for packet in packets])
+triggers = numpy.concatenate([packet["triggers"] for packet in packets])
+assert events.tolist() == [(69, 3, 2, True), (128, 1, 0, False)], events.tolist()
+assert triggers.tolist() == [(71, 4, True), (129, 4, False)], triggers.tolist()
+
+# reset rewinds the decoder and clears the time high state
+print("faery.evt.Decoder.reset")
+path = data_generated / "evt3_rollover.raw"
+with faery.evt.Decoder(path) as decoder:
+    expected_events = numpy.concatenate(
+        [packet["events"] for packet in decoder if "events" in packet]
+    )
+    decoder.reset()
+    indices = [index for index, packet in decoder.enumerate()]
+    decoder.reset()
+    events = numpy.concatenate(
+        [packet["events"] for packet in decoder if "events" in packet]
+    )
+assert indices[0] == 0
+assert events.tolist() == expected_events.tolist()
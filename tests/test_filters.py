@@ -1,6 +1,7 @@
 import pathlib
 
 import faery
+import numpy
 
 # time slice
 original_stream = faery.stream_from_file(
@@ -45,3 +46,110 @@ assert sliced_events_time_range == ("00:00:00.000544", "00:00:00.554545")
 sliced_stream = original_stream.event_slice(start=100000, end=300000)
 sliced_events = sliced_stream.to_array()
 assert len(sliced_events) == 200000
+
+
+# active range
+events = numpy.zeros(10000 + 2, dtype=faery.DVS_DTYPE)
+events["t"][0] = 100000
+events["t"][1:-1] = numpy.arange(1000000, 2000000, 100)
+events["t"][-1] = 3000000
+padded_stream = faery.stream_from_array(events, dimensions=(16, 16))
+active_range = padded_stream.active_range_us(min_rate=1000.0, window=0.01)
+assert active_range == (1000000, 2000000), f"{active_range=}"
+trimmed_events = padded_stream.trim(min_rate=1000.0, window=0.01).to_array()
+assert numpy.array_equal(trimmed_events, events[1:-1])
+
+
+# normalized coordinates
+normalized_events = faery.stream_from_file(
+    pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+    normalize_coordinates=True,
+).to_array()
+assert normalized_events.dtype == faery.NORMALIZED_DVS_DTYPE
+assert numpy.all(normalized_events["x"] >= 0.0) and numpy.all(normalized_events["x"] < 1.0)
+assert numpy.all(normalized_events["y"] >= 0.0) and numpy.all(normalized_events["y"] < 1.0)
+assert numpy.array_equal(
+    numpy.round(normalized_events["x"] * width).astype(numpy.uint16),
+    original_events["x"],
+)
+assert numpy.array_equal(
+    numpy.round(normalized_events["y"] * height).astype(numpy.uint16),
+    original_events["y"],
+)
+
+
+# record stride
+padded_events = faery.stream_from_file(
+    pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+    record_stride=16,
+).to_array()
+assert padded_events.dtype.itemsize == 16
+assert padded_events.dtype.names == faery.DVS_DTYPE.names
+for name in faery.DVS_DTYPE.names:
+    assert padded_events.dtype.fields[name][1] == faery.DVS_DTYPE.fields[name][1]
+    assert numpy.array_equal(padded_events[name], original_events[name])
+padded_normalized_events = faery.stream_from_file(
+    pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+    normalize_coordinates=True,
+    record_stride=24,
+).to_array()
+assert padded_normalized_events.dtype.itemsize == 24
+for name in faery.NORMALIZED_DVS_DTYPE.names:
+    assert numpy.array_equal(padded_normalized_events[name], normalized_events[name])
+try:
+    faery.stream_from_file(
+        pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+        record_stride=4,
+    ).to_array()
+    raise AssertionError("a record_stride smaller than the dtype must raise ValueError")
+except ValueError:
+    pass
+
+
+# coalesced packets
+native_packets = list(
+    faery.stream_from_file(pathlib.Path(__file__).resolve().parent / "data" / "dvs.es")
+)
+coalesced_packets = list(
+    faery.stream_from_file(
+        pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+        coalesce_packets=True,
+    )
+)
+assert len(coalesced_packets) == 1
+assert numpy.array_equal(coalesced_packets[0], original_events)
+max_events = max(len(packet) for packet in native_packets) * 3
+capped_packets = list(
+    faery.stream_from_file(
+        pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+        coalesce_packets=True,
+        coalesce_max_events=max_events,
+    )
+)
+assert len(capped_packets) < len(native_packets)
+assert all(len(packet) <= max_events for packet in capped_packets)
+assert numpy.array_equal(numpy.concatenate(capped_packets), original_events)
+
+
+# reverse order
+reversed_packets = list(
+    faery.stream_from_file(
+        pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+        reverse=True,
+    )
+)
+assert len(reversed_packets) == len(native_packets)
+for reversed_packet, native_packet in zip(reversed_packets, reversed(native_packets)):
+    assert numpy.array_equal(reversed_packet, native_packet[::-1])
+reversed_events = numpy.concatenate(reversed_packets)
+assert numpy.all(numpy.diff(reversed_events["t"].astype(numpy.int64)) <= 0)
+assert numpy.array_equal(reversed_events[::-1], original_events)
+assert (
+    faery.stream_from_file(
+        pathlib.Path(__file__).resolve().parent / "data" / "dvs.es",
+        reverse=True,
+    ).time_range_us()
+    == faery.stream_from_file(
+        pathlib.Path(__file__).resolve().parent / "data" / "dvs.es"
+    ).time_range_us()
+)
@@ -0,0 +1,52 @@
+import pathlib
+
+import faery
+import numpy
+
+data_generated = pathlib.Path(__file__).resolve().parent / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+# three files with different event rates, one of which lags far behind the others
+print("faery.MergeDecoder")
+paths = []
+expected = []
+for index, (count, period) in enumerate(((100000, 1), (20000, 5), (1000, 100))):
+    events = numpy.zeros(count, dtype=faery.DVS_DTYPE)
+    events["t"] = numpy.arange(count, dtype=numpy.uint64) * period
+    events["x"] = index
+    path = data_generated / f"merge_decoder_{index}.es"
+    with faery.event_stream.Encoder(
+        path=path,
+        event_type="dvs",
+        zero_t0=True,
+        dimensions=(16, 16),
+    ) as encoder:
+        encoder.write(events)
+    paths.append(path)
+    expected.append(events["t"])
+
+buffer_size = 2
+merged = list(faery.MergeDecoder(paths, buffer_size=buffer_size))
+for index, events in merged:
+    assert numpy.all(events["x"] == index)
+# the merged stream is globally sorted and each file's events are complete and in order
+merged_ts = numpy.concatenate([events["t"] for _, events in merged])
+assert numpy.all(numpy.diff(merged_ts.astype(numpy.int64)) >= 0)
+assert len(merged_ts) == sum(len(ts) for ts in expected)
+for index in range(len(paths)):
+    assert numpy.array_equal(
+        numpy.concatenate([events["t"] for file_index, events in merged if file_index == index]),
+        expected[index],
+    )
+
+# leaving the context manager early stops the reader threads
+print("faery.MergeDecoder close")
+with iter(faery.MergeDecoder(paths, buffer_size=buffer_size)) as iterator:
+    index, events = next(iterator)
+    assert index == 0 and events["t"][0] == 0
+assert not any(thread.is_alive() for thread in iterator.threads)
+try:
+    next(iterator)
+    raise AssertionError("a closed iterator must be exhausted")
+except StopIteration:
+    pass
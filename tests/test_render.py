@@ -0,0 +1,341 @@
+import pathlib
+
+import faery
+import numpy
+
+dirname = pathlib.Path(__file__).resolve().parent
+data_generated = dirname / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+# a red left half and a green right half
+print("faery.ColorRender")
+with faery.event_stream.Decoder(path=dirname / "data" / "color.es", t0=0) as decoder:
+    color_dtype = next(decoder).dtype
+width, height = 16, 16
+raw_events = numpy.zeros(
+    width * height,
+    dtype=[
+        ("t", "<u8"),
+        ("x", "<u2"),
+        ("y", "<u2"),
+        ("r", "u1"),
+        ("g", "u1"),
+        ("b", "u1"),
+    ],
+)
+raw_events["t"] = numpy.arange(width * height)
+raw_events["x"] = numpy.tile(numpy.arange(width), height)
+raw_events["y"] = numpy.repeat(numpy.arange(height), width)
+raw_events["r"] = numpy.where(raw_events["x"] < width // 2, 255, 0)
+raw_events["g"] = numpy.where(raw_events["x"] >= width // 2, 255, 0)
+path = data_generated / "bayer.es"
+with faery.event_stream.Encoder(
+    path=path,
+    event_type="color",
+    zero_t0=True,
+    dimensions=(width, height),
+) as encoder:
+    encoder.write(raw_events.view(color_dtype))
+for bayer_pattern in ("RGGB", "BGGR", "GRBG", "GBRG"):
+    frames = list(
+        faery.ColorRender(path, frame_duration=1.0, bayer_pattern=bayer_pattern)
+    )
+    assert len(frames) == 1
+    pixels = frames[0].pixels
+    assert pixels.shape == (height, width, 3)
+    # skip the columns next to the boundary, where bilinear interpolation mixes both halves
+    red = pixels[:, 0 : width // 2 - 2].reshape((-1, 3))
+    green = pixels[:, width // 2 + 2 :].reshape((-1, 3))
+    assert numpy.all(red == (255, 0, 0)), f"{bayer_pattern=}, {red=}"
+    assert numpy.all(green == (0, 255, 0)), f"{bayer_pattern=}, {green=}"
+
+# decayed pixel values, frames after the last event only decay
+print("faery.render.RenderIterator")
+events = numpy.zeros(3, dtype=faery.DVS_DTYPE)
+events["t"] = [0, 500, 1500]
+events["x"] = [0, 1, 2]
+events["y"] = [0, 0, 1]
+events["on"] = [True, False, True]
+frames = list(
+    faery.render.RenderIterator(
+        parent=iter([events]),
+        dimensions=(4, 2),
+        next_frame_t=1000,
+        frame_duration=1000,
+        frame_count=3,
+        decay="exponential",
+        tau=1000,
+    )
+)
+assert len(frames) == 3
+for index, frame in enumerate(frames):
+    frame_t = (index + 1) * 1000
+    expected = numpy.zeros((2, 4), dtype=numpy.float64)
+    expected[0, 0] = numpy.exp(-frame_t / 1000)
+    expected[0, 1] = -numpy.exp(-(frame_t - 500) / 1000)
+    if frame_t > 1500:
+        expected[1, 2] = numpy.exp(-(frame_t - 1500) / 1000)
+    assert frame.dtype == numpy.float64
+    assert numpy.allclose(frame, expected), f"{index=}, {frame=}, {expected=}"
+
+# colormaps map the decayed value (in [0, 1] with ignore_polarity) to RGB
+print("faery.render.RenderIterator colormap")
+events = numpy.zeros(2, dtype=faery.DVS_DTYPE)
+events["t"] = [0, 500]
+events["x"] = [0, 1]
+for colormap, low, middle, high in (
+    ("gray", (0, 0, 0), (128, 128, 128), (255, 255, 255)),
+    ("viridis", (68, 1, 84), (33, 145, 140), (253, 231, 37)),
+    ("jet", (0, 0, 128), (128, 255, 128), (128, 0, 0)),
+):
+    # linear decay, pixel 0 is fully decayed, pixel 1 is half-way, pixel 2 is untouched
+    (frame,) = faery.render.RenderIterator(
+        parent=iter([events]),
+        dimensions=(3, 1),
+        next_frame_t=1000,
+        frame_duration=1000,
+        frame_count=1,
+        decay="linear",
+        tau=1000,
+        ignore_polarity=True,
+        colormap=colormap,
+    )
+    assert frame.dtype == numpy.uint8 and frame.shape == (1, 3, 3)
+    assert tuple(frame[0, 0]) == low, f"{colormap=}, {frame[0, 0]=}"
+    assert tuple(frame[0, 1]) == middle, f"{colormap=}, {frame[0, 1]=}"
+    assert tuple(frame[0, 2]) == low, f"{colormap=}, {frame[0, 2]=}"
+    # step decay, the touched pixel is at the top of the colormap
+    (frame,) = faery.render.RenderIterator(
+        parent=iter([events[1:]]),
+        dimensions=(3, 1),
+        next_frame_t=1000,
+        frame_duration=1000,
+        frame_count=1,
+        decay="step",
+        tau=1000,
+        ignore_polarity=True,
+        colormap=colormap,
+    )
+    assert tuple(frame[0, 1]) == high, f"{colormap=}, {frame[0, 1]=}"
+
+# hand-computed values for each decay, ON events are positive and OFF events negative
+print("faery.render.RenderIterator decays")
+events = numpy.zeros(4, dtype=faery.DVS_DTYPE)
+events["t"] = [0, 250, 750, 900]
+events["x"] = [0, 1, 2, 3]
+events["on"] = [True, False, True, False]
+for decay, expected in (
+    (
+        "exponential",
+        [numpy.exp(-2.0), -numpy.exp(-1.5), numpy.exp(-0.5), -numpy.exp(-0.2), 0.0],
+    ),
+    ("linear", [0.0, 0.0, 0.5, -0.8, 0.0]),
+    ("step", [0.0, 0.0, 1.0, -1.0, 0.0]),
+):
+    (frame,) = faery.render.RenderIterator(
+        parent=iter([events]),
+        dimensions=(5, 1),
+        next_frame_t=1000,
+        frame_duration=1000,
+        frame_count=1,
+        decay=decay,
+        tau=500,
+    )
+    assert frame.shape == (1, 5)
+    assert numpy.allclose(frame[0], expected), f"{decay=}, {frame=}"
+
+# time-encoded images map the window's events from dark (earliest) to bright (latest)
+print("faery.render.RenderIterator time_encoded")
+frame_values = []
+for t in (600, 700, 800, 900):
+    events = numpy.zeros(4, dtype=faery.DVS_DTYPE)
+    events["t"] = [100, 501, t, 999]
+    events["x"] = [0, 1, 2, 3]
+    events["on"] = [True, True, True, False]
+    (frame,) = faery.render.RenderIterator(
+        parent=iter([events]),
+        dimensions=(5, 1),
+        next_frame_t=1000,
+        frame_duration=1000,
+        frame_count=1,
+        decay="time_encoded",
+        tau=500,
+    )
+    # pixel 0 is outside the window, pixel 1 is the earliest event and pixel 3 is the latest (OFF)
+    assert numpy.allclose(
+        frame[0], [0.0, 1 / 499, (t - 500) / 499, -1.0, 0.0]
+    ), f"{t=}, {frame=}"
+    frame_values.append(frame[0, 2])
+assert all(
+    earlier < later for earlier, later in zip(frame_values[:-1], frame_values[1:])
+), f"{frame_values=}"
+
+# the polarity colormap blends from gray to green (ON) or red (OFF)
+print("faery.render.RenderIterator polarity colormap")
+events = numpy.zeros(4, dtype=faery.DVS_DTYPE)
+events["t"] = [500, 500, 999, 999]
+events["x"] = [0, 1, 2, 3]
+events["on"] = [True, False, True, False]
+(frame,) = faery.render.RenderIterator(
+    parent=iter([events]),
+    dimensions=(5, 1),
+    next_frame_t=1000,
+    frame_duration=1000,
+    frame_count=1,
+    decay="step",
+    tau=1000,
+    colormap="polarity",
+)
+assert frame.dtype == numpy.uint8 and frame.shape == (1, 5, 3)
+assert [tuple(pixel) for pixel in frame[0]] == [
+    (0, 255, 0),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 0, 0),
+    (128, 128, 128),
+], f"{frame=}"
+(frame,) = faery.render.RenderIterator(
+    parent=iter([events]),
+    dimensions=(5, 1),
+    next_frame_t=1000,
+    frame_duration=1000,
+    frame_count=1,
+    decay="linear",
+    tau=1000,
+    colormap="polarity",
+)
+assert tuple(frame[0, 0]) == (64, 192, 64), f"{frame[0, 0]=}"
+assert tuple(frame[0, 1]) == (192, 64, 64), f"{frame[0, 1]=}"
+
+# image_at matches the streaming renderer's frame when the window covers all the events that have not fully decayed
+print("faery.Stream.image_at")
+events = numpy.zeros(100, dtype=faery.DVS_DTYPE)
+events["t"] = 1000000 + numpy.arange(100) * 100
+events["x"] = numpy.arange(100) % 8
+events["y"] = (numpy.arange(100) // 8) % 4
+events["on"] = numpy.arange(100) % 3 == 0
+stream = faery.stream_from_array(events, dimensions=(8, 4))
+frames = list(
+    faery.render.RenderIterator(
+        parent=iter([events]),
+        dimensions=(8, 4),
+        next_frame_t=1000000,
+        frame_duration=2000,
+        frame_count=8,
+        decay="step",
+        tau=3000,
+    )
+)
+for index, frame in enumerate(frames):
+    image = stream.image_at(
+        t=faery.timestamp_to_timecode(1000000 + index * 2000),
+        window=0.003,
+        decay="step",
+        tau=0.003,
+    )
+    assert image.shape == (4, 8)
+    assert numpy.array_equal(image, frame), f"{index=}, {image=}, {frame=}"
+assert numpy.all(stream.image_at(t=0.5, window=0.1, decay="step", tau=0.003) == 0.0)
+assert numpy.all(stream.image_at(t=0, window=0.1, decay="step", tau=0.003) == 0.0)
+for decay in ("exponential", "linear"):
+    try:
+        faery.render.RenderIterator(
+            parent=iter([events]),
+            dimensions=(8, 4),
+            next_frame_t=1000000,
+            frame_duration=2000,
+            frame_count=8,
+            decay=decay,
+            tau=0,
+        )
+        raise AssertionError(f"RenderIterator accepted tau=0 with {decay=}")
+    except ValueError:
+        pass
+
+# staleness is the time elapsed since each pixel's last event, untouched pixels report the maximum uint64 value
+print("faery.render.RenderIterator staleness")
+events = numpy.zeros(2, dtype=faery.DVS_DTYPE)
+events["t"] = [100, 400]
+events["x"] = [0, 1]
+events["y"] = [0, 0]
+frames = list(
+    faery.render.RenderIterator(
+        parent=iter([events]),
+        dimensions=(2, 2),
+        next_frame_t=1000,
+        frame_duration=1000,
+        frame_count=2,
+        decay="exponential",
+        tau=1000,
+        staleness=True,
+    )
+)
+assert len(frames) == 2
+for index, (pixels, staleness) in enumerate(frames):
+    frame_t = (index + 1) * 1000
+    assert staleness.dtype == numpy.uint64
+    assert staleness.shape == pixels.shape
+    assert staleness[0, 0] == frame_t - 100, f"{index=}, {staleness=}"
+    assert staleness[0, 1] == frame_t - 400, f"{index=}, {staleness=}"
+    assert staleness[1, 0] == numpy.iinfo(numpy.uint64).max, f"{index=}, {staleness=}"
+    assert staleness[1, 1] == numpy.iinfo(numpy.uint64).max, f"{index=}, {staleness=}"
+
+# time surfaces have one channel per polarity, or the most recent event of each pixel
+print("faery.render.TimeSurfaceIterator")
+events = numpy.zeros(4, dtype=faery.DVS_DTYPE)
+events["t"] = [0, 500, 700, 1500]
+events["x"] = [0, 1, 1, 2]
+events["y"] = [0, 0, 0, 1]
+events["on"] = [True, False, True, True]
+for split_polarities in (True, False):
+    frames = list(
+        faery.render.TimeSurfaceIterator(
+            parent=iter([events[:2], events[2:]]),
+            dimensions=(4, 2),
+            next_frame_t=1000,
+            frame_duration=1000,
+            frame_count=2,
+            tau=1000,
+            split_polarities=split_polarities,
+        )
+    )
+    assert len(frames) == 2
+    for index, frame in enumerate(frames):
+        frame_t = (index + 1) * 1000
+        expected = numpy.zeros((2, 2, 4), dtype=numpy.float64)
+        expected[1, 0, 0] = numpy.exp(-frame_t / 1000)
+        expected[0, 0, 1] = numpy.exp(-(frame_t - 500) / 1000)
+        expected[1, 0, 1] = numpy.exp(-(frame_t - 700) / 1000)
+        if frame_t > 1500:
+            expected[1, 1, 2] = numpy.exp(-(frame_t - 1500) / 1000)
+        if not split_polarities:
+            expected = expected.max(axis=0)
+        assert frame.dtype == numpy.float64
+        assert numpy.allclose(frame, expected), f"{split_polarities=}, {index=}"
+
+# count images are reset after each frame, split images have an OFF and an ON channel
+print("faery.render.CountImageIterator")
+for split_polarities in (False, True):
+    frames = list(
+        faery.render.CountImageIterator(
+            parent=iter([events[:2], events[2:]]),
+            dimensions=(4, 2),
+            next_frame_t=1000,
+            frame_duration=1000,
+            frame_count=3,
+            split_polarities=split_polarities,
+        )
+    )
+    assert len(frames) == 3
+    expected = numpy.zeros((3, 2, 4, 2), dtype=numpy.int32)
+    expected[0, 0, 0, 1] = 1
+    expected[0, 0, 1, 0] = 1
+    expected[0, 0, 1, 1] = 1
+    expected[1, 1, 2, 1] = 1
+    if not split_polarities:
+        expected = expected.sum(axis=3, dtype=numpy.int32)
+    for index, frame in enumerate(frames):
+        assert frame.dtype == numpy.int32
+        assert numpy.array_equal(
+            frame, expected[index]
+        ), f"{split_polarities=}, {index=}"
@@ -0,0 +1,149 @@
+import gzip
+import pathlib
+import shutil
+
+import faery
+import numpy
+
+import assets
+
+data_generated = pathlib.Path(__file__).resolve().parent / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+
+class ForwardOnlyReader:
+    """
+    Exposes read but not seek, like a pipe.
+    """
+
+    def __init__(self, path):
+        self.file = open(path, "rb")
+
+    def read(self, size: int) -> bytes:
+        return self.file.read(size)
+
+
+def packet_to_bytes(packet) -> bytes:
+    if isinstance(packet, faery.aedat.Frame):
+        return packet.pixels.tobytes()
+    if isinstance(packet, dict):
+        return b"".join(packet[key].tobytes() for key in sorted(packet.keys()))
+    if packet.dtype.names is not None and "bytes" in packet.dtype.names:
+        return packet["t"].tobytes() + b"".join(packet["bytes"])
+    return packet.tobytes()
+
+
+def decode(format: assets.Format, source, **options) -> bytes:
+    if format == "aedat":
+        decoder = faery.aedat.Decoder(source, **options)
+    elif format == "dat2":
+        decoder = faery.dat.Decoder(source, **options)
+    elif format.startswith("es-"):
+        decoder = faery.event_stream.Decoder(source, t0=0, **options)
+    else:
+        decoder = faery.evt.Decoder(source, **options)
+    with decoder:
+        # native packet boundaries may differ between sources, only the content is compared
+        return b"".join(
+            packet_to_bytes(packet[1] if format == "aedat" else packet)
+            for packet in decoder
+        )
+
+
+for file in assets.files:
+    print(f"forward-only source ({file.path.name})")
+    expected = decode(file.format, file.path)
+    assert decode(file.format, ForwardOnlyReader(file.path)) == expected
+
+# track_ids skips the other tracks' packets by reading them on forward-only sources
+for file in assets.files:
+    if file.format != "aedat":
+        continue
+    print(f"faery.aedat.Decoder track_ids forward-only ({file.path.name})")
+    gzip_path = data_generated / f"{file.path.name}.gz"
+    gzip_path.write_bytes(gzip.compress(file.path.read_bytes()))
+    with faery.aedat.Decoder(file.path) as decoder:
+        all_packets = [(track.id, packet_to_bytes(packet)) for track, packet in decoder]
+    for track_id in sorted(set(id for id, _ in all_packets)):
+        expected = [packet for id, packet in all_packets if id == track_id]
+        # a small buffer makes skipped payloads overrun it
+        for source in (gzip_path, ForwardOnlyReader(file.path)):
+            with faery.aedat.Decoder(
+                source, track_ids=[track_id], buffer_size=1024
+            ) as decoder:
+                packets = [packet_to_bytes(packet) for _, packet in decoder]
+            assert packets == expected, f"{track_id=}, {source=}"
+    assert numpy.array_equal(
+        faery.stream_from_file(gzip_path).to_array(),
+        faery.stream_from_file(file.path).to_array(),
+    )
+
+# the read buffer size changes the packet boundaries but not the events
+for file in assets.files:
+    print(f"buffer_size ({file.path.name})")
+    expected = decode(file.format, file.path)
+    assert decode(file.format, file.path, buffer_size=1024) == expected
+    assert decode(file.format, file.path, buffer_size=1 << 20) == expected
+    try:
+        decode(file.format, file.path, buffer_size=16)
+        raise AssertionError("the decoder accepted a 16 bytes buffer")
+    except ValueError:
+        pass
+
+# features that re-read the file fail clearly on forward-only sources
+for file in assets.files:
+    if file.format == "aedat":
+        print(f"faery.aedat.Decoder.seek forward-only ({file.path.name})")
+        with faery.aedat.Decoder(ForwardOnlyReader(file.path)) as decoder:
+            try:
+                decoder.seek(0)
+                raise AssertionError("seek succeeded on a forward-only source")
+            except RuntimeError as error:
+                assert "seekable" in str(error), f"{error=}"
+    elif file.format.startswith("es-"):
+        print(f"faery.event_stream.Decoder.duration forward-only ({file.path.name})")
+        with faery.event_stream.Decoder(ForwardOnlyReader(file.path), t0=0) as decoder:
+            try:
+                decoder.duration()
+                raise AssertionError("duration succeeded on a forward-only source")
+            except RuntimeError as error:
+                assert "seekable" in str(error), f"{error=}"
+
+# faery.open uses the given format, misnamed files are also recognized by their first bytes
+print("faery.open")
+for name, format, misnamed_name in (
+    ("dvs.es", "es", "misnamed_es.dat"),
+    ("evt3.raw", "evt", "misnamed_evt.dat"),
+):
+    path = assets.dirname / "data" / name
+    misnamed_path = data_generated / misnamed_name
+    shutil.copyfile(path, misnamed_path)
+    expected_events = numpy.concatenate([packet for packet in faery.open(path)])
+    for open_format in (format, None):
+        events = numpy.concatenate(
+            [packet for packet in faery.open(misnamed_path, format=open_format)]
+        )
+        assert numpy.array_equal(events, expected_events), f"{name=}, {open_format=}"
+try:
+    faery.open(assets.dirname / "data" / "dvs.es", format="mp4")  # type: ignore
+    raise AssertionError("faery.open accepted an unknown format")
+except ValueError as exception:
+    assert "unknown format" in str(exception), f"{exception=}"
+
+# files that match no magic number, header, or extension are reported with their first bytes
+print("faery.open unsupported file")
+unknown_path = data_generated / "unknown.bin"
+unknown_path.write_bytes(b"\x00\x01unknown")
+try:
+    faery.open(unknown_path)
+    raise AssertionError("faery.open accepted an unknown file")
+except ValueError as exception:
+    assert "unsupported file" in str(exception), f"{exception=}"
+    assert "\\x00\\x01unknown" in str(exception), f"{exception=}"
+    assert '".bin"' in str(exception), f"{exception=}"
+
+# only a bounded prefix of Prophesee headers is read, even without line breaks
+print("faery.FileType.guess header without line breaks")
+header_path = data_generated / "header_without_line_breaks.raw"
+header_path.write_bytes(b"% " + b"x" * (1 << 22))
+assert faery.FileType.guess(header_path) == faery.FileType.EVT
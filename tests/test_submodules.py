@@ -0,0 +1,30 @@
+import pathlib
+
+import faery
+
+dirname = pathlib.Path(__file__).resolve().parent
+
+# each submodule must expose its own decoder (not a copy of another format's)
+print("faery.aedat.Decoder")
+with faery.aedat.Decoder(dirname / "data" / "davis346.aedat4") as decoder:
+    track, packet = next(decoder)
+    assert isinstance(track, faery.aedat.Track)
+
+print("faery.dat.Decoder")
+with faery.dat.Decoder(dirname / "data" / "gen4.dat") as decoder:
+    assert decoder.event_type == "cd"
+    packet = next(decoder)
+    assert packet.dtype.names == ("t", "x", "y", "payload"), f"{packet.dtype.names=}"
+
+print("faery.event_stream.Decoder")
+with faery.event_stream.Decoder(dirname / "data" / "dvs.es", t0=0) as decoder:
+    assert decoder.event_type == "dvs"
+    packet = next(decoder)
+    assert packet.dtype.names == ("t", "x", "y", "on"), f"{packet.dtype.names=}"
+
+print("faery.evt.Decoder")
+with faery.evt.Decoder(dirname / "data" / "evt3.raw") as decoder:
+    assert decoder.version == "evt3"
+    packet = next(decoder)
+    assert isinstance(packet, dict)
+    assert packet["events"].dtype.names == ("t", "x", "y", "on")
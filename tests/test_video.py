@@ -0,0 +1,166 @@
+import pathlib
+import sys
+import time
+
+import faery
+import numpy
+
+if not faery.VIDEO_AVAILABLE:
+    print("faery was built without the video feature, skipping the video tests")
+    sys.exit(0)
+
+dirname = pathlib.Path(__file__).resolve().parent
+data_generated = dirname / "data_generated"
+data_generated.mkdir(exist_ok=True)
+
+
+def check_mp4(path: pathlib.Path):
+    data = path.read_bytes()
+    assert data[4:8] == b"ftyp", f"{data[4:8]=}"
+    assert b"moov" in data
+    assert b"avc1" in data
+    assert b"avcC" in data
+
+
+def mp4_box(data: bytes, name: bytes) -> bytes:
+    """Returns the payload of the first box with the given name (the moov box follows the samples)."""
+    index = data.rindex(name)
+    size = int.from_bytes(data[index - 4 : index], "big")
+    return data[index + 4 : index - 4 + size]
+
+
+def mp4_sample_count(path: pathlib.Path) -> int:
+    # stsz: version and flags (4 bytes), sample size (4 bytes), sample count (4 bytes)
+    return int.from_bytes(mp4_box(path.read_bytes(), b"stsz")[8:12], "big")
+
+
+def mp4_sync_samples(path: pathlib.Path) -> list[int]:
+    # stss: version and flags (4 bytes), entry count (4 bytes), one-based sample numbers (4 bytes each)
+    payload = mp4_box(path.read_bytes(), b"stss")
+    count = int.from_bytes(payload[4:8], "big")
+    return [
+        int.from_bytes(payload[8 + index * 4 : 12 + index * 4], "big")
+        for index in range(count)
+    ]
+
+
+# RGB frames produced by the renderer
+print("faery.video.VideoEncoder (RGB)")
+events = numpy.zeros(1000, dtype=faery.DVS_DTYPE)
+events["t"] = numpy.arange(1000) * 100
+events["x"] = numpy.arange(1000) % 64
+events["y"] = (numpy.arange(1000) // 64) % 48
+events["on"] = numpy.arange(1000) % 2 == 0
+path = data_generated / "render_rgb.mp4"
+with faery.video.VideoEncoder(path, dimensions=(64, 48), frame_rate=30.0) as encoder:
+    for frame in faery.render.RenderIterator(
+        parent=iter([events]),
+        dimensions=(64, 48),
+        next_frame_t=10000,
+        frame_duration=10000,
+        frame_count=10,
+        decay="exponential",
+        tau=10000,
+        colormap="viridis",
+    ):
+        assert frame.dtype == numpy.uint8 and frame.shape == (48, 64, 3)
+        encoder.write_frame(frame)
+    assert not encoder.closed
+assert encoder.closed
+check_mp4(path)
+
+# grayscale and RGBA frames, explicit close
+print("faery.video.VideoEncoder (grayscale and RGBA)")
+path = data_generated / "gray.mp4"
+encoder = faery.video.VideoEncoder(path, dimensions=(32, 16), frame_rate=29.97)
+for index in range(30):
+    encoder.write_frame(numpy.full((16, 32), index * 8, dtype=numpy.uint8))
+    encoder.write_frame(numpy.full((16, 32, 4), index * 8, dtype=numpy.uint8))
+try:
+    encoder.write_frame(numpy.zeros((32, 16), dtype=numpy.uint8))
+    raise AssertionError("write_frame accepted a transposed frame")
+except RuntimeError:
+    pass
+encoder.close()
+check_mp4(path)
+try:
+    encoder.write_frame(numpy.zeros((16, 32), dtype=numpy.uint8))
+    raise AssertionError("write_frame succeeded after close")
+except Exception as error:
+    assert str(error) == "write_frame called after __exit__"
+
+# x264 requires even dimensions with 4:2:0 chroma subsampling
+print("faery.video.VideoEncoder dimensions")
+for dimensions in ((33, 16), (32, 15), (0, 16)):
+    try:
+        faery.video.VideoEncoder(
+            data_generated / "invalid.mp4", dimensions=dimensions, frame_rate=30.0
+        )
+        raise AssertionError(f"VideoEncoder accepted {dimensions=}")
+    except RuntimeError:
+        pass
+
+# a lower CRF (higher quality) produces a larger file, crf and bitrate_kbps are exclusive
+print("faery.video.VideoEncoder rate control")
+generator = numpy.random.default_rng(0)
+frames = generator.integers(0, 256, size=(10, 48, 64, 3), dtype=numpy.uint8)
+sizes = []
+for name, options in (
+    ("crf_10", {"crf": 10.0, "preset": "ultrafast"}),
+    ("crf_40", {"crf": 40.0, "preset": "ultrafast"}),
+    ("bitrate", {"bitrate_kbps": 500, "preset": "veryslow"}),
+):
+    path = data_generated / f"rate_control_{name}.mp4"
+    with faery.video.VideoEncoder(
+        path, dimensions=(64, 48), frame_rate=30.0, **options
+    ) as encoder:
+        for frame in frames:
+            encoder.write_frame(frame)
+    check_mp4(path)
+    sizes.append(path.stat().st_size)
+assert sizes[0] > sizes[1], f"{sizes=}"
+for options in (
+    {"crf": 23.0, "bitrate_kbps": 1000},
+    {"crf": 52.0},
+    {"bitrate_kbps": 0},
+    {"preset": "fastest"},
+):
+    try:
+        faery.video.VideoEncoder(
+            data_generated / "invalid.mp4", dimensions=(64, 48), frame_rate=30.0, **options
+        )
+        raise AssertionError(f"VideoEncoder accepted {options=}")
+    except ValueError:
+        pass
+
+# chunk_frames encodes chunks that start with a keyframe on worker threads, the chunks are written in order
+print("faery.video.VideoEncoder chunk_frames")
+frames = generator.integers(0, 256, size=(53, 48, 64, 3), dtype=numpy.uint8)
+durations = {}
+for name, options in (
+    ("sequential", {}),
+    ("chunked", {"chunk_frames": 8}),
+    ("chunked_one_worker", {"chunk_frames": 8, "workers": 1}),
+):
+    path = data_generated / f"{name}.mp4"
+    begin = time.monotonic()
+    with faery.video.VideoEncoder(
+        path, dimensions=(64, 48), frame_rate=30.0, **options
+    ) as encoder:
+        for frame in frames:
+            encoder.write_frame(frame)
+    durations[name] = time.monotonic() - begin
+    check_mp4(path)
+    assert mp4_sample_count(path) == len(frames), f"{mp4_sample_count(path)=}"
+    if name != "sequential":
+        assert mp4_sync_samples(path) == [1, 9, 17, 25, 33, 41, 49], f"{mp4_sync_samples(path)=}"
+print(
+    f"sequential: {durations['sequential']:.3f} s, chunked: {durations['chunked']:.3f} s, chunked with one worker: {durations['chunked_one_worker']:.3f} s"
+)
+try:
+    faery.video.VideoEncoder(
+        data_generated / "invalid.mp4", dimensions=(64, 48), frame_rate=30.0, chunk_frames=1
+    )
+    raise AssertionError("VideoEncoder accepted chunk_frames=1")
+except ValueError:
+    pass